@@ -56,6 +56,14 @@ impl FaucetClientError {
         matches!(self.inner.kind, Kind::NeedSync)
     }
 
+    /// HTTP status the faucet responded with, if it responded with a non-success one.
+    pub fn status_code(&self) -> Option<u16> {
+        match self.inner.kind {
+            Kind::HttpStatus(status) => Some(status),
+            _ => None,
+        }
+    }
+
     //
     // Private Constructors
     //
@@ -26,6 +26,18 @@ impl FaucetClient {
         }
     }
 
+    /// Faucet waiting on its transactions through the given client.
+    pub fn new_from_rest_client(faucet_url: Url, rest_client: Client) -> Self {
+        Self {
+            faucet_url,
+            inner: ReqwestClient::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .unwrap(),
+            rest_client,
+        }
+    }
+
     pub fn new_for_testing(faucet_url: Url, rest_url: Url) -> Self {
         Self {
             faucet_url,
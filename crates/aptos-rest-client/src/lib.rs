@@ -39,7 +39,7 @@ use aptos_types::{
 use move_core_types::language_storage::StructTag;
 use reqwest::{
     header::{ACCEPT, CONTENT_TYPE},
-    Client as ReqwestClient, StatusCode,
+    Client as ReqwestClient, ClientBuilder as ReqwestClientBuilder, StatusCode,
 };
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::{json, Value};
@@ -78,12 +78,18 @@ impl Client {
         timeout: Duration,
         user_agent: &str,
     ) -> Self {
-        let inner = ReqwestClient::builder()
-            .timeout(timeout)
-            .user_agent(user_agent)
-            .cookie_store(true)
-            .build()
-            .unwrap();
+        Client::new_with_client_builder(
+            base_url,
+            ReqwestClient::builder()
+                .timeout(timeout)
+                .user_agent(user_agent),
+        )
+    }
+
+    /// Builds the client with the given reqwest builder, e.g. to tune connection
+    /// pooling. Cookie store is always enabled.
+    pub fn new_with_client_builder(base_url: Url, builder: ReqwestClientBuilder) -> Self {
+        let inner = builder.cookie_store(true).build().unwrap();
 
         // If the user provided no version in the path, use the default. If the
         // provided version has no trailing slash, add it, otherwise url.join
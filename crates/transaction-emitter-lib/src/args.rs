@@ -1,23 +1,38 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-use anyhow::{bail, format_err, Result};
+use crate::emitter::{endpoint_discovery::EndpointDiscovery, TpsSegment};
+use anyhow::{bail, ensure, format_err, Context, Result};
 use aptos::common::types::EncodingType;
 use aptos_config::keys::ConfigKey;
 use aptos_crypto::ed25519::Ed25519PrivateKey;
-use aptos_sdk::types::chain_id::ChainId;
-use aptos_transaction_generator_lib::args::TransactionTypeArg;
+use aptos_rest_client::{Client as RestClient, USER_AGENT};
+use aptos_sdk::types::{
+    chain_id::{deserialize_config_chain_id, ChainId},
+    LocalAccount,
+};
+use aptos_transaction_generator_lib::{
+    args::TransactionTypeArg,
+    entry_function_generator::EntryFunctionCall,
+    workload_mix::{WorkloadConfig, WorkloadMix},
+};
 use clap::{ArgGroup, Parser};
-use serde::{Deserialize, Serialize};
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION},
+    Proxy,
+};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::{
     convert::TryFrom,
     fs::File,
     io::{BufRead, BufReader},
-    path::Path,
+    path::{Path, PathBuf},
+    time::Duration,
 };
 use url::Url;
 
 const DEFAULT_API_PORT: u16 = 8080;
+const DEFAULT_REST_CLIENT_TIMEOUT_SECS: u64 = 10;
 
 #[derive(Clone, Debug, Default, Deserialize, Parser, Serialize)]
 pub struct CoinSourceArgs {
@@ -34,10 +49,41 @@ pub struct CoinSourceArgs {
 
     #[clap(long, conflicts_with_all = &["mint-key", "mint-file", "coin-source-key"])]
     pub coin_source_file: Option<String>,
+
+    /// Mnemonic phrase to derive the key of the account funding the run from, for networks
+    /// without a faucet or a mint key
+    #[clap(long, conflicts_with_all = &["mint-key", "mint-file", "coin-source-key", "coin-source-file"])]
+    pub coin_source_mnemonic: Option<String>,
+
+    /// Derivation path of the key of the funding account from the mnemonic phrase,
+    /// by default m/44'/637'/0'/0'/0'
+    #[clap(long, requires = "coin-source-mnemonic")]
+    pub coin_source_derive_path: Option<String>,
 }
 
+const DEFAULT_DERIVE_PATH: &str = "m/44'/637'/0'/0'/0'";
+
 impl CoinSourceArgs {
     pub fn get_private_key(&self) -> Result<(Ed25519PrivateKey, bool)> {
+        if let Some(mnemonic) = &self.coin_source_mnemonic {
+            ensure!(
+                self.mint_key.is_none()
+                    && self.mint_file.is_none()
+                    && self.coin_source_key.is_none()
+                    && self.coin_source_file.is_none(),
+                "Please provide exactly one of mint-key, mint-file, coin-source-key, coin-source-file, or coin-source-mnemonic"
+            );
+            let derive_path = self
+                .coin_source_derive_path
+                .as_deref()
+                .unwrap_or(DEFAULT_DERIVE_PATH);
+            let account = LocalAccount::from_derive_path(derive_path, mnemonic, 0)
+                .context("Failed to derive coin source key from mnemonic")?;
+            return Ok((
+                Ed25519PrivateKey::try_from(account.private_key().to_bytes().as_slice())?,
+                false,
+            ));
+        }
         match (
             &self.mint_key,
             &self.mint_file,
@@ -56,7 +102,7 @@ impl CoinSourceArgs {
                     .load_key::<Ed25519PrivateKey>("mint key pair", Path::new(path))?,
                 false,
             )),
-            _ => Err(anyhow::anyhow!("Please provide exactly one of mint-key, mint-file, coin-source-key, or coin-source-file")),
+            _ => Err(anyhow::anyhow!("Please provide exactly one of mint-key, mint-file, coin-source-key, coin-source-file, or coin-source-mnemonic")),
         }
     }
 }
@@ -71,15 +117,256 @@ pub struct ClusterArgs {
     #[clap(long, conflicts_with = "targets")]
     pub targets_file: Option<String>,
 
+    /// Node to read the on-chain validator set from, to target the REST APIs of the
+    /// fullnode addresses of all validators, instead of giving targets
+    #[clap(long, conflicts_with_all = &["targets", "targets-file"], parse(try_from_str = parse_target))]
+    pub discover_targets_from: Option<Url>,
+
+    /// Port of the REST API on the discovered hosts, 8080 if not set
+    #[clap(long, requires = "discover-targets-from")]
+    pub discovered_rest_port: Option<u16>,
+
+    /// Start a local single-node testnet in-process and target it, funding accounts
+    /// from its root account, instead of giving targets
+    #[clap(long, conflicts_with_all = &["targets", "targets-file", "discover-targets-from"])]
+    #[serde(default)]
+    pub local_testnet: bool,
+
+    /// Directory of the local testnet, attaching to the node already running from it
+    /// if there is one, a temporary directory if not set
+    #[clap(long, requires = "local-testnet")]
+    pub local_testnet_dir: Option<PathBuf>,
+
     /// If set, try to use public peers instead of localhost.
     #[clap(long)]
+    #[serde(default)]
     pub reuse_accounts: bool,
 
     #[clap(long, default_value = "TESTING")]
+    #[serde(default, deserialize_with = "deserialize_config_chain_id")]
     pub chain_id: ChainId,
 
     #[clap(flatten)]
+    #[serde(default)]
     pub coin_source_args: CoinSourceArgs,
+
+    #[clap(flatten)]
+    #[serde(default)]
+    pub http_client_args: HttpClientArgs,
+}
+
+/// Connection settings of the RestClients created for the cluster.
+/// Unset values keep the reqwest defaults.
+#[derive(Clone, Debug, Default, Deserialize, Parser, Serialize)]
+#[serde(default)]
+pub struct HttpClientArgs {
+    /// Timeout of each request, 10 seconds if not set.
+    #[clap(long)]
+    pub rest_client_timeout_secs: Option<u64>,
+
+    /// Maximum number of idle connections kept open per host. Lowering it
+    /// makes connections get closed after bursts, instead of piling up.
+    #[clap(long)]
+    pub pool_max_idle_per_host: Option<usize>,
+
+    /// Idle connections are closed after this many seconds.
+    #[clap(long)]
+    pub pool_idle_timeout_secs: Option<u64>,
+
+    /// Interval of TCP keepalive probes, keeping idle connections (and their
+    /// TLS sessions) alive. Disabled if not set.
+    #[clap(long)]
+    pub tcp_keepalive_secs: Option<u64>,
+
+    /// Use HTTP/2 for all requests, without negotiating it first. Needs all
+    /// targets to support it.
+    #[clap(long)]
+    pub http2_prior_knowledge: bool,
+
+    #[clap(long)]
+    pub tcp_nodelay: Option<bool>,
+
+    /// Header added to all requests to matching targets, as `<target>=<name>:<value>`,
+    /// where target is either a host (e.g. `node.mysite.com`), or `*` for all targets.
+    #[clap(long, parse(try_from_str = parse_target_header))]
+    pub target_header: Vec<TargetHeader>,
+
+    /// API key sent as a bearer token to matching targets, as `<target>=<token>`.
+    #[clap(long, parse(try_from_str = parse_target_bearer_token))]
+    pub target_bearer_token: Vec<TargetHeader>,
+
+    /// Proxy to send requests to matching targets through, as `<target>=<proxy url>`,
+    /// e.g. `*=http://proxy.mysite.com:3128`. Only http and https proxies are
+    /// supported. Targets without one use the proxy environment variables.
+    #[clap(long, parse(try_from_str = parse_target_proxy))]
+    pub target_proxy: Vec<TargetProxy>,
+
+    /// Targets (hosts, or `*` for all) that are connected to directly, ignoring
+    /// both target_proxy and the proxy environment variables.
+    #[clap(long)]
+    pub no_proxy: Vec<String>,
+}
+
+pub(crate) fn target_matches(target: &str, url: &Url) -> bool {
+    target == "*" || url.host_str() == Some(target)
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub struct TargetHeader {
+    /// Host of the targets the header is sent to, or `*` for all targets.
+    pub target: String,
+    pub name: String,
+    pub value: String,
+}
+
+impl TargetHeader {
+    fn matches(&self, url: &Url) -> bool {
+        target_matches(&self.target, url)
+    }
+
+    fn to_header(&self) -> Result<(HeaderName, HeaderValue)> {
+        let name = HeaderName::from_bytes(self.name.as_bytes())
+            .map_err(|e| format_err!("Invalid header name {}: {}", self.name, e))?;
+        let mut value = HeaderValue::from_str(&self.value)
+            .map_err(|e| format_err!("Invalid value of header {}: {}", self.name, e))?;
+        value.set_sensitive(true);
+        Ok((name, value))
+    }
+}
+
+fn split_target(arg: &str) -> Result<(&str, &str)> {
+    arg.split_once('=')
+        .filter(|(target, _)| !target.is_empty())
+        .ok_or_else(|| format_err!("Expected <target>=..., got {}", arg))
+}
+
+fn parse_target_header(arg: &str) -> Result<TargetHeader> {
+    let (target, header) = split_target(arg)?;
+    let (name, value) = header
+        .split_once(':')
+        .ok_or_else(|| format_err!("Expected <target>=<name>:<value>, got {}", arg))?;
+    let header = TargetHeader {
+        target: target.to_string(),
+        name: name.trim().to_string(),
+        value: value.trim().to_string(),
+    };
+    header.to_header()?;
+    Ok(header)
+}
+
+fn parse_target_bearer_token(arg: &str) -> Result<TargetHeader> {
+    let (target, token) = split_target(arg)?;
+    let header = TargetHeader {
+        target: target.to_string(),
+        name: AUTHORIZATION.to_string(),
+        value: format!("Bearer {}", token),
+    };
+    header.to_header()?;
+    Ok(header)
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub struct TargetProxy {
+    /// Host of the targets using the proxy, or `*` for all targets.
+    pub target: String,
+    pub proxy: Url,
+}
+
+fn parse_target_proxy(arg: &str) -> Result<TargetProxy> {
+    let (target, proxy) = split_target(arg)?;
+    let proxy = Url::parse(proxy).map_err(|e| format_err!("Invalid proxy url {}: {}", proxy, e))?;
+    // SOCKS would need reqwest's socks feature
+    if !matches!(proxy.scheme(), "http" | "https") {
+        bail!(
+            "Unsupported proxy scheme {}, only http(s) proxies are supported",
+            proxy.scheme()
+        );
+    }
+    Proxy::all(proxy.clone())?;
+    Ok(TargetProxy {
+        target: target.to_string(),
+        proxy,
+    })
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TargetProxySetting<'a> {
+    /// Proxy environment variables apply.
+    FromEnv,
+    Direct,
+    Proxy(&'a Url),
+}
+
+impl HttpClientArgs {
+    /// Proxy to connect to the target through, later target_proxy entries
+    /// overriding earlier ones.
+    pub fn proxy_for(&self, url: &Url) -> TargetProxySetting<'_> {
+        if self
+            .no_proxy
+            .iter()
+            .any(|target| target_matches(target, url))
+        {
+            return TargetProxySetting::Direct;
+        }
+        self.target_proxy
+            .iter()
+            .rev()
+            .find(|proxy| target_matches(&proxy.target, url))
+            .map_or(TargetProxySetting::FromEnv, |proxy| {
+                TargetProxySetting::Proxy(&proxy.proxy)
+            })
+    }
+
+    /// Headers to send to the target, later entries overriding earlier ones.
+    pub fn headers_for(&self, url: &Url) -> Result<HeaderMap> {
+        let mut headers = HeaderMap::new();
+        for header in self
+            .target_header
+            .iter()
+            .chain(self.target_bearer_token.iter())
+            .filter(|header| header.matches(url))
+        {
+            let (name, value) = header.to_header()?;
+            headers.insert(name, value);
+        }
+        Ok(headers)
+    }
+
+    pub fn rest_client(&self, url: Url) -> RestClient {
+        let mut builder = reqwest::Client::builder()
+            .timeout(Duration::from_secs(
+                self.rest_client_timeout_secs
+                    .unwrap_or(DEFAULT_REST_CLIENT_TIMEOUT_SECS),
+            ))
+            .user_agent(USER_AGENT)
+            .default_headers(
+                self.headers_for(&url)
+                    .expect("target headers are validated when parsed"),
+            )
+            .tcp_keepalive(self.tcp_keepalive_secs.map(Duration::from_secs));
+        if let Some(pool_max_idle_per_host) = self.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(pool_max_idle_per_host);
+        }
+        if let Some(pool_idle_timeout_secs) = self.pool_idle_timeout_secs {
+            builder = builder.pool_idle_timeout(Duration::from_secs(pool_idle_timeout_secs));
+        }
+        if self.http2_prior_knowledge {
+            builder = builder.http2_prior_knowledge();
+        }
+        if let Some(tcp_nodelay) = self.tcp_nodelay {
+            builder = builder.tcp_nodelay(tcp_nodelay);
+        }
+        match self.proxy_for(&url) {
+            TargetProxySetting::FromEnv => {},
+            TargetProxySetting::Direct => builder = builder.no_proxy(),
+            TargetProxySetting::Proxy(proxy) => {
+                builder = builder.proxy(
+                    Proxy::all(proxy.clone()).expect("target proxies are validated when parsed"),
+                );
+            },
+        }
+        RestClient::new_with_client_builder(url, builder)
+    }
 }
 
 impl ClusterArgs {
@@ -91,6 +378,17 @@ impl ClusterArgs {
         };
     }
 
+    /// Discovery of targets from the validator set, if targets are discovered.
+    pub fn get_endpoint_discovery(&self) -> Option<EndpointDiscovery> {
+        self.discover_targets_from
+            .clone()
+            .map(|bootstrap| EndpointDiscovery {
+                bootstrap,
+                rest_port: self.discovered_rest_port.unwrap_or(DEFAULT_API_PORT),
+                http_client_args: self.http_client_args.clone(),
+            })
+    }
+
     fn get_targets_from_file(path: &String) -> Result<Vec<Url>> {
         let reader = BufReader::new(File::open(path)?);
         let mut urls = Vec::new();
@@ -107,7 +405,7 @@ impl ClusterArgs {
 #[clap(group(
     ArgGroup::new("mode")
         .required(true)
-        .args(&["mempool-backlog", "target-tps"]),
+        .args(&["mempool-backlog", "target-tps", "tps-schedule"]),
 ))]
 pub struct EmitArgs {
     #[clap(long)]
@@ -121,6 +419,43 @@ pub struct EmitArgs {
     #[clap(long)]
     pub target_tps: Option<usize>,
 
+    /// Segments to emit at constant TPS in turn, as `<duration secs>:<tps>`, e.g.
+    /// `--tps-schedule 60:100 60:1000 60:100`. Overrides duration.
+    #[clap(long, min_values = 1, parse(try_from_str = parse_tps_segment))]
+    #[serde(
+        default,
+        serialize_with = "serialize_tps_schedule",
+        deserialize_with = "deserialize_tps_schedule"
+    )]
+    pub tps_schedule: Vec<TpsSegment>,
+
+    /// If set, emission bursts to this TPS for burst_duration_secs every
+    /// burst_interval_secs, with target_tps being the baseline.
+    #[clap(long, requires = "target-tps")]
+    pub burst_tps: Option<usize>,
+
+    #[clap(long, default_value = "5")]
+    #[serde(default = "EmitArgs::default_burst_duration_secs")]
+    pub burst_duration_secs: u64,
+
+    #[clap(long, default_value = "60")]
+    #[serde(default = "EmitArgs::default_burst_interval_secs")]
+    pub burst_interval_secs: u64,
+
+    /// If set, TPS is adjusted during the run to keep p99 commit latency under this,
+    /// starting at target_tps and never exceeding max_tps.
+    #[clap(long, requires_all = &["target-tps", "max-tps"], conflicts_with = "burst-tps")]
+    pub target_p99_latency_ms: Option<u64>,
+
+    /// Upper bound on TPS, when targeting latency
+    #[clap(long)]
+    pub max_tps: Option<usize>,
+
+    /// How often TPS is adjusted, when targeting latency
+    #[clap(long, default_value = "10")]
+    #[serde(default = "EmitArgs::default_latency_adjust_interval_secs")]
+    pub latency_adjust_interval_secs: u64,
+
     #[clap(long, default_value = "30")]
     pub txn_expiration_time_secs: u64,
 
@@ -140,9 +475,194 @@ pub struct EmitArgs {
     #[clap(long, min_values = 0)]
     pub transaction_weights: Vec<usize>,
 
+    /// Workload mix (see WorkloadMix) to run, from a YAML file, instead of the one given
+    /// by transaction_type, transaction_weights, transaction_phases,
+    /// transaction_expiration_secs, transaction_max_gas_amount, transaction_gas_unit_price,
+    /// hot_spot_ratio and loop_count
+    #[clap(long, parse(from_os_str))]
+    pub workload_mix_file: Option<PathBuf>,
+
+    /// Entry functions called by entry-function transaction types, in order, each as
+    /// YAML, e.g. `--entry-function '{function: 0xcafe::counter::increment_by, args:
+    /// ["address:{receiver}", "u64:{random:1:100}"]}'`. See EntryFunctionCall for the
+    /// placeholders arguments can have.
+    #[clap(long, min_values = 1, parse(try_from_str = parse_entry_function))]
+    #[serde(default)]
+    pub entry_function: Vec<EntryFunctionCall>,
+
+    /// Iterations of each call of compute-loop transaction types, tuning the gas each of
+    /// their transactions uses (max-gas-per-txn needs to cover it)
+    #[clap(long)]
+    pub loop_count: Option<u64>,
+
     #[clap(long, min_values = 0)]
     pub transaction_phases: Vec<usize>,
 
+    /// Expiration of transactions of each transaction type, e.g. longer for bulk
+    /// initialization than for latency sensitive workloads, with 0 keeping
+    /// txn_expiration_time_secs. Expired transactions are then reported per workload
+    #[clap(long, min_values = 0)]
+    #[serde(default)]
+    pub transaction_expiration_secs: Vec<u64>,
+
+    /// Max gas amount of transactions of each transaction type, e.g. higher for module
+    /// publishing than for transfers, with 0 keeping max_gas_per_txn
+    #[clap(long, min_values = 0)]
+    #[serde(default)]
+    pub transaction_max_gas_amount: Vec<u64>,
+
+    /// Gas unit price of transactions of each transaction type, e.g. to have a workload
+    /// outbid the others, with 0 keeping gas_price
+    #[clap(long, min_values = 0)]
+    #[serde(default)]
+    pub transaction_gas_unit_price: Vec<u64>,
+
+    /// Percent of transfers of each transaction type going to the same few receivers,
+    /// e.g. growing from phase to phase to dial contention up within a run. Only
+    /// applies to coin and batch transfer types
+    #[clap(long, min_values = 0)]
+    #[serde(default)]
+    pub hot_spot_ratio: Vec<usize>,
+
+    /// Duration of each phase (see transaction_phases), instead of splitting duration
+    /// evenly between them. Overrides duration.
+    #[clap(long, min_values = 1, conflicts_with = "tps-schedule")]
+    #[serde(default)]
+    pub phase_duration_secs: Vec<u64>,
+
+    /// Criteria a planned phase needs to meet for the run to succeed, as
+    /// `<phase>:<criterion>=<value>[,<criterion>=<value>]`, with criteria being
+    /// `min_tps`, `max_p99_latency_ms` and `max_expired_ratio`,
+    /// e.g. `--phase-success-criteria 2:min_tps=1000,max_p99_latency_ms=5000`.
+    #[clap(long, min_values = 1, requires = "phase-duration-secs")]
+    #[serde(default)]
+    pub phase_success_criteria: Vec<String>,
+
+    /// Track stats of each worker, and warn about workers whose submitted, committed
+    /// or expired counts deviate from the mean across workers by more than this factor.
+    #[clap(long)]
+    pub worker_imbalance_factor: Option<f64>,
+
+    /// File to write a JSON report of the results to at the end of the run (stats per
+    /// phase, workload and endpoint, and coins spent), for CI pipelines to assert on.
+    #[clap(long)]
+    pub result_report_file: Option<String>,
+
+    /// Result report of a previous run to compare this one to, failing the run if it
+    /// regressed beyond max_tps_drop_pct or max_p99_latency_increase_pct
+    #[clap(long)]
+    pub baseline_report_file: Option<String>,
+
+    /// Max drop of committed TPS compared to the baseline, in percent
+    #[clap(long, requires = "baseline-report-file")]
+    pub max_tps_drop_pct: Option<f64>,
+
+    /// Max increase of p99 latency compared to the baseline, in percent
+    #[clap(long, requires = "baseline-report-file")]
+    pub max_p99_latency_increase_pct: Option<f64>,
+
+    /// CSV file to write throughput and latency percentiles of each interval to,
+    /// while running, so that runs can be plotted and compared offline.
+    #[clap(long)]
+    pub stats_csv_file: Option<String>,
+
+    #[clap(long, default_value = "1")]
+    #[serde(default = "EmitArgs::default_stats_csv_interval_secs")]
+    pub stats_csv_interval_secs: u64,
+
+    /// Prometheus pushgateway to push metrics of the run to (e.g. http://pushgateway:9091),
+    /// for short-lived runs that cannot be scraped reliably.
+    #[clap(long)]
+    pub pushgateway_url: Option<String>,
+
+    /// Metrics are grouped under it in the pushgateway, defaults to the start time
+    /// of the run in seconds.
+    #[clap(long, requires = "pushgateway-url")]
+    pub pushgateway_run_id: Option<String>,
+
+    #[clap(long, default_value = "15")]
+    #[serde(default = "EmitArgs::default_pushgateway_interval_secs")]
+    pub pushgateway_interval_secs: u64,
+
+    /// Stop the run once this many transactions were submitted
+    #[clap(long)]
+    pub stop_after_txns: Option<u64>,
+
+    /// Stop the run once the estimated coins spent on gas (committed transactions times
+    /// expected_gas_per_txn times gas_price) reach this budget
+    #[clap(long)]
+    pub stop_after_coins_spent: Option<u64>,
+
+    /// Stop the run once the ratio of submitted transactions that expired or failed
+    /// submission exceeds this threshold
+    #[clap(long)]
+    pub stop_on_failure_ratio: Option<f64>,
+
+    /// How long to wait for in-flight transactions when stopping (at the end of the run,
+    /// or on ctrl-c), before counting the remaining ones as expired. A second ctrl-c
+    /// exits right away.
+    #[clap(long, default_value = "60")]
+    #[serde(default = "EmitArgs::default_drain_timeout_secs")]
+    pub drain_timeout_secs: u64,
+
+    /// At the end of the run, compare sequence numbers of the accounts with the ones on
+    /// chain, reporting transactions counted as committed that aren't, and commits that
+    /// were never observed
+    #[clap(long)]
+    #[serde(default)]
+    pub audit_sequence_numbers: bool,
+
+    /// Stats of this many seconds at the start of the run are excluded from the
+    /// reported results (but still logged)
+    #[clap(long)]
+    pub warm_up_secs: Option<u64>,
+
+    /// Stats of this many seconds at the end of the run are excluded from the
+    /// reported results (but still logged)
+    #[clap(long)]
+    pub cool_down_secs: Option<u64>,
+
+    /// Faults to inject into init submissions, as comma separated
+    /// `<fault>=<probability>`, e.g. `delay=0.1:500,drop=0.01,duplicate=0.01,corrupt_hash=0.01`,
+    /// with delay also taking the max delay in ms
+    #[clap(long)]
+    pub init_fault_injection: Option<String>,
+
+    /// Group (e.g. region) of matching targets, as `<target>=<group>`, where target is
+    /// either a host or `*` for all targets, later entries overriding earlier ones.
+    /// TPS and latency are reported per group, with targets not matching any entry
+    /// in the `default` group.
+    #[clap(long)]
+    #[serde(default)]
+    pub endpoint_group: Vec<String>,
+
+    /// Share of workers pinned to targets of a group, as `<group>=<share>`, e.g.
+    /// `eu=0.5`. The rest of the workers are spread across groups without a share.
+    #[clap(long, requires = "endpoint-group")]
+    #[serde(default)]
+    pub group_sender_share: Vec<String>,
+
+    /// Max transactions in flight (submitted, and not yet committed or expired) to each
+    /// endpoint, across all of its workers. Workers wait for permits before submitting
+    #[clap(long)]
+    pub max_in_flight_per_endpoint: Option<usize>,
+
+    /// Max transactions in flight to matching targets, as `<target>=<max>`, where target
+    /// is either a host or `*` for all targets, later entries overriding earlier ones
+    /// and max_in_flight_per_endpoint
+    #[clap(long)]
+    #[serde(default)]
+    pub endpoint_max_in_flight: Vec<String>,
+
+    /// Reduce the number of emitting workers while more transactions than this are in
+    /// flight (submitted, and not yet committed or expired), instead of filling up mempools
+    #[clap(long)]
+    pub max_backlog: Option<u64>,
+
+    /// How often the backlog is sampled and emission adjusted (default 5)
+    #[clap(long, requires = "max-backlog")]
+    pub backlog_sample_interval_secs: Option<u64>,
+
     #[clap(long)]
     pub gas_price: Option<u64>,
 
@@ -152,6 +672,83 @@ pub struct EmitArgs {
     #[clap(long)]
     pub init_gas_price_multiplier: Option<u64>,
 
+    /// Derive accounts from the key of the account funding the run, so that every run
+    /// funded by it uses the same accounts, only topping them up
+    #[clap(long)]
+    #[serde(default)]
+    pub derive_accounts_from_source: bool,
+
+    /// Derive the keys of accounts from this seed and their index, so that the same
+    /// accounts can be reconstructed on another machine or in a later run, without a pool
+    /// file, and keep their addresses across runs
+    #[clap(long, conflicts_with = "derive-accounts-from-source")]
+    pub account_pool_seed: Option<u64>,
+
+    /// Share the accounts of all workers in a pool, from which each worker checks out the
+    /// senders of a round, returning them once their transactions are done
+    #[clap(long)]
+    #[serde(default)]
+    pub recycle_accounts: bool,
+
+    /// Number of accounts each intermediate account of the funding tree funds. If not set,
+    /// accounts are funded through a single level of seed accounts
+    #[clap(long)]
+    pub funding_fan_out: Option<usize>,
+
+    /// Levels of intermediate accounts in the funding tree, between the source account
+    /// and the created accounts
+    #[clap(long, requires = "funding-fan-out")]
+    pub funding_depth: Option<usize>,
+
+    /// Coins given to each account of every level of the funding tree, from the first
+    /// intermediate one to the created accounts, instead of the computed ones
+    #[clap(long, min_values = 2)]
+    #[serde(default)]
+    pub funding_level_amounts: Vec<u64>,
+
+    /// Fund seed accounts through the faucet at this URL, instead of from the source account
+    #[clap(long)]
+    pub faucet_url: Option<Url>,
+
+    /// Number of faucet requests in flight at once
+    #[clap(long, requires = "faucet-url")]
+    pub faucet_parallelism: Option<usize>,
+
+    /// Attempts of each faucet request, including the first one
+    #[clap(long, requires = "faucet-url")]
+    pub faucet_max_attempts: Option<usize>,
+
+    /// Largest amount to request from the faucet at once, with larger amounts split
+    /// across multiple requests
+    #[clap(long, requires = "faucet-url")]
+    pub faucet_max_amount_per_request: Option<u64>,
+
+    /// Refresh the gas price of emitted transactions from the gas estimation API, as
+    /// `<interval secs>:<floor>:<ceiling>`, with estimates clamped to [floor, ceiling].
+    /// gas_price is used until the first refresh
+    #[clap(long)]
+    pub gas_price_refresh: Option<String>,
+
+    /// Re-discover targets from the validator set at this interval, moving workers to the
+    /// new targets when it changes. Needs targets to be discovered
+    #[clap(long)]
+    pub targets_refresh_interval_secs: Option<u64>,
+
+    /// Before emitting, check that every endpoint is reachable, has a recent ledger, is
+    /// not pruned past --required-version, and estimates gas, failing with a diagnosis
+    /// of each unhealthy endpoint otherwise
+    #[clap(long)]
+    #[serde(default)]
+    pub pre_run_health_check: bool,
+
+    /// Max age of the latest ledger info of an endpoint, for it to pass the health check (default 30)
+    #[clap(long, requires = "pre-run-health-check")]
+    pub max_ledger_age_secs: Option<u64>,
+
+    /// Version every endpoint needs to still have, i.e. not be pruned past, to pass the health check
+    #[clap(long, requires = "pre-run-health-check")]
+    pub required_version: Option<u64>,
+
     #[clap(long)]
     pub expected_max_txns: Option<u64>,
 
@@ -174,6 +771,154 @@ pub struct EmitArgs {
     //   basically creating a new source account (to then create seed accounts from).
     #[clap(long)]
     pub coordination_delay_between_instances: Option<u64>,
+
+    /// File to persist created accounts to, so that rerunning with the same file after
+    /// a crash resumes account creation, instead of creating all accounts again.
+    #[clap(long)]
+    pub run_state_file: Option<String>,
+
+    /// Encrypted file to load funded accounts from at the start of the run (creating only
+    /// the missing ones), and to save them to at the end, so that repeated runs against
+    /// the same network can skip funding.
+    #[clap(long, requires = "account-pool-password")]
+    pub account_pool_file: Option<String>,
+
+    /// Password the account pool file is encrypted with
+    #[clap(long, env = "ACCOUNT_POOL_PASSWORD", hide_env_values = true)]
+    #[serde(skip_serializing)]
+    pub account_pool_password: Option<String>,
+
+    /// Signature scheme of the accounts emitting transactions, `ed25519` (default), or
+    /// K-of-N multi-ed25519 as `multi_ed25519:<threshold>:<num keys>`. Accounts with
+    /// multi-ed25519 keys can't be reused or persisted.
+    #[clap(long)]
+    pub account_signature_scheme: Option<String>,
+
+    /// Seed of all randomness of the run (accounts, senders and payloads), so that runs
+    /// with the same seed against a fresh chain emit the same transactions, as long as
+    /// workers don't draw from the shared accounts pool. Random if not set, and logged.
+    #[clap(long)]
+    pub seed: Option<u64>,
+}
+
+impl EmitArgs {
+    fn default_burst_duration_secs() -> u64 {
+        5
+    }
+
+    fn default_burst_interval_secs() -> u64 {
+        60
+    }
+
+    fn default_latency_adjust_interval_secs() -> u64 {
+        10
+    }
+
+    fn default_stats_csv_interval_secs() -> u64 {
+        1
+    }
+
+    fn default_pushgateway_interval_secs() -> u64 {
+        15
+    }
+
+    fn default_drain_timeout_secs() -> u64 {
+        60
+    }
+
+    /// Workload mix of the run, from workload_mix_file, or else from the transaction
+    /// type args, which need to be of the same length as transaction_type when given.
+    pub fn workload_mix(&self) -> Result<WorkloadMix> {
+        if let Some(workload_mix_file) = &self.workload_mix_file {
+            return WorkloadMix::load(workload_mix_file);
+        }
+        let num_workloads = self.transaction_type.len();
+        for (name, len) in [
+            ("weights", self.transaction_weights.len()),
+            ("phases", self.transaction_phases.len()),
+            ("expirations", self.transaction_expiration_secs.len()),
+            ("max gas amounts", self.transaction_max_gas_amount.len()),
+            ("gas unit prices", self.transaction_gas_unit_price.len()),
+            ("hot spot ratios", self.hot_spot_ratio.len()),
+        ] {
+            ensure!(
+                len == 0 || len == num_workloads,
+                "Transaction types and {} need to be the same length",
+                name
+            );
+        }
+        let workloads = self
+            .transaction_type
+            .iter()
+            .enumerate()
+            .map(|(index, transaction_type)| WorkloadConfig {
+                transaction_type: *transaction_type,
+                weight: self.transaction_weights.get(index).copied().unwrap_or(1),
+                phase: self.transaction_phases.get(index).copied().unwrap_or(0),
+                hot_spot_ratio: self.hot_spot_ratio.get(index).copied(),
+                loop_count: self.loop_count,
+                num_resources: None,
+                write_set_bytes: None,
+                expiration_secs: self.transaction_expiration_secs.get(index).copied(),
+                max_gas_amount: self
+                    .transaction_max_gas_amount
+                    .get(index)
+                    .copied()
+                    .filter(|amount| *amount > 0),
+                gas_unit_price: self
+                    .transaction_gas_unit_price
+                    .get(index)
+                    .copied()
+                    .filter(|price| *price > 0),
+                gas_unit_price_multiplier: None,
+                label: None,
+                run_for_secs: None,
+                run_for_transactions: None,
+            })
+            .collect();
+        Ok(WorkloadMix { workloads })
+    }
+}
+
+/// TPS schedule is (de)serialized in the same `<duration secs>:<tps>` format as on the CLI.
+fn serialize_tps_schedule<S>(segments: &[TpsSegment], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.collect_seq(
+        segments
+            .iter()
+            .map(|segment| format!("{}:{}", segment.duration.as_secs(), segment.tps)),
+    )
+}
+
+fn deserialize_tps_schedule<'de, D>(deserializer: D) -> Result<Vec<TpsSegment>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|segment| parse_tps_segment(segment).map_err(serde::de::Error::custom))
+        .collect()
+}
+
+fn parse_entry_function(entry_function: &str) -> Result<EntryFunctionCall> {
+    serde_yaml::from_str(entry_function)
+        .map_err(|e| format_err!("Invalid entry function {}: {}", entry_function, e))
+}
+
+fn parse_tps_segment(segment: &str) -> Result<TpsSegment> {
+    let (duration_secs, tps) = segment
+        .split_once(':')
+        .ok_or_else(|| format_err!("Expected <duration secs>:<tps>, got {}", segment))?;
+    let tps = tps.parse()?;
+    if tps == 0 {
+        bail!("TPS of a schedule segment needs to be positive");
+    }
+    Ok(TpsSegment {
+        duration: Duration::from_secs(duration_secs.parse()?),
+        tps,
+    })
 }
 
 fn parse_target(target: &str) -> Result<Url> {
@@ -195,3 +940,112 @@ fn parse_target(target: &str) -> Result<Url> {
     }
     Ok(url)
 }
+
+#[cfg(test)]
+mod test {
+    use super::{
+        parse_target_bearer_token, parse_target_header, parse_target_proxy, CoinSourceArgs,
+        HttpClientArgs, TargetProxySetting,
+    };
+    use aptos_crypto::ed25519::Ed25519PublicKey;
+    use aptos_sdk::{
+        move_types::account_address::AccountAddress,
+        types::transaction::authenticator::AuthenticationKey,
+    };
+    use url::Url;
+
+    #[test]
+    pub fn test_target_headers() {
+        assert!(parse_target_header("node.mysite.com=x-api-key").is_err());
+        assert!(parse_target_header("=x-api-key:abc").is_err());
+        assert!(parse_target_header("*=bad header:abc").is_err());
+
+        let args = HttpClientArgs {
+            target_header: vec![
+                parse_target_header("*=x-api-key: default").unwrap(),
+                parse_target_header("node.mysite.com=x-api-key: abc").unwrap(),
+            ],
+            target_bearer_token: vec![parse_target_bearer_token("other.com=xyz").unwrap()],
+            ..HttpClientArgs::default()
+        };
+        let headers = args
+            .headers_for(&Url::parse("http://node.mysite.com:8080").unwrap())
+            .unwrap();
+        assert_eq!(headers.len(), 1);
+        assert_eq!(headers["x-api-key"], "abc");
+
+        let headers = args
+            .headers_for(&Url::parse("https://other.com").unwrap())
+            .unwrap();
+        assert_eq!(headers.len(), 2);
+        assert_eq!(headers["x-api-key"], "default");
+        assert_eq!(headers["authorization"], "Bearer xyz");
+    }
+
+    #[test]
+    pub fn test_target_proxies() {
+        assert!(parse_target_proxy("*=not a url").is_err());
+        assert!(parse_target_proxy("*=socks5://localhost:1080").is_err());
+
+        let args = HttpClientArgs {
+            target_proxy: vec![
+                parse_target_proxy("*=https://default-proxy:3129").unwrap(),
+                parse_target_proxy("node.mysite.com=http://proxy:3128").unwrap(),
+            ],
+            no_proxy: vec!["localhost".to_string()],
+            ..HttpClientArgs::default()
+        };
+        let proxy_for = |url: &str| args.proxy_for(&Url::parse(url).unwrap());
+        let proxy = Url::parse("http://proxy:3128").unwrap();
+        assert_eq!(
+            proxy_for("http://node.mysite.com:8080"),
+            TargetProxySetting::Proxy(&proxy)
+        );
+        let proxy = Url::parse("https://default-proxy:3129").unwrap();
+        assert_eq!(
+            proxy_for("https://other.com"),
+            TargetProxySetting::Proxy(&proxy)
+        );
+        assert_eq!(
+            proxy_for("http://localhost:8080"),
+            TargetProxySetting::Direct
+        );
+        assert_eq!(
+            HttpClientArgs::default().proxy_for(&Url::parse("http://localhost").unwrap()),
+            TargetProxySetting::FromEnv
+        );
+    }
+
+    #[test]
+    pub fn test_coin_source_mnemonic() {
+        let mnemonic =
+            "shoot island position soft burden budget tooth cruel issue economy destroy above";
+        let args = CoinSourceArgs {
+            coin_source_mnemonic: Some(mnemonic.to_string()),
+            ..CoinSourceArgs::default()
+        };
+        let (key, is_root) = args.get_private_key().unwrap();
+        assert!(!is_root);
+        assert_eq!(
+            AuthenticationKey::ed25519(&Ed25519PublicKey::from(&key)).derived_address(),
+            AccountAddress::from_hex_literal(
+                "0x7968dab936c1bad187c60ce4082f307d030d780e91e694ae03aef16aba73f30"
+            )
+            .unwrap()
+        );
+
+        let other_path = CoinSourceArgs {
+            coin_source_derive_path: Some("m/44'/637'/1'/0'/0'".to_string()),
+            ..args.clone()
+        };
+        assert_ne!(
+            other_path.get_private_key().unwrap().0.to_bytes(),
+            key.to_bytes()
+        );
+        let conflicting = CoinSourceArgs {
+            coin_source_file: Some("mint.key".to_string()),
+            ..args
+        };
+        assert!(conflicting.get_private_key().is_err());
+    }
+}
@@ -1,7 +1,11 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{emitter::query_sequence_number, instance::Instance, ClusterArgs};
+use crate::{
+    emitter::{endpoint_discovery::EndpointDiscovery, query_sequence_number},
+    instance::Instance,
+    ClusterArgs, HttpClientArgs, LocalTestnet,
+};
 use anyhow::{anyhow, bail, format_err, Result};
 use aptos_crypto::{
     ed25519::{Ed25519PrivateKey, Ed25519PublicKey},
@@ -23,6 +27,10 @@ pub struct Cluster {
     coin_source_key_pair: KeyPair<Ed25519PrivateKey, Ed25519PublicKey>,
     pub coin_source_is_root: bool,
     pub chain_id: ChainId,
+    // set if the targets were discovered from the validator set
+    pub endpoint_discovery: Option<EndpointDiscovery>,
+    // set if the cluster is a local testnet, kept running with the cluster
+    pub local_testnet: Option<LocalTestnet>,
 }
 
 fn clone(key: &Ed25519PrivateKey) -> Ed25519PrivateKey {
@@ -38,6 +46,7 @@ impl Cluster {
         coin_source_key: Ed25519PrivateKey,
         coin_source_is_root: bool,
         chain_id: ChainId,
+        http_client_args: &HttpClientArgs,
     ) -> Result<Self> {
         let num_peers = peers.len();
 
@@ -54,7 +63,8 @@ impl Cluster {
                 ), /* short_hash */
                 url.clone(),
                 None,
-            );
+            )
+            .with_http_client_args(http_client_args.clone());
             futures.push(async move {
                 let result = instance.rest_client().get_ledger_information().await;
                 (instance, result)
@@ -133,12 +143,26 @@ impl Cluster {
             coin_source_key_pair: KeyPair::from(coin_source_key),
             coin_source_is_root,
             chain_id,
+            endpoint_discovery: None,
+            local_testnet: None,
         })
     }
 
     pub async fn try_from_cluster_args(args: &ClusterArgs) -> Result<Self> {
+        if args.local_testnet {
+            let local_testnet =
+                LocalTestnet::start_or_attach(args.local_testnet_dir.clone()).await?;
+            let mut cluster = local_testnet.cluster(&args.http_client_args).await?;
+            cluster.local_testnet = Some(local_testnet);
+            return Ok(cluster);
+        }
+        let endpoint_discovery = args.get_endpoint_discovery();
+        let targets = match &endpoint_discovery {
+            Some(endpoint_discovery) => endpoint_discovery.discover().await?,
+            None => args.get_targets()?,
+        };
         let mut urls = Vec::new();
-        for url in &args.get_targets()? {
+        for url in &targets {
             if !url.has_host() {
                 bail!("No host found in URL: {}", url);
             }
@@ -152,9 +176,16 @@ impl Cluster {
 
         let (coin_source_key, is_root) = args.coin_source_args.get_private_key()?;
 
-        let cluster = Cluster::from_host_port(urls, coin_source_key, is_root, args.chain_id)
-            .await
-            .map_err(|e| format_err!("failed to create a cluster from host and port: {:?}", e))?;
+        let mut cluster = Cluster::from_host_port(
+            urls,
+            coin_source_key,
+            is_root,
+            args.chain_id,
+            &args.http_client_args,
+        )
+        .await
+        .map_err(|e| format_err!("failed to create a cluster from host and port: {:?}", e))?;
+        cluster.endpoint_discovery = endpoint_discovery;
 
         Ok(cluster)
     }
@@ -1,12 +1,25 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{EmitJobRequest, EmitModeParams};
+use crate::{
+    emitter::{
+        faucet_funding::{FaucetFunder, FaucetFunding},
+        funding_tree::FundingLevel,
+        run_state::{RunPhase, RunState},
+        signature_scheme::SignatureScheme,
+    },
+    EmitJobRequest, EmitModeParams,
+};
 use anyhow::{anyhow, bail, format_err, Context, Result};
 use aptos::common::{types::EncodingType, utils::prompt_yes};
-use aptos_crypto::ed25519::{Ed25519PrivateKey, Ed25519PublicKey};
+use aptos_crypto::{
+    ed25519::{Ed25519PrivateKey, Ed25519PublicKey},
+    HashValue,
+};
 use aptos_logger::{error, info};
+use aptos_rest_client::{error::RestError, Client as RestClient};
 use aptos_sdk::{
+    move_types::account_address::AccountAddress,
     transaction_builder::{aptos_stdlib, TransactionFactory},
     types::{
         transaction::{
@@ -23,6 +36,7 @@ use core::{
 };
 use futures::StreamExt;
 use rand::{rngs::StdRng, SeedableRng};
+use reqwest::StatusCode;
 use std::{path::Path, time::Instant};
 
 #[derive(Debug)]
@@ -55,66 +69,86 @@ impl<'t> AccountMinter<'t> {
     /// example:
     /// requested totally 100 new accounts with 10 endpoints
     /// will create 10 seed accounts, each seed account create 10 new accounts
+    /// If run_state is given, progress is persisted to it, and seed accounts and
+    /// accounts already in it are used instead of being created again.
     pub async fn create_accounts(
         &mut self,
         txn_executor: &dyn TransactionExecutor,
         req: &EmitJobRequest,
         mode_params: &EmitModeParams,
         total_requested_accounts: usize,
+        run_state: Option<&RunState>,
     ) -> Result<Vec<LocalAccount>> {
         let mut accounts = vec![];
-        let expected_num_seed_accounts = (total_requested_accounts / 50)
+        let mut resumed_seed_accounts = None;
+        if let Some(run_state) = run_state {
+            accounts = run_state.load_accounts(txn_executor).await?;
+            if accounts.len() >= total_requested_accounts {
+                info!(
+                    "Resuming run with {} previously created accounts",
+                    accounts.len()
+                );
+                accounts.truncate(total_requested_accounts);
+                return Ok(accounts);
+            }
+            if run_state.phase() >= RunPhase::CreatingAccounts {
+                let seed_accounts = run_state.load_seed_accounts(txn_executor).await?;
+                info!(
+                    "Resuming run with {} previously funded seed accounts, and {} previously created accounts",
+                    seed_accounts.len(),
+                    accounts.len()
+                );
+                resumed_seed_accounts = Some(seed_accounts);
+            }
+        }
+        let default_num_seed_accounts = (total_requested_accounts / 50)
             .clamp(1, (total_requested_accounts as f32).sqrt() as usize + 1);
         let num_accounts = total_requested_accounts - accounts.len(); // Only minting extra accounts
+                                                                      // for the workload with the highest gas price, and the one with the highest fee
+        let (max_gas_price, max_txn_fee) = req.max_workload_gas();
         let coins_per_account = (req.expected_max_txns / total_requested_accounts as u64)
-            .checked_mul(SEND_AMOUNT + req.expected_gas_per_txn * req.gas_price)
+            .checked_mul(SEND_AMOUNT + req.expected_gas_per_txn * max_gas_price)
             .unwrap()
-            .checked_add(req.max_gas_per_txn * req.gas_price)
+            .checked_add(max_txn_fee)
             .unwrap(); // extra coins for secure to pay none zero gas price
         let txn_factory = self.txn_factory.clone();
-        let expected_children_per_seed_account =
-            (num_accounts + expected_num_seed_accounts - 1) / expected_num_seed_accounts;
+        let fee_per_transfer = req.max_gas_per_txn * req.gas_price * req.init_gas_price_multiplier;
+        let plan = req.funding_tree.plan(
+            num_accounts,
+            default_num_seed_accounts,
+            coins_per_account,
+            fee_per_transfer,
+        )?;
+        let coins_per_account = plan.levels.last().unwrap().coins_per_account;
         info!(
             "Account creation plan created for {} accounts with {} balance each.",
             num_accounts, coins_per_account
         );
         info!(
             "    because of expecting {} txns and {} gas at {} gas price for each ",
-            req.expected_max_txns, req.expected_gas_per_txn, req.gas_price,
-        );
-        let coins_per_seed_account = (expected_children_per_seed_account as u64)
-            .checked_mul(
-                coins_per_account
-                    + req.max_gas_per_txn * req.gas_price * req.init_gas_price_multiplier,
-            )
-            .unwrap_or_else(|| {
-                panic!(
-                    "coins_per_seed_account exceeds u64: {} * ({} + {} * {} * {})",
-                    expected_children_per_seed_account,
-                    coins_per_account,
-                    req.max_gas_per_txn,
-                    req.gas_price,
-                    req.init_gas_price_multiplier
-                )
-            })
-            .checked_add(req.max_gas_per_txn * req.gas_price * req.init_gas_price_multiplier)
-            .unwrap();
-        info!(
-            "    through {} seed accounts with {} each, each to fund {} accounts",
-            expected_num_seed_accounts, coins_per_seed_account, expected_children_per_seed_account,
+            req.expected_max_txns, req.expected_gas_per_txn, max_gas_price,
         );
-        let coins_for_source = coins_per_seed_account
-            .checked_mul(expected_num_seed_accounts as u64)
-            .unwrap_or_else(|| {
-                panic!(
-                    "coins_for_source exceeds u64: {} * {}",
-                    coins_per_seed_account, expected_num_seed_accounts
-                )
-            })
-            .checked_add(req.max_gas_per_txn * req.gas_price * req.init_gas_price_multiplier)
-            .unwrap();
-
-        if req.mint_to_root {
+        for level in 0..plan.levels.len() - 1 {
+            info!(
+                "    through {} {} accounts with {} each, each to fund {} accounts",
+                plan.levels[level].num_accounts,
+                if level + 2 == plan.levels.len() {
+                    "seed"
+                } else {
+                    "intermediate"
+                },
+                plan.levels[level].coins_per_account,
+                plan.children_per_account(level),
+            );
+        }
+        let top_level = plan.levels[0].clone();
+        let coins_for_source = plan.coins_for_source(fee_per_transfer)?;
+
+        if resumed_seed_accounts.is_some() {
+            info!("Seed accounts are already funded, not funding them from source account");
+        } else if req.faucet_funding.is_some() {
+            info!("Seed accounts are funded through faucet, not from source account");
+        } else if req.mint_to_root {
             self.mint_to_root(txn_executor, coins_for_source).await?;
         } else {
             let balance = txn_executor
@@ -136,7 +170,7 @@ impl<'t> AccountMinter<'t> {
                 }
             } else {
                 let max_allowed = (2 * req.expected_max_txns as u128)
-                    .checked_mul((req.expected_gas_per_txn * req.gas_price).into())
+                    .checked_mul((req.expected_gas_per_txn * max_gas_price).into())
                     .unwrap();
                 assert!(coins_for_source as u128 <= max_allowed,
                     "Estimated total coins needed for load test ({}) are larger than expected_max_txns * expected_gas_per_txn, multiplied by 2 to account for rounding up ({})",
@@ -155,7 +189,10 @@ impl<'t> AccountMinter<'t> {
             }
         }
 
-        let new_source_account = if !req.coordination_delay_between_instances.is_zero() {
+        let new_source_account = if resumed_seed_accounts.is_none()
+            && req.faucet_funding.is_none()
+            && !req.coordination_delay_between_instances.is_zero()
+        {
             Some(
                 self.create_new_source_account(txn_executor, coins_for_source)
                     .await?,
@@ -167,19 +204,56 @@ impl<'t> AccountMinter<'t> {
         let start = Instant::now();
 
         let request_counters = txn_executor.create_counter_state();
+        let derived_accounts_client = req
+            .derive_accounts_from_source
+            .then(|| &req.rest_clients[0]);
 
         // Create seed accounts with which we can create actual accounts concurrently. Adding
         // additional fund for paying gas fees later.
-        let seed_accounts = self
-            .create_and_fund_seed_accounts(
-                new_source_account,
-                txn_executor,
-                expected_num_seed_accounts,
-                coins_per_seed_account,
-                mode_params.max_submit_batch_size,
-                &request_counters,
-            )
-            .await?;
+        let seed_accounts = if let Some(seed_accounts) = resumed_seed_accounts {
+            seed_accounts
+        } else {
+            let mut seed_accounts = match &req.faucet_funding {
+                Some(faucet_funding) => {
+                    self.fund_seed_accounts_through_faucet(
+                        faucet_funding,
+                        req,
+                        top_level.num_accounts,
+                        top_level.coins_per_account,
+                    )
+                    .await?
+                },
+                None => {
+                    self.create_and_fund_seed_accounts(
+                        new_source_account,
+                        txn_executor,
+                        top_level.num_accounts,
+                        top_level.coins_per_account,
+                        mode_params.max_submit_batch_size,
+                        &request_counters,
+                        derived_accounts_client,
+                    )
+                    .await?
+                },
+            };
+            // each level of intermediate accounts funds the next one, down to seed accounts
+            for level in &plan.levels[1..plan.levels.len() - 1] {
+                seed_accounts = self
+                    .fund_next_level(
+                        seed_accounts,
+                        level,
+                        mode_params.max_submit_batch_size,
+                        txn_executor,
+                        &request_counters,
+                        derived_accounts_client,
+                    )
+                    .await?;
+            }
+            if let Some(run_state) = run_state {
+                run_state.record_seed_accounts(&seed_accounts)?;
+            }
+            seed_accounts
+        };
         let actual_num_seed_accounts = seed_accounts.len();
         let num_new_child_accounts =
             (num_accounts + actual_num_seed_accounts - 1) / actual_num_seed_accounts;
@@ -187,7 +261,7 @@ impl<'t> AccountMinter<'t> {
             "Completed creating {} seed accounts in {}s, each with {} coins, request stats: {}",
             seed_accounts.len(),
             start.elapsed().as_secs(),
-            coins_per_seed_account,
+            plan.levels[plan.levels.len() - 2].coins_per_account,
             request_counters.show_simple(),
         );
         info!(
@@ -195,6 +269,13 @@ impl<'t> AccountMinter<'t> {
             num_accounts, coins_per_account
         );
 
+        if let Some(account_pool_seed) = req.account_pool_seed {
+            info!(
+                "Deriving accounts from pool seed {}, with indices below {}",
+                account_pool_seed,
+                actual_num_seed_accounts * num_new_child_accounts
+            );
+        }
         let seed_rngs = gen_rng_for_reusable_account(actual_num_seed_accounts);
         let start = Instant::now();
         let request_counters = txn_executor.create_counter_state();
@@ -213,12 +294,19 @@ impl<'t> AccountMinter<'t> {
                     txn_executor,
                     &txn_factory,
                     req.reuse_accounts,
+                    req.account_signature_scheme,
                     if req.reuse_accounts {
                         seed_rngs[i].clone()
                     } else {
                         StdRng::from_rng(self.rng()).unwrap()
                     },
                     &request_counters,
+                    run_state,
+                    // accounts of the pool may exist from earlier runs too
+                    derived_accounts_client
+                        .or_else(|| req.account_pool_seed.map(|_| &req.rest_clients[0])),
+                    req.account_pool_seed
+                        .map(|seed| (seed, (i * num_new_child_accounts) as u64)),
                 )
             });
 
@@ -275,6 +363,7 @@ impl<'t> AccountMinter<'t> {
         coins_per_seed_account: u64,
         max_submit_batch_size: usize,
         counters: &CounterState,
+        derived_accounts_client: Option<&RestClient>,
     ) -> Result<Vec<LocalAccount>> {
         info!("Creating and funding seeds accounts");
         let mut i = 0;
@@ -282,26 +371,36 @@ impl<'t> AccountMinter<'t> {
         while i < seed_account_num {
             let batch_size = min(max_submit_batch_size, seed_account_num - i);
             let mut rng = StdRng::from_rng(self.rng()).unwrap();
-            let mut batch = gen_random_accounts(batch_size, &mut rng);
+            let mut batch = gen_random_accounts(SignatureScheme::Ed25519, batch_size, &mut rng);
+            let top_ups = match derived_accounts_client {
+                Some(client) => {
+                    sync_derived_accounts(client, &mut batch, coins_per_seed_account).await?
+                },
+                None => vec![coins_per_seed_account; batch.len()],
+            };
             let txn_factory = &self.txn_factory;
             let create_requests: Vec<_> = batch
                 .iter()
-                .map(|account| {
+                .zip(top_ups)
+                .filter(|(_, top_up)| *top_up > 0)
+                .map(|(account, top_up)| {
                     create_and_fund_account_request(
                         if let Some(account) = &mut new_source_account {
                             account
                         } else {
                             self.source_account
                         },
-                        coins_per_seed_account,
+                        top_up,
                         account.public_key(),
                         txn_factory,
                     )
                 })
                 .collect();
-            txn_executor
-                .execute_transactions_with_counter(&create_requests, counters)
-                .await?;
+            if !create_requests.is_empty() {
+                txn_executor
+                    .execute_transactions_with_counter(&create_requests, counters)
+                    .await?;
+            }
 
             i += batch_size;
             seed_accounts.append(&mut batch);
@@ -310,6 +409,65 @@ impl<'t> AccountMinter<'t> {
         Ok(seed_accounts)
     }
 
+    /// Creates the accounts of the next level of the funding tree, each of the parents
+    /// funding its share of them.
+    async fn fund_next_level(
+        &mut self,
+        parents: Vec<LocalAccount>,
+        level: &FundingLevel,
+        max_submit_batch_size: usize,
+        txn_executor: &dyn TransactionExecutor,
+        counters: &CounterState,
+        derived_accounts_client: Option<&RestClient>,
+    ) -> Result<Vec<LocalAccount>> {
+        let children_per_parent = (level.num_accounts + parents.len() - 1) / parents.len();
+        let txn_factory = self.txn_factory.clone();
+        let level_futures = parents.into_iter().map(|parent| {
+            create_and_fund_new_accounts(
+                parent,
+                children_per_parent,
+                level.coins_per_account,
+                max_submit_batch_size,
+                txn_executor,
+                &txn_factory,
+                false,
+                SignatureScheme::Ed25519,
+                StdRng::from_rng(self.rng()).unwrap(),
+                counters,
+                None,
+                derived_accounts_client,
+                None,
+            )
+        });
+        Ok(futures::stream::iter(level_futures)
+            .buffer_unordered(CREATION_PARALLELISM)
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<Result<Vec<_>>>()
+            .map_err(|e| format_err!("Failed to create intermediate accounts: {:?}", e))?
+            .into_iter()
+            .flatten()
+            .collect())
+    }
+
+    /// Creates and funds seed accounts through the faucet, with as many of them as
+    /// the faucet funded.
+    pub async fn fund_seed_accounts_through_faucet(
+        &mut self,
+        faucet_funding: &FaucetFunding,
+        req: &EmitJobRequest,
+        seed_account_num: usize,
+        coins_per_seed_account: u64,
+    ) -> Result<Vec<LocalAccount>> {
+        let mut rng = StdRng::from_rng(self.rng()).unwrap();
+        let seed_accounts =
+            gen_random_accounts(SignatureScheme::Ed25519, seed_account_num, &mut rng);
+        FaucetFunder::new(faucet_funding.clone(), req.rest_clients[0].clone())
+            .fund_accounts(seed_accounts, coins_per_seed_account)
+            .await
+    }
+
     pub async fn load_vasp_account(
         &self,
         txn_executor: &dyn TransactionExecutor,
@@ -399,8 +557,13 @@ async fn create_and_fund_new_accounts<R>(
     txn_executor: &dyn TransactionExecutor,
     txn_factory: &TransactionFactory,
     reuse_account: bool,
+    signature_scheme: SignatureScheme,
     mut rng: R,
     counters: &CounterState,
+    run_state: Option<&RunState>,
+    derived_accounts_client: Option<&RestClient>,
+    // pool seed, and pool index of the first of the accounts, if derived from it
+    account_pool_seed: Option<(u64, u64)>,
 ) -> Result<Vec<LocalAccount>>
 where
     R: ::rand_core::RngCore + ::rand_core::CryptoRng,
@@ -414,24 +577,43 @@ where
             info!("Loading {} accounts if they exist", batch_size);
             gen_reusable_accounts(txn_executor, batch_size, &mut rng).await?
         } else {
-            let batch = gen_random_accounts(batch_size, &mut rng);
+            let mut batch = match account_pool_seed {
+                Some((seed, first_index)) => (0..batch_size)
+                    .map(|offset| derive_pool_account(seed, first_index + (i + offset) as u64))
+                    .collect(),
+                None => gen_random_accounts(signature_scheme, batch_size, &mut rng),
+            };
+            let top_ups = match derived_accounts_client {
+                Some(client) => {
+                    sync_derived_accounts(client, &mut batch, coins_per_new_account).await?
+                },
+                None => vec![coins_per_new_account; batch.len()],
+            };
             let creation_requests: Vec<_> = batch
-                .as_slice()
                 .iter()
-                .map(|account| {
-                    create_and_fund_account_request(
+                .zip(top_ups)
+                .filter(|(_, top_up)| *top_up > 0)
+                .map(|(account, top_up)| {
+                    fund_account_request(
                         &mut source_account,
-                        coins_per_new_account,
-                        account.public_key(),
+                        top_up,
+                        account.address(),
                         txn_factory,
                     )
                 })
                 .collect();
 
-            txn_executor
-                .execute_transactions_with_counter(&creation_requests, counters)
-                .await
-                .with_context(|| format!("Account {} couldn't mint", source_account.address()))?;
+            if !creation_requests.is_empty() {
+                txn_executor
+                    .execute_transactions_with_counter(&creation_requests, counters)
+                    .await
+                    .with_context(|| {
+                        format!("Account {} couldn't mint", source_account.address())
+                    })?;
+            }
+            if let Some(run_state) = run_state {
+                run_state.record_accounts(&batch)?;
+            }
 
             batch
         };
@@ -472,12 +654,82 @@ where
     Ok(LocalAccount::new(address, account_key, sequence_number))
 }
 
-fn gen_random_accounts<R>(num_accounts: usize, rng: &mut R) -> Vec<LocalAccount>
+/// Accounts derived from the source account are the same in every run, and may have been
+/// created by a previous one. Syncs their sequence numbers, and returns the amount each
+/// of them needs to be topped up with to have coins_per_account.
+async fn sync_derived_accounts(
+    client: &RestClient,
+    accounts: &mut [LocalAccount],
+    coins_per_account: u64,
+) -> Result<Vec<u64>> {
+    let account_futures = accounts.iter_mut().map(|account| async move {
+        let (sequence_number, balance) = query_derived_account(client, account.address())
+            .await
+            .with_context(|| format!("Failed to sync derived account {}", account.address()))?;
+        *account.sequence_number_mut() = sequence_number;
+        Ok(coins_per_account.saturating_sub(balance))
+    });
+    futures::stream::iter(account_futures)
+        .buffered(SYNC_PARALLELISM)
+        .collect::<Vec<Result<_>>>()
+        .await
+        .into_iter()
+        .collect()
+}
+
+// per seed account, many of which are syncing their accounts concurrently
+const SYNC_PARALLELISM: usize = 10;
+
+/// Sequence number and balance of the account, both 0 if it doesn't exist yet.
+async fn query_derived_account(client: &RestClient, address: AccountAddress) -> Result<(u64, u64)> {
+    match client.get_account_bcs(address).await {
+        Ok(account) => {
+            let balance = client
+                .get_account_balance(address)
+                .await?
+                .into_inner()
+                .get();
+            Ok((account.into_inner().sequence_number(), balance))
+        },
+        Err(RestError::Api(response)) if response.status_code == StatusCode::NOT_FOUND => {
+            Ok((0, 0))
+        },
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Seed of the account minter, derived from the private key of the source account, so
+/// that the same accounts are created in every run funded by it, and only by its owner.
+pub fn derived_minter_seed(source_account: &LocalAccount) -> [u8; 32] {
+    let mut preimage = b"aptos-emitter-derived-accounts".to_vec();
+    preimage.extend(source_account.private_key().to_bytes());
+    *HashValue::sha3_256_of(&preimage).as_ref()
+}
+
+/// Account of the given index in the pool derived from pool_seed, the same on any
+/// machine and in any run, so the pool can be reconstructed from the seed alone.
+/// Its sequence number is 0 until synced with the chain.
+pub fn derive_pool_account(pool_seed: u64, index: u64) -> LocalAccount {
+    let mut preimage = b"aptos-emitter-pool-accounts".to_vec();
+    preimage.extend(pool_seed.to_le_bytes());
+    preimage.extend(index.to_le_bytes());
+    let private_key =
+        Ed25519PrivateKey::try_from(HashValue::sha3_256_of(&preimage).as_ref()).unwrap();
+    let account_key = AccountKey::from_private_key(private_key);
+    let address = account_key.authentication_key().derived_address();
+    LocalAccount::new(address, account_key, 0)
+}
+
+fn gen_random_accounts<R>(
+    signature_scheme: SignatureScheme,
+    num_accounts: usize,
+    rng: &mut R,
+) -> Vec<LocalAccount>
 where
     R: ::rand_core::RngCore + ::rand_core::CryptoRng,
 {
     (0..num_accounts)
-        .map(|_| LocalAccount::generate(rng))
+        .map(|_| signature_scheme.generate_account(rng))
         .collect()
 }
 
@@ -489,9 +741,25 @@ pub fn create_and_fund_account_request(
 ) -> SignedTransaction {
     let preimage = AuthenticationKeyPreimage::ed25519(pubkey);
     let auth_key = AuthenticationKey::from_preimage(&preimage);
-    creation_account.sign_with_transaction_builder(txn_factory.payload(
-        aptos_stdlib::aptos_account_transfer(auth_key.derived_address(), amount),
-    ))
+    fund_account_request(
+        creation_account,
+        amount,
+        auth_key.derived_address(),
+        txn_factory,
+    )
+}
+
+/// Transfer creates the account if it doesn't exist, with its address as the
+/// authentication key, whatever the signature scheme it was derived with.
+pub fn fund_account_request(
+    creation_account: &mut LocalAccount,
+    amount: u64,
+    address: AccountAddress,
+    txn_factory: &TransactionFactory,
+) -> SignedTransaction {
+    creation_account.sign_with_transaction_builder(
+        txn_factory.payload(aptos_stdlib::aptos_account_transfer(address, amount)),
+    )
 }
 
 const CREATION_PARALLELISM: usize = 500;
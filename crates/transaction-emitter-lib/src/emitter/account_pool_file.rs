@@ -0,0 +1,172 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::{ensure, format_err, Context, Result};
+use aptos_crypto::{ed25519::Ed25519PrivateKey, ValidCryptoMaterialStringExt};
+use aptos_sdk::{
+    move_types::account_address::AccountAddress,
+    types::{AccountKey, LocalAccount},
+};
+use ring::{
+    aead::{self, Aad, LessSafeKey, Nonce, UnboundKey, NONCE_LEN},
+    pbkdf2,
+    rand::{SecureRandom, SystemRandom},
+};
+use serde::{Deserialize, Serialize};
+use std::{fmt, fs, num::NonZeroU32, path::PathBuf};
+
+const SALT_LEN: usize = 16;
+const PBKDF2_ITERATIONS: u32 = 100_000;
+
+#[derive(Deserialize, Serialize)]
+struct PersistedAccount {
+    address: AccountAddress,
+    private_key: String,
+    sequence_number: u64,
+}
+
+/// Funded accounts, saved at the end of a run, and loaded at the start of the next one,
+/// so that repeated runs against the same network don't need to fund accounts again.
+/// The file is encrypted with AES-256-GCM, with the key derived from password.
+#[derive(Clone)]
+pub struct AccountPoolFile {
+    path: PathBuf,
+    password: String,
+}
+
+impl fmt::Debug for AccountPoolFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AccountPoolFile")
+            .field("path", &self.path)
+            .finish_non_exhaustive()
+    }
+}
+
+impl AccountPoolFile {
+    pub fn new(path: PathBuf, password: String) -> Self {
+        Self { path, password }
+    }
+
+    /// Returns no accounts if the file doesn't exist yet.
+    pub fn load(&self) -> Result<Vec<LocalAccount>> {
+        if !self.path.exists() {
+            return Ok(vec![]);
+        }
+        let data = fs::read(&self.path)
+            .with_context(|| format!("Failed to read account pool {}", self.path.display()))?;
+        let persisted: Vec<PersistedAccount> =
+            serde_json::from_slice(&decrypt(&self.password, &data)?)?;
+        persisted
+            .into_iter()
+            .map(|account| {
+                let account_key = AccountKey::from_private_key(
+                    Ed25519PrivateKey::from_encoded_string(&account.private_key)?,
+                );
+                ensure!(
+                    account_key.authentication_key().derived_address() == account.address,
+                    "Private key of account {} doesn't match its address",
+                    account.address
+                );
+                Ok(LocalAccount::new(
+                    account.address,
+                    account_key,
+                    account.sequence_number,
+                ))
+            })
+            .collect()
+    }
+
+    pub fn save(&self, accounts: &[LocalAccount]) -> Result<()> {
+        let persisted = accounts
+            .iter()
+            .map(|account| {
+                Ok(PersistedAccount {
+                    address: account.address(),
+                    private_key: account.private_key().to_encoded_string()?,
+                    sequence_number: account.sequence_number(),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let data = encrypt(&self.password, &serde_json::to_vec(&persisted)?)?;
+        fs::write(&self.path, data)
+            .with_context(|| format!("Failed to write account pool {}", self.path.display()))
+    }
+}
+
+fn derive_key(password: &str, salt: &[u8]) -> LessSafeKey {
+    let mut key = [0u8; 32];
+    pbkdf2::derive(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        NonZeroU32::new(PBKDF2_ITERATIONS).unwrap(),
+        salt,
+        password.as_bytes(),
+        &mut key,
+    );
+    LessSafeKey::new(UnboundKey::new(&aead::AES_256_GCM, &key).unwrap())
+}
+
+/// Returns salt, followed by nonce and ciphertext (with the tag).
+fn encrypt(password: &str, plaintext: &[u8]) -> Result<Vec<u8>> {
+    let rng = SystemRandom::new();
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    rng.fill(&mut salt)
+        .and_then(|_| rng.fill(&mut nonce))
+        .map_err(|_| format_err!("Failed to generate salt and nonce"))?;
+    let mut in_out = plaintext.to_vec();
+    derive_key(password, &salt)
+        .seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::empty(),
+            &mut in_out,
+        )
+        .map_err(|_| format_err!("Failed to encrypt account pool"))?;
+    Ok([&salt[..], &nonce[..], &in_out[..]].concat())
+}
+
+fn decrypt(password: &str, data: &[u8]) -> Result<Vec<u8>> {
+    ensure!(
+        data.len() >= SALT_LEN + NONCE_LEN,
+        "Account pool file is too short"
+    );
+    let (salt, rest) = data.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    let mut in_out = ciphertext.to_vec();
+    let plaintext = derive_key(password, salt)
+        .open_in_place(
+            Nonce::try_assume_unique_for_key(nonce).unwrap(),
+            Aad::empty(),
+            &mut in_out,
+        )
+        .map_err(|_| format_err!("Failed to decrypt account pool, wrong password?"))?;
+    Ok(plaintext.to_vec())
+}
+
+#[cfg(test)]
+mod test {
+    use super::AccountPoolFile;
+    use aptos_sdk::types::LocalAccount;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    pub fn test_account_pool_file() {
+        let path = std::env::temp_dir().join(format!("account_pool_{}.bin", std::process::id()));
+        let mut rng = StdRng::from_seed([0; 32]);
+        let mut accounts: Vec<_> = (0..3).map(|_| LocalAccount::generate(&mut rng)).collect();
+        *accounts[2].sequence_number_mut() = 7;
+
+        let pool_file = AccountPoolFile::new(path.clone(), "password".to_string());
+        assert!(pool_file.load().unwrap().is_empty());
+        pool_file.save(&accounts).unwrap();
+
+        let loaded = pool_file.load().unwrap();
+        assert_eq!(loaded.len(), 3);
+        assert_eq!(loaded[2].address(), accounts[2].address());
+        assert_eq!(loaded[2].sequence_number(), 7);
+
+        assert!(AccountPoolFile::new(path.clone(), "wrong".to_string())
+            .load()
+            .is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,147 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{counters::EMITTER_THROTTLED_WORKERS, stats::DynamicStatsTracking};
+use aptos_logger::info;
+use std::{
+    cmp::{max, min},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::task::JoinHandle;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BacklogTarget {
+    /// Transactions in flight (submitted, and not yet committed or expired) to keep under
+    pub max_backlog: u64,
+    /// How often the backlog is sampled, and emission adjusted
+    pub sample_interval: Duration,
+}
+
+/// Reduces the number of emitting workers while the backlog of in-flight transactions
+/// is over target, so that the emitter doesn't just fill up mempools, decreasing them
+/// multiplicatively while over target, and increasing them back additively once the
+/// backlog has drained to under half of it.
+#[derive(Debug)]
+pub struct BacklogController {
+    target: BacklogTarget,
+    num_workers: usize,
+    allowed_workers: usize,
+}
+
+impl BacklogController {
+    pub fn new(target: BacklogTarget, num_workers: usize) -> Self {
+        Self {
+            target,
+            num_workers,
+            allowed_workers: num_workers,
+        }
+    }
+
+    /// Adjusts the number of allowed workers based on the sampled backlog, returning it.
+    pub fn on_sample(&mut self, backlog: u64) -> usize {
+        if backlog > self.target.max_backlog {
+            self.allowed_workers = max(1, self.allowed_workers * 3 / 4);
+        } else if backlog <= self.target.max_backlog / 2 {
+            self.allowed_workers = min(
+                self.num_workers,
+                self.allowed_workers + max(1, self.num_workers / 20),
+            );
+        }
+        self.allowed_workers
+    }
+}
+
+/// Throttles emission based on the backlog in the background, for the duration of the job.
+#[derive(Debug)]
+pub struct BacklogThrottle {
+    allowed_workers: Arc<AtomicUsize>,
+    throttle_task: JoinHandle<()>,
+}
+
+impl BacklogThrottle {
+    pub fn start(
+        target: BacklogTarget,
+        stats: Arc<DynamicStatsTracking>,
+        num_workers: usize,
+    ) -> Self {
+        let allowed_workers = Arc::new(AtomicUsize::new(num_workers));
+        EMITTER_THROTTLED_WORKERS.set(num_workers as i64);
+        let throttle_task = tokio::spawn(throttle_periodically(
+            BacklogController::new(target, num_workers),
+            stats,
+            allowed_workers.clone(),
+        ));
+        Self {
+            allowed_workers,
+            throttle_task,
+        }
+    }
+
+    /// Handle to the number of workers allowed to emit, workers with a lower index emitting.
+    pub fn allowed_workers(&self) -> Arc<AtomicUsize> {
+        self.allowed_workers.clone()
+    }
+}
+
+impl Drop for BacklogThrottle {
+    fn drop(&mut self) {
+        self.throttle_task.abort();
+    }
+}
+
+async fn throttle_periodically(
+    mut controller: BacklogController,
+    stats: Arc<DynamicStatsTracking>,
+    allowed_workers: Arc<AtomicUsize>,
+) {
+    loop {
+        tokio::time::sleep(controller.target.sample_interval).await;
+        let total = stats.accumulate_total(Duration::ZERO);
+        let backlog = total
+            .submitted
+            .saturating_sub(total.committed + total.expired);
+        let allowed = controller.on_sample(backlog);
+        let prev_allowed = allowed_workers.swap(allowed, Ordering::Relaxed);
+        if allowed != prev_allowed {
+            info!(
+                "Backlog of {} in-flight transactions, {} of {} workers emitting",
+                backlog, allowed, controller.num_workers
+            );
+            EMITTER_THROTTLED_WORKERS.set(allowed as i64);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BacklogController, BacklogTarget};
+    use std::time::Duration;
+
+    #[test]
+    pub fn test_backlog_controller() {
+        let mut controller = BacklogController::new(
+            BacklogTarget {
+                max_backlog: 10_000,
+                sample_interval: Duration::from_secs(5),
+            },
+            100,
+        );
+        assert_eq!(controller.on_sample(20_000), 75);
+        assert_eq!(controller.on_sample(12_000), 56);
+        // between half and the max, keeps the workers
+        assert_eq!(controller.on_sample(8_000), 56);
+        assert_eq!(controller.on_sample(1_000), 61);
+        for _ in 0..10 {
+            controller.on_sample(0);
+        }
+        assert_eq!(controller.on_sample(0), 100);
+        for _ in 0..100 {
+            controller.on_sample(u64::MAX);
+        }
+        assert_eq!(controller.on_sample(u64::MAX), 1);
+    }
+}
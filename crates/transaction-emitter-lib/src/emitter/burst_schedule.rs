@@ -0,0 +1,91 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use std::time::Duration;
+
+/// Alternates between baseline_tps and burst_tps, bursting for burst_duration
+/// at the end of every burst_interval (e.g. at 10x TPS for 5s every minute).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BurstSchedule {
+    pub baseline_tps: usize,
+    pub burst_tps: usize,
+    pub burst_duration: Duration,
+    pub burst_interval: Duration,
+}
+
+impl BurstSchedule {
+    pub fn new(
+        baseline_tps: usize,
+        burst_tps: usize,
+        burst_duration: Duration,
+        burst_interval: Duration,
+    ) -> Self {
+        assert!(
+            burst_tps >= baseline_tps,
+            "Burst TPS needs to be at least baseline TPS"
+        );
+        assert!(
+            !burst_duration.is_zero() && burst_duration < burst_interval,
+            "Burst duration needs to be positive, and shorter than burst interval"
+        );
+        Self {
+            baseline_tps,
+            burst_tps,
+            burst_duration,
+            burst_interval,
+        }
+    }
+
+    fn offset_in_interval(&self, elapsed: Duration) -> Duration {
+        Duration::from_nanos((elapsed.as_nanos() % self.burst_interval.as_nanos()) as u64)
+    }
+
+    /// Whether the given time since the start of emission falls into a burst window.
+    pub fn is_burst(&self, elapsed: Duration) -> bool {
+        self.offset_in_interval(elapsed) >= self.burst_interval - self.burst_duration
+    }
+
+    pub fn tps_at(&self, elapsed: Duration) -> usize {
+        if self.is_burst(elapsed) {
+            self.burst_tps
+        } else {
+            self.baseline_tps
+        }
+    }
+
+    /// Time from elapsed until the next switch between baseline and burst.
+    pub fn until_next_switch(&self, elapsed: Duration) -> Duration {
+        let offset = self.offset_in_interval(elapsed);
+        let burst_start = self.burst_interval - self.burst_duration;
+        if offset < burst_start {
+            burst_start - offset
+        } else {
+            self.burst_interval - offset
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::BurstSchedule;
+    use std::time::Duration;
+
+    #[test]
+    pub fn test_burst_schedule() {
+        let schedule =
+            BurstSchedule::new(100, 1000, Duration::from_secs(5), Duration::from_secs(60));
+        assert_eq!(schedule.tps_at(Duration::ZERO), 100);
+        assert_eq!(
+            schedule.until_next_switch(Duration::from_secs(10)),
+            Duration::from_secs(45)
+        );
+        assert_eq!(schedule.tps_at(Duration::from_secs(55)), 1000);
+        assert_eq!(
+            schedule.until_next_switch(Duration::from_secs(57)),
+            Duration::from_secs(3)
+        );
+        assert_eq!(schedule.tps_at(Duration::from_secs(60)), 100);
+        assert!(schedule.is_burst(Duration::from_secs(119)));
+        assert!(!schedule.is_burst(Duration::from_secs(121)));
+    }
+}
@@ -0,0 +1,148 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use aptos_infallible::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Debug)]
+pub struct CircuitBreakerConfig {
+    /// Number of consecutive failures after which the circuit opens.
+    pub failure_threshold: usize,
+    /// How long the circuit stays open, before letting a probe request through.
+    pub open_duration: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            open_duration: Duration::from_secs(30),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum BreakerState {
+    Closed { consecutive_failures: usize },
+    Open { until: Instant },
+    HalfOpen { probe_in_flight: bool },
+}
+
+/// Tracks health of a single endpoint.
+/// Closed circuit lets all requests through, open circuit none,
+/// and half-open lets through a single probe request, which then
+/// either closes (on success) or reopens (on failure) the circuit.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    state: Mutex<BreakerState>,
+}
+
+impl CircuitBreaker {
+    pub fn new(config: CircuitBreakerConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::new(BreakerState::Closed {
+                consecutive_failures: 0,
+            }),
+        }
+    }
+
+    /// Whether request can be sent through this circuit right now.
+    pub fn is_available(&self) -> bool {
+        match *self.state.lock() {
+            BreakerState::Closed { .. } => true,
+            BreakerState::Open { until } => Instant::now() >= until,
+            BreakerState::HalfOpen { probe_in_flight } => !probe_in_flight,
+        }
+    }
+
+    /// Mark that a request is going to be sent through this circuit.
+    pub fn on_selected(&self) {
+        let mut state = self.state.lock();
+        match *state {
+            BreakerState::Open { until } if Instant::now() >= until => {
+                *state = BreakerState::HalfOpen {
+                    probe_in_flight: true,
+                };
+            },
+            BreakerState::HalfOpen {
+                probe_in_flight: false,
+            } => {
+                *state = BreakerState::HalfOpen {
+                    probe_in_flight: true,
+                };
+            },
+            _ => {},
+        }
+    }
+
+    /// Returns true if the circuit was not closed before.
+    pub fn record_success(&self) -> bool {
+        let mut state = self.state.lock();
+        let was_closed = matches!(*state, BreakerState::Closed { .. });
+        *state = BreakerState::Closed {
+            consecutive_failures: 0,
+        };
+        !was_closed
+    }
+
+    /// Returns true if this failure opened the circuit.
+    pub fn record_failure(&self) -> bool {
+        let mut state = self.state.lock();
+        let open = BreakerState::Open {
+            until: Instant::now() + self.config.open_duration,
+        };
+        match *state {
+            BreakerState::Closed {
+                consecutive_failures,
+            } => {
+                if consecutive_failures + 1 >= self.config.failure_threshold {
+                    *state = open;
+                    true
+                } else {
+                    *state = BreakerState::Closed {
+                        consecutive_failures: consecutive_failures + 1,
+                    };
+                    false
+                }
+            },
+            BreakerState::HalfOpen { .. } => {
+                *state = open;
+                true
+            },
+            BreakerState::Open { .. } => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CircuitBreaker, CircuitBreakerConfig};
+    use std::time::Duration;
+
+    #[test]
+    pub fn test_circuit_breaker_transitions() {
+        let breaker = CircuitBreaker::new(CircuitBreakerConfig {
+            failure_threshold: 3,
+            open_duration: Duration::ZERO,
+        });
+        assert!(!breaker.record_failure());
+        assert!(!breaker.record_failure());
+        assert!(!breaker.record_success());
+        assert!(!breaker.record_failure());
+        assert!(!breaker.record_failure());
+        assert!(breaker.record_failure());
+
+        // open_duration passed, single probe is let through.
+        assert!(breaker.is_available());
+        breaker.on_selected();
+        assert!(!breaker.is_available());
+
+        // failed probe reopens the circuit.
+        assert!(breaker.record_failure());
+        breaker.on_selected();
+        assert!(breaker.record_success());
+        assert!(breaker.is_available());
+    }
+}
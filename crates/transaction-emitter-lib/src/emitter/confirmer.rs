@@ -0,0 +1,459 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::rate_limiter::TokenBucket;
+use anyhow::{bail, Result};
+use aptos_crypto::HashValue;
+use aptos_infallible::Mutex;
+use aptos_logger::{info, sample, sample::SampleRate, warn};
+use aptos_protos::{
+    datastream::v1::{
+        indexer_stream_client::IndexerStreamClient, raw_datastream_response::Response,
+        RawDatastreamRequest,
+    },
+    transaction::testing1::v1::Transaction as TransactionProto,
+};
+use aptos_rest_client::{aptos_api_types::AptosErrorCode, error::RestError, Client as RestClient};
+use aptos_sdk::types::transaction::SignedTransaction;
+use prost::Message;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use tokio::{sync::oneshot, task::JoinHandle};
+
+/// Resolves to whether the transaction executed successfully, once it is committed.
+/// Resolves to an error if confirmer stopped tracking the transaction, in which
+/// case commit needs to be checked some other way.
+pub type ConfirmationReceiver = oneshot::Receiver<bool>;
+
+/// Confirms commits of submitted transactions, without polling for each of them.
+pub trait TransactionConfirmer: Sync + Send {
+    /// Starts tracking the transaction with the given hash.
+    /// Needs to be called before the transaction is submitted, so that its commit is not missed.
+    /// Returns None if confirmer is currently not available.
+    fn subscribe(&self, hash: HashValue) -> Option<ConfirmationReceiver>;
+}
+
+/// Transactions waiting to be seen in a stream of committed transactions.
+/// Shared between the stream task, and the submitters.
+#[derive(Default)]
+pub struct PendingConfirmations {
+    pending: Mutex<HashMap<HashValue, oneshot::Sender<bool>>>,
+    available: AtomicBool,
+}
+
+impl PendingConfirmations {
+    pub fn subscribe(&self, hash: HashValue) -> Option<ConfirmationReceiver> {
+        if !self.available.load(Ordering::Relaxed) {
+            return None;
+        }
+        let (sender, receiver) = oneshot::channel();
+        self.pending.lock().insert(hash, sender);
+        Some(receiver)
+    }
+
+    /// Resolves subscriptions for a batch of committed transactions, given as (hash, success).
+    pub fn resolve<I>(&self, committed: I)
+    where
+        I: IntoIterator<Item = (HashValue, bool)>,
+    {
+        let mut pending = self.pending.lock();
+        for (hash, success) in committed {
+            if let Some(sender) = pending.remove(&hash) {
+                let _ = sender.send(success);
+            }
+        }
+        // submitters that stopped waiting (e.g. transaction expired)
+        pending.retain(|_, sender| !sender.is_closed());
+    }
+
+    /// When stream becomes unavailable, all pending subscriptions are dropped,
+    /// as their commit might be missed.
+    pub fn set_available(&self, available: bool) {
+        let was_available = self.available.swap(available, Ordering::Relaxed);
+        if !available {
+            self.pending.lock().clear();
+        }
+        if was_available != available {
+            info!(
+                "Transaction confirmation stream is {}",
+                if available {
+                    "available"
+                } else {
+                    "unavailable, falling back to polling"
+                }
+            );
+        }
+    }
+}
+
+// page size used for tailing the ledger
+const STREAM_PAGE_SIZE: u16 = 1000;
+const STREAM_POLL_DELAY: Duration = Duration::from_millis(100);
+const STREAM_RECONNECT_DELAY: Duration = Duration::from_secs(5);
+// consecutive failed requests, after which stream is considered unavailable
+const STREAM_MAX_FAILURES: usize = 3;
+
+/// Confirms transactions from a single stream of committed transactions, read from
+/// the node's REST API, resolving all pending hashes from it with a single request per page,
+/// instead of polling each transaction by hash.
+pub struct RestStreamConfirmer {
+    confirmations: Arc<PendingConfirmations>,
+    stream_task: JoinHandle<()>,
+}
+
+impl RestStreamConfirmer {
+    pub fn start(rest_client: RestClient) -> Self {
+        let confirmations = Arc::new(PendingConfirmations::default());
+        let stream_task = tokio::spawn(stream_committed_transactions(
+            rest_client,
+            confirmations.clone(),
+        ));
+        Self {
+            confirmations,
+            stream_task,
+        }
+    }
+}
+
+impl TransactionConfirmer for RestStreamConfirmer {
+    fn subscribe(&self, hash: HashValue) -> Option<ConfirmationReceiver> {
+        self.confirmations.subscribe(hash)
+    }
+}
+
+impl Drop for RestStreamConfirmer {
+    fn drop(&mut self) {
+        self.stream_task.abort();
+    }
+}
+
+async fn stream_committed_transactions(
+    rest_client: RestClient,
+    confirmations: Arc<PendingConfirmations>,
+) {
+    loop {
+        // (re)start the stream from the current ledger version
+        let mut next_version = match rest_client.get_ledger_information().await {
+            Ok(response) => response.into_inner().version + 1,
+            Err(err) => {
+                warn!(
+                    "[{}] Failed starting transaction confirmation stream: {}",
+                    rest_client.path_prefix_string(),
+                    err
+                );
+                confirmations.set_available(false);
+                tokio::time::sleep(STREAM_RECONNECT_DELAY).await;
+                continue;
+            },
+        };
+        confirmations.set_available(true);
+
+        let mut failures = 0;
+        while failures < STREAM_MAX_FAILURES {
+            match rest_client
+                .get_transactions_bcs(Some(next_version), Some(STREAM_PAGE_SIZE))
+                .await
+            {
+                Ok(response) => {
+                    failures = 0;
+                    let transactions = response.into_inner();
+                    if let Some(last) = transactions.last() {
+                        next_version = last.version + 1;
+                    }
+                    let caught_up = transactions.len() < STREAM_PAGE_SIZE as usize;
+                    confirmations.resolve(transactions.into_iter().map(|transaction| {
+                        (
+                            transaction.info.transaction_hash(),
+                            transaction.info.status().is_success(),
+                        )
+                    }));
+                    if caught_up {
+                        tokio::time::sleep(STREAM_POLL_DELAY).await;
+                    }
+                },
+                // next version is not yet committed
+                Err(RestError::Api(response))
+                    if matches!(response.error.error_code, AptosErrorCode::VersionNotFound) =>
+                {
+                    failures = 0;
+                    tokio::time::sleep(STREAM_POLL_DELAY).await;
+                },
+                Err(err) => {
+                    failures += 1;
+                    sample!(
+                        SampleRate::Duration(Duration::from_secs(60)),
+                        warn!(
+                            "[{}] Failed reading transaction confirmation stream: {}",
+                            rest_client.path_prefix_string(),
+                            err
+                        )
+                    );
+                    tokio::time::sleep(STREAM_POLL_DELAY).await;
+                },
+            }
+        }
+        confirmations.set_available(false);
+        tokio::time::sleep(STREAM_RECONNECT_DELAY).await;
+    }
+}
+
+/// Waits for a batch of transactions submitted to the same client, resolving them by
+/// scanning committed transactions from before their submission, with a single request
+/// per page, instead of polling for each transaction by hash.
+#[derive(Default)]
+pub struct BatchWaiter {
+    // hash -> (subscription, expiration timestamp secs)
+    pending: HashMap<HashValue, (oneshot::Sender<bool>, u64)>,
+}
+
+impl BatchWaiter {
+    pub fn subscribe(&mut self, txn: &SignedTransaction) -> ConfirmationReceiver {
+        let (sender, receiver) = oneshot::channel();
+        self.pending.insert(
+            txn.clone().committed_hash(),
+            (sender, txn.expiration_timestamp_secs()),
+        );
+        receiver
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Scans from start_version, until all transactions are committed or expired, or
+    /// timeout is reached. Unresolved subscriptions are dropped, so that commit of
+    /// those transactions is checked some other way.
+    pub async fn run(
+        mut self,
+        rest_client: &RestClient,
+        rate_limiter: Option<&TokenBucket>,
+        start_version: u64,
+        timeout: Duration,
+    ) {
+        let start = Instant::now();
+        let mut next_version = start_version;
+        let mut failures = 0;
+        while !self.pending.is_empty()
+            && failures < STREAM_MAX_FAILURES
+            && start.elapsed() < timeout
+        {
+            if let Some(rate_limiter) = rate_limiter {
+                rate_limiter.acquire().await;
+            }
+            match rest_client
+                .get_transactions_bcs(Some(next_version), Some(STREAM_PAGE_SIZE))
+                .await
+            {
+                Ok(response) => {
+                    failures = 0;
+                    let (transactions, state) = response.into_parts();
+                    if let Some(last) = transactions.last() {
+                        next_version = last.version + 1;
+                    }
+                    let caught_up = transactions.len() < STREAM_PAGE_SIZE as usize;
+                    for transaction in transactions {
+                        if let Some((sender, _)) =
+                            self.pending.remove(&transaction.info.transaction_hash())
+                        {
+                            let _ = sender.send(transaction.info.status().is_success());
+                        }
+                    }
+                    if caught_up {
+                        // whole ledger was scanned, so expired transactions are not going
+                        // to be committed, and are left for the submitter to confirm
+                        let ledger_timestamp_secs = state.timestamp_usecs / 1_000_000;
+                        self.pending
+                            .retain(|_, (sender, expiration_timestamp_secs)| {
+                                !sender.is_closed()
+                                    && *expiration_timestamp_secs > ledger_timestamp_secs
+                            });
+                        tokio::time::sleep(STREAM_POLL_DELAY).await;
+                    }
+                },
+                // next version is not yet committed
+                Err(RestError::Api(response))
+                    if matches!(response.error.error_code, AptosErrorCode::VersionNotFound) =>
+                {
+                    failures = 0;
+                    tokio::time::sleep(STREAM_POLL_DELAY).await;
+                },
+                Err(err) => {
+                    failures += 1;
+                    sample!(
+                        SampleRate::Duration(Duration::from_secs(60)),
+                        warn!(
+                            "[{}] Failed scanning for committed batch: {}",
+                            rest_client.path_prefix_string(),
+                            err
+                        )
+                    );
+                    tokio::time::sleep(STREAM_POLL_DELAY).await;
+                },
+            }
+        }
+    }
+}
+
+// same headers as used by indexer grpc data service
+const GRPC_AUTH_TOKEN_HEADER: &str = "x-aptos-data-authorization";
+const GRPC_REQUEST_NAME_HEADER: &str = "x-aptos-request-name";
+
+/// Confirms transactions from the indexer grpc raw transaction stream, so that
+/// a single stream is used per run, instead of polling for each transaction.
+pub struct GrpcStreamConfirmer {
+    confirmations: Arc<PendingConfirmations>,
+    stream_task: JoinHandle<()>,
+}
+
+impl GrpcStreamConfirmer {
+    /// Streams from the data service at grpc_address, starting with
+    /// the current ledger version of rest_client.
+    pub fn start(
+        grpc_address: String,
+        auth_token: Option<String>,
+        rest_client: RestClient,
+    ) -> Self {
+        let confirmations = Arc::new(PendingConfirmations::default());
+        let stream_task = tokio::spawn(grpc_stream_committed_transactions(
+            grpc_address,
+            auth_token,
+            rest_client,
+            confirmations.clone(),
+        ));
+        Self {
+            confirmations,
+            stream_task,
+        }
+    }
+}
+
+impl TransactionConfirmer for GrpcStreamConfirmer {
+    fn subscribe(&self, hash: HashValue) -> Option<ConfirmationReceiver> {
+        self.confirmations.subscribe(hash)
+    }
+}
+
+impl Drop for GrpcStreamConfirmer {
+    fn drop(&mut self) {
+        self.stream_task.abort();
+    }
+}
+
+async fn grpc_stream_committed_transactions(
+    grpc_address: String,
+    auth_token: Option<String>,
+    rest_client: RestClient,
+    confirmations: Arc<PendingConfirmations>,
+) {
+    loop {
+        if let Err(err) = grpc_stream_until_failure(
+            &grpc_address,
+            auth_token.as_deref(),
+            &rest_client,
+            &confirmations,
+        )
+        .await
+        {
+            warn!(
+                "[{}] Transaction confirmation stream failed: {}",
+                grpc_address, err
+            );
+        }
+        confirmations.set_available(false);
+        tokio::time::sleep(STREAM_RECONNECT_DELAY).await;
+    }
+}
+
+async fn grpc_stream_until_failure(
+    grpc_address: &str,
+    auth_token: Option<&str>,
+    rest_client: &RestClient,
+    confirmations: &PendingConfirmations,
+) -> Result<()> {
+    let starting_version = rest_client
+        .get_ledger_information()
+        .await?
+        .into_inner()
+        .version
+        + 1;
+    let mut grpc_client = IndexerStreamClient::connect(grpc_address.to_string()).await?;
+    let mut request = tonic::Request::new(RawDatastreamRequest {
+        starting_version: Some(starting_version),
+        transactions_count: None,
+    });
+    if let Some(auth_token) = auth_token {
+        request
+            .metadata_mut()
+            .insert(GRPC_AUTH_TOKEN_HEADER, auth_token.parse()?);
+    }
+    request
+        .metadata_mut()
+        .insert(GRPC_REQUEST_NAME_HEADER, "transaction-emitter".parse()?);
+    let mut stream = grpc_client.raw_datastream(request).await?.into_inner();
+    confirmations.set_available(true);
+
+    while let Some(response) = stream.message().await? {
+        // status frames only mark batch boundaries
+        if let Some(Response::Data(data)) = response.response {
+            confirmations.resolve(data.transactions.into_iter().filter_map(|output| {
+                let encoded = base64::decode(output.encoded_proto_data).ok()?;
+                let info = TransactionProto::decode(&*encoded).ok()?.info?;
+                Some((HashValue::from_slice(&info.hash).ok()?, info.success))
+            }));
+        }
+    }
+    bail!("Stream ended")
+}
+
+#[cfg(test)]
+mod test {
+    use super::PendingConfirmations;
+    use aptos_crypto::HashValue;
+    use tokio::sync::oneshot::error::TryRecvError;
+
+    #[test]
+    pub fn test_pending_confirmations() {
+        let confirmations = PendingConfirmations::default();
+        // not available until the stream starts
+        assert!(confirmations.subscribe(HashValue::random()).is_none());
+        confirmations.set_available(true);
+
+        // subscribed before submitting, so resolved by the first batch having them
+        let (succeeded, failed, pending, stopped) = (
+            HashValue::random(),
+            HashValue::random(),
+            HashValue::random(),
+            HashValue::random(),
+        );
+        let mut succeeded_receiver = confirmations.subscribe(succeeded).unwrap();
+        let mut failed_receiver = confirmations.subscribe(failed).unwrap();
+        let mut pending_receiver = confirmations.subscribe(pending).unwrap();
+        drop(confirmations.subscribe(stopped).unwrap());
+        assert_eq!(confirmations.pending.lock().len(), 4);
+
+        confirmations.resolve(vec![
+            (HashValue::random(), true),
+            (succeeded, true),
+            (failed, false),
+        ]);
+        assert_eq!(succeeded_receiver.try_recv(), Ok(true));
+        assert_eq!(failed_receiver.try_recv(), Ok(false));
+        assert_eq!(pending_receiver.try_recv(), Err(TryRecvError::Empty));
+        // the one no longer waited for is pruned
+        assert_eq!(confirmations.pending.lock().len(), 1);
+        assert!(confirmations.pending.lock().contains_key(&pending));
+
+        // commits might be missed while unavailable, so pending ones are dropped
+        confirmations.set_available(false);
+        assert_eq!(pending_receiver.try_recv(), Err(TryRecvError::Closed));
+        assert!(confirmations.pending.lock().is_empty());
+        assert!(confirmations.subscribe(HashValue::random()).is_none());
+        confirmations.resolve(vec![(pending, true)]);
+    }
+}
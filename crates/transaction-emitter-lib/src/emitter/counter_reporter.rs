@@ -0,0 +1,170 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use aptos_infallible::Mutex;
+use aptos_logger::{sample, sample::SampleRate, warn};
+use aptos_transaction_generator_lib::CounterState;
+use futures::Future;
+use std::{
+    fmt,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// Where CounterState JSON snapshots are reported to.
+#[derive(Clone)]
+pub enum CounterReportSink {
+    /// File is overwritten with the latest snapshot on each report.
+    File(PathBuf),
+    Callback(Arc<dyn Fn(&str) + Send + Sync>),
+}
+
+impl fmt::Debug for CounterReportSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CounterReportSink::File(path) => f.debug_tuple("File").field(path).finish(),
+            CounterReportSink::Callback(_) => f.write_str("Callback"),
+        }
+    }
+}
+
+/// Periodically reports CounterState as JSON, while transactions are being executed,
+/// so that long running phases can be monitored by external tooling.
+#[derive(Debug)]
+pub struct CounterReporter {
+    interval: Duration,
+    sink: CounterReportSink,
+    last_report: Mutex<Option<Instant>>,
+}
+
+impl CounterReporter {
+    pub fn new(interval: Duration, sink: CounterReportSink) -> Self {
+        Self {
+            interval,
+            sink,
+            last_report: Mutex::new(None),
+        }
+    }
+
+    /// Reports counters, unless they were already reported within the interval.
+    /// Concurrent executions sharing the same counters are reported only once.
+    pub async fn maybe_report(&self, counters: &CounterState) {
+        {
+            let mut last_report = self.last_report.lock();
+            let now = Instant::now();
+            if last_report.map_or(false, |last| now.duration_since(last) < self.interval) {
+                return;
+            }
+            *last_report = Some(now);
+        }
+        let json = match serde_json::to_string(&counters.snapshot()) {
+            Ok(json) => json,
+            Err(err) => {
+                warn!("Failed serializing counter state: {}", err);
+                return;
+            },
+        };
+        match &self.sink {
+            CounterReportSink::File(path) => {
+                // written fully to a temporary file first, so that readers never see a partial one
+                let tmp_path = path.with_extension("tmp");
+                let result = match tokio::fs::write(&tmp_path, json).await {
+                    Ok(()) => tokio::fs::rename(&tmp_path, path).await,
+                    Err(err) => Err(err),
+                };
+                if let Err(err) = result {
+                    sample!(
+                        SampleRate::Duration(Duration::from_secs(60)),
+                        warn!(
+                            "Failed writing counter state to {}: {}",
+                            path.display(),
+                            err
+                        )
+                    );
+                }
+            },
+            CounterReportSink::Callback(callback) => callback(&json),
+        }
+    }
+
+    /// Runs work to completion, reporting counters every interval while it runs,
+    /// and once it is done.
+    pub async fn report_while<F>(&self, counters: &CounterState, work: F) -> F::Output
+    where
+        F: Future,
+    {
+        tokio::pin!(work);
+        let mut ticker = tokio::time::interval(self.interval);
+        loop {
+            tokio::select! {
+                output = &mut work => {
+                    self.maybe_report(counters).await;
+                    return output;
+                },
+                _ = ticker.tick() => self.maybe_report(counters).await,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CounterReportSink, CounterReporter};
+    use aptos_infallible::Mutex;
+    use aptos_temppath::TempPath;
+    use aptos_transaction_generator_lib::CounterState;
+    use std::{
+        sync::{atomic::Ordering, Arc},
+        time::Duration,
+    };
+
+    #[tokio::test]
+    pub async fn test_counter_reporter() {
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let sink_reports = reports.clone();
+        let reporter = CounterReporter::new(
+            Duration::from_secs(3600),
+            CounterReportSink::Callback(Arc::new(move |json| {
+                sink_reports.lock().push(json.to_string())
+            })),
+        );
+        let counters = CounterState::default();
+        counters.successes.fetch_add(3, Ordering::Relaxed);
+        reporter.maybe_report(&counters).await;
+        // within the interval
+        reporter.maybe_report(&counters).await;
+        assert_eq!(reports.lock().len(), 1);
+        let snapshot: serde_json::Value = serde_json::from_str(&reports.lock()[0]).unwrap();
+        assert_eq!(snapshot["successes"], 3);
+        assert_eq!(snapshot["accepted"], 0);
+
+        // reports while work runs are also limited to one per interval
+        let sink_reports = reports.clone();
+        let reporter = CounterReporter::new(
+            Duration::from_secs(3600),
+            CounterReportSink::Callback(Arc::new(move |json| {
+                sink_reports.lock().push(json.to_string())
+            })),
+        );
+        assert_eq!(reporter.report_while(&counters, async { 42 }).await, 42);
+        assert_eq!(reports.lock().len(), 2);
+    }
+
+    #[tokio::test]
+    pub async fn test_counter_report_file() {
+        let path = TempPath::new();
+        let reporter = CounterReporter::new(
+            Duration::from_secs(3600),
+            CounterReportSink::File(path.path().to_path_buf()),
+        );
+        let counters = CounterState::default();
+        counters.accepted.fetch_add(5, Ordering::Relaxed);
+        reporter.maybe_report(&counters).await;
+        let snapshot: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(path.path()).unwrap()).unwrap();
+        assert_eq!(snapshot["accepted"], 5);
+        // written through a temporary file, which doesn't stay around
+        assert!(!path.path().with_extension("tmp").exists());
+    }
+}
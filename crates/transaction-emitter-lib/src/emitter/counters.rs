@@ -0,0 +1,167 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use aptos_metrics_core::{
+    register_histogram_vec, register_int_counter, register_int_counter_vec, register_int_gauge,
+    register_int_gauge_vec, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec,
+};
+use once_cell::sync::Lazy;
+
+/// Transactions committed through RestApiTransactionExecutor, per client
+pub static EXECUTOR_SUCCESSES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_emitter_executor_successes",
+        "Number of transactions committed by the retrying transaction executor",
+        &["client"]
+    )
+    .unwrap()
+});
+
+/// Failed submissions through RestApiTransactionExecutor, per client, round and category
+pub static EXECUTOR_SUBMIT_FAILURES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_emitter_executor_submit_failures",
+        "Number of failed transaction submissions by the retrying transaction executor",
+        &["client", "round", "category"]
+    )
+    .unwrap()
+});
+
+/// Failed waits on submitted transactions, per client, round and category
+pub static EXECUTOR_WAIT_FAILURES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_emitter_executor_wait_failures",
+        "Number of failed waits for transaction commit by the retrying transaction executor",
+        &["client", "round", "category"]
+    )
+    .unwrap()
+});
+
+/// Latency of successful submission rounds, from submit until commit is observed
+pub static EXECUTOR_ROUND_LATENCY: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        "aptos_emitter_executor_round_latency_seconds",
+        "Latency from submission until commit, of successful submission rounds",
+        &["client"]
+    )
+    .unwrap()
+});
+
+/// Transactions accepted into mempool, when not waiting for commit, per client
+pub static EXECUTOR_ACCEPTED: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_emitter_executor_accepted",
+        "Number of transactions accepted into mempool by the transaction executor, in ack only mode",
+        &["client"]
+    )
+    .unwrap()
+});
+
+/// Submissions that were not acknowledged within the hedge delay, and
+/// were also submitted to another client, per original client
+pub static EXECUTOR_HEDGED_SUBMITS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_emitter_executor_hedged_submits",
+        "Number of transaction submissions hedged to a second client by the retrying transaction executor",
+        &["client"]
+    )
+    .unwrap()
+});
+
+/// Transactions found committed by the on-chain sequence number check before a retry round,
+/// per round
+pub static EXECUTOR_SEQUENCE_CHECK_HITS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_emitter_executor_sequence_check_hits",
+        "Number of retry rounds skipped, because transaction's sequence number was already used on chain",
+        &["round"]
+    )
+    .unwrap()
+});
+
+/// Faults injected into submissions by RestApiTransactionExecutor, per fault
+pub static EXECUTOR_INJECTED_FAULTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_emitter_executor_injected_faults",
+        "Number of faults injected into transaction submissions by the transaction executor",
+        &["fault"]
+    )
+    .unwrap()
+});
+
+/// Faucet requests made when funding seed accounts through a faucet, per result
+pub static EMITTER_FAUCET_REQUESTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_emitter_faucet_requests",
+        "Number of faucet requests made by the emitter for funding accounts",
+        &["result"]
+    )
+    .unwrap()
+});
+
+/// Divergences between clients found by DivergenceDetector
+pub static EMITTER_DIVERGENCES: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "aptos_emitter_divergences",
+        "Number of times clients were found to have different accumulator root hashes at the same version"
+    )
+    .unwrap()
+});
+
+/// Transactions of the whole emitter run so far, per kind (submitted, committed,
+/// expired, failed_submission), set before each push to the pushgateway
+pub static EMITTER_RUN_TXNS: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "aptos_emitter_run_txns",
+        "Number of transactions of the emitter run so far, per kind",
+        &["kind"]
+    )
+    .unwrap()
+});
+
+/// Latency percentiles (p50, p90, p99) of the whole emitter run so far, in milliseconds
+pub static EMITTER_RUN_LATENCY_MS: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "aptos_emitter_run_latency_ms",
+        "Latency percentiles of the emitter run so far, in milliseconds",
+        &["percentile"]
+    )
+    .unwrap()
+});
+
+/// Current phase of the emitter run
+pub static EMITTER_RUN_PHASE: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "aptos_emitter_run_phase",
+        "Current phase of the emitter run"
+    )
+    .unwrap()
+});
+
+/// Gas unit price of transactions emitted in the run, if refreshed from gas estimation
+pub static EMITTER_GAS_UNIT_PRICE: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "aptos_emitter_gas_unit_price",
+        "Gas unit price of emitted transactions, refreshed from gas estimation"
+    )
+    .unwrap()
+});
+
+/// Workers allowed to emit by backlog throttling, if enabled
+pub static EMITTER_THROTTLED_WORKERS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "aptos_emitter_throttled_workers",
+        "Number of workers allowed to emit, reduced while the backlog of in-flight transactions is over the threshold"
+    )
+    .unwrap()
+});
+
+/// Failed code publishing transactions, per stage (submission or execution) and reason
+pub static EMITTER_PUBLISH_FAILURES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_emitter_publish_failures",
+        "Number of code publishing transactions rejected on submission, or failed in execution, per reason",
+        &["stage", "reason"]
+    )
+    .unwrap()
+});
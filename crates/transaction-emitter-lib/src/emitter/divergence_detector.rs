@@ -0,0 +1,175 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::counters::EMITTER_DIVERGENCES;
+use anyhow::{bail, Context, Result};
+use aptos_crypto::HashValue;
+use aptos_infallible::Mutex;
+use aptos_logger::{error, sample, sample::SampleRate, warn};
+use aptos_rest_client::{aptos_api_types::TransactionData, Client as RestClient};
+use futures::future::join_all;
+use std::{fmt, sync::Arc, time::Duration};
+use tokio::task::JoinHandle;
+
+/// Endpoints reporting different accumulator root hashes at the same version.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Divergence {
+    pub version: u64,
+    /// (client, accumulator root hash) for every client checked at the version
+    pub accumulator_root_hashes: Vec<(String, HashValue)>,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Clients diverged at version {}:", self.version)?;
+        for (client, hash) in &self.accumulator_root_hashes {
+            write!(f, " [{}] {}", client, hash)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Divergence {}
+
+impl Divergence {
+    /// Returns divergence if not all given hashes (at version) are the same.
+    pub fn find(version: u64, accumulator_root_hashes: Vec<(String, HashValue)>) -> Option<Self> {
+        let first = accumulator_root_hashes.first()?.1;
+        if accumulator_root_hashes
+            .iter()
+            .all(|(_, hash)| *hash == first)
+        {
+            None
+        } else {
+            Some(Self {
+                version,
+                accumulator_root_hashes,
+            })
+        }
+    }
+}
+
+/// Periodically compares accumulator root hashes across clients, at the highest
+/// version all of them have, to detect forks during the run.
+/// Only the first detected divergence is kept.
+pub struct DivergenceDetector {
+    divergence: Arc<Mutex<Option<Divergence>>>,
+    check_task: JoinHandle<()>,
+}
+
+impl DivergenceDetector {
+    pub fn start(rest_clients: Vec<RestClient>, interval: Duration) -> Self {
+        let divergence = Arc::new(Mutex::new(None));
+        let check_task = tokio::spawn(check_divergence_periodically(
+            rest_clients,
+            interval,
+            divergence.clone(),
+        ));
+        Self {
+            divergence,
+            check_task,
+        }
+    }
+
+    pub fn divergence(&self) -> Option<Divergence> {
+        self.divergence.lock().clone()
+    }
+}
+
+impl Drop for DivergenceDetector {
+    fn drop(&mut self) {
+        self.check_task.abort();
+    }
+}
+
+async fn check_divergence_periodically(
+    rest_clients: Vec<RestClient>,
+    interval: Duration,
+    divergence: Arc<Mutex<Option<Divergence>>>,
+) {
+    loop {
+        tokio::time::sleep(interval).await;
+        match check_divergence(&rest_clients).await {
+            Ok(None) => {},
+            Ok(Some(found)) => {
+                EMITTER_DIVERGENCES.inc();
+                error!("{}", found);
+                divergence.lock().get_or_insert(found);
+            },
+            Err(err) => {
+                sample!(
+                    SampleRate::Duration(Duration::from_secs(60)),
+                    warn!("Failed checking clients for divergence: {:?}", err)
+                );
+            },
+        }
+    }
+}
+
+pub async fn check_divergence(rest_clients: &[RestClient]) -> Result<Option<Divergence>> {
+    let mut version = u64::MAX;
+    for (result, rest_client) in join_all(
+        rest_clients
+            .iter()
+            .map(|rest_client| rest_client.get_ledger_information()),
+    )
+    .await
+    .into_iter()
+    .zip(rest_clients.iter())
+    {
+        let state = result.with_context(|| {
+            format!(
+                "[{}] Failed to get ledger information",
+                rest_client.path_prefix_string()
+            )
+        })?;
+        version = version.min(state.into_inner().version);
+    }
+
+    let mut accumulator_root_hashes = Vec::new();
+    for (result, rest_client) in join_all(
+        rest_clients
+            .iter()
+            .map(|rest_client| rest_client.get_transaction_by_version_bcs(version)),
+    )
+    .await
+    .into_iter()
+    .zip(rest_clients.iter())
+    {
+        let client = rest_client.path_prefix_string();
+        match result
+            .with_context(|| format!("[{}] Failed to get transaction {}", client, version))?
+            .into_inner()
+        {
+            TransactionData::OnChain(txn) => {
+                accumulator_root_hashes.push((client, txn.accumulator_root_hash))
+            },
+            TransactionData::Pending(_) => {
+                bail!("[{}] Transaction {} is not committed", client, version)
+            },
+        }
+    }
+    Ok(Divergence::find(version, accumulator_root_hashes))
+}
+
+#[cfg(test)]
+mod test {
+    use super::Divergence;
+    use aptos_crypto::HashValue;
+
+    #[test]
+    pub fn test_find_divergence() {
+        let a = HashValue::sha3_256_of(b"a");
+        let b = HashValue::sha3_256_of(b"b");
+        assert_eq!(Divergence::find(1, vec![]), None);
+        assert_eq!(
+            Divergence::find(1, vec![("x".to_string(), a), ("y".to_string(), a)]),
+            None
+        );
+
+        let divergence =
+            Divergence::find(2, vec![("x".to_string(), a), ("y".to_string(), b)]).unwrap();
+        assert_eq!(divergence.version, 2);
+        assert!(divergence.to_string().contains(&b.to_string()));
+    }
+}
@@ -0,0 +1,66 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+/// Shared between a job and its workers, to bound how long stopped workers keep waiting
+/// for their in-flight transactions to be committed (or to expire).
+#[derive(Debug, Default)]
+pub struct InFlightDrain {
+    abandoned: AtomicBool,
+    // transactions still in flight when waiting for them was abandoned
+    unresolved: AtomicU64,
+}
+
+impl InFlightDrain {
+    pub fn is_abandoned(&self) -> bool {
+        self.abandoned.load(Ordering::Relaxed)
+    }
+
+    pub fn record_unresolved(&self, num_txns: u64) {
+        self.unresolved.fetch_add(num_txns, Ordering::Relaxed);
+    }
+
+    pub fn unresolved(&self) -> u64 {
+        self.unresolved.load(Ordering::Relaxed)
+    }
+
+    pub fn abandon(&self) {
+        self.abandoned.store(true, Ordering::Relaxed);
+    }
+
+    /// Abandons waiting for in-flight transactions after timeout.
+    pub async fn abandon_after(self: Arc<Self>, timeout: Duration) {
+        tokio::time::sleep(timeout).await;
+        self.abandon();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::InFlightDrain;
+    use std::{sync::Arc, time::Duration};
+
+    #[tokio::test]
+    pub async fn test_in_flight_drain() {
+        let drain = Arc::new(InFlightDrain::default());
+        let abandon = tokio::spawn(drain.clone().abandon_after(Duration::from_millis(10)));
+        assert!(!drain.is_abandoned());
+        abandon.await.unwrap();
+        assert!(drain.is_abandoned());
+
+        let drain = InFlightDrain::default();
+        drain.abandon();
+        assert!(drain.is_abandoned());
+
+        drain.record_unresolved(3);
+        drain.record_unresolved(2);
+        assert_eq!(drain.unresolved(), 5);
+    }
+}
@@ -0,0 +1,265 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::HttpClientArgs;
+use anyhow::{ensure, Context, Result};
+use aptos_infallible::RwLock;
+use aptos_logger::{info, sample, sample::SampleRate, warn};
+use aptos_rest_client::Client as RestClient;
+use aptos_sdk::types::{
+    account_config::CORE_CODE_ADDRESS,
+    network_address::{NetworkAddress, Protocol},
+    on_chain_config::ValidatorSet,
+};
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::task::JoinHandle;
+use url::Url;
+
+/// Discovers the REST endpoints to emit to from the fullnode addresses of the on-chain
+/// validator set, read from a bootstrap endpoint, so that targets don't need to be
+/// updated every time the validator set changes.
+/// Only hosts are on chain, so the REST API is assumed to be on the same port (and
+/// scheme) on every host.
+#[derive(Clone, Debug)]
+pub struct EndpointDiscovery {
+    pub bootstrap: Url,
+    pub rest_port: u16,
+    pub http_client_args: HttpClientArgs,
+}
+
+impl EndpointDiscovery {
+    /// REST URL of the host of a fullnode network address, None if it has no host.
+    pub fn rest_url(&self, address: &NetworkAddress) -> Option<Url> {
+        let host = match address.as_slice().first()? {
+            Protocol::Ip4(ip) => ip.to_string(),
+            Protocol::Ip6(ip) => format!("[{}]", ip),
+            Protocol::Dns(name) | Protocol::Dns4(name) | Protocol::Dns6(name) => name.to_string(),
+            _ => return None,
+        };
+        Url::parse(&format!(
+            "{}://{}:{}",
+            self.bootstrap.scheme(),
+            host,
+            self.rest_port
+        ))
+        .ok()
+    }
+
+    /// REST URLs of the active validators (including ones leaving at the end of the epoch),
+    /// in order of the validator set. Validators without valid fullnode addresses are skipped.
+    pub async fn discover(&self) -> Result<Vec<Url>> {
+        let validator_set: ValidatorSet = self
+            .http_client_args
+            .rest_client(self.bootstrap.clone())
+            .get_account_resource_bcs(CORE_CODE_ADDRESS, "0x1::stake::ValidatorSet")
+            .await
+            .with_context(|| format!("Failed to read the validator set from {}", self.bootstrap))?
+            .into_inner();
+        let mut urls: Vec<Url> = vec![];
+        for validator in validator_set
+            .active_validators
+            .iter()
+            .chain(validator_set.pending_inactive.iter())
+        {
+            match validator.config().fullnode_network_addresses() {
+                Ok(addresses) => {
+                    for url in addresses
+                        .iter()
+                        .filter_map(|address| self.rest_url(address))
+                    {
+                        if !urls.contains(&url) {
+                            urls.push(url);
+                        }
+                    }
+                },
+                Err(err) => warn!(
+                    "Skipping validator {} with invalid fullnode addresses: {:?}",
+                    validator.account_address, err
+                ),
+            }
+        }
+        ensure!(
+            !urls.is_empty(),
+            "No fullnode addresses found in the validator set read from {}",
+            self.bootstrap
+        );
+        info!(
+            "Discovered {} endpoints from the validator set read from {}",
+            urls.len(),
+            self.bootstrap
+        );
+        Ok(urls)
+    }
+}
+
+/// Clients of the discovered endpoints, replaced whenever the validator set changes.
+#[derive(Debug)]
+pub struct DiscoveredEndpoints {
+    clients: RwLock<Vec<RestClient>>,
+    // incremented on each change, for workers to notice it cheaply
+    generation: AtomicU64,
+}
+
+impl DiscoveredEndpoints {
+    pub fn new(clients: Vec<RestClient>) -> Self {
+        Self {
+            clients: RwLock::new(clients),
+            generation: AtomicU64::new(0),
+        }
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// Client for the given worker, spreading workers round-robin across the endpoints.
+    pub fn client_for(&self, worker_index: usize) -> RestClient {
+        let clients = self.clients.read();
+        clients[worker_index % clients.len()].clone()
+    }
+
+    /// Replaces the clients if the endpoints changed, returning whether they did.
+    pub fn update(&self, clients: Vec<RestClient>) -> bool {
+        if clients.is_empty() {
+            return false;
+        }
+        let mut current = self.clients.write();
+        let urls = |clients: &[RestClient]| {
+            clients
+                .iter()
+                .map(RestClient::path_prefix_string)
+                .collect::<Vec<_>>()
+        };
+        if urls(&current) == urls(&clients) {
+            return false;
+        }
+        *current = clients;
+        self.generation.fetch_add(1, Ordering::Release);
+        true
+    }
+}
+
+/// Re-discovers endpoints periodically in the background, for the duration of the job.
+#[derive(Debug)]
+pub struct EndpointRefresh {
+    endpoints: Arc<DiscoveredEndpoints>,
+    refresh_task: JoinHandle<()>,
+}
+
+impl EndpointRefresh {
+    pub fn start(
+        discovery: EndpointDiscovery,
+        interval: Duration,
+        initial_clients: Vec<RestClient>,
+    ) -> Self {
+        let endpoints = Arc::new(DiscoveredEndpoints::new(initial_clients));
+        let refresh_task =
+            tokio::spawn(refresh_periodically(discovery, interval, endpoints.clone()));
+        Self {
+            endpoints,
+            refresh_task,
+        }
+    }
+
+    /// Handle to the current endpoints, for workers to switch to.
+    pub fn endpoints(&self) -> Arc<DiscoveredEndpoints> {
+        self.endpoints.clone()
+    }
+}
+
+impl Drop for EndpointRefresh {
+    fn drop(&mut self) {
+        self.refresh_task.abort();
+    }
+}
+
+async fn refresh_periodically(
+    discovery: EndpointDiscovery,
+    interval: Duration,
+    endpoints: Arc<DiscoveredEndpoints>,
+) {
+    loop {
+        tokio::time::sleep(interval).await;
+        match discovery.discover().await {
+            Ok(urls) => {
+                let num_endpoints = urls.len();
+                let clients = urls
+                    .into_iter()
+                    .map(|url| discovery.http_client_args.rest_client(url))
+                    .collect();
+                if endpoints.update(clients) {
+                    info!(
+                        "Validator set changed, workers moving to {} discovered endpoints",
+                        num_endpoints
+                    );
+                }
+            },
+            Err(err) => {
+                sample!(
+                    SampleRate::Duration(Duration::from_secs(60)),
+                    warn!(
+                        "Failed to refresh endpoints, keeping the current ones: {:?}",
+                        err
+                    )
+                );
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{DiscoveredEndpoints, EndpointDiscovery};
+    use crate::HttpClientArgs;
+    use aptos_rest_client::Client as RestClient;
+    use url::Url;
+
+    #[test]
+    pub fn test_endpoint_discovery() {
+        let discovery = EndpointDiscovery {
+            bootstrap: Url::parse("https://bootstrap.example.com:443").unwrap(),
+            rest_port: 8080,
+            http_client_args: HttpClientArgs::default(),
+        };
+        let rest_url = |address: &str| {
+            discovery
+                .rest_url(&address.parse().unwrap())
+                .map(|url| url.to_string())
+        };
+        assert_eq!(
+            rest_url("/dns/node0.example.com/tcp/6182").unwrap(),
+            "https://node0.example.com:8080/"
+        );
+        assert_eq!(
+            rest_url("/ip4/10.0.0.1/tcp/6182").unwrap(),
+            "https://10.0.0.1:8080/"
+        );
+        assert_eq!(
+            rest_url("/ip6/::1/tcp/6182").unwrap(),
+            "https://[::1]:8080/"
+        );
+
+        let client = |url: &str| RestClient::new(Url::parse(url).unwrap());
+        let endpoints =
+            DiscoveredEndpoints::new(vec![client("http://a:8080"), client("http://b:8080")]);
+        assert_eq!(
+            endpoints.client_for(3).path_prefix_string(),
+            client("http://b:8080").path_prefix_string()
+        );
+        assert!(!endpoints.update(vec![client("http://a:8080"), client("http://b:8080")]));
+        assert!(!endpoints.update(vec![]));
+        assert_eq!(endpoints.generation(), 0);
+        assert!(endpoints.update(vec![client("http://c:8080")]));
+        assert_eq!(endpoints.generation(), 1);
+        assert_eq!(
+            endpoints.client_for(3).path_prefix_string(),
+            client("http://c:8080").path_prefix_string()
+        );
+    }
+}
@@ -0,0 +1,174 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::emitter::stats::{StatsAccumulator, TxnStats};
+use anyhow::{ensure, Result};
+use std::{sync::Arc, time::Duration};
+
+/// Groups (e.g. regions) the endpoints are in, for stats to be reported per group, and
+/// optionally for a share of the workers to be pinned to each group.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EndpointGroups {
+    // group of each endpoint, by index of its client
+    groups: Vec<String>,
+    // share of all workers pinned to endpoints of the group, the rest of the workers
+    // are spread across endpoints of the groups without a share
+    sender_shares: Vec<(String, f64)>,
+}
+
+impl EndpointGroups {
+    pub fn new(groups: Vec<String>, sender_shares: Vec<(String, f64)>) -> Result<Self> {
+        for (group, share) in &sender_shares {
+            ensure!(
+                groups.contains(group),
+                "Sender share given for group {} without endpoints",
+                group
+            );
+            ensure!(
+                (0.0..=1.0).contains(share),
+                "Sender share of group {} needs to be between 0 and 1",
+                group
+            );
+        }
+        ensure!(
+            sender_shares.iter().map(|(_, share)| share).sum::<f64>() <= 1.0 + f64::EPSILON,
+            "Sender shares cannot add up to more than 1"
+        );
+        Ok(Self {
+            groups,
+            sender_shares,
+        })
+    }
+
+    /// Names of the groups, in order of their first endpoint.
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = vec![];
+        for group in &self.groups {
+            if !names.contains(group) {
+                names.push(group.clone());
+            }
+        }
+        names
+    }
+
+    pub fn num_endpoints(&self) -> usize {
+        self.groups.len()
+    }
+
+    pub fn group_of(&self, client_index: usize) -> &str {
+        &self.groups[client_index]
+    }
+
+    fn clients_in<'a>(&'a self, is_in: impl Fn(&str) -> bool + 'a) -> Vec<usize> {
+        (0..self.groups.len())
+            .filter(|client_index| is_in(&self.groups[*client_index]))
+            .collect()
+    }
+
+    /// Index of the client each of the workers submits to. Workers of each group are
+    /// spread round-robin across its endpoints.
+    pub fn assign_workers(&self, total_workers: usize) -> Vec<usize> {
+        let mut assigned = vec![];
+        for (group, share) in &self.sender_shares {
+            let clients = self.clients_in(|other| other == group);
+            let num_workers = ((total_workers as f64 * share).round() as usize)
+                .min(total_workers - assigned.len());
+            assigned.extend((0..num_workers).map(|i| clients[i % clients.len()]));
+        }
+        let mut rest_clients =
+            self.clients_in(|group| !self.sender_shares.iter().any(|(shared, _)| shared == group));
+        if rest_clients.is_empty() {
+            // every group has a share, with rounding leaving some workers over
+            rest_clients = (0..self.groups.len()).collect();
+        }
+        let num_rest = total_workers - assigned.len();
+        assigned.extend((0..num_rest).map(|i| rest_clients[i % rest_clients.len()]));
+        assigned
+    }
+}
+
+/// Stats of each group, tracked by every worker submitting to endpoints of the group.
+#[derive(Debug)]
+pub struct GroupStats {
+    groups: EndpointGroups,
+    stats: Vec<(String, Arc<StatsAccumulator>)>,
+}
+
+impl GroupStats {
+    pub fn new(groups: EndpointGroups) -> Self {
+        let stats = groups
+            .names()
+            .into_iter()
+            .map(|name| (name, Arc::new(StatsAccumulator::default())))
+            .collect();
+        Self { groups, stats }
+    }
+
+    pub fn tracker_for(&self, client_index: usize) -> Arc<StatsAccumulator> {
+        let group = self.groups.group_of(client_index);
+        let (_, stats) = self
+            .stats
+            .iter()
+            .find(|(name, _)| name == group)
+            .expect("every group has stats");
+        Arc::clone(stats)
+    }
+
+    pub fn accumulate(&self, lasted: Duration) -> Vec<(String, TxnStats)> {
+        self.stats
+            .iter()
+            .map(|(name, stats)| (name.clone(), stats.accumulate(lasted)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{EndpointGroups, GroupStats};
+    use std::{sync::atomic::Ordering, time::Duration};
+
+    #[test]
+    pub fn test_endpoint_groups() {
+        let groups = |names: &[&str]| names.iter().map(ToString::to_string).collect::<Vec<_>>();
+        let endpoint_groups = EndpointGroups::new(groups(&["us", "eu", "us", "asia"]), vec![(
+            "eu".to_string(),
+            0.5,
+        )])
+        .unwrap();
+        assert_eq!(endpoint_groups.names(), groups(&["us", "eu", "asia"]));
+        // half of the workers pinned to eu, the rest spread across us and asia
+        assert_eq!(endpoint_groups.assign_workers(8), vec![
+            1, 1, 1, 1, 0, 2, 3, 0
+        ]);
+
+        // without shares, same as spreading workers across all endpoints
+        let unshared = EndpointGroups::new(groups(&["us", "eu"]), vec![]).unwrap();
+        assert_eq!(unshared.assign_workers(4), vec![0, 1, 0, 1]);
+        assert!(EndpointGroups::new(groups(&["us"]), vec![("eu".to_string(), 0.5)]).is_err());
+        assert!(EndpointGroups::new(groups(&["us", "eu"]), vec![
+            ("us".to_string(), 0.6),
+            ("eu".to_string(), 0.6)
+        ])
+        .is_err());
+
+        let group_stats = GroupStats::new(endpoint_groups);
+        group_stats
+            .tracker_for(0)
+            .committed
+            .fetch_add(10, Ordering::Relaxed);
+        group_stats
+            .tracker_for(2)
+            .committed
+            .fetch_add(5, Ordering::Relaxed);
+        let committed: Vec<_> = group_stats
+            .accumulate(Duration::from_secs(1))
+            .into_iter()
+            .map(|(name, stats)| (name, stats.committed))
+            .collect();
+        assert_eq!(committed, vec![
+            ("us".to_string(), 15),
+            ("eu".to_string(), 0),
+            ("asia".to_string(), 0)
+        ]);
+    }
+}
@@ -0,0 +1,250 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{counters::EMITTER_FAUCET_REQUESTS, transaction_executor::RetryBackoff};
+use anyhow::{bail, Error, Result};
+use aptos_infallible::Mutex;
+use aptos_logger::{info, sample, sample::SampleRate, warn};
+use aptos_rest_client::{error::FaucetClientError, Client as RestClient, FaucetClient};
+use aptos_sdk::{move_types::account_address::AccountAddress, types::LocalAccount};
+use futures::StreamExt;
+use rand::thread_rng;
+use std::{
+    cmp::max,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+use url::Url;
+
+const TOO_MANY_REQUESTS: u16 = 429;
+
+/// Funding of seed accounts through a faucet, instead of from the source account,
+/// with requests made in parallel and retried, backing off all of them when the
+/// faucet rate limits.
+#[derive(Clone, Debug)]
+pub struct FaucetFunding {
+    pub faucet_url: Url,
+    /// Number of faucet requests in flight at once
+    pub parallelism: usize,
+    /// Attempts of each request, including the first one
+    pub max_attempts: usize,
+    pub backoff: RetryBackoff,
+    /// Largest amount the faucet gives out in a single request, larger amounts are
+    /// requested in multiple ones
+    pub max_amount_per_request: Option<u64>,
+}
+
+impl FaucetFunding {
+    pub fn new(faucet_url: Url) -> Self {
+        Self {
+            faucet_url,
+            parallelism: 10,
+            max_attempts: 5,
+            backoff: RetryBackoff::Exponential {
+                initial_delay: Duration::from_secs(1),
+                max_delay: Duration::from_secs(30),
+                jitter: true,
+            },
+            max_amount_per_request: None,
+        }
+    }
+}
+
+/// Amounts of the requests funding an account with amount.
+fn request_amounts(amount: u64, max_amount_per_request: Option<u64>) -> Vec<u64> {
+    match max_amount_per_request {
+        Some(max_amount) if max_amount > 0 && amount > max_amount => {
+            let mut amounts = vec![max_amount; (amount / max_amount) as usize];
+            if amount % max_amount > 0 {
+                amounts.push(amount % max_amount);
+            }
+            amounts
+        },
+        _ => vec![amount],
+    }
+}
+
+fn is_rate_limited(err: &Error) -> bool {
+    err.downcast_ref::<FaucetClientError>()
+        .and_then(FaucetClientError::status_code)
+        == Some(TOO_MANY_REQUESTS)
+}
+
+pub struct FaucetFunder {
+    faucet: FaucetClient,
+    config: FaucetFunding,
+    // once rate limited, no requests are made until then
+    paused_until: Mutex<Option<Instant>>,
+    funded: AtomicUsize,
+}
+
+impl FaucetFunder {
+    /// Funder waiting on faucet transactions through the given client.
+    pub fn new(config: FaucetFunding, rest_client: RestClient) -> Self {
+        Self {
+            faucet: FaucetClient::new_from_rest_client(config.faucet_url.clone(), rest_client),
+            config,
+            paused_until: Mutex::new(None),
+            funded: AtomicUsize::new(0),
+        }
+    }
+
+    /// Funds the accounts with amount each, returning the ones that got funded.
+    /// Accounts failing all attempts are dropped, failing only if none got funded.
+    pub async fn fund_accounts(
+        &self,
+        accounts: Vec<LocalAccount>,
+        amount: u64,
+    ) -> Result<Vec<LocalAccount>> {
+        let total = accounts.len();
+        let start = Instant::now();
+        info!(
+            "Funding {} accounts with {} coins each through faucet {}, {} requests at a time",
+            total, amount, self.config.faucet_url, self.config.parallelism
+        );
+        let results: Vec<_> = futures::stream::iter(accounts.into_iter().map(|account| async {
+            let result = self.fund_account(account.address(), amount, total).await;
+            (account, result)
+        }))
+        .buffer_unordered(max(self.config.parallelism, 1))
+        .collect()
+        .await;
+
+        let mut funded = vec![];
+        let mut last_err = None;
+        for (account, result) in results {
+            match result {
+                Ok(()) => funded.push(account),
+                Err(err) => last_err = Some(err),
+            }
+        }
+        if let Some(err) = last_err {
+            if funded.is_empty() {
+                bail!(
+                    "Failed to fund any of {} accounts through faucet: {:?}",
+                    total,
+                    err
+                );
+            }
+            warn!(
+                "Failed to fund {} of {} accounts through faucet, continuing without them, last error: {:?}",
+                total - funded.len(),
+                total,
+                err
+            );
+        }
+        info!(
+            "Funded {} accounts through faucet in {}s",
+            funded.len(),
+            start.elapsed().as_secs()
+        );
+        Ok(funded)
+    }
+
+    async fn fund_account(&self, address: AccountAddress, amount: u64, total: usize) -> Result<()> {
+        for request_amount in request_amounts(amount, self.config.max_amount_per_request) {
+            self.request(address, request_amount).await?;
+        }
+        let funded = self.funded.fetch_add(1, Ordering::Relaxed) + 1;
+        // progress at every 10%
+        if funded * 10 / total != (funded - 1) * 10 / total {
+            info!("Funded {}/{} accounts through faucet", funded, total);
+        }
+        Ok(())
+    }
+
+    async fn request(&self, address: AccountAddress, amount: u64) -> Result<()> {
+        let mut attempt = 0;
+        loop {
+            self.wait_if_paused().await;
+            let err = match self.faucet.fund(address, amount).await {
+                Ok(()) => {
+                    EMITTER_FAUCET_REQUESTS
+                        .with_label_values(&["success"])
+                        .inc();
+                    return Ok(());
+                },
+                Err(err) => err,
+            };
+            let rate_limited = is_rate_limited(&err);
+            EMITTER_FAUCET_REQUESTS
+                .with_label_values(&[if rate_limited {
+                    "rate_limited"
+                } else {
+                    "failed"
+                }])
+                .inc();
+            attempt += 1;
+            if attempt >= self.config.max_attempts {
+                return Err(err.context(format!(
+                    "Funding {} through faucet failed after {} attempts",
+                    address, attempt
+                )));
+            }
+            let delay = self
+                .config
+                .backoff
+                .delay_for_round(attempt - 1, &mut thread_rng());
+            if rate_limited {
+                // back off all requests, as the faucet would reject them as well
+                self.pause_for(delay);
+            } else {
+                sample!(
+                    SampleRate::Duration(Duration::from_secs(60)),
+                    warn!(
+                        "Funding {} through faucet failed, attempt {}, retrying: {:?}",
+                        address, attempt, err
+                    )
+                );
+                tokio::time::sleep(delay).await;
+            }
+        }
+    }
+
+    fn pause_for(&self, delay: Duration) {
+        let until = Instant::now() + delay;
+        let mut paused_until = self.paused_until.lock();
+        if paused_until.map_or(true, |paused_until| paused_until < until) {
+            sample!(
+                SampleRate::Duration(Duration::from_secs(60)),
+                warn!(
+                    "Faucet is rate limiting, pausing requests for {}ms",
+                    delay.as_millis()
+                )
+            );
+            *paused_until = Some(until);
+        }
+    }
+
+    async fn wait_if_paused(&self) {
+        loop {
+            let paused_until = *self.paused_until.lock();
+            match paused_until {
+                Some(until) if until > Instant::now() => {
+                    tokio::time::sleep_until(until.into()).await
+                },
+                _ => return,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{is_rate_limited, request_amounts};
+    use aptos_rest_client::error::FaucetClientError;
+
+    #[test]
+    pub fn test_faucet_funding() {
+        assert_eq!(request_amounts(250, Some(100)), vec![100, 100, 50]);
+        assert_eq!(request_amounts(200, Some(100)), vec![100, 100]);
+        assert_eq!(request_amounts(50, Some(100)), vec![50]);
+        assert_eq!(request_amounts(250, None), vec![250]);
+
+        assert!(is_rate_limited(&FaucetClientError::status(429).into()));
+        assert!(!is_rate_limited(&FaucetClientError::status(500).into()));
+        assert!(!is_rate_limited(&anyhow::anyhow!(
+            "body: too many requests"
+        )));
+    }
+}
@@ -0,0 +1,129 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::{bail, ensure, format_err, Result};
+use rand::Rng;
+use std::time::Duration;
+
+/// Probabilities of faults artificially injected into each submission, to test retry
+/// and accounting logic, and robustness of the network, without external tooling.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FaultInjection {
+    /// Submission is delayed by up to max_delay
+    pub delay_probability: f64,
+    pub max_delay: Duration,
+    /// Transaction is not submitted, as if the request got lost
+    pub drop_probability: f64,
+    /// Transaction is submitted a second time
+    pub duplicate_probability: f64,
+    /// Commit is waited for under a wrong hash, so it is never seen
+    pub corrupt_hash_probability: f64,
+}
+
+/// Faults to inject into a single submission.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct InjectedFaults {
+    pub delay: Option<Duration>,
+    pub drop: bool,
+    pub duplicate: bool,
+    pub corrupt_hash: bool,
+}
+
+impl InjectedFaults {
+    /// Names of the injected faults, for counters.
+    pub fn names(&self) -> Vec<&'static str> {
+        [
+            (self.delay.is_some(), "delay"),
+            (self.drop, "drop"),
+            (self.duplicate, "duplicate"),
+            (self.corrupt_hash, "corrupt_hash"),
+        ]
+        .iter()
+        .filter(|(injected, _)| *injected)
+        .map(|(_, name)| *name)
+        .collect()
+    }
+}
+
+impl FaultInjection {
+    /// Parses comma separated `<fault>=<probability>` pairs, with faults being `drop`,
+    /// `duplicate`, `corrupt_hash` and `delay`, which also takes the max delay, as
+    /// `delay=<probability>:<max delay ms>`.
+    pub fn parse(faults: &str) -> Result<Self> {
+        let mut result = Self::default();
+        for fault in faults.split(',') {
+            let (name, value) = fault
+                .split_once('=')
+                .ok_or_else(|| format_err!("Expected <fault>=<probability>, got {}", fault))?;
+            let probability = match name {
+                "delay" => {
+                    let (probability, max_delay_ms) = value.split_once(':').ok_or_else(|| {
+                        format_err!("Expected delay=<probability>:<max delay ms>, got {}", fault)
+                    })?;
+                    result.max_delay = Duration::from_millis(max_delay_ms.parse()?);
+                    result.delay_probability = probability.parse()?;
+                    result.delay_probability
+                },
+                "drop" => {
+                    result.drop_probability = value.parse()?;
+                    result.drop_probability
+                },
+                "duplicate" => {
+                    result.duplicate_probability = value.parse()?;
+                    result.duplicate_probability
+                },
+                "corrupt_hash" => {
+                    result.corrupt_hash_probability = value.parse()?;
+                    result.corrupt_hash_probability
+                },
+                _ => bail!("Unknown fault {}", name),
+            };
+            ensure!(
+                (0.0..=1.0).contains(&probability),
+                "Probability of {} needs to be between 0 and 1",
+                name
+            );
+        }
+        Ok(result)
+    }
+
+    /// Each of the faults is chosen independently.
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> InjectedFaults {
+        let delay = (rng.gen_bool(self.delay_probability) && !self.max_delay.is_zero())
+            .then(|| rng.gen_range(Duration::ZERO, self.max_delay));
+        InjectedFaults {
+            delay,
+            drop: rng.gen_bool(self.drop_probability),
+            duplicate: rng.gen_bool(self.duplicate_probability),
+            corrupt_hash: rng.gen_bool(self.corrupt_hash_probability),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{FaultInjection, InjectedFaults};
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::time::Duration;
+
+    #[test]
+    pub fn test_fault_injection() {
+        let fault_injection = FaultInjection::parse("delay=1:500,drop=0,duplicate=1").unwrap();
+        assert_eq!(fault_injection.max_delay, Duration::from_millis(500));
+        assert!(FaultInjection::parse("drop=2").is_err());
+        assert!(FaultInjection::parse("delay=0.5").is_err());
+        assert!(FaultInjection::parse("crash=0.5").is_err());
+
+        let mut rng = StdRng::from_seed([0; 32]);
+        for _ in 0..100 {
+            let faults = fault_injection.sample(&mut rng);
+            assert!(faults.delay.unwrap() < Duration::from_millis(500));
+            assert!(!faults.drop && faults.duplicate && !faults.corrupt_hash);
+            assert_eq!(faults.names(), vec!["delay", "duplicate"]);
+        }
+        assert_eq!(
+            FaultInjection::default().sample(&mut rng),
+            InjectedFaults::default()
+        );
+    }
+}
@@ -0,0 +1,212 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::{ensure, format_err, Result};
+
+/// Shape of the tree accounts are funded through: the source account funds the first
+/// level of intermediate accounts, each of which funds its share of the next level,
+/// with the last level of intermediate (seed) accounts funding the created accounts.
+/// Each level is funded in parallel, so a deeper tree funds large pools in fewer rounds.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FundingTree {
+    /// Number of accounts each intermediate account funds. If not set, there is a
+    /// single level of seed accounts, sized from the number of accounts to create.
+    pub fan_out: Option<usize>,
+    /// Levels of intermediate accounts between the source and the created accounts
+    pub depth: usize,
+    /// Coins given to each account of every level, from the first intermediate one to
+    /// the created accounts. If empty, they are computed from the coins the created
+    /// accounts need.
+    pub level_amounts: Vec<u64>,
+}
+
+impl Default for FundingTree {
+    fn default() -> Self {
+        Self {
+            fan_out: None,
+            depth: 1,
+            level_amounts: vec![],
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FundingLevel {
+    pub num_accounts: usize,
+    pub coins_per_account: u64,
+}
+
+/// Levels of the tree, from the first one funded by the source account, down to the
+/// created accounts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FundingPlan {
+    pub levels: Vec<FundingLevel>,
+}
+
+impl FundingTree {
+    pub fn validate(&self) -> Result<()> {
+        ensure!(self.depth > 0, "Funding tree needs at least one level");
+        ensure!(
+            self.fan_out.is_some() || self.depth == 1,
+            "Funding tree deeper than one level needs a fan-out"
+        );
+        ensure!(
+            self.fan_out.map_or(true, |fan_out| fan_out > 1),
+            "Funding tree fan-out needs to be at least 2"
+        );
+        ensure!(
+            self.level_amounts.is_empty() || self.level_amounts.len() == self.depth + 1,
+            "Funding tree of depth {} needs {} level amounts, got {}",
+            self.depth,
+            self.depth + 1,
+            self.level_amounts.len()
+        );
+        Ok(())
+    }
+
+    /// Plan funding num_accounts with coins_per_account each, with every transfer paying
+    /// up to fee_per_transfer. Without fan-out, default_num_seed_accounts are used.
+    pub fn plan(
+        &self,
+        num_accounts: usize,
+        default_num_seed_accounts: usize,
+        coins_per_account: u64,
+        fee_per_transfer: u64,
+    ) -> Result<FundingPlan> {
+        self.validate()?;
+        let mut sizes = vec![num_accounts];
+        match self.fan_out {
+            Some(fan_out) => {
+                for _ in 0..self.depth {
+                    let children = *sizes.last().unwrap();
+                    sizes.push(((children + fan_out - 1) / fan_out).max(1));
+                }
+            },
+            None => sizes.push(default_num_seed_accounts),
+        }
+        sizes.reverse();
+
+        let mut amounts = vec![coins_per_account];
+        for level in (0..self.depth).rev() {
+            let children_per_account = children_per_account(sizes[level], sizes[level + 1]);
+            let child_amount = *amounts.last().unwrap();
+            let amount = child_amount
+                .checked_add(fee_per_transfer)
+                .and_then(|per_child| per_child.checked_mul(children_per_account as u64))
+                .and_then(|amount| amount.checked_add(fee_per_transfer))
+                .ok_or_else(|| {
+                    format_err!(
+                        "Coins per account of funding level {} exceed u64: {} * ({} + {}) + {}",
+                        level,
+                        children_per_account,
+                        child_amount,
+                        fee_per_transfer,
+                        fee_per_transfer
+                    )
+                })?;
+            amounts.push(amount);
+        }
+        amounts.reverse();
+        if !self.level_amounts.is_empty() {
+            amounts = self.level_amounts.clone();
+        }
+
+        Ok(FundingPlan {
+            levels: sizes
+                .into_iter()
+                .zip(amounts)
+                .map(|(num_accounts, coins_per_account)| FundingLevel {
+                    num_accounts,
+                    coins_per_account,
+                })
+                .collect(),
+        })
+    }
+}
+
+fn children_per_account(num_accounts: usize, num_children: usize) -> usize {
+    (num_children + num_accounts - 1) / num_accounts
+}
+
+impl FundingPlan {
+    /// Number of accounts each account of the level funds.
+    pub fn children_per_account(&self, level: usize) -> usize {
+        children_per_account(
+            self.levels[level].num_accounts,
+            self.levels[level + 1].num_accounts,
+        )
+    }
+
+    /// Coins the source account needs for funding the first level.
+    pub fn coins_for_source(&self, fee_per_transfer: u64) -> Result<u64> {
+        let first = &self.levels[0];
+        first
+            .coins_per_account
+            .checked_mul(first.num_accounts as u64)
+            .and_then(|coins| coins.checked_add(fee_per_transfer))
+            .ok_or_else(|| {
+                format_err!(
+                    "coins_for_source exceeds u64: {} * {}",
+                    first.coins_per_account,
+                    first.num_accounts
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{FundingLevel, FundingTree};
+
+    #[test]
+    pub fn test_funding_tree() {
+        let level = |num_accounts, coins_per_account| FundingLevel {
+            num_accounts,
+            coins_per_account,
+        };
+        // single level of seed accounts, as without a funding tree
+        let plan = FundingTree::default().plan(100, 3, 10, 1).unwrap();
+        assert_eq!(plan.levels, vec![level(3, 34 * 11 + 1), level(100, 10)]);
+        assert_eq!(plan.children_per_account(0), 34);
+        assert_eq!(plan.coins_for_source(1).unwrap(), 3 * 375 + 1);
+
+        let tree = FundingTree {
+            fan_out: Some(10),
+            depth: 2,
+            level_amounts: vec![],
+        };
+        let plan = tree.plan(1000, 3, 10, 1).unwrap();
+        assert_eq!(plan.levels, vec![
+            level(10, 10 * 112 + 1),
+            level(100, 111),
+            level(1000, 10)
+        ]);
+
+        let plan = FundingTree {
+            level_amounts: vec![500, 50, 5],
+            ..tree.clone()
+        }
+        .plan(1000, 3, 10, 1)
+        .unwrap();
+        assert_eq!(plan.levels, vec![
+            level(10, 500),
+            level(100, 50),
+            level(1000, 5)
+        ]);
+
+        assert!(FundingTree {
+            level_amounts: vec![500, 50],
+            ..tree
+        }
+        .validate()
+        .is_err());
+        assert!(FundingTree {
+            fan_out: None,
+            depth: 2,
+            level_amounts: vec![],
+        }
+        .validate()
+        .is_err());
+        assert!(FundingTree::default().plan(10, 1, u64::MAX, 1).is_err());
+    }
+}
@@ -0,0 +1,153 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::counters::EMITTER_GAS_UNIT_PRICE;
+use anyhow::{bail, ensure, Result};
+use aptos_logger::{info, sample, sample::SampleRate, warn};
+use aptos_rest_client::Client as RestClient;
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::task::JoinHandle;
+
+/// Gas unit price of emitted transactions periodically refreshed from the gas estimation
+/// API of a node, instead of staying at the configured one, so that long runs keep
+/// committing when network gas prices drift. Estimates are clamped to [floor, ceiling].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GasPriceRefresh {
+    pub interval: Duration,
+    pub floor: u64,
+    pub ceiling: u64,
+}
+
+impl GasPriceRefresh {
+    /// Parses `<interval secs>:<floor>:<ceiling>`, e.g. `30:100:10000`.
+    pub fn parse(s: &str) -> Result<Self> {
+        let parts: Vec<_> = s.split(':').collect();
+        let (interval_secs, floor, ceiling) = match parts[..] {
+            [interval_secs, floor, ceiling] => (interval_secs, floor, ceiling),
+            _ => bail!("Expected <interval secs>:<floor>:<ceiling>, got {}", s),
+        };
+        let refresh = Self {
+            interval: Duration::from_secs(interval_secs.parse()?),
+            floor: floor.parse()?,
+            ceiling: ceiling.parse()?,
+        };
+        refresh.validate()?;
+        Ok(refresh)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        ensure!(
+            !self.interval.is_zero(),
+            "Gas price refresh interval needs to be non zero"
+        );
+        ensure!(
+            0 < self.floor && self.floor <= self.ceiling,
+            "Gas price needs 0 < floor <= ceiling, got floor {} and ceiling {}",
+            self.floor,
+            self.ceiling
+        );
+        Ok(())
+    }
+
+    pub fn clamp(&self, estimate: u64) -> u64 {
+        estimate.clamp(self.floor, self.ceiling)
+    }
+}
+
+/// Gas unit price of the run, kept up to date by a background task.
+#[derive(Debug)]
+pub struct DynamicGasPrice {
+    gas_unit_price: Arc<AtomicU64>,
+    refresh_task: JoinHandle<()>,
+}
+
+impl DynamicGasPrice {
+    /// Starts refreshing, from the (clamped) initial gas unit price.
+    pub fn start(client: RestClient, refresh: GasPriceRefresh, initial: u64) -> Self {
+        let gas_unit_price = Arc::new(AtomicU64::new(refresh.clamp(initial)));
+        EMITTER_GAS_UNIT_PRICE.set(gas_unit_price.load(Ordering::Relaxed) as i64);
+        let refresh_task = tokio::spawn(refresh_periodically(
+            client,
+            refresh,
+            gas_unit_price.clone(),
+        ));
+        Self {
+            gas_unit_price,
+            refresh_task,
+        }
+    }
+
+    /// Handle to the current gas unit price, for workers to read.
+    pub fn gas_unit_price(&self) -> Arc<AtomicU64> {
+        self.gas_unit_price.clone()
+    }
+}
+
+impl Drop for DynamicGasPrice {
+    fn drop(&mut self) {
+        self.refresh_task.abort();
+    }
+}
+
+async fn refresh_periodically(
+    client: RestClient,
+    refresh: GasPriceRefresh,
+    gas_unit_price: Arc<AtomicU64>,
+) {
+    loop {
+        match client.estimate_gas_price().await {
+            Ok(estimation) => {
+                let new_price = refresh.clamp(estimation.into_inner().gas_estimate);
+                let old_price = gas_unit_price.swap(new_price, Ordering::Relaxed);
+                if new_price != old_price {
+                    info!(
+                        "Gas unit price of emitted transactions changed {} -> {}",
+                        old_price, new_price
+                    );
+                    EMITTER_GAS_UNIT_PRICE.set(new_price as i64);
+                }
+            },
+            Err(err) => {
+                sample!(
+                    SampleRate::Duration(Duration::from_secs(60)),
+                    warn!(
+                        "[{:?}] Failed to estimate gas price, keeping the current one: {:?}",
+                        client.path_prefix_string(),
+                        err
+                    )
+                );
+            },
+        }
+        tokio::time::sleep(refresh.interval).await;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::GasPriceRefresh;
+    use std::time::Duration;
+
+    #[test]
+    pub fn test_gas_price_refresh() {
+        let refresh = GasPriceRefresh::parse("30:100:1000").unwrap();
+        assert_eq!(refresh, GasPriceRefresh {
+            interval: Duration::from_secs(30),
+            floor: 100,
+            ceiling: 1000,
+        });
+        assert_eq!(refresh.clamp(50), 100);
+        assert_eq!(refresh.clamp(500), 500);
+        assert_eq!(refresh.clamp(5000), 1000);
+
+        assert!(GasPriceRefresh::parse("30:100").is_err());
+        assert!(GasPriceRefresh::parse("30:1000:100").is_err());
+        assert!(GasPriceRefresh::parse("0:100:1000").is_err());
+        assert!(GasPriceRefresh::parse("30:0:1000").is_err());
+    }
+}
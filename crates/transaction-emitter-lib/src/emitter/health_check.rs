@@ -0,0 +1,217 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use aptos_rest_client::{Client as RestClient, State};
+use futures::future::join_all;
+use std::{fmt, time::Duration};
+
+/// Checks of every endpoint before emitting, so that a broken endpoint fails the run
+/// up front with a diagnosis, instead of with confusing submit failures mid-run.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HealthCheck {
+    /// Timestamp of the latest ledger info of each endpoint can be at most this old
+    pub max_ledger_age: Duration,
+    /// If set, each endpoint needs to still have this version, i.e. not be pruned past it
+    pub required_version: Option<u64>,
+}
+
+impl Default for HealthCheck {
+    fn default() -> Self {
+        Self {
+            max_ledger_age: Duration::from_secs(30),
+            required_version: None,
+        }
+    }
+}
+
+/// Problems found with an endpoint, empty if it is healthy.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EndpointDiagnosis {
+    pub endpoint: String,
+    pub problems: Vec<String>,
+}
+
+impl fmt::Display for EndpointDiagnosis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}", self.endpoint, self.problems.join(", "))
+    }
+}
+
+/// Endpoints failing the health check.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HealthCheckFailure {
+    pub unhealthy: Vec<EndpointDiagnosis>,
+    pub num_endpoints: usize,
+}
+
+impl fmt::Display for HealthCheckFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} of {} endpoints failed the health check:",
+            self.unhealthy.len(),
+            self.num_endpoints
+        )?;
+        for diagnosis in &self.unhealthy {
+            write!(f, "\n  {}", diagnosis)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for HealthCheckFailure {}
+
+impl HealthCheck {
+    /// Checks all endpoints concurrently, failing with the diagnosis of each unhealthy one.
+    pub async fn check(&self, rest_clients: &[RestClient]) -> Result<(), HealthCheckFailure> {
+        let now_usecs = aptos_infallible::duration_since_epoch().as_micros() as u64;
+        let states = join_all(
+            rest_clients
+                .iter()
+                .map(|client| client.get_ledger_information()),
+        )
+        .await;
+        let gas_estimations = join_all(
+            rest_clients
+                .iter()
+                .map(|client| client.estimate_gas_price()),
+        )
+        .await;
+        let chain_id = states
+            .iter()
+            .find_map(|state| state.as_ref().ok())
+            .map(|state| state.inner().chain_id);
+
+        let unhealthy: Vec<_> = rest_clients
+            .iter()
+            .zip(states)
+            .zip(gas_estimations)
+            .map(|((client, state), gas_estimation)| {
+                let mut problems = match state {
+                    Ok(state) => self.diagnose_ledger(state.inner(), now_usecs, chain_id),
+                    Err(err) => vec![format!("API unreachable: {}", err)],
+                };
+                if let Err(err) = gas_estimation {
+                    problems.push(format!("gas estimation failed: {}", err));
+                }
+                EndpointDiagnosis {
+                    endpoint: client.path_prefix_string(),
+                    problems,
+                }
+            })
+            .filter(|diagnosis| !diagnosis.problems.is_empty())
+            .collect();
+        if unhealthy.is_empty() {
+            Ok(())
+        } else {
+            Err(HealthCheckFailure {
+                unhealthy,
+                num_endpoints: rest_clients.len(),
+            })
+        }
+    }
+
+    /// Problems with the ledger of an endpoint, given the chain the others are on.
+    pub fn diagnose_ledger(
+        &self,
+        state: &State,
+        now_usecs: u64,
+        chain_id: Option<u8>,
+    ) -> Vec<String> {
+        let mut problems = vec![];
+        if let Some(chain_id) = chain_id {
+            if state.chain_id != chain_id {
+                problems.push(format!(
+                    "on chain {}, while other endpoints are on chain {}",
+                    state.chain_id, chain_id
+                ));
+            }
+        }
+        let ledger_age = Duration::from_micros(now_usecs.saturating_sub(state.timestamp_usecs));
+        if ledger_age > self.max_ledger_age {
+            problems.push(format!(
+                "ledger is {}s old, more than allowed {}s",
+                ledger_age.as_secs(),
+                self.max_ledger_age.as_secs()
+            ));
+        }
+        if let Some(required_version) = self.required_version {
+            if state.oldest_ledger_version > required_version {
+                problems.push(format!(
+                    "pruned past required version {}, oldest version is {}",
+                    required_version, state.oldest_ledger_version
+                ));
+            } else if state.version < required_version {
+                problems.push(format!(
+                    "not yet at required version {}, latest version is {}",
+                    required_version, state.version
+                ));
+            }
+        }
+        problems
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{EndpointDiagnosis, HealthCheck, HealthCheckFailure};
+    use aptos_rest_client::State;
+    use std::time::Duration;
+
+    #[test]
+    pub fn test_diagnose_ledger() {
+        let health_check = HealthCheck {
+            max_ledger_age: Duration::from_secs(30),
+            required_version: Some(1000),
+        };
+        let now_usecs = 100_000_000;
+        let state = State {
+            chain_id: 4,
+            epoch: 2,
+            version: 5000,
+            timestamp_usecs: now_usecs - 10_000_000,
+            oldest_ledger_version: 0,
+            oldest_block_height: 0,
+            block_height: 100,
+            cursor: None,
+        };
+        assert!(health_check
+            .diagnose_ledger(&state, now_usecs, Some(4))
+            .is_empty());
+
+        let unhealthy = State {
+            chain_id: 5,
+            timestamp_usecs: now_usecs - 60_000_000,
+            oldest_ledger_version: 2000,
+            ..state.clone()
+        };
+        assert_eq!(
+            health_check.diagnose_ledger(&unhealthy, now_usecs, Some(4)),
+            vec![
+                "on chain 5, while other endpoints are on chain 4".to_string(),
+                "ledger is 60s old, more than allowed 30s".to_string(),
+                "pruned past required version 1000, oldest version is 2000".to_string(),
+            ]
+        );
+        let behind = State {
+            version: 500,
+            ..state
+        };
+        assert_eq!(
+            health_check.diagnose_ledger(&behind, now_usecs, None),
+            vec!["not yet at required version 1000, latest version is 500".to_string()]
+        );
+
+        let failure = HealthCheckFailure {
+            unhealthy: vec![EndpointDiagnosis {
+                endpoint: "http://node:8080/v1".to_string(),
+                problems: vec!["API unreachable: timeout".to_string(), "x".to_string()],
+            }],
+            num_endpoints: 2,
+        };
+        assert_eq!(
+            failure.to_string(),
+            "1 of 2 endpoints failed the health check:\n  [http://node:8080/v1] API unreachable: timeout, x"
+        );
+    }
+}
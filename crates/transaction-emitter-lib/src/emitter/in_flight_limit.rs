@@ -0,0 +1,113 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::args::target_matches;
+use aptos_infallible::Mutex;
+use aptos_rest_client::Client as RestClient;
+use std::{collections::HashMap, sync::Arc};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use url::Url;
+
+/// Caps on transactions in flight (submitted, and not yet committed or expired) to each
+/// endpoint, so that workers of a slow endpoint wait for its transactions to complete,
+/// instead of piling up requests to it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct InFlightLimits {
+    /// Cap of endpoints without one of their own
+    pub default: Option<usize>,
+    /// Caps of matching endpoints, as (host or `*`, cap), later entries overriding earlier ones
+    pub per_endpoint: Vec<(String, usize)>,
+}
+
+impl InFlightLimits {
+    pub fn limit_for(&self, url: &Url) -> Option<usize> {
+        self.per_endpoint
+            .iter()
+            .rev()
+            .find(|(target, _)| target_matches(target, url))
+            .map(|(_, limit)| *limit)
+            .or(self.default)
+    }
+}
+
+/// Permits for in-flight transactions, shared by all workers submitting to an endpoint.
+#[derive(Debug)]
+pub struct InFlightPermits {
+    limits: InFlightLimits,
+    // semaphore of each endpoint, by its url, None if it has no cap
+    semaphores: Mutex<HashMap<String, Option<(Arc<Semaphore>, usize)>>>,
+}
+
+impl InFlightPermits {
+    pub fn new(limits: InFlightLimits) -> Self {
+        Self {
+            limits,
+            semaphores: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn semaphore_for(&self, client: &RestClient) -> Option<(Arc<Semaphore>, usize)> {
+        let endpoint = client.path_prefix_string();
+        self.semaphores
+            .lock()
+            .entry(endpoint.clone())
+            .or_insert_with(|| {
+                let limit = self.limits.limit_for(&Url::parse(&endpoint).ok()?)?;
+                Some((Arc::new(Semaphore::new(limit)), limit))
+            })
+            .clone()
+    }
+
+    /// Waits for permits for the given number of transactions to the client's endpoint,
+    /// held until dropped. A batch larger than the cap takes all of it. None if the
+    /// endpoint has no cap.
+    pub async fn acquire(
+        &self,
+        client: &RestClient,
+        num_txns: usize,
+    ) -> Option<OwnedSemaphorePermit> {
+        let (semaphore, limit) = self.semaphore_for(client)?;
+        semaphore
+            .acquire_many_owned(num_txns.clamp(1, limit) as u32)
+            .await
+            .ok()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{InFlightLimits, InFlightPermits};
+    use aptos_rest_client::Client as RestClient;
+    use url::Url;
+
+    #[tokio::test]
+    pub async fn test_in_flight_permits() {
+        let limits = InFlightLimits {
+            default: Some(100),
+            per_endpoint: vec![("*".to_string(), 50), ("slow".to_string(), 10)],
+        };
+        let url = |url: &str| Url::parse(url).unwrap();
+        assert_eq!(limits.limit_for(&url("http://slow:8080")), Some(10));
+        assert_eq!(limits.limit_for(&url("http://fast:8080")), Some(50));
+        assert_eq!(
+            InFlightLimits::default().limit_for(&url("http://fast:8080")),
+            None
+        );
+
+        let permits = InFlightPermits::new(limits);
+        let slow = RestClient::new(url("http://slow:8080"));
+        let first = permits.acquire(&slow, 6).await.unwrap();
+        // a batch larger than the cap takes all of it, so waits for the first to complete
+        let second = permits.acquire(&slow, 20);
+        tokio::pin!(second);
+        assert!(futures::poll!(second.as_mut()).is_pending());
+        drop(first);
+        let second = second.await.unwrap();
+        assert!(futures::poll!(Box::pin(permits.acquire(&slow, 1))).is_pending());
+        drop(second);
+        assert!(InFlightPermits::new(InFlightLimits::default())
+            .acquire(&slow, 6)
+            .await
+            .is_none());
+    }
+}
@@ -0,0 +1,219 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::emitter::{stats::TxnStats, EmitJobRequest, TxnEmitter};
+use anyhow::{format_err, Result};
+use aptos_rest_client::Client as RestClient;
+use aptos_sdk::{transaction_builder::TransactionFactory, types::LocalAccount};
+use rand::rngs::StdRng;
+use rand_core::SeedableRng;
+use std::{fmt, sync::Arc, time::Duration};
+use tokio::{sync::Notify, task::JoinHandle};
+
+type PhaseStartCallback = Arc<dyn Fn(usize) + Send + Sync>;
+type StatsTickCallback = Arc<dyn Fn(usize, &TxnStats) + Send + Sync>;
+type FinishCallback = Arc<dyn Fn(&Result<Vec<TxnStats>>) + Send + Sync>;
+
+/// Callbacks invoked at points of the run's lifecycle, for harnesses embedding the
+/// emitter to follow the run without parsing its logs.
+#[derive(Clone, Default)]
+pub struct EmitCallbacks {
+    on_phase_start: Option<PhaseStartCallback>,
+    on_stats_tick: Option<StatsTickCallback>,
+    on_finish: Option<FinishCallback>,
+}
+
+impl fmt::Debug for EmitCallbacks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EmitCallbacks")
+            .field("on_phase_start", &self.on_phase_start.is_some())
+            .field("on_stats_tick", &self.on_stats_tick.is_some())
+            .field("on_finish", &self.on_finish.is_some())
+            .finish()
+    }
+}
+
+impl EmitCallbacks {
+    pub fn has_stats_tick(&self) -> bool {
+        self.on_stats_tick.is_some()
+    }
+
+    pub fn phase_started(&self, phase: usize) {
+        if let Some(on_phase_start) = &self.on_phase_start {
+            on_phase_start(phase);
+        }
+    }
+
+    /// Stats of the phase since the previous tick.
+    pub fn stats_tick(&self, phase: usize, stats: &TxnStats) {
+        if let Some(on_stats_tick) = &self.on_stats_tick {
+            on_stats_tick(phase, stats);
+        }
+    }
+
+    pub fn finished(&self, result: &Result<Vec<TxnStats>>) {
+        if let Some(on_finish) = &self.on_finish {
+            on_finish(result);
+        }
+    }
+}
+
+/// Builds and starts an emitter run in the background, for embedding the emitter into
+/// tests and harnesses, instead of running the CLI.
+pub struct EmitJobBuilder {
+    emitter: TxnEmitter,
+    request: EmitJobRequest,
+    duration: Duration,
+    stats_interval_secs: Option<u64>,
+}
+
+impl EmitJobBuilder {
+    pub fn new(txn_factory: TransactionFactory, rest_clients: Vec<RestClient>) -> Self {
+        Self {
+            emitter: TxnEmitter::new(txn_factory, StdRng::from_entropy()),
+            request: EmitJobRequest::new(rest_clients),
+            duration: Duration::from_secs(60),
+            stats_interval_secs: None,
+        }
+    }
+
+    pub fn rng(mut self, rng: StdRng) -> Self {
+        self.emitter = TxnEmitter::new(self.emitter.txn_factory.clone(), rng);
+        self
+    }
+
+    /// Configures the request of the run, e.g. its mode and transaction mix.
+    pub fn request(mut self, configure: impl FnOnce(EmitJobRequest) -> EmitJobRequest) -> Self {
+        self.request = configure(self.request);
+        self
+    }
+
+    /// Duration of the run, split across phases unless they have their own.
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    /// How often stats are logged and on_stats_tick is called, every 10s if not set
+    /// while there is an on_stats_tick callback.
+    pub fn stats_interval_secs(mut self, stats_interval_secs: u64) -> Self {
+        self.stats_interval_secs = Some(stats_interval_secs);
+        self
+    }
+
+    pub fn on_phase_start(
+        mut self,
+        on_phase_start: impl Fn(usize) + Send + Sync + 'static,
+    ) -> Self {
+        self.request.callbacks.on_phase_start = Some(Arc::new(on_phase_start));
+        self
+    }
+
+    pub fn on_stats_tick(
+        mut self,
+        on_stats_tick: impl Fn(usize, &TxnStats) + Send + Sync + 'static,
+    ) -> Self {
+        self.request.callbacks.on_stats_tick = Some(Arc::new(on_stats_tick));
+        self
+    }
+
+    pub fn on_finish(
+        mut self,
+        on_finish: impl Fn(&Result<Vec<TxnStats>>) + Send + Sync + 'static,
+    ) -> Self {
+        self.request.callbacks.on_finish = Some(Arc::new(on_finish));
+        self
+    }
+
+    /// Starts the run, funding it from the source account, which is handed back once the
+    /// run finishes, with its sequence number updated.
+    pub fn start(mut self, mut source_account: LocalAccount) -> EmitJobHandle {
+        let stop = Arc::new(Notify::new());
+        self.request.stop_notify = Some(stop.clone());
+        let stats_interval_secs = self
+            .stats_interval_secs
+            .or_else(|| self.request.callbacks.has_stats_tick().then_some(10));
+        let task = tokio::spawn(async move {
+            let result = self
+                .emitter
+                .emit_txn_for_impl(
+                    &mut source_account,
+                    self.request,
+                    self.duration,
+                    stats_interval_secs,
+                )
+                .await;
+            (source_account, result)
+        });
+        EmitJobHandle { stop, task }
+    }
+}
+
+/// Handle to a run started by EmitJobBuilder.
+#[derive(Debug)]
+pub struct EmitJobHandle {
+    stop: Arc<Notify>,
+    task: JoinHandle<(LocalAccount, Result<Vec<TxnStats>>)>,
+}
+
+impl EmitJobHandle {
+    /// Stops the run early, waiting for in-flight transactions as at the end of the run.
+    pub fn stop(&self) {
+        self.stop.notify_one();
+    }
+
+    /// Waits for the run to finish, returning the source account, and stats of each phase.
+    pub async fn join(self) -> Result<(LocalAccount, Vec<TxnStats>)> {
+        let (source_account, result) = self
+            .task
+            .await
+            .map_err(|e| format_err!("Emit job task failed: {:?}", e))?;
+        Ok((source_account, result?))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::EmitJobBuilder;
+    use crate::emitter::stats::TxnStats;
+    use aptos_rest_client::Client as RestClient;
+    use aptos_sdk::{transaction_builder::TransactionFactory, types::chain_id::ChainId};
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+    use url::Url;
+
+    #[test]
+    pub fn test_emit_callbacks() {
+        let phases_started = Arc::new(AtomicUsize::new(0));
+        let committed = Arc::new(AtomicUsize::new(0));
+        let builder = EmitJobBuilder::new(TransactionFactory::new(ChainId::test()), vec![
+            RestClient::new(Url::parse("http://localhost:8080").unwrap()),
+        ])
+        .on_phase_start({
+            let phases_started = phases_started.clone();
+            move |phase| {
+                phases_started.fetch_max(phase + 1, Ordering::Relaxed);
+            }
+        })
+        .on_stats_tick({
+            let committed = committed.clone();
+            move |_, stats| {
+                committed.fetch_add(stats.committed as usize, Ordering::Relaxed);
+            }
+        });
+        let callbacks = &builder.request.callbacks;
+        assert!(callbacks.has_stats_tick());
+        callbacks.phase_started(0);
+        callbacks.phase_started(1);
+        callbacks.stats_tick(1, &TxnStats {
+            committed: 100,
+            ..TxnStats::default()
+        });
+        // no on_finish callback
+        callbacks.finished(&Ok(vec![]));
+        assert_eq!(phases_started.load(Ordering::Relaxed), 2);
+        assert_eq!(committed.load(Ordering::Relaxed), 100);
+    }
+}
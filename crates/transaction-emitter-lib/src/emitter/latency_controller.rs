@@ -0,0 +1,107 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::emitter::stats::TxnStats;
+use std::{
+    cmp::{max, min},
+    time::Duration,
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LatencyTarget {
+    /// p99 commit latency to keep emission under.
+    pub target_p99_latency: Duration,
+    pub initial_tps: usize,
+    /// Workers are created for this TPS, so it is never exceeded.
+    pub max_tps: usize,
+    /// How often TPS is adjusted, based on stats since the previous adjustment.
+    pub adjust_interval: Duration,
+}
+
+/// Adjusts emitted TPS to keep p99 commit latency under target, increasing TPS
+/// additively while under target, and decreasing it multiplicatively once over it.
+#[derive(Debug)]
+pub struct LatencyController {
+    target: LatencyTarget,
+    tps: usize,
+    sustainable_tps: Option<u64>,
+}
+
+impl LatencyController {
+    pub fn new(target: LatencyTarget) -> Self {
+        assert!(
+            target.initial_tps > 0 && target.initial_tps <= target.max_tps,
+            "Initial TPS needs to be positive, and at most max TPS"
+        );
+        Self {
+            tps: target.initial_tps,
+            target,
+            sustainable_tps: None,
+        }
+    }
+
+    pub fn target(&self) -> &LatencyTarget {
+        &self.target
+    }
+
+    pub fn tps(&self) -> usize {
+        self.tps
+    }
+
+    /// Highest committed TPS observed during an interval with p99 latency under target.
+    pub fn sustainable_tps(&self) -> Option<u64> {
+        self.sustainable_tps
+    }
+
+    /// Adjusts TPS based on the stats of the last interval, returning the new TPS.
+    /// TPS is kept if there were no latency samples in the interval.
+    pub fn on_interval(&mut self, stats: &TxnStats) -> usize {
+        let rate = stats.rate();
+        if rate.latency_samples == 0 {
+            return self.tps;
+        }
+        if rate.p99_latency <= self.target.target_p99_latency.as_millis() as u64 {
+            self.sustainable_tps = max(self.sustainable_tps, Some(rate.committed));
+            self.tps = min(
+                self.target.max_tps,
+                self.tps + max(1, self.target.max_tps / 20),
+            );
+        } else {
+            self.tps = max(1, self.tps * 3 / 4);
+        }
+        self.tps
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{LatencyController, LatencyTarget};
+    use crate::emitter::stats::{AtomicHistogramAccumulator, TxnStats};
+    use std::time::Duration;
+
+    fn stats(committed: u64, latency_millis: u64) -> TxnStats {
+        let latencies = AtomicHistogramAccumulator::default();
+        latencies.record_data_point(latency_millis, 100);
+        TxnStats {
+            committed,
+            latency_samples: 100,
+            latency_buckets: latencies.snapshot(),
+            lasted: Duration::from_secs(1),
+            ..TxnStats::default()
+        }
+    }
+
+    #[test]
+    pub fn test_latency_controller() {
+        let mut controller = LatencyController::new(LatencyTarget {
+            target_p99_latency: Duration::from_secs(2),
+            initial_tps: 1000,
+            max_tps: 2000,
+            adjust_interval: Duration::from_secs(10),
+        });
+        assert_eq!(controller.on_interval(&TxnStats::default()), 1000);
+        assert_eq!(controller.on_interval(&stats(1000, 1000)), 1100);
+        assert_eq!(controller.on_interval(&stats(1100, 3000)), 825);
+        assert_eq!(controller.sustainable_tps(), Some(1000));
+    }
+}
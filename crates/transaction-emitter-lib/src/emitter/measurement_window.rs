@@ -0,0 +1,120 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::emitter::stats::{DynamicStatsTracking, TxnStats};
+use aptos_infallible::Mutex;
+use std::{
+    cmp::{max, min},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::time;
+
+/// Stats of each phase at some point in the run.
+pub type StatsSnapshot = (Instant, Vec<TxnStats>);
+
+/// Periods at the start and at the end of the run, excluded from the reported stats,
+/// so that warm-up (JIT, caches filling) and the final drain don't skew steady-state
+/// results. Stats of the whole run are still logged.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MeasurementWindow {
+    pub warm_up: Duration,
+    pub cool_down: Duration,
+}
+
+/// Snapshots of stats when warm-up ended and when cool-down started.
+#[derive(Debug, Default)]
+pub struct WindowSnapshots {
+    warm_up_end: Mutex<Option<StatsSnapshot>>,
+    cool_down_start: Mutex<Option<StatsSnapshot>>,
+}
+
+impl MeasurementWindow {
+    /// Takes snapshots at the boundaries of the window, given the run started at start,
+    /// and lasts for duration.
+    pub async fn take_snapshots(
+        self,
+        start: Instant,
+        duration: Duration,
+        stats: Arc<DynamicStatsTracking>,
+        snapshots: Arc<WindowSnapshots>,
+    ) {
+        let warm_up_end = start + self.warm_up;
+        time::sleep_until(warm_up_end.into()).await;
+        *snapshots.warm_up_end.lock() = Some((warm_up_end, stats.snapshot()));
+
+        let cool_down_start = start + duration.saturating_sub(self.cool_down);
+        time::sleep_until(cool_down_start.into()).await;
+        *snapshots.cool_down_start.lock() = Some((cool_down_start, stats.snapshot()));
+    }
+}
+
+impl WindowSnapshots {
+    /// Stats of each phase between the end of warm-up and the start of cool-down, given
+    /// stats of each phase at the end of the run. If the run stopped before cool-down
+    /// started, it counts as starting at the end.
+    /// None if the run stopped before warm-up ended.
+    pub fn exclude_windows(
+        &self,
+        stats: &[TxnStats],
+        phase_starts: &[Instant],
+        end: Instant,
+    ) -> Option<Vec<TxnStats>> {
+        let (warm_up_end, at_warm_up_end) = self.warm_up_end.lock().clone()?;
+        let (cool_down_start, at_cool_down_start) = self
+            .cool_down_start
+            .lock()
+            .clone()
+            .unwrap_or_else(|| (end, stats.to_vec()));
+        let default_stats = TxnStats::default();
+        Some(
+            (0..stats.len())
+                .map(|phase| {
+                    let phase_end = phase_starts.get(phase + 1).copied().unwrap_or(end);
+                    let window_start = max(phase_starts[phase], warm_up_end);
+                    let window_end = min(phase_end, cool_down_start);
+                    let mut steady = at_cool_down_start.get(phase).unwrap_or(&default_stats)
+                        - at_warm_up_end.get(phase).unwrap_or(&default_stats);
+                    steady.lasted = window_end.saturating_duration_since(window_start);
+                    steady
+                })
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::WindowSnapshots;
+    use crate::emitter::stats::TxnStats;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    pub fn test_exclude_windows() {
+        let stats = |committed, lasted_secs| TxnStats {
+            submitted: committed,
+            committed,
+            lasted: Duration::from_secs(lasted_secs),
+            ..TxnStats::default()
+        };
+        let start = Instant::now();
+        let secs = |secs| start + Duration::from_secs(secs);
+        // two phases of 60s, with 10s warm-up and 20s cool-down
+        let phase_starts = [start, secs(60)];
+        let snapshots = WindowSnapshots::default();
+        let final_stats = [stats(6000, 60), stats(9000, 60)];
+        assert!(snapshots
+            .exclude_windows(&final_stats, &phase_starts, secs(120))
+            .is_none());
+
+        *snapshots.warm_up_end.lock() = Some((secs(10), vec![stats(500, 0)]));
+        *snapshots.cool_down_start.lock() = Some((secs(100), vec![stats(6000, 0), stats(6000, 0)]));
+        let steady = snapshots
+            .exclude_windows(&final_stats, &phase_starts, secs(120))
+            .unwrap();
+        assert_eq!(steady[0].committed, 5500);
+        assert_eq!(steady[0].lasted, Duration::from_secs(50));
+        assert_eq!(steady[1].committed, 6000);
+        assert_eq!(steady[1].lasted, Duration::from_secs(40));
+    }
+}
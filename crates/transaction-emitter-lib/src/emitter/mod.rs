@@ -2,19 +2,89 @@
 // SPDX-License-Identifier: Apache-2.0
 
 pub mod account_minter;
+pub mod account_pool_file;
+pub mod backlog_throttle;
+pub mod burst_schedule;
+pub mod circuit_breaker;
+pub mod confirmer;
+pub mod counter_reporter;
+pub mod counters;
+pub mod divergence_detector;
+pub mod drain;
+pub mod endpoint_discovery;
+pub mod endpoint_groups;
+pub mod faucet_funding;
+pub mod fault_injection;
+pub mod funding_tree;
+pub mod gas_price_refresh;
+pub mod health_check;
+pub mod in_flight_limit;
+pub mod job_builder;
+pub mod latency_controller;
+pub mod measurement_window;
+pub mod phase_plan;
+pub mod publish_failures;
+pub mod pushgateway;
+pub mod rate_limiter;
+pub mod recycling_pool;
+pub mod regression_check;
+pub mod result_report;
+pub mod retry_policy;
+pub mod run_state;
+pub mod sender_affinity;
+pub mod sequence_audit;
+pub mod sequence_number_cache;
+pub mod signature_scheme;
 pub mod stats;
+pub mod stats_csv;
+pub mod stop_conditions;
 pub mod submission_worker;
 pub mod transaction_executor;
+pub mod worker_stats;
 
 use crate::emitter::{
-    account_minter::AccountMinter,
-    stats::{DynamicStatsTracking, TxnStats},
+    account_minter::{derived_minter_seed, AccountMinter},
+    account_pool_file::AccountPoolFile,
+    backlog_throttle::{BacklogTarget, BacklogThrottle},
+    burst_schedule::BurstSchedule,
+    circuit_breaker::CircuitBreakerConfig,
+    confirmer::{GrpcStreamConfirmer, RestStreamConfirmer},
+    counter_reporter::{CounterReportSink, CounterReporter},
+    divergence_detector::{Divergence, DivergenceDetector},
+    drain::InFlightDrain,
+    endpoint_discovery::{EndpointDiscovery, EndpointRefresh},
+    endpoint_groups::{EndpointGroups, GroupStats},
+    faucet_funding::FaucetFunding,
+    fault_injection::FaultInjection,
+    funding_tree::FundingTree,
+    gas_price_refresh::{DynamicGasPrice, GasPriceRefresh},
+    health_check::HealthCheck,
+    in_flight_limit::{InFlightLimits, InFlightPermits},
+    job_builder::EmitCallbacks,
+    latency_controller::{LatencyController, LatencyTarget},
+    measurement_window::{MeasurementWindow, WindowSnapshots},
+    phase_plan::{PhaseFailures, PlannedPhase},
+    pushgateway::{PushgatewayConfig, RunMetricsPusher},
+    rate_limiter::RateLimit,
+    recycling_pool::RecyclingAccountPool,
+    regression_check::RegressionCheck,
+    result_report::{total_balance, GasReport, ResultReport},
+    retry_policy::{retry, ExponentialRetryPolicy},
+    run_state::{RunPhase, RunState},
+    sequence_audit::SequenceAudit,
+    signature_scheme::SignatureScheme,
+    stats::{DynamicStatsTracking, StatsAccumulator, TxnStats},
+    stats_csv::StatsCsvWriter,
+    stop_conditions::{StopConditions, StopReason, StopSignal},
     submission_worker::SubmissionWorker,
-    transaction_executor::RestApiTransactionExecutor,
+    transaction_executor::{
+        ClientSelection, MempoolFullBackoff, RestApiTransactionExecutor, RetryBackoff, WaitStrategy,
+    },
+    worker_stats::{WorkerImbalance, WorkerStats},
 };
-use again::RetryPolicy;
-use anyhow::{ensure, format_err, Result};
+use anyhow::{ensure, format_err, Context, Result};
 use aptos_config::config::DEFAULT_MAX_SUBMIT_TRANSACTION_BATCH_SIZE;
+use aptos_infallible::Mutex;
 use aptos_logger::{debug, error, info, sample, sample::SampleRate, warn};
 use aptos_rest_client::Client as RestClient;
 use aptos_sdk::{
@@ -22,21 +92,30 @@ use aptos_sdk::{
     transaction_builder::{aptos_stdlib, TransactionFactory},
     types::{transaction::SignedTransaction, LocalAccount},
 };
-use aptos_transaction_generator_lib::{create_txn_generator_creator, TransactionType};
+use aptos_transaction_generator_lib::{
+    create_txn_generator_creator, entry_function_generator::EntryFunctionTemplate,
+    phased_generator::GeneratorPhase, txn_factory_overrides::TxnFactoryOverrides, TransactionType,
+};
 use futures::future::{try_join_all, FutureExt};
 use once_cell::sync::Lazy;
-use rand::{rngs::StdRng, seq::IteratorRandom, Rng};
+use rand::{
+    rngs::StdRng,
+    seq::{IteratorRandom, SliceRandom},
+    Rng,
+};
 use rand_core::SeedableRng;
 use std::{
     cmp::{max, min},
     collections::{HashMap, HashSet},
+    iter,
+    path::PathBuf,
     sync::{
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicBool, AtomicUsize, Ordering},
         Arc,
     },
     time::{Duration, Instant},
 };
-use tokio::{runtime::Handle, task::JoinHandle, time};
+use tokio::{runtime::Handle, sync::Notify, task::JoinHandle, time};
 
 // Max is 100k TPS for 3 hours
 const MAX_TXNS: u64 = 1_000_000_000;
@@ -48,11 +127,8 @@ const MAX_RETRIES: usize = 12;
 // account sequence numbers). If these fail, the whole test fails. We do not use
 // this for submitting transactions, as we have a way to handle when that fails.
 // This retry policy means an operation will take 8 seconds at most.
-pub static RETRY_POLICY: Lazy<RetryPolicy> = Lazy::new(|| {
-    RetryPolicy::exponential(Duration::from_millis(125))
-        .with_max_retries(MAX_RETRIES)
-        .with_jitter(true)
-});
+pub static RETRY_POLICY: Lazy<ExponentialRetryPolicy> =
+    Lazy::new(|| ExponentialRetryPolicy::new(MAX_RETRIES, Duration::from_millis(125)));
 
 #[derive(Clone, Debug)]
 pub struct EmitModeParams {
@@ -79,6 +155,13 @@ pub enum WorkerOffsetMode {
     Wave { wave_ratio: f64, num_waves: f64 },
 }
 
+/// Part of a TPS schedule, emitting at tps for duration.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TpsSegment {
+    pub duration: Duration,
+    pub tps: usize,
+}
+
 #[derive(Clone, Debug)]
 pub enum EmitJobMode {
     MaxLoad {
@@ -92,6 +175,22 @@ pub enum EmitJobMode {
         wave_ratio: f32,
         num_waves: usize,
     },
+    /// Emits at the TPS of each segment in turn, tracking stats of each segment
+    /// as a separate phase. Workers are created for the peak TPS, and only a part
+    /// of them submits transactions during segments with lower TPS.
+    TpsSchedule {
+        segments: Vec<TpsSegment>,
+    },
+    /// Emits at baseline TPS, with periodic bursts at higher TPS. Intervals
+    /// of periodic stats that overlap a burst are marked as such.
+    BurstTps {
+        schedule: BurstSchedule,
+    },
+    /// Adjusts TPS during the run, to keep p99 commit latency under target,
+    /// finding the sustainable TPS of the network.
+    LatencyTargeted {
+        target: LatencyTarget,
+    },
 }
 
 impl EmitJobMode {
@@ -110,6 +209,148 @@ impl EmitJobMode {
             }
         }
     }
+
+    /// TPS to create workers for, None in MaxLoad mode.
+    fn peak_tps(&self) -> Option<usize> {
+        match self {
+            Self::MaxLoad { .. } => None,
+            Self::ConstTps { tps } => Some(*tps),
+            Self::WaveTps { average_tps, .. } => Some(*average_tps),
+            Self::TpsSchedule { segments } => segments.iter().map(|segment| segment.tps).max(),
+            Self::BurstTps { schedule } => Some(schedule.burst_tps),
+            Self::LatencyTargeted { target } => Some(target.max_tps),
+        }
+    }
+}
+
+/// Switches number of active workers between baseline and bursts, until aborted.
+async fn run_bursts(
+    schedule: BurstSchedule,
+    start: Instant,
+    active_workers: Arc<AtomicUsize>,
+    num_workers: usize,
+) {
+    loop {
+        let elapsed = start.elapsed();
+        let tps = schedule.tps_at(elapsed);
+        if schedule.is_burst(elapsed) {
+            info!("Burst started, emitting at {} TPS", tps);
+        }
+        active_workers.store(
+            active_workers_for_tps(num_workers, tps, schedule.burst_tps),
+            Ordering::Relaxed,
+        );
+        time::sleep(schedule.until_next_switch(elapsed)).await;
+    }
+}
+
+/// Logs gaps between sequence numbers of the accounts and the ones on chain, returning
+/// the audit, None if it failed.
+async fn audit_sequence_numbers(
+    client: &RestClient,
+    accounts: &[LocalAccount],
+) -> Option<SequenceAudit> {
+    match SequenceAudit::run(client, accounts).await {
+        Ok(audit) => {
+            if audit.gaps.is_empty() {
+                info!(
+                    "Sequence numbers of all {} accounts match the chain",
+                    audit.accounts_checked
+                );
+            } else {
+                warn!(
+                    "Sequence numbers of {} of {} accounts don't match the chain: {} transactions counted as committed never committed, {} commits never observed",
+                    audit.gaps.len(),
+                    audit.accounts_checked,
+                    audit.never_committed,
+                    audit.never_observed
+                );
+                for gap in audit.gaps.iter().take(MAX_LOGGED_SEQUENCE_GAPS) {
+                    warn!("Sequence gap of {}", gap);
+                }
+            }
+            Some(audit)
+        },
+        Err(e) => {
+            error!("Failed to audit sequence numbers: {:?}", e);
+            None
+        },
+    }
+}
+
+const MAX_LOGGED_SEQUENCE_GAPS: usize = 20;
+
+/// Loads accounts from account_pool_file, with their sequence numbers updated from chain,
+/// as they might have changed since the accounts were saved.
+async fn load_account_pool(
+    account_pool_file: &AccountPoolFile,
+    client: &RestClient,
+) -> Result<Vec<LocalAccount>> {
+    let mut accounts = account_pool_file.load()?;
+    refresh_sequence_numbers(client, &mut accounts).await?;
+    info!("Loaded {} accounts from account pool", accounts.len());
+    Ok(accounts)
+}
+
+/// Sets sequence numbers of accounts to the ones on chain.
+async fn refresh_sequence_numbers(
+    client: &RestClient,
+    accounts: &mut [LocalAccount],
+) -> Result<()> {
+    for batch in accounts.chunks_mut(SEQUENCE_NUMBER_QUERY_BATCH_SIZE) {
+        let addresses = batch.iter().map(LocalAccount::address).collect::<Vec<_>>();
+        let (seq_nums, _) = query_sequence_numbers(client, addresses.iter()).await?;
+        for (account, (_, seq_num)) in batch.iter_mut().zip(seq_nums) {
+            *account.sequence_number_mut() = seq_num;
+        }
+    }
+    Ok(())
+}
+
+const SEQUENCE_NUMBER_QUERY_BATCH_SIZE: usize = 100;
+
+/// Adjusts number of active workers every adjust_interval, based on the stats since
+/// the previous adjustment, until aborted.
+async fn run_latency_controller(
+    controller: Arc<Mutex<LatencyController>>,
+    adjust_interval: Duration,
+    stats: Arc<DynamicStatsTracking>,
+    active_workers: Arc<AtomicUsize>,
+    num_workers: usize,
+) {
+    let max_tps = controller.lock().target().max_tps;
+    let mut prev: Option<(usize, TxnStats)> = None;
+    loop {
+        time::sleep(adjust_interval).await;
+        let phase = stats.get_cur_phase();
+        let cur = stats.get_cur().accumulate(adjust_interval);
+        let delta = match &prev {
+            Some((prev_phase, prev_stats)) if *prev_phase == phase => {
+                let mut delta = &cur - prev_stats;
+                delta.lasted = adjust_interval;
+                delta
+            },
+            _ => cur.clone(),
+        };
+        prev = Some((phase, cur));
+
+        let tps = controller.lock().on_interval(&delta);
+        info!(
+            "Latency controller: p99 latency {} ms, adjusting to {} TPS",
+            delta.rate().p99_latency,
+            tps
+        );
+        active_workers.store(
+            active_workers_for_tps(num_workers, tps, max_tps),
+            Ordering::Relaxed,
+        );
+    }
+}
+
+/// Number of workers (out of num_workers, created for peak_tps) that need to be active
+/// to emit at tps.
+fn active_workers_for_tps(num_workers: usize, tps: usize, peak_tps: usize) -> usize {
+    min(num_workers, (num_workers * tps + peak_tps - 1) / peak_tps)
 }
 
 /// total coins consumed are less than 2 * max_txns * expected_gas_per_txn * gas_price,
@@ -120,18 +361,110 @@ pub struct EmitJobRequest {
     mode: EmitJobMode,
 
     transaction_mix_per_phase: Vec<Vec<(TransactionType, usize)>>,
+    // gas and expiration of each workload of each phase's transaction mix, over the global ones
+    workload_txn_factory_overrides: Vec<Vec<TxnFactoryOverrides>>,
+    // for phases running their workloads one after another, the generator phase of each
+    workload_generator_phases: Vec<Option<Vec<GeneratorPhase>>>,
+    // called by CustomEntryFunction workloads, by index
+    entry_functions: Vec<EntryFunctionTemplate>,
 
     max_gas_per_txn: u64,
     gas_price: u64,
     init_gas_price_multiplier: u64,
+    // if set, gas price of emitted transactions is refreshed from gas estimation,
+    // starting from gas_price
+    gas_price_refresh: Option<GasPriceRefresh>,
+    // if set, endpoints are checked before emitting, failing the job if any is unhealthy
+    health_check: Option<HealthCheck>,
+    // if set, endpoints are re-discovered from the validator set at the interval,
+    // with workers moving to the new ones when it changes
+    endpoint_refresh: Option<(EndpointDiscovery, Duration)>,
+    // caps on transactions in flight to each endpoint, none if empty
+    in_flight_limits: InFlightLimits,
+    // if set, emission is reduced while the backlog of in-flight transactions is over target
+    backlog_target: Option<BacklogTarget>,
 
     reuse_accounts: bool,
+    // if set, workers check out the senders of each round from a pool shared by all
+    recycle_accounts: bool,
     mint_to_root: bool,
+    // if set, seed accounts are funded through the faucet, instead of from the source account
+    faucet_funding: Option<FaucetFunding>,
+    // if set, accounts are derived from the source account, being the same in every run
+    derive_accounts_from_source: bool,
+    // if set, accounts are derived from it and their index, being the same in every run
+    account_pool_seed: Option<u64>,
+    funding_tree: FundingTree,
+    // scheme of the accounts created for emitting transactions
+    account_signature_scheme: SignatureScheme,
 
     txn_expiration_time_secs: u64,
     init_expiration_multiplier: f64,
 
     init_retry_interval: Duration,
+    init_retry_backoff: RetryBackoff,
+    init_circuit_breaker: Option<CircuitBreakerConfig>,
+    init_client_selection: ClientSelection,
+    init_batch_submit: bool,
+    init_batch_wait: bool,
+    init_mempool_full_backoff: Option<MempoolFullBackoff>,
+    init_simulate_before_submit: bool,
+    init_rate_limit: Option<RateLimit>,
+    init_stream_confirmation: bool,
+    // address of indexer grpc data service, and its auth token
+    init_grpc_stream_confirmation: Option<(String, Option<String>)>,
+    // if set, clients are validated before initialization, with the given max version lag
+    init_validate_clients: Option<u64>,
+    // number of clients initialization transactions need to be visible on
+    init_confirmation_quorum: Option<usize>,
+    init_wait_strategy: Option<WaitStrategy>,
+    // interval, and where to report init request counters to
+    init_counter_reports: Option<(Duration, CounterReportSink)>,
+    init_sequence_number_cache: bool,
+    init_sequence_check_before_retry: bool,
+    // max duration of executing each batch of init transactions
+    init_execution_budget: Option<Duration>,
+    // if set, init transactions are verified in bulk, this long after submission
+    init_bulk_verify_delay: Option<Duration>,
+    // if set, init submissions not acknowledged within this delay are also sent to another client
+    init_hedge_delay: Option<Duration>,
+    // if set, faults are injected into init submissions
+    init_fault_injection: Option<FaultInjection>,
+    // if set, clients are checked for divergence with this interval, for the duration of the run
+    divergence_check_interval: Option<Duration>,
+    // if set, account creation progress is persisted to this file, and resumed from it
+    run_state_file: Option<PathBuf>,
+    // if set, accounts are loaded from it at the start, and saved to it at the end of the run
+    account_pool_file: Option<AccountPoolFile>,
+    // if set, duration and success criteria of each phase
+    phase_plan: Option<Vec<PlannedPhase>>,
+    // if set, the run fails if it regressed compared to the baseline
+    regression_check: Option<RegressionCheck>,
+    // if set, stats of each worker are tracked, to warn about workers deviating from the mean
+    // by more than this factor
+    worker_imbalance_factor: Option<f64>,
+    // if set, stats are tracked per group of endpoints, with workers assigned to groups by it
+    endpoint_groups: Option<EndpointGroups>,
+    // if set, a JSON report of the results is written to it when the job is stopped
+    result_report_file: Option<PathBuf>,
+    // if set, throughput and latency of each interval are written to the CSV file
+    stats_csv: Option<(PathBuf, Duration)>,
+    // if set, metrics of the run are pushed to the pushgateway while running, and at the end
+    pushgateway: Option<PushgatewayConfig>,
+    // conditions other than duration, for stopping the run early
+    stop_conditions: StopConditions,
+    // if set, the run is stopped early once notified (by the handle of the job)
+    stop_notify: Option<Arc<Notify>>,
+    // if set, the run is stopped early once signaled (by the caller, e.g. on ctrl-c)
+    stop_signal: Option<StopSignal>,
+    callbacks: EmitCallbacks,
+    // if set, stats of warm-up and cool-down are excluded from the returned stats
+    measurement_window: Option<MeasurementWindow>,
+    // if set, how long workers keep waiting for in-flight transactions once stopped,
+    // otherwise they wait until the transactions expire
+    drain_timeout: Option<Duration>,
+    // if set, sequence numbers of the accounts are compared with the chain when stopped
+    audit_sequence_numbers: bool,
 
     max_transactions_per_account: usize,
 
@@ -150,14 +483,65 @@ impl Default for EmitJobRequest {
                 mempool_backlog: 3000,
             },
             transaction_mix_per_phase: vec![vec![(TransactionType::default(), 1)]],
+            workload_txn_factory_overrides: Vec::new(),
+            workload_generator_phases: Vec::new(),
+            entry_functions: Vec::new(),
             max_gas_per_txn: aptos_global_constants::MAX_GAS_AMOUNT,
             gas_price: aptos_global_constants::GAS_UNIT_PRICE,
             init_gas_price_multiplier: 10,
+            gas_price_refresh: None,
+            health_check: None,
+            endpoint_refresh: None,
+            in_flight_limits: InFlightLimits::default(),
+            backlog_target: None,
             reuse_accounts: false,
+            recycle_accounts: false,
             mint_to_root: false,
+            faucet_funding: None,
+            derive_accounts_from_source: false,
+            account_pool_seed: None,
+            funding_tree: FundingTree::default(),
+            account_signature_scheme: SignatureScheme::default(),
             txn_expiration_time_secs: 60,
             init_expiration_multiplier: 3.0,
             init_retry_interval: Duration::from_secs(10),
+            init_retry_backoff: RetryBackoff::default(),
+            init_circuit_breaker: None,
+            init_client_selection: ClientSelection::default(),
+            init_batch_submit: false,
+            init_batch_wait: false,
+            init_mempool_full_backoff: None,
+            init_simulate_before_submit: false,
+            init_rate_limit: None,
+            init_stream_confirmation: false,
+            init_grpc_stream_confirmation: None,
+            init_validate_clients: None,
+            init_confirmation_quorum: None,
+            init_wait_strategy: None,
+            init_counter_reports: None,
+            init_sequence_number_cache: false,
+            init_sequence_check_before_retry: false,
+            init_execution_budget: None,
+            init_bulk_verify_delay: None,
+            init_hedge_delay: None,
+            init_fault_injection: None,
+            divergence_check_interval: None,
+            run_state_file: None,
+            account_pool_file: None,
+            phase_plan: None,
+            regression_check: None,
+            worker_imbalance_factor: None,
+            endpoint_groups: None,
+            result_report_file: None,
+            stats_csv: None,
+            pushgateway: None,
+            stop_conditions: StopConditions::default(),
+            stop_notify: None,
+            stop_signal: None,
+            callbacks: EmitCallbacks::default(),
+            measurement_window: None,
+            drain_timeout: None,
+            audit_sequence_numbers: false,
             max_transactions_per_account: 20,
             expected_max_txns: MAX_TXNS,
             expected_gas_per_txn: aptos_global_constants::MAX_GAS_AMOUNT,
@@ -192,6 +576,62 @@ impl EmitJobRequest {
         self
     }
 
+    /// Periodically refresh the gas price of emitted transactions from the gas estimation
+    /// API, clamped to the floor and ceiling. Accounts are funded for the ceiling.
+    pub fn gas_price_refresh(mut self, gas_price_refresh: GasPriceRefresh) -> Self {
+        self.gas_price_refresh = Some(gas_price_refresh);
+        self
+    }
+
+    /// Highest gas price emitted transactions can use, for funding accounts.
+    pub(crate) fn max_gas_price(&self) -> u64 {
+        match &self.gas_price_refresh {
+            Some(refresh) => max(self.gas_price, refresh.ceiling),
+            None => self.gas_price,
+        }
+    }
+
+    /// Max gas amount and gas unit price of transactions of the run, and of each
+    /// workload with settings of its own, at the highest gas price of the run.
+    fn workload_gas(&self) -> impl Iterator<Item = Result<(u64, u64)>> + '_ {
+        let gas_unit_price = self.max_gas_price();
+        iter::once(TxnFactoryOverrides::default())
+            .chain(
+                self.workload_txn_factory_overrides
+                    .iter()
+                    .flatten()
+                    .copied(),
+            )
+            .map(move |overrides| {
+                let max_gas_amount = overrides.max_gas_amount.unwrap_or(self.max_gas_per_txn);
+                let gas_unit_price = overrides.checked_gas_unit_price(gas_unit_price)?;
+                max_gas_amount.checked_mul(gas_unit_price).ok_or_else(|| {
+                    format_err!(
+                        "Max gas amount {} at gas unit price {} overflows",
+                        max_gas_amount,
+                        gas_unit_price
+                    )
+                })?;
+                Ok((max_gas_amount, gas_unit_price))
+            })
+    }
+
+    /// Highest gas unit price, and highest max gas fee, of emitted transactions over
+    /// all workloads, for funding accounts. Workload gas is checked by start_job.
+    pub(crate) fn max_workload_gas(&self) -> (u64, u64) {
+        self.workload_gas()
+            .map(|gas| gas.expect("Workload gas should have been checked"))
+            .fold(
+                (0, 0),
+                |(max_price, max_fee), (max_gas_amount, gas_unit_price)| {
+                    (
+                        max(max_price, gas_unit_price),
+                        max(max_fee, max_gas_amount * gas_unit_price),
+                    )
+                },
+            )
+    }
+
     pub fn expected_max_txns(mut self, expected_max_txns: u64) -> Self {
         self.expected_max_txns = expected_max_txns;
         self
@@ -225,6 +665,123 @@ impl EmitJobRequest {
         self
     }
 
+    /// Entry functions called by CustomEntryFunction workloads, by their index.
+    pub fn entry_functions(mut self, entry_functions: Vec<EntryFunctionTemplate>) -> Self {
+        self.entry_functions = entry_functions;
+        self
+    }
+
+    /// Max gas amount, gas unit price and expiration of transactions of each workload in
+    /// each phase's transaction mix, with unset ones taken from max_gas_per_txn, gas_price
+    /// and txn_expiration_time_secs. Expired transactions are then counted per workload.
+    /// Gas prices of workloads are still replaced while repricing with dynamic_gas_price.
+    pub fn workload_txn_factory_overrides(
+        mut self,
+        workload_txn_factory_overrides: Vec<Vec<TxnFactoryOverrides>>,
+    ) -> Self {
+        self.workload_txn_factory_overrides = workload_txn_factory_overrides;
+        self
+    }
+
+    /// Run workloads of the phases given generator phases one after another, each for
+    /// its generator phase, instead of as a weighted mix. Stats are then also reported
+    /// per generator phase, by label.
+    pub fn workload_generator_phases(
+        mut self,
+        workload_generator_phases: Vec<Option<Vec<GeneratorPhase>>>,
+    ) -> Self {
+        self.workload_generator_phases = workload_generator_phases;
+        self
+    }
+
+    /// Run each phase (with its transaction mix) for its planned duration, instead of
+    /// splitting duration evenly, failing the run if a phase doesn't meet its success criteria.
+    pub fn phase_plan(mut self, phase_plan: Vec<PlannedPhase>) -> Self {
+        self.phase_plan = Some(phase_plan);
+        self
+    }
+
+    /// Fail the run if its TPS or p99 latency regressed compared to a baseline run, beyond
+    /// the check's thresholds. Compared is the same as written to the result report, i.e.
+    /// including warm-up and cool-down.
+    pub fn regression_check(mut self, regression_check: RegressionCheck) -> Self {
+        self.regression_check = Some(regression_check);
+        self
+    }
+
+    /// Track stats of each worker, warning during the run (and reporting at the end)
+    /// about workers whose counts deviate from the mean by more than the given factor.
+    pub fn worker_imbalance_factor(mut self, worker_imbalance_factor: f64) -> Self {
+        self.worker_imbalance_factor = Some(worker_imbalance_factor);
+        self
+    }
+
+    /// Report stats per group (e.g. region) of endpoints, and pin shares of workers
+    /// to groups, as given. Groups are given by index of the endpoint's client.
+    pub fn endpoint_groups(mut self, endpoint_groups: EndpointGroups) -> Self {
+        self.endpoint_groups = Some(endpoint_groups);
+        self
+    }
+
+    /// Write a JSON report of the results (stats per phase, workload and endpoint,
+    /// and coins spent) to the given file when the job is stopped.
+    pub fn result_report_file(mut self, result_report_file: PathBuf) -> Self {
+        self.result_report_file = Some(result_report_file);
+        self
+    }
+
+    /// Write throughput and latency percentiles of each interval of the run to the
+    /// given CSV file, as the run progresses.
+    pub fn stats_csv(mut self, path: PathBuf, interval: Duration) -> Self {
+        self.stats_csv = Some((path, interval));
+        self
+    }
+
+    /// Push metrics of the run, grouped by the run ID, to a Prometheus pushgateway
+    /// every interval, and once more when the job is stopped.
+    pub fn pushgateway(mut self, pushgateway: PushgatewayConfig) -> Self {
+        self.pushgateway = Some(pushgateway);
+        self
+    }
+
+    /// Stop the run before its duration is over, if any of the conditions triggers.
+    /// Only applies to runs with a duration, i.e. emit_txn_for* functions.
+    pub fn stop_conditions(mut self, stop_conditions: StopConditions) -> Self {
+        self.stop_conditions = stop_conditions;
+        self
+    }
+
+    /// Stop the run before its duration is over once signaled, waiting for in-flight
+    /// transactions as at the end of the run. Only applies to runs with a duration,
+    /// i.e. emit_txn_for* functions.
+    pub fn stop_signal(mut self, stop_signal: StopSignal) -> Self {
+        self.stop_signal = Some(stop_signal);
+        self
+    }
+
+    /// Exclude stats of the warm-up at the start, and of the cool-down at the end of
+    /// the run from the returned stats, logging stats including them as well.
+    /// Only applies to runs with a duration, i.e. emit_txn_for* functions.
+    pub fn measurement_window(mut self, measurement_window: MeasurementWindow) -> Self {
+        self.measurement_window = Some(measurement_window);
+        self
+    }
+
+    /// Bound how long the job waits for in-flight transactions when stopped. Ones still
+    /// unresolved after the timeout are counted as expired, and sequence numbers of the
+    /// accounts are reconciled with the chain.
+    pub fn drain_timeout(mut self, drain_timeout: Duration) -> Self {
+        self.drain_timeout = Some(drain_timeout);
+        self
+    }
+
+    /// When stopped, compare sequence numbers of the accounts with the ones on chain,
+    /// reporting transactions counted as committed that aren't, and commits not observed.
+    pub fn audit_sequence_numbers(mut self) -> Self {
+        self.audit_sequence_numbers = true;
+        self
+    }
+
     pub fn get_num_phases(&self) -> usize {
         self.transaction_mix_per_phase.len()
     }
@@ -234,16 +791,238 @@ impl EmitJobRequest {
         self
     }
 
+    /// Check that every endpoint is reachable, recent, not pruned past the required
+    /// version, and estimates gas, before creating accounts and emitting.
+    pub fn health_check(mut self, health_check: HealthCheck) -> Self {
+        self.health_check = Some(health_check);
+        self
+    }
+
+    /// Re-discover endpoints from the validator set at the interval, moving workers to
+    /// the new endpoints when it changes. Stats per endpoint and group stay with the
+    /// endpoints workers were started on.
+    pub fn endpoint_refresh(mut self, discovery: EndpointDiscovery, interval: Duration) -> Self {
+        self.endpoint_refresh = Some((discovery, interval));
+        self
+    }
+
+    /// Cap transactions in flight to each endpoint, across all workers submitting to it.
+    pub fn in_flight_limits(mut self, in_flight_limits: InFlightLimits) -> Self {
+        self.in_flight_limits = in_flight_limits;
+        self
+    }
+
+    /// Reduce the number of emitting workers while the backlog of transactions in flight
+    /// is over target, instead of filling up mempools.
+    pub fn backlog_throttle(mut self, backlog_target: BacklogTarget) -> Self {
+        self.backlog_target = Some(backlog_target);
+        self
+    }
+
     pub fn reuse_accounts(mut self) -> Self {
         self.reuse_accounts = true;
         self
     }
 
+    /// Pool the accounts of all workers, each checking out the senders of a round and
+    /// returning them once it's done, instead of every worker having accounts of its own.
+    pub fn recycle_accounts(mut self) -> Self {
+        self.recycle_accounts = true;
+        self
+    }
+
+    /// Fund seed accounts through the faucet, for networks without a funded source account.
+    pub fn faucet_funding(mut self, faucet_funding: FaucetFunding) -> Self {
+        self.faucet_funding = Some(faucet_funding);
+        self
+    }
+
+    /// Derive accounts from the private key of the source account, so that every run
+    /// funded by it uses the same accounts, topping them up through direct transfers
+    /// instead of creating new ones.
+    pub fn derive_accounts_from_source(mut self) -> Self {
+        self.derive_accounts_from_source = true;
+        self
+    }
+
+    /// Derive the keys of accounts from the seed and their index in the pool, so that the
+    /// same pool can be reconstructed from the seed alone, on any machine and in any later
+    /// run, which tops them up instead of creating new ones.
+    pub fn account_pool_seed(mut self, account_pool_seed: u64) -> Self {
+        self.account_pool_seed = Some(account_pool_seed);
+        self
+    }
+
+    /// Fan-out, depth and per-level amounts of the tree accounts are funded through.
+    pub fn funding_tree(mut self, funding_tree: FundingTree) -> Self {
+        self.funding_tree = funding_tree;
+        self
+    }
+
+    /// Create the accounts emitting transactions with the given signature scheme.
+    /// Accounts with schemes other than ed25519 can't be reused or persisted.
+    pub fn account_signature_scheme(mut self, account_signature_scheme: SignatureScheme) -> Self {
+        self.account_signature_scheme = account_signature_scheme;
+        self
+    }
+
     pub fn txn_expiration_time_secs(mut self, txn_expiration_time_secs: u64) -> Self {
         self.txn_expiration_time_secs = txn_expiration_time_secs;
         self
     }
 
+    pub fn init_retry_backoff(mut self, init_retry_backoff: RetryBackoff) -> Self {
+        self.init_retry_backoff = init_retry_backoff;
+        self
+    }
+
+    pub fn init_circuit_breaker(mut self, init_circuit_breaker: CircuitBreakerConfig) -> Self {
+        self.init_circuit_breaker = Some(init_circuit_breaker);
+        self
+    }
+
+    pub fn init_client_selection(mut self, init_client_selection: ClientSelection) -> Self {
+        self.init_client_selection = init_client_selection;
+        self
+    }
+
+    pub fn init_batch_submit(mut self) -> Self {
+        self.init_batch_submit = true;
+        self
+    }
+
+    /// With init_batch_submit, wait for each batch by scanning committed transactions,
+    /// instead of polling for each of its transactions.
+    pub fn init_batch_wait(mut self) -> Self {
+        self.init_batch_wait = true;
+        self
+    }
+
+    pub fn init_mempool_full_backoff(
+        mut self,
+        init_mempool_full_backoff: MempoolFullBackoff,
+    ) -> Self {
+        self.init_mempool_full_backoff = Some(init_mempool_full_backoff);
+        self
+    }
+
+    pub fn init_simulate_before_submit(mut self) -> Self {
+        self.init_simulate_before_submit = true;
+        self
+    }
+
+    /// Rate limit applied to each endpoint, for requests made during initialization.
+    pub fn init_rate_limit(mut self, init_rate_limit: RateLimit) -> Self {
+        self.init_rate_limit = Some(init_rate_limit);
+        self
+    }
+
+    /// Confirm commits during initialization from a stream of committed transactions,
+    /// instead of polling for each of them.
+    pub fn init_stream_confirmation(mut self) -> Self {
+        self.init_stream_confirmation = true;
+        self
+    }
+
+    /// Same as init_stream_confirmation, but reading the stream from
+    /// the indexer grpc data service, at the given address.
+    pub fn init_grpc_stream_confirmation(
+        mut self,
+        grpc_address: String,
+        auth_token: Option<String>,
+    ) -> Self {
+        self.init_grpc_stream_confirmation = Some((grpc_address, auth_token));
+        self
+    }
+
+    /// Fail fast if clients are on different chains, or if any of them is
+    /// more than max_version_lag versions behind the others.
+    pub fn init_validate_clients(mut self, max_version_lag: u64) -> Self {
+        self.init_validate_clients = Some(max_version_lag);
+        self
+    }
+
+    /// Only consider initialization transactions committed once they are visible on
+    /// quorum of rest_clients, protecting against confirming on a lagging node.
+    pub fn init_confirmation_quorum(mut self, quorum: usize) -> Self {
+        self.init_confirmation_quorum = Some(quorum);
+        self
+    }
+
+    /// How init transactions are polled for, instead of the REST client defaults.
+    pub fn init_wait_strategy(mut self, wait_strategy: WaitStrategy) -> Self {
+        self.init_wait_strategy = Some(wait_strategy);
+        self
+    }
+
+    /// Periodically report init request counters as JSON, while accounts are created.
+    pub fn init_counter_reports(mut self, interval: Duration, sink: CounterReportSink) -> Self {
+        self.init_counter_reports = Some((interval, sink));
+        self
+    }
+
+    /// Serve sequence numbers of init accounts from the executor's cache, when known.
+    pub fn init_sequence_number_cache(mut self) -> Self {
+        self.init_sequence_number_cache = true;
+        self
+    }
+
+    /// Before retrying an init transaction, check whether its sequence number was already
+    /// used on chain, and if so, count it as committed instead of resubmitting it.
+    pub fn init_sequence_check_before_retry(mut self) -> Self {
+        self.init_sequence_check_before_retry = true;
+        self
+    }
+
+    /// Cancel execution of a batch of init transactions, if it takes longer than
+    /// execution_budget across all retries.
+    pub fn init_execution_budget(mut self, execution_budget: Duration) -> Self {
+        self.init_execution_budget = Some(execution_budget);
+        self
+    }
+
+    /// Instead of waiting on each init transaction, submit all of them, and after
+    /// verify_delay, check which got committed in a single pass, resubmitting the rest.
+    pub fn init_bulk_verify(mut self, verify_delay: Duration) -> Self {
+        self.init_bulk_verify_delay = Some(verify_delay);
+        self
+    }
+
+    /// If submitting an init transaction is not acknowledged within hedge_delay,
+    /// also submit it to a second client, cutting tail latency of a slow node.
+    pub fn init_hedged_submit(mut self, hedge_delay: Duration) -> Self {
+        self.init_hedge_delay = Some(hedge_delay);
+        self
+    }
+
+    /// Randomly delay, drop, duplicate or wait under a wrong hash for init submissions,
+    /// to test retry and accounting logic of the emitter, and robustness of the network.
+    pub fn init_fault_injection(mut self, fault_injection: FaultInjection) -> Self {
+        self.init_fault_injection = Some(fault_injection);
+        self
+    }
+
+    /// Periodically compare accumulator root hashes across clients during the run,
+    /// failing emit_txn_for if any of them diverged.
+    pub fn divergence_check_interval(mut self, interval: Duration) -> Self {
+        self.divergence_check_interval = Some(interval);
+        self
+    }
+
+    /// Persist created accounts to the given file, so that a rerun with the same file
+    /// resumes account creation where the previous run left off.
+    pub fn run_state_file(mut self, run_state_file: PathBuf) -> Self {
+        self.run_state_file = Some(run_state_file);
+        self
+    }
+
+    /// Load accounts from the given file instead of creating them (creating only the
+    /// missing ones), and save them back to it when the job is stopped.
+    pub fn account_pool_file(mut self, account_pool_file: AccountPoolFile) -> Self {
+        self.account_pool_file = Some(account_pool_file);
+        self
+    }
+
     pub fn max_transactions_per_account(mut self, max_transactions_per_account: usize) -> Self {
         self.max_transactions_per_account = max_transactions_per_account;
         self
@@ -297,10 +1076,15 @@ impl EmitJobRequest {
                     check_account_sequence_sleep_millis: 300,
                 }
             },
-            EmitJobMode::ConstTps { tps }
-            | EmitJobMode::WaveTps {
-                average_tps: tps, ..
-            } => {
+            EmitJobMode::ConstTps { .. }
+            | EmitJobMode::WaveTps { .. }
+            | EmitJobMode::TpsSchedule { .. }
+            | EmitJobMode::BurstTps { .. }
+            | EmitJobMode::LatencyTargeted { .. } => {
+                let tps = self
+                    .mode
+                    .peak_tps()
+                    .expect("TPS schedule needs at least one segment");
                 // We are going to create ConstTps (open-loop) txn-emitter, by:
                 // - having a single worker handle a single account, with:
                 //   - issuing a batch request (which generally either suceeeds or fails)
@@ -456,9 +1240,51 @@ struct Worker {
 #[derive(Debug)]
 pub struct EmitJob {
     workers: Vec<Worker>,
+    callbacks: EmitCallbacks,
     stop: Arc<AtomicBool>,
     stats: Arc<DynamicStatsTracking>,
     phase_starts: Vec<Instant>,
+    divergence_detector: Option<DivergenceDetector>,
+    // set in TpsSchedule, BurstTps and LatencyTargeted modes, number of workers
+    // submitting transactions, with workers activated in random order
+    active_workers: Option<Arc<AtomicUsize>>,
+    peak_tps: usize,
+    bursts: Option<BurstSchedule>,
+    latency_controller: Option<Arc<Mutex<LatencyController>>>,
+    // task changing active_workers during the run, in BurstTps and LatencyTargeted modes
+    tps_driver: Option<JoinHandle<()>>,
+    stats_csv_writer: Option<JoinHandle<()>>,
+    drain: Arc<InFlightDrain>,
+    drain_timeout: Option<Duration>,
+    audit_sequence_numbers: bool,
+    // client to reconcile sequence numbers with, if waiting for in-flight transactions
+    // was abandoned, and to audit them with
+    client: RestClient,
+    // pusher, and task pushing with it periodically
+    metrics_pusher: Option<(Arc<RunMetricsPusher>, JoinHandle<()>)>,
+    // file to save accounts to when stopped, together with accounts not given to any worker
+    account_pool: Option<(AccountPoolFile, Vec<LocalAccount>)>,
+    // accounts workers check out, if recycling them
+    recycling_pool: Option<Arc<RecyclingAccountPool>>,
+    worker_stats: Option<WorkerStats>,
+    // stats of each endpoint, tracked only if writing a result report
+    endpoint_stats: Vec<(String, Arc<StatsAccumulator>)>,
+    group_stats: Option<GroupStats>,
+    result_report: Option<PendingResultReport>,
+    dynamic_gas_price: Option<DynamicGasPrice>,
+    endpoint_refresh: Option<EndpointRefresh>,
+    backlog_throttle: Option<BacklogThrottle>,
+}
+
+/// Where to write the result report to when the job is stopped, with what is needed
+/// to compute the coins spent.
+#[derive(Debug)]
+struct PendingResultReport {
+    path: PathBuf,
+    client: RestClient,
+    gas_price: u64,
+    // none if it couldn't be queried
+    initial_balance: Option<u64>,
 }
 
 impl EmitJob {
@@ -473,13 +1299,116 @@ impl EmitJob {
         self.stats.get_cur_phase()
     }
 
-    pub async fn stop_and_accumulate(self) -> Vec<TxnStats> {
+    /// Changes the emitted TPS, up to the peak TPS of the mode. Only available for jobs
+    /// started in TpsSchedule, BurstTps and LatencyTargeted modes, and overridden by the
+    /// bursts or latency adjustments of the latter two.
+    pub fn set_target_tps(&self, tps: usize) {
+        let active_workers = self
+            .active_workers
+            .as_ref()
+            .expect("set_target_tps requires TpsSchedule, BurstTps or LatencyTargeted mode");
+        active_workers.store(
+            active_workers_for_tps(self.workers.len(), tps, self.peak_tps),
+            Ordering::Relaxed,
+        );
+    }
+
+    pub async fn stop_and_accumulate(mut self) -> Vec<TxnStats> {
         self.stop.store(true, Ordering::Relaxed);
-        for worker in self.workers {
-            let _accounts = worker
+        if let Some(tps_driver) = &self.tps_driver {
+            tps_driver.abort();
+        }
+        if let Some(stats_csv_writer) = &self.stats_csv_writer {
+            stats_csv_writer.abort();
+        }
+        let abandon_task = self.drain_timeout.map(|drain_timeout| {
+            info!(
+                "Waiting up to {}s for in-flight transactions",
+                drain_timeout.as_secs()
+            );
+            tokio::spawn(self.drain.clone().abandon_after(drain_timeout))
+        });
+        let mut returned_accounts = vec![];
+        for worker in std::mem::take(&mut self.workers) {
+            let mut accounts = worker
                 .join_handle
                 .await
                 .expect("TxnEmitter worker thread failed");
+            returned_accounts.append(&mut accounts);
+        }
+        if let Some(recycling_pool) = &self.recycling_pool {
+            returned_accounts.append(&mut recycling_pool.drain());
+        }
+        if let Some(abandon_task) = abandon_task {
+            abandon_task.abort();
+        }
+        // before reconciling, which would hide the gaps
+        let sequence_audit = if self.audit_sequence_numbers {
+            audit_sequence_numbers(&self.client, &returned_accounts).await
+        } else {
+            None
+        };
+        if self.drain.is_abandoned() {
+            warn!(
+                "Stopped waiting for in-flight transactions, {} of them were unresolved and counted as expired",
+                self.drain.unresolved()
+            );
+            match refresh_sequence_numbers(&self.client, &mut returned_accounts).await {
+                Ok(()) => info!(
+                    "Reconciled sequence numbers of {} accounts",
+                    returned_accounts.len()
+                ),
+                Err(e) => error!("Failed to reconcile sequence numbers: {:?}", e),
+            }
+        }
+        if let Some((metrics_pusher, push_task)) = &self.metrics_pusher {
+            push_task.abort();
+            match metrics_pusher.push().await {
+                Ok(()) => info!("Pushed final metrics of the run"),
+                Err(e) => error!("{:?}", e),
+            }
+        }
+        for (workload, stats) in self
+            .stats
+            .accumulate_workloads(self.phase_starts[0].elapsed())
+        {
+            info!(
+                "Workload {}: {}, {} of {} submitted expired",
+                workload,
+                stats.rate(),
+                stats.expired,
+                stats.submitted
+            );
+        }
+        for (group, stats) in self.accumulate_groups() {
+            info!("Endpoint group {}: {}", group, stats.rate());
+        }
+        if let Some(result_report) = &self.result_report {
+            let mut report = self.result_report(result_report, &returned_accounts).await;
+            report.sequence_audit = sequence_audit;
+            match report.write(&result_report.path) {
+                Ok(()) => info!("Wrote result report to {}", result_report.path.display()),
+                Err(e) => error!("Failed to write result report: {:?}", e),
+            }
+        }
+        if let Some(worker_stats) = &self.worker_stats {
+            for (worker, stats) in worker_stats.accumulate(self.phase_starts[0].elapsed()) {
+                info!("Worker {}: {}", worker, stats.rate());
+            }
+            let imbalances = worker_stats.imbalances();
+            if imbalances.is_empty() {
+                info!("Workers were balanced");
+            }
+            for imbalance in imbalances {
+                warn!("{}", imbalance);
+            }
+        }
+        if let Some((account_pool_file, mut spare_accounts)) = self.account_pool {
+            returned_accounts.append(&mut spare_accounts);
+            match account_pool_file.save(&returned_accounts) {
+                Ok(()) => info!("Saved {} accounts to account pool", returned_accounts.len()),
+                Err(e) => error!("Failed to save account pool: {:?}", e),
+            }
         }
 
         self.stats.accumulate(&self.phase_starts)
@@ -488,6 +1417,105 @@ impl EmitJob {
     pub fn accumulate(&self) -> Vec<TxnStats> {
         self.stats.accumulate(&self.phase_starts)
     }
+
+    async fn result_report(
+        &self,
+        result_report: &PendingResultReport,
+        accounts: &[LocalAccount],
+    ) -> ResultReport {
+        let lasted = self.phase_starts[0].elapsed();
+        let mut report = ResultReport::new(&self.stats.accumulate(&self.phase_starts));
+        report.workloads = self
+            .stats
+            .accumulate_workloads(lasted)
+            .iter()
+            .map(|(workload, stats)| (workload.clone(), stats.into()))
+            .collect();
+        report.endpoints = self
+            .endpoint_stats
+            .iter()
+            .map(|(endpoint, stats)| (endpoint.clone(), (&stats.accumulate(lasted)).into()))
+            .collect();
+        report.groups = self
+            .accumulate_groups()
+            .iter()
+            .map(|(group, stats)| (group.clone(), stats.into()))
+            .collect();
+        report.worker_imbalances = self
+            .worker_imbalances()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        if let Some(initial_balance) = result_report.initial_balance {
+            match total_balance(&result_report.client, accounts.iter()).await {
+                Ok(final_balance) => {
+                    let gas_price = self.dynamic_gas_price.as_ref().map_or(
+                        result_report.gas_price,
+                        |dynamic_gas_price| {
+                            dynamic_gas_price.gas_unit_price().load(Ordering::Relaxed)
+                        },
+                    );
+                    report.gas = Some(GasReport::new(
+                        gas_price,
+                        initial_balance,
+                        final_balance,
+                        report.total.committed,
+                    ))
+                },
+                Err(e) => warn!("Failed to query final balances for result report: {:?}", e),
+            }
+        }
+        report
+    }
+
+    /// Workers deviating from the mean so far, if tracking worker stats.
+    pub fn worker_imbalances(&self) -> Vec<WorkerImbalance> {
+        self.worker_stats
+            .as_ref()
+            .map_or_else(Vec::new, WorkerStats::imbalances)
+    }
+
+    /// Stats of each group of endpoints for the whole run, when endpoints are grouped.
+    pub fn accumulate_groups(&self) -> Vec<(String, TxnStats)> {
+        self.group_stats
+            .as_ref()
+            .map_or_else(Vec::new, |group_stats| {
+                group_stats.accumulate(self.phase_starts[0].elapsed())
+            })
+    }
+
+    /// Stats of each workload for the whole run, when running a mix of workloads.
+    pub fn accumulate_workloads(&self) -> Vec<(String, TxnStats)> {
+        self.stats
+            .accumulate_workloads(self.phase_starts[0].elapsed())
+    }
+
+    /// Whether any burst window (in BurstTps mode) overlaps the given time range.
+    pub fn overlaps_burst(&self, from: Instant, to: Instant) -> bool {
+        match &self.bursts {
+            Some(schedule) => {
+                let elapsed = from.saturating_duration_since(self.phase_starts[0]);
+                schedule.is_burst(elapsed)
+                    || schedule.until_next_switch(elapsed) < to.saturating_duration_since(from)
+            },
+            None => false,
+        }
+    }
+
+    /// Highest committed TPS observed while p99 latency was under target,
+    /// in LatencyTargeted mode.
+    pub fn sustainable_tps(&self) -> Option<u64> {
+        self.latency_controller
+            .as_ref()
+            .and_then(|controller| controller.lock().sustainable_tps())
+    }
+
+    /// First divergence between clients found during the run, if checking was enabled.
+    pub fn divergence(&self) -> Option<Divergence> {
+        self.divergence_detector
+            .as_ref()
+            .and_then(|detector| detector.divergence())
+    }
 }
 
 #[derive(Debug)]
@@ -519,6 +1547,80 @@ impl TxnEmitter {
         stats_tracking_phases: usize,
     ) -> Result<EmitJob> {
         ensure!(req.gas_price > 0, "gas_price is required to be non zero");
+        if let EmitJobMode::TpsSchedule { segments } = &req.mode {
+            ensure!(
+                !segments.is_empty(),
+                "TPS schedule needs at least one segment"
+            );
+            ensure!(
+                segments.iter().all(|segment| segment.tps > 0),
+                "TPS of each TPS schedule segment needs to be non zero"
+            );
+        }
+        for gas in req.workload_gas() {
+            gas.context("Invalid workload gas settings")?;
+        }
+        ensure!(
+            req.workload_txn_factory_overrides
+                .iter()
+                .zip(req.transaction_mix_per_phase.iter())
+                .all(|(overrides, txn_mix)| overrides.len() == txn_mix.len())
+                && req.workload_txn_factory_overrides.len() <= req.transaction_mix_per_phase.len(),
+            "Workload transaction settings need to match the transaction mix of each phase"
+        );
+        ensure!(
+            req.workload_generator_phases
+                .iter()
+                .zip(req.transaction_mix_per_phase.iter())
+                .all(|(generator_phases, txn_mix)| generator_phases
+                    .as_ref()
+                    .map_or(true, |generator_phases| generator_phases.len()
+                        == txn_mix.len()))
+                && req.workload_generator_phases.len() <= req.transaction_mix_per_phase.len(),
+            "Generator phases need to match the transaction mix of each phase"
+        );
+        ensure!(
+            req.transaction_mix_per_phase
+                .iter()
+                .flatten()
+                .all(|(transaction_type, _)| match transaction_type {
+                    TransactionType::CustomEntryFunction { index } =>
+                        *index < req.entry_functions.len(),
+                    _ => true,
+                }),
+            "Each CustomEntryFunction workload needs an entry function with its index"
+        );
+        if let Some(gas_price_refresh) = &req.gas_price_refresh {
+            gas_price_refresh.validate()?;
+        }
+        ensure!(
+            req.account_signature_scheme == SignatureScheme::Ed25519
+                || !(req.reuse_accounts
+                    || req.run_state_file.is_some()
+                    || req.account_pool_file.is_some()),
+            "Accounts with {} signature scheme can't be reused or persisted",
+            req.account_signature_scheme
+        );
+        ensure!(
+            !req.derive_accounts_from_source
+                || !(req.reuse_accounts || req.faucet_funding.is_some()),
+            "Derived accounts can't be combined with reusing accounts, or funding through faucet"
+        );
+        ensure!(
+            req.account_pool_seed.is_none()
+                || !(req.reuse_accounts
+                    || req.derive_accounts_from_source
+                    || req.account_signature_scheme != SignatureScheme::Ed25519),
+            "Accounts derived from a pool seed can't be combined with reusing accounts, deriving them from the source, or the {} signature scheme",
+            req.account_signature_scheme
+        );
+        if let Some(health_check) = &req.health_check {
+            health_check.check(&req.rest_clients).await?;
+            info!(
+                "All {} endpoints passed the health check",
+                req.rest_clients.len()
+            );
+        }
 
         let mode_params = req.calculate_mode_params();
         let workers_per_endpoint = mode_params.workers_per_endpoint;
@@ -541,11 +1643,17 @@ impl TxnEmitter {
             .clone()
             .with_gas_unit_price(req.gas_price * req.init_gas_price_multiplier)
             .with_transaction_expiration_time(init_expiration_time);
-        let seed = self.rng.gen();
-        info!(
-            "AccountMinter Seed (can be passed in to reuse accounts): {:?}",
+        let seed = if req.derive_accounts_from_source {
+            info!("AccountMinter Seed derived from the source account");
+            derived_minter_seed(root_account)
+        } else {
+            let seed = self.rng.gen();
+            info!(
+                "AccountMinter Seed (can be passed in to reuse accounts): {:?}",
+                seed
+            );
             seed
-        );
+        };
         let mut account_minter = AccountMinter::new(
             root_account,
             init_txn_factory.clone(),
@@ -558,16 +1666,128 @@ impl TxnEmitter {
             init_retries,
             req.init_retry_interval.as_secs_f32()
         );
-        let txn_executor = RestApiTransactionExecutor {
-            rest_clients: req.rest_clients.clone(),
-            max_retries: init_retries,
-            retry_after: req.init_retry_interval,
+        let mut txn_executor = RestApiTransactionExecutor::new(
+            req.rest_clients.clone(),
+            init_retries,
+            req.init_retry_interval,
+        )
+        .with_retry_backoff(req.init_retry_backoff.clone())
+        .with_client_selection(req.init_client_selection)
+        .with_batch_submit(req.init_batch_submit)
+        .with_batch_wait(req.init_batch_wait)
+        .with_simulate_before_submit(req.init_simulate_before_submit);
+        if let Some(circuit_breaker) = &req.init_circuit_breaker {
+            txn_executor = txn_executor.with_circuit_breaker(circuit_breaker.clone());
+        }
+        if let Some(mempool_full_backoff) = &req.init_mempool_full_backoff {
+            txn_executor = txn_executor.with_mempool_full_backoff(mempool_full_backoff.clone());
+        }
+        if let Some(rate_limit) = &req.init_rate_limit {
+            txn_executor = txn_executor.with_rate_limit(rate_limit.clone());
+        }
+        if let Some((grpc_address, auth_token)) = &req.init_grpc_stream_confirmation {
+            txn_executor = txn_executor.with_confirmer(Arc::new(GrpcStreamConfirmer::start(
+                grpc_address.clone(),
+                auth_token.clone(),
+                req.rest_clients[0].clone(),
+            )));
+        } else if req.init_stream_confirmation {
+            txn_executor = txn_executor.with_confirmer(Arc::new(RestStreamConfirmer::start(
+                req.rest_clients[0].clone(),
+            )));
+        }
+        if let Some(wait_strategy) = &req.init_wait_strategy {
+            txn_executor = txn_executor.with_wait_strategy(wait_strategy.clone());
+        }
+        if let Some((interval, sink)) = &req.init_counter_reports {
+            txn_executor =
+                txn_executor.with_counter_reporter(CounterReporter::new(*interval, sink.clone()));
+        }
+        if let Some(verify_delay) = req.init_bulk_verify_delay {
+            txn_executor = txn_executor.with_bulk_verify(verify_delay);
+        }
+        if let Some(hedge_delay) = req.init_hedge_delay {
+            txn_executor = txn_executor.with_hedged_submit(hedge_delay);
+        }
+        if let Some(fault_injection) = &req.init_fault_injection {
+            txn_executor = txn_executor.with_fault_injection(fault_injection.clone());
+        }
+        if let Some(execution_budget) = req.init_execution_budget {
+            txn_executor = txn_executor.with_execution_budget(execution_budget);
+        }
+        if req.init_sequence_check_before_retry {
+            txn_executor = txn_executor.with_sequence_check_before_retry(true);
+        }
+        if req.init_sequence_number_cache {
+            txn_executor = txn_executor.with_sequence_number_cache();
+        }
+        if let Some(quorum) = req.init_confirmation_quorum {
+            txn_executor = txn_executor.with_confirmation_quorum(quorum);
+        }
+        if let Some(max_version_lag) = req.init_validate_clients {
+            txn_executor.validate_clients(max_version_lag).await?;
+        }
+        let run_state = req
+            .run_state_file
+            .as_deref()
+            .map(RunState::load_or_create)
+            .transpose()?;
+        let mut all_accounts = match &req.account_pool_file {
+            Some(account_pool_file) => {
+                load_account_pool(account_pool_file, &req.rest_clients[0]).await?
+            },
+            None => vec![],
         };
-        let mut all_accounts = account_minter
-            .create_accounts(&txn_executor, &req, &mode_params, num_accounts)
-            .await?;
+        if all_accounts.len() < num_accounts {
+            let mut created_accounts = account_minter
+                .create_accounts(
+                    &txn_executor,
+                    &req,
+                    &mode_params,
+                    num_accounts - all_accounts.len(),
+                    run_state.as_ref(),
+                )
+                .await?;
+            all_accounts.append(&mut created_accounts);
+        }
+        if let Some(run_state) = &run_state {
+            run_state.set_phase(RunPhase::Emitting)?;
+        }
         let stop = Arc::new(AtomicBool::new(false));
-        let stats = Arc::new(DynamicStatsTracking::new(stats_tracking_phases));
+        let mut stats = DynamicStatsTracking::new(stats_tracking_phases);
+        let workload_names_per_phase = req
+            .transaction_mix_per_phase
+            .iter()
+            .enumerate()
+            .map(
+                |(phase, txn_mix)| match req.workload_generator_phases.get(phase) {
+                    // tracked by generator phase instead
+                    Some(Some(_)) => Vec::new(),
+                    _ => txn_mix
+                        .iter()
+                        .map(|(transaction_type, _)| format!("{:?}", transaction_type))
+                        .collect::<Vec<_>>(),
+                },
+            )
+            .collect::<Vec<_>>();
+        if workload_names_per_phase.iter().any(|names| names.len() > 1)
+            || !req.workload_txn_factory_overrides.is_empty()
+            || !req.workload_generator_phases.is_empty()
+        {
+            // only track stats per workload for mixes, as they would be same as overall otherwise,
+            // unless workloads have their own settings, e.g. to see how many of each expired
+            stats = stats
+                .with_workloads(workload_names_per_phase)
+                .with_generator_phases(
+                    req.workload_generator_phases
+                        .iter()
+                        .flatten()
+                        .flatten()
+                        .map(|generator_phase| generator_phase.label.clone())
+                        .collect(),
+                );
+        }
+        let stats = Arc::new(stats);
         let tokio_handle = Handle::current();
 
         let (mut txn_generator_creator, _, _) = create_txn_generator_creator(
@@ -578,6 +1798,10 @@ impl TxnEmitter {
             &txn_executor,
             &txn_factory,
             &init_txn_factory,
+            &req.workload_txn_factory_overrides,
+            &req.workload_generator_phases,
+            &req.entry_functions,
+            self.from_rng(),
             stats.get_cur_phase_obj(),
         )
         .await;
@@ -591,6 +1815,19 @@ impl TxnEmitter {
         }
 
         let total_workers = req.rest_clients.len() * workers_per_endpoint;
+        let worker_clients = match &req.endpoint_groups {
+            Some(endpoint_groups) => {
+                ensure!(
+                    endpoint_groups.num_endpoints() == req.rest_clients.len(),
+                    "Need a group for each of the {} endpoints",
+                    req.rest_clients.len()
+                );
+                endpoint_groups.assign_workers(total_workers)
+            },
+            None => (0..total_workers)
+                .map(|worker_index| worker_index % req.rest_clients.len())
+                .collect(),
+        };
 
         let check_account_sequence_only_once_for = (0..total_workers)
             .choose_multiple(
@@ -608,40 +1845,244 @@ impl TxnEmitter {
         );
 
         let all_start_sleep_durations = mode_params.get_all_start_sleep_durations(self.from_rng());
+        let peak_tps = req.mode.peak_tps().unwrap_or(0);
+        let active_workers = match &req.mode {
+            EmitJobMode::TpsSchedule { segments } => Some(Arc::new(AtomicUsize::new(
+                active_workers_for_tps(total_workers, segments[0].tps, peak_tps),
+            ))),
+            EmitJobMode::BurstTps { schedule } => Some(Arc::new(AtomicUsize::new(
+                active_workers_for_tps(total_workers, schedule.baseline_tps, peak_tps),
+            ))),
+            EmitJobMode::LatencyTargeted { target } => Some(Arc::new(AtomicUsize::new(
+                active_workers_for_tps(total_workers, target.initial_tps, peak_tps),
+            ))),
+            _ => None,
+        };
+        // random, so that active workers are spread out the same as all of them
+        let mut activation_ranks = (0..total_workers).collect::<Vec<_>>();
+        activation_ranks.shuffle(&mut self.from_rng());
+        let mut worker_stats = req.worker_imbalance_factor.map(WorkerStats::new);
+        let endpoint_stats = match &req.result_report_file {
+            Some(_) => req
+                .rest_clients
+                .iter()
+                .map(|client| {
+                    (
+                        client.path_prefix_string(),
+                        Arc::new(StatsAccumulator::default()),
+                    )
+                })
+                .collect(),
+            None => vec![],
+        };
+        let group_stats = req.endpoint_groups.clone().map(GroupStats::new);
+        let result_report = match &req.result_report_file {
+            Some(path) => {
+                let worker_accounts = all_accounts
+                    .iter()
+                    .take(total_workers * mode_params.accounts_per_worker);
+                let initial_balance = total_balance(&req.rest_clients[0], worker_accounts)
+                    .await
+                    .map_err(|e| {
+                        warn!(
+                            "Failed to query initial balances for result report: {:?}",
+                            e
+                        )
+                    })
+                    .ok();
+                Some(PendingResultReport {
+                    path: path.clone(),
+                    client: req.rest_clients[0].clone(),
+                    gas_price: req.gas_price,
+                    initial_balance,
+                })
+            },
+            None => None,
+        };
+        let stats_csv_writer = match &req.stats_csv {
+            Some((path, interval)) => Some((StatsCsvWriter::create(path)?, *interval)),
+            None => None,
+        };
+        let drain = Arc::new(InFlightDrain::default());
+        let dynamic_gas_price = req.gas_price_refresh.clone().map(|gas_price_refresh| {
+            DynamicGasPrice::start(
+                req.rest_clients[0].clone(),
+                gas_price_refresh,
+                req.gas_price,
+            )
+        });
+        let endpoint_refresh = req.endpoint_refresh.clone().map(|(discovery, interval)| {
+            EndpointRefresh::start(discovery, interval, req.rest_clients.clone())
+        });
+        let in_flight_permits = (req.in_flight_limits != InFlightLimits::default())
+            .then(|| Arc::new(InFlightPermits::new(req.in_flight_limits.clone())));
+        let backlog_throttle = req
+            .backlog_target
+            .clone()
+            .map(|target| BacklogThrottle::start(target, stats.clone(), total_workers));
         let mut all_accounts_iter = all_accounts.into_iter();
+        let recycling_pool = req.recycle_accounts.then(|| {
+            Arc::new(RecyclingAccountPool::new(
+                (&mut all_accounts_iter)
+                    .take(total_workers * mode_params.accounts_per_worker)
+                    .collect(),
+            ))
+        });
         let mut workers = vec![];
-        for _ in 0..workers_per_endpoint {
-            for client in &req.rest_clients {
-                let accounts = (&mut all_accounts_iter)
+        for (worker_index, client_index) in worker_clients.into_iter().enumerate() {
+            let client = &req.rest_clients[client_index];
+            let accounts = match &recycling_pool {
+                Some(_) => vec![],
+                None => (&mut all_accounts_iter)
                     .take(mode_params.accounts_per_worker)
-                    .collect::<Vec<_>>();
-                let stop = stop.clone();
-                let stats = Arc::clone(&stats);
-                let txn_generator = txn_generator_creator.create_transaction_generator();
-                let worker_index = workers.len();
-
-                let worker = SubmissionWorker::new(
-                    accounts,
-                    client.clone(),
-                    stop,
-                    mode_params.clone(),
-                    stats,
-                    txn_generator,
-                    all_start_sleep_durations[worker_index],
-                    check_account_sequence_only_once_for.contains(&worker_index),
-                    self.from_rng(),
-                );
-                let join_handle = tokio_handle.spawn(worker.run().boxed());
-                workers.push(Worker { join_handle });
-            }
+                    .collect::<Vec<_>>(),
+            };
+            let stop = stop.clone();
+            let stats = Arc::clone(&stats);
+            let txn_generator = txn_generator_creator.create_transaction_generator();
+
+            let worker = SubmissionWorker::new(
+                accounts,
+                client.clone(),
+                stop,
+                drain.clone(),
+                mode_params.clone(),
+                stats,
+                txn_generator,
+                all_start_sleep_durations[worker_index],
+                check_account_sequence_only_once_for.contains(&worker_index),
+                active_workers
+                    .clone()
+                    .map(|active_workers| (active_workers, activation_ranks[worker_index])),
+                worker_stats
+                    .as_mut()
+                    .map(|worker_stats| {
+                        worker_stats.add_worker(format!(
+                            "{}#{}",
+                            client.path_prefix_string(),
+                            worker_index
+                        ))
+                    })
+                    .into_iter()
+                    .chain(
+                        endpoint_stats
+                            .get(client_index)
+                            .map(|(_, stats)| Arc::clone(stats)),
+                    )
+                    .chain(
+                        group_stats
+                            .as_ref()
+                            .map(|group_stats| group_stats.tracker_for(client_index)),
+                    )
+                    .collect(),
+                self.from_rng(),
+                dynamic_gas_price
+                    .as_ref()
+                    .map(DynamicGasPrice::gas_unit_price),
+            );
+            let worker = match &endpoint_refresh {
+                Some(endpoint_refresh) => {
+                    worker.with_discovered_endpoints(endpoint_refresh.endpoints(), worker_index)
+                },
+                None => worker,
+            };
+            let worker = match &in_flight_permits {
+                Some(in_flight_permits) => worker.with_in_flight_permits(in_flight_permits.clone()),
+                None => worker,
+            };
+            let worker = match &backlog_throttle {
+                Some(backlog_throttle) => {
+                    worker.with_backlog_throttle(backlog_throttle.allowed_workers(), worker_index)
+                },
+                None => worker,
+            };
+            let worker = match &recycling_pool {
+                Some(recycling_pool) => worker.with_account_pool(recycling_pool.clone()),
+                None => worker,
+            };
+            let join_handle = tokio_handle.spawn(worker.run().boxed());
+            workers.push(Worker { join_handle });
         }
+        let account_pool = req
+            .account_pool_file
+            .clone()
+            .map(|account_pool_file| (account_pool_file, all_accounts_iter.collect()));
         info!("Tx emitter workers started");
 
+        let divergence_detector = req
+            .divergence_check_interval
+            .map(|interval| DivergenceDetector::start(req.rest_clients.clone(), interval));
+
+        let start = Instant::now();
+        let bursts = match &req.mode {
+            EmitJobMode::BurstTps { schedule } => Some(schedule.clone()),
+            _ => None,
+        };
+        let latency_controller = match &req.mode {
+            EmitJobMode::LatencyTargeted { target } => {
+                Some(Arc::new(Mutex::new(LatencyController::new(target.clone()))))
+            },
+            _ => None,
+        };
+        let tps_driver = match (&req.mode, &active_workers) {
+            (EmitJobMode::BurstTps { schedule }, Some(active_workers)) => {
+                Some(tokio_handle.spawn(run_bursts(
+                    schedule.clone(),
+                    start,
+                    active_workers.clone(),
+                    total_workers,
+                )))
+            },
+            (EmitJobMode::LatencyTargeted { target }, Some(active_workers)) => {
+                Some(tokio_handle.spawn(run_latency_controller(
+                    latency_controller.clone().unwrap(),
+                    target.adjust_interval,
+                    stats.clone(),
+                    active_workers.clone(),
+                    total_workers,
+                )))
+            },
+            _ => None,
+        };
+        let stats_csv_writer = stats_csv_writer
+            .map(|(writer, interval)| tokio_handle.spawn(writer.run(interval, stats.clone())));
+        let metrics_pusher = req.pushgateway.clone().map(|config| {
+            info!(
+                "Pushing metrics to pushgateway, with run ID {}",
+                config.run_id()
+            );
+            let metrics_pusher = Arc::new(RunMetricsPusher::new(config, stats.clone()));
+            let push_task = tokio_handle.spawn(metrics_pusher.clone().run());
+            (metrics_pusher, push_task)
+        });
+
         Ok(EmitJob {
             workers,
+            callbacks: req.callbacks.clone(),
             stop,
             stats,
-            phase_starts: vec![Instant::now()],
+            phase_starts: vec![start],
+            divergence_detector,
+            active_workers,
+            peak_tps,
+            bursts,
+            latency_controller,
+            tps_driver,
+            stats_csv_writer,
+            drain,
+            drain_timeout: req.drain_timeout,
+            audit_sequence_numbers: req.audit_sequence_numbers,
+            client: req.rest_clients[0].clone(),
+            metrics_pusher,
+            account_pool,
+            recycling_pool,
+            worker_stats,
+            endpoint_stats,
+            group_stats,
+            result_report,
+            dynamic_gas_price,
+            endpoint_refresh,
+            backlog_throttle,
         })
     }
 
@@ -656,6 +2097,7 @@ impl TxnEmitter {
     pub async fn periodic_stat(&mut self, job: &EmitJob, duration: Duration, interval_secs: u64) {
         let deadline = Instant::now() + duration;
         let mut prev_stats: Option<Vec<TxnStats>> = None;
+        let mut prev_group_stats: Vec<(String, TxnStats)> = vec![];
         let default_stats = TxnStats::default();
         let window = Duration::from_secs(max(interval_secs, 1));
         while Instant::now() < deadline {
@@ -668,45 +2110,235 @@ impl TxnEmitter {
                     .map(|p| &p[cur_phase])
                     .unwrap_or(&default_stats);
             prev_stats = Some(stats);
-            info!("phase {}: {}", cur_phase, delta.rate());
+            job.callbacks.stats_tick(cur_phase, &delta);
+            let now = Instant::now();
+            info!(
+                "phase {}{}: {}",
+                cur_phase,
+                if job.overlaps_burst(now - window, now) {
+                    " (burst)"
+                } else {
+                    ""
+                },
+                delta.rate()
+            );
+            let group_stats = job.accumulate_groups();
+            for (group, stats) in &group_stats {
+                let prev = prev_group_stats
+                    .iter()
+                    .find(|(prev_group, _)| prev_group == group)
+                    .map_or(&default_stats, |(_, prev)| prev);
+                let mut delta = stats - prev;
+                delta.lasted = window;
+                info!("phase {} group {}: {}", cur_phase, group, delta.rate());
+            }
+            prev_group_stats = group_stats;
+            for imbalance in job.worker_imbalances() {
+                warn!("{}", imbalance);
+            }
         }
     }
 
     async fn emit_txn_for_impl(
+        self,
+        source_account: &mut LocalAccount,
+        emit_job_request: EmitJobRequest,
+        duration: Duration,
+        print_stats_interval: Option<u64>,
+    ) -> Result<Vec<TxnStats>> {
+        let callbacks = emit_job_request.callbacks.clone();
+        let result = self
+            .run_job(
+                source_account,
+                emit_job_request,
+                duration,
+                print_stats_interval,
+            )
+            .await;
+        callbacks.finished(&result);
+        result
+    }
+
+    async fn run_job(
         mut self,
         source_account: &mut LocalAccount,
         emit_job_request: EmitJobRequest,
         duration: Duration,
         print_stats_interval: Option<u64>,
-    ) -> Result<TxnStats> {
-        let phases = emit_job_request.transaction_mix_per_phase.len();
+    ) -> Result<Vec<TxnStats>> {
+        let segments = match &emit_job_request.mode {
+            EmitJobMode::TpsSchedule { segments } => {
+                let mixes = emit_job_request.transaction_mix_per_phase.len();
+                ensure!(
+                    mixes == 1 || mixes == segments.len(),
+                    "Need either a single transaction mix, or one per TPS schedule segment"
+                );
+                Some(segments.clone())
+            },
+            _ => None,
+        };
+        let phase_plan = emit_job_request.phase_plan.clone();
+        let regression_check = emit_job_request.regression_check.clone();
+        if let Some(phase_plan) = &phase_plan {
+            let mixes = emit_job_request.transaction_mix_per_phase.len();
+            ensure!(
+                segments.is_none(),
+                "Phase plan cannot be used together with a TPS schedule"
+            );
+            ensure!(
+                mixes == 1 || mixes == phase_plan.len(),
+                "Need either a single transaction mix, or one per planned phase"
+            );
+        }
+        let phase_durations = match (&segments, &phase_plan) {
+            (Some(segments), _) => segments.iter().map(|segment| segment.duration).collect(),
+            (None, Some(phase_plan)) => phase_plan.iter().map(|phase| phase.duration).collect(),
+            (None, None) => {
+                let phases = emit_job_request.transaction_mix_per_phase.len();
+                vec![duration.checked_div(phases as u32).unwrap(); phases]
+            },
+        };
+        let phases = phase_durations.len();
+        let duration: Duration = phase_durations.iter().sum();
+        let stop_conditions = emit_job_request.stop_conditions.clone();
+        let stop_notify = emit_job_request.stop_notify.clone();
+        let stop_signal = emit_job_request.stop_signal.clone();
+        let callbacks = emit_job_request.callbacks.clone();
+        let coins_per_txn = emit_job_request.expected_gas_per_txn * emit_job_request.gas_price;
+        let measurement_window = emit_job_request.measurement_window;
+        if let Some(measurement_window) = measurement_window {
+            ensure!(
+                measurement_window.warm_up + measurement_window.cool_down < duration,
+                "Warm-up and cool-down need to be shorter than the run"
+            );
+        }
 
         let mut job = self
             .start_job(source_account, emit_job_request, phases)
             .await?;
+        let window_snapshots = measurement_window.map(|measurement_window| {
+            let snapshots = Arc::new(WindowSnapshots::default());
+            let snapshot_task = tokio::spawn(measurement_window.take_snapshots(
+                job.phase_starts[0],
+                duration,
+                job.stats.clone(),
+                snapshots.clone(),
+            ));
+            (snapshots, snapshot_task)
+        });
         info!(
             "Starting emitting txns for {} secs in {} phases",
             duration.as_secs(),
             phases
         );
 
-        let per_phase_duration = duration.checked_div(phases as u32).unwrap();
-        for phase in 0..phases {
+        let mut abandon_on_signal = None;
+        for (phase, phase_duration) in phase_durations.into_iter().enumerate() {
             if phase > 0 {
                 info!("Starting next phase");
                 job.start_next_phase();
             }
-            if let Some(interval_secs) = print_stats_interval {
-                self.periodic_stat(&job, per_phase_duration, interval_secs)
-                    .await;
-            } else {
-                time::sleep(per_phase_duration).await;
+            if let Some(segments) = &segments {
+                info!("Emitting at {} TPS", segments[phase].tps);
+                job.set_target_tps(segments[phase].tps);
+            }
+            callbacks.phase_started(phase);
+            let phase_wait = async {
+                if let Some(interval_secs) = print_stats_interval {
+                    self.periodic_stat(&job, phase_duration, interval_secs)
+                        .await;
+                } else {
+                    time::sleep(phase_duration).await;
+                }
+            };
+            let stop_requested = async {
+                match &stop_notify {
+                    Some(stop_notify) => stop_notify.notified().await,
+                    None => futures::future::pending().await,
+                }
+            };
+            let signaled = async {
+                match &stop_signal {
+                    Some(stop_signal) => stop_signal.received().await,
+                    None => futures::future::pending().await,
+                }
+            };
+            let stop_reason = tokio::select! {
+                _ = phase_wait => None,
+                reason = stop_conditions.triggered(&job.stats, coins_per_txn) => Some(reason),
+                _ = stop_requested => Some(StopReason::Requested),
+                _ = signaled => Some(StopReason::Signal),
+            };
+            if let Some(stop_reason) = stop_reason {
+                warn!("Stopping run early, as it {}", stop_reason);
+                if let (StopReason::Signal, Some(stop_signal)) = (&stop_reason, &stop_signal) {
+                    // waiting for in-flight transactions can be cut short by another signal
+                    let stop_signal = stop_signal.clone();
+                    let drain = job.drain.clone();
+                    abandon_on_signal = Some(tokio::spawn(async move {
+                        stop_signal.received().await;
+                        warn!(
+                            "Received another stop signal, not waiting for in-flight transactions"
+                        );
+                        drain.abandon();
+                    }));
+                }
+                break;
             }
         }
-        info!("Ran for {} secs, stopping job...", duration.as_secs());
-        let stats = self.stop_job(job).await;
+        info!(
+            "Ran for {} secs, stopping job...",
+            job.phase_starts[0].elapsed().as_secs()
+        );
+        if let Some(sustainable_tps) = job.sustainable_tps() {
+            info!(
+                "Achieved sustainable TPS (with p99 latency under target): {}",
+                sustainable_tps
+            );
+        }
+        let divergence = job.divergence();
+        let phase_starts = job.phase_starts.clone();
+        let end = Instant::now();
+        let mut stats = self.stop_job(job).await;
+        if let Some(abandon_on_signal) = abandon_on_signal {
+            abandon_on_signal.abort();
+        }
         info!("Stopped job");
-        Ok(stats.into_iter().next().unwrap())
+        let report = regression_check.as_ref().map(|_| ResultReport::new(&stats));
+        if let Some((snapshots, snapshot_task)) = window_snapshots {
+            snapshot_task.abort();
+            match snapshots.exclude_windows(&stats, &phase_starts, end) {
+                Some(steady_stats) => {
+                    for (phase, phase_stats) in stats.iter().enumerate() {
+                        info!(
+                            "Phase {} including warm-up and cool-down: {}",
+                            phase,
+                            phase_stats.rate()
+                        );
+                    }
+                    stats = steady_stats;
+                },
+                None => warn!("Run stopped during warm-up, reporting stats including it"),
+            }
+        }
+        if let Some(divergence) = divergence {
+            return Err(divergence.into());
+        }
+        if let Some(phase_plan) = &phase_plan {
+            for (phase, phase_stats) in stats.iter().enumerate() {
+                info!("Phase {}: {}", phase, phase_stats.rate());
+            }
+            if let Some(failures) = PhaseFailures::find(phase_plan, &stats) {
+                return Err(failures.into());
+            }
+        }
+        if let (Some(regression_check), Some(report)) = (&regression_check, &report) {
+            if let Some(regressions) = regression_check.find(report) {
+                return Err(regressions.into());
+            }
+            info!("No regressions compared to the baseline");
+        }
+        Ok(stats)
     }
 
     pub async fn emit_txn_for(
@@ -717,6 +2349,7 @@ impl TxnEmitter {
     ) -> Result<TxnStats> {
         self.emit_txn_for_impl(source_account, emit_job_request, duration, None)
             .await
+            .map(|stats| stats.into_iter().next().unwrap())
     }
 
     pub async fn emit_txn_for_with_stats(
@@ -733,6 +2366,25 @@ impl TxnEmitter {
             Some(interval_secs),
         )
         .await
+        .map(|stats| stats.into_iter().next().unwrap())
+    }
+
+    /// Same as emit_txn_for_with_stats, but returns stats of each phase (i.e. of each
+    /// segment in TpsSchedule mode, which determines the duration instead).
+    pub async fn emit_txn_for_with_phase_stats(
+        self,
+        source_account: &mut LocalAccount,
+        emit_job_request: EmitJobRequest,
+        duration: Duration,
+        interval_secs: u64,
+    ) -> Result<Vec<TxnStats>> {
+        self.emit_txn_for_impl(
+            source_account,
+            emit_job_request,
+            duration,
+            Some(interval_secs),
+        )
+        .await
     }
 
     pub async fn submit_single_transaction(
@@ -762,6 +2414,7 @@ async fn wait_for_accounts_sequence(
     account_seqs: &HashMap<AccountAddress, (u64, u64)>,
     txn_expiration_ts_secs: u64,
     sleep_between_cycles: Duration,
+    drain: &InFlightDrain,
 ) -> (HashMap<AccountAddress, u64>, u128) {
     let mut pending_addresses: HashSet<_> = account_seqs.keys().copied().collect();
     let mut latest_fetched_counts = HashMap::new();
@@ -817,6 +2470,22 @@ async fn wait_for_accounts_sequence(
             },
         }
 
+        if drain.is_abandoned() {
+            let unresolved = pending_addresses
+                .iter()
+                .map(|address| {
+                    let (start_seq_num, end_seq_num) = account_seqs.get(address).unwrap();
+                    end_seq_num
+                        - latest_fetched_counts
+                            .get(address)
+                            .copied()
+                            .unwrap_or(*start_seq_num)
+                })
+                .sum();
+            drain.record_unresolved(unresolved);
+            break;
+        }
+
         if aptos_infallible::duration_since_epoch().as_secs() >= txn_expiration_ts_secs + 240 {
             sample!(
                 SampleRate::Duration(Duration::from_secs(15)),
@@ -913,7 +2582,7 @@ where
         .map(|address| {
             (
                 *address,
-                RETRY_POLICY.retry(move || client.get_account_bcs(*address)),
+                retry(&*RETRY_POLICY, move || client.get_account_bcs(*address)),
             )
         })
         .unzip();
@@ -947,3 +2616,52 @@ pub fn gen_transfer_txn_request(
         txn_factory.payload(aptos_stdlib::aptos_coin_transfer(*receiver, num_coins)),
     )
 }
+
+#[cfg(test)]
+mod test {
+    use super::{active_workers_for_tps, EmitJobRequest};
+    use aptos_transaction_generator_lib::{
+        txn_factory_overrides::TxnFactoryOverrides, TransactionType,
+    };
+
+    #[test]
+    pub fn test_active_workers_for_tps() {
+        assert_eq!(active_workers_for_tps(100, 5000, 5000), 100);
+        assert_eq!(active_workers_for_tps(100, 100, 5000), 2);
+        assert_eq!(active_workers_for_tps(100, 1, 5000), 1);
+        assert_eq!(active_workers_for_tps(100, 0, 5000), 0);
+        assert_eq!(active_workers_for_tps(3, 2500, 5000), 2);
+    }
+
+    #[test]
+    pub fn test_max_workload_gas() {
+        let req = EmitJobRequest::default()
+            .gas_price(100)
+            .max_gas_per_txn(1000);
+        assert_eq!(req.max_workload_gas(), (100, 100_000));
+
+        let publish = TxnFactoryOverrides {
+            max_gas_amount: Some(2_000_000),
+            ..TxnFactoryOverrides::default()
+        };
+        let priority = TxnFactoryOverrides {
+            gas_unit_price_multiplier: Some(3),
+            ..TxnFactoryOverrides::default()
+        };
+        let req = req
+            .transaction_mix(vec![
+                (TransactionType::default(), 1),
+                (TransactionType::default(), 1),
+            ])
+            .workload_txn_factory_overrides(vec![vec![publish, priority]]);
+        assert!(req.workload_gas().all(|gas| gas.is_ok()));
+        assert_eq!(req.max_workload_gas(), (300, 200_000_000));
+
+        let overflowing = TxnFactoryOverrides {
+            gas_unit_price_multiplier: Some(u64::MAX),
+            ..TxnFactoryOverrides::default()
+        };
+        let req = req.workload_txn_factory_overrides(vec![vec![publish, overflowing]]);
+        assert!(req.workload_gas().any(|gas| gas.is_err()));
+    }
+}
@@ -0,0 +1,148 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::emitter::stats::TxnStats;
+use anyhow::{bail, format_err, Result};
+use std::{fmt, time::Duration};
+
+/// Requirements on the stats of a phase, for the phase to succeed.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SuccessCriteria {
+    /// Minimum committed transactions per second
+    pub min_tps: Option<u64>,
+    pub max_p99_latency: Option<Duration>,
+    /// Maximum ratio of submitted transactions that expired
+    pub max_expired_ratio: Option<f64>,
+}
+
+impl SuccessCriteria {
+    /// Parses comma separated `<criterion>=<value>` pairs, with criteria being
+    /// `min_tps`, `max_p99_latency_ms` and `max_expired_ratio`.
+    pub fn parse(criteria: &str) -> Result<Self> {
+        let mut result = Self::default();
+        for criterion in criteria.split(',') {
+            let (name, value) = criterion
+                .split_once('=')
+                .ok_or_else(|| format_err!("Expected <criterion>=<value>, got {}", criterion))?;
+            match name {
+                "min_tps" => result.min_tps = Some(value.parse()?),
+                "max_p99_latency_ms" => {
+                    result.max_p99_latency = Some(Duration::from_millis(value.parse()?))
+                },
+                "max_expired_ratio" => result.max_expired_ratio = Some(value.parse()?),
+                _ => bail!("Unknown success criterion {}", name),
+            }
+        }
+        Ok(result)
+    }
+
+    /// Returns descriptions of the criteria the stats don't meet.
+    pub fn violations(&self, stats: &TxnStats) -> Vec<String> {
+        let rate = stats.rate();
+        let mut violations = vec![];
+        if let Some(min_tps) = self.min_tps {
+            if rate.committed < min_tps {
+                violations.push(format!("committed {} TPS < {}", rate.committed, min_tps));
+            }
+        }
+        if let Some(max_p99_latency) = self.max_p99_latency {
+            if rate.p99_latency > max_p99_latency.as_millis() as u64 {
+                violations.push(format!(
+                    "p99 latency {} ms > {} ms",
+                    rate.p99_latency,
+                    max_p99_latency.as_millis()
+                ));
+            }
+        }
+        if let Some(max_expired_ratio) = self.max_expired_ratio {
+            let expired_ratio = stats.expired as f64 / stats.submitted.max(1) as f64;
+            if expired_ratio > max_expired_ratio {
+                violations.push(format!(
+                    "expired ratio {:.3} > {}",
+                    expired_ratio, max_expired_ratio
+                ));
+            }
+        }
+        violations
+    }
+}
+
+/// Phase of a run, emitting with the phase's transaction mix for duration.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PlannedPhase {
+    pub duration: Duration,
+    pub success_criteria: Option<SuccessCriteria>,
+}
+
+/// Phases of a run that didn't meet their success criteria.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PhaseFailures {
+    /// (phase, violated criteria)
+    pub failures: Vec<(usize, Vec<String>)>,
+}
+
+impl fmt::Display for PhaseFailures {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Phases didn't meet success criteria:")?;
+        for (phase, violations) in &self.failures {
+            write!(f, " [phase {}] {}", phase, violations.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for PhaseFailures {}
+
+impl PhaseFailures {
+    /// Checks stats of each phase against the criteria of the planned phase.
+    pub fn find(plan: &[PlannedPhase], stats_per_phase: &[TxnStats]) -> Option<Self> {
+        let failures: Vec<_> = plan
+            .iter()
+            .zip(stats_per_phase)
+            .enumerate()
+            .filter_map(|(phase, (planned, stats))| {
+                let violations = planned.success_criteria.as_ref()?.violations(stats);
+                (!violations.is_empty()).then_some((phase, violations))
+            })
+            .collect();
+        (!failures.is_empty()).then_some(Self { failures })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{PhaseFailures, PlannedPhase, SuccessCriteria};
+    use crate::emitter::stats::TxnStats;
+    use std::time::Duration;
+
+    #[test]
+    pub fn test_phase_failures() {
+        let criteria = SuccessCriteria::parse("min_tps=100,max_expired_ratio=0.1").unwrap();
+        assert_eq!(criteria.min_tps, Some(100));
+        assert!(SuccessCriteria::parse("max_tps=100").is_err());
+
+        let plan = vec![
+            PlannedPhase {
+                duration: Duration::from_secs(10),
+                success_criteria: None,
+            },
+            PlannedPhase {
+                duration: Duration::from_secs(10),
+                success_criteria: Some(criteria),
+            },
+        ];
+        let stats = |committed, expired| TxnStats {
+            submitted: committed + expired,
+            committed,
+            expired,
+            lasted: Duration::from_secs(10),
+            ..TxnStats::default()
+        };
+        assert!(PhaseFailures::find(&plan, &[stats(0, 0), stats(2000, 100)]).is_none());
+
+        let failures = PhaseFailures::find(&plan, &[stats(0, 0), stats(500, 500)]).unwrap();
+        assert_eq!(failures.failures.len(), 1);
+        assert_eq!(failures.failures[0].0, 1);
+        assert_eq!(failures.failures[0].1.len(), 2);
+    }
+}
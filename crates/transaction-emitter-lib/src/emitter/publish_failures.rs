@@ -0,0 +1,88 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::emitter::counters::EMITTER_PUBLISH_FAILURES;
+use aptos_crypto::HashValue;
+use aptos_rest_client::Client as RestClient;
+use aptos_sdk::{
+    move_types::account_address::AccountAddress,
+    types::{
+        transaction::{SignedTransaction, TransactionPayload},
+        vm_status::StatusCode,
+    },
+};
+use futures::future::join_all;
+
+/// Whether the transaction publishes (or upgrades) a package.
+pub fn is_code_publish(txn: &SignedTransaction) -> bool {
+    match txn.payload() {
+        TransactionPayload::EntryFunction(entry_function) => {
+            entry_function.module().address() == &AccountAddress::ONE
+                && entry_function.module().name().as_str() == "code"
+                && entry_function.function().as_str() == "publish_package_txn"
+        },
+        _ => false,
+    }
+}
+
+/// Counts a publish rejected on submission, with the status code of the rejection.
+pub fn record_rejected(status_code: Option<StatusCode>) {
+    let reason = status_code.map_or_else(|| "unknown".to_string(), |code| format!("{:?}", code));
+    EMITTER_PUBLISH_FAILURES
+        .with_label_values(&["submission", &reason])
+        .inc();
+}
+
+/// Fetches committed publishes by hash, counting the ones that failed in execution by
+/// reason. Publishes not found (e.g. expired) are skipped, as they are counted as expired.
+pub async fn record_execution_failures(client: &RestClient, hashes: &[HashValue]) {
+    let results = join_all(
+        hashes
+            .iter()
+            .map(|hash| client.get_transaction_by_hash(*hash)),
+    )
+    .await;
+    for response in results.into_iter().flatten() {
+        let transaction = response.into_inner();
+        if !transaction.is_pending() && !transaction.success() {
+            EMITTER_PUBLISH_FAILURES
+                .with_label_values(&["execution", failure_reason(&transaction.vm_status())])
+                .inc();
+        }
+    }
+}
+
+/// Reason of a failed execution, with few enough distinct values to be a label: the
+/// abort code name for Move aborts (e.g. EPACKAGE_DEP_MISSING), and the status otherwise
+/// (e.g. BACKWARD_INCOMPATIBLE_MODULE_UPDATE), without the explanation.
+pub fn failure_reason(vm_status: &str) -> &str {
+    let reason = match vm_status.strip_prefix("Move abort in ") {
+        Some(abort) => abort
+            .split_once(": ")
+            .map_or(abort, |(_, code)| code.split('(').next().unwrap_or(code)),
+        None => vm_status.split(':').next().unwrap_or(vm_status),
+    };
+    reason.trim()
+}
+
+#[cfg(test)]
+mod test {
+    use super::failure_reason;
+
+    #[test]
+    pub fn test_failure_reason() {
+        assert_eq!(
+            failure_reason("Move abort in 0x1::code: EPACKAGE_DEP_MISSING(0x60005): Dependency could not be resolved to any published package."),
+            "EPACKAGE_DEP_MISSING"
+        );
+        assert_eq!(
+            failure_reason("BACKWARD_INCOMPATIBLE_MODULE_UPDATE"),
+            "BACKWARD_INCOMPATIBLE_MODULE_UPDATE"
+        );
+        assert_eq!(failure_reason("Out of gas"), "Out of gas");
+        assert_eq!(
+            failure_reason("Move abort in 0x1::code: 0x10003"),
+            "0x10003"
+        );
+    }
+}
@@ -0,0 +1,167 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::emitter::{
+    counters::{EMITTER_RUN_LATENCY_MS, EMITTER_RUN_PHASE, EMITTER_RUN_TXNS},
+    stats::DynamicStatsTracking,
+};
+use anyhow::{ensure, Context, Result};
+use aptos_logger::{sample, sample::SampleRate, warn};
+use aptos_metrics_core::{Encoder, TextEncoder};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::time;
+
+const JOB_NAME: &str = "aptos_transaction_emitter";
+
+/// Where, and how often, metrics of a run are pushed to.
+#[derive(Clone, Debug)]
+pub struct PushgatewayConfig {
+    /// Base URL of the pushgateway, e.g. `http://pushgateway:9091`
+    url: String,
+    /// Metrics of the run are grouped under it, to tell runs apart
+    run_id: String,
+    interval: Duration,
+}
+
+impl PushgatewayConfig {
+    pub fn new(url: String, run_id: String, interval: Duration) -> Result<Self> {
+        ensure!(
+            !run_id.is_empty()
+                && run_id
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'),
+            "Run ID can only contain alphanumeric characters, '-' and '_', got {:?}",
+            run_id
+        );
+        ensure!(!interval.is_zero(), "Push interval needs to be positive");
+        Ok(Self {
+            url,
+            run_id,
+            interval,
+        })
+    }
+
+    pub fn run_id(&self) -> &str {
+        &self.run_id
+    }
+
+    fn push_url(&self) -> String {
+        format!(
+            "{}/metrics/job/{}/run_id/{}",
+            self.url.trim_end_matches('/'),
+            JOB_NAME,
+            self.run_id
+        )
+    }
+}
+
+/// Pushes metrics of the process, with the stats of the run set as gauges, to a
+/// Prometheus pushgateway, as short-lived runs cannot be scraped reliably.
+#[derive(Debug)]
+pub struct RunMetricsPusher {
+    config: PushgatewayConfig,
+    client: reqwest::Client,
+    stats: Arc<DynamicStatsTracking>,
+    start: Instant,
+}
+
+impl RunMetricsPusher {
+    pub fn new(config: PushgatewayConfig, stats: Arc<DynamicStatsTracking>) -> Self {
+        Self {
+            config,
+            client: reqwest::Client::new(),
+            stats,
+            start: Instant::now(),
+        }
+    }
+
+    fn update_metrics(&self) {
+        let total = self.stats.accumulate_total(self.start.elapsed());
+        for (kind, value) in [
+            ("submitted", total.submitted),
+            ("committed", total.committed),
+            ("expired", total.expired),
+            ("failed_submission", total.failed_submission),
+        ] {
+            EMITTER_RUN_TXNS
+                .with_label_values(&[kind])
+                .set(value as i64);
+        }
+        let rate = total.rate();
+        for (percentile, value) in [
+            ("p50", rate.p50_latency),
+            ("p90", rate.p90_latency),
+            ("p99", rate.p99_latency),
+        ] {
+            EMITTER_RUN_LATENCY_MS
+                .with_label_values(&[percentile])
+                .set(value as i64);
+        }
+        EMITTER_RUN_PHASE.set(self.stats.get_cur_phase() as i64);
+    }
+
+    /// Replaces metrics of the run in the pushgateway with the current ones.
+    pub async fn push(&self) -> Result<()> {
+        self.update_metrics();
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&aptos_metrics_core::gather(), &mut buffer)?;
+        self.client
+            .put(self.config.push_url())
+            .timeout(Duration::from_secs(10))
+            .body(buffer)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .with_context(|| format!("Failed to push metrics to {}", self.config.url))?;
+        Ok(())
+    }
+
+    /// Pushes every interval, until aborted.
+    pub async fn run(self: Arc<Self>) {
+        loop {
+            time::sleep(self.config.interval).await;
+            if let Err(e) = self.push().await {
+                sample!(
+                    SampleRate::Duration(Duration::from_secs(60)),
+                    warn!("{:?}", e)
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{PushgatewayConfig, RunMetricsPusher};
+    use crate::emitter::{counters::EMITTER_RUN_TXNS, stats::DynamicStatsTracking};
+    use std::{
+        sync::{atomic::Ordering, Arc},
+        time::Duration,
+    };
+
+    #[test]
+    pub fn test_pushgateway() {
+        let interval = Duration::from_secs(15);
+        let config = PushgatewayConfig::new(
+            "http://localhost:9091/".to_string(),
+            "run-1".to_string(),
+            interval,
+        )
+        .unwrap();
+        assert_eq!(
+            config.push_url(),
+            "http://localhost:9091/metrics/job/aptos_transaction_emitter/run_id/run-1"
+        );
+        assert!(PushgatewayConfig::new(String::new(), "run/1".to_string(), interval).is_err());
+
+        let stats = Arc::new(DynamicStatsTracking::new(2));
+        stats.get_cur().committed.fetch_add(10, Ordering::Relaxed);
+        stats.start_next_phase();
+        stats.get_cur().committed.fetch_add(5, Ordering::Relaxed);
+        RunMetricsPusher::new(config, stats).update_metrics();
+        assert_eq!(EMITTER_RUN_TXNS.with_label_values(&["committed"]).get(), 15);
+    }
+}
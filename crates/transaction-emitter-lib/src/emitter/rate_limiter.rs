@@ -0,0 +1,88 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use aptos_infallible::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Debug)]
+pub struct RateLimit {
+    /// Sustained number of requests per second.
+    pub qps: f64,
+    /// Number of requests that can be sent at once, after a period of inactivity.
+    pub burst: usize,
+}
+
+#[derive(Debug)]
+struct BucketState {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+/// Token bucket, refilled at rate.qps, and holding at most rate.burst tokens.
+#[derive(Debug)]
+pub struct TokenBucket {
+    rate: RateLimit,
+    state: Mutex<BucketState>,
+}
+
+impl TokenBucket {
+    pub fn new(rate: RateLimit) -> Self {
+        assert!(rate.qps > 0.0, "Rate limit qps needs to be positive");
+        assert!(rate.burst > 0, "Rate limit burst needs to be positive");
+        Self {
+            state: Mutex::new(BucketState {
+                tokens: rate.burst as f64,
+                last_refill: Instant::now(),
+            }),
+            rate,
+        }
+    }
+
+    /// Takes a token, and returns how long the caller needs to wait before using it.
+    /// Bucket can go into debt, so that concurrent callers are queued in order.
+    pub fn reserve(&self) -> Duration {
+        let mut state = self.state.lock();
+        let now = Instant::now();
+        state.tokens = (state.tokens
+            + now.duration_since(state.last_refill).as_secs_f64() * self.rate.qps)
+            .min(self.rate.burst as f64);
+        state.last_refill = now;
+        state.tokens -= 1.0;
+        if state.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-state.tokens / self.rate.qps)
+        }
+    }
+
+    pub async fn acquire(&self) {
+        let delay = self.reserve();
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{RateLimit, TokenBucket};
+    use std::time::Duration;
+
+    #[test]
+    pub fn test_token_bucket() {
+        let bucket = TokenBucket::new(RateLimit {
+            qps: 10.0,
+            burst: 2,
+        });
+        assert_eq!(bucket.reserve(), Duration::ZERO);
+        assert_eq!(bucket.reserve(), Duration::ZERO);
+
+        let delay = bucket.reserve();
+        assert!(delay <= Duration::from_millis(100));
+        assert!(delay > Duration::from_millis(50));
+
+        let delay = bucket.reserve();
+        assert!(delay <= Duration::from_millis(200));
+        assert!(delay > Duration::from_millis(150));
+    }
+}
@@ -0,0 +1,107 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use aptos_sdk::types::LocalAccount;
+use crossbeam::queue::SegQueue;
+
+/// Accounts shared by all workers, each checking out the senders of a round and
+/// returning them, with their sequence numbers updated, once the round's transactions
+/// are committed or expired, so that a fixed set of accounts serves any number of
+/// rounds. Backed by a lock-free queue, so that checking out doesn't contend on a lock
+/// however many workers there are. Accounts are used in turn, the least recently
+/// returned first.
+#[derive(Debug, Default)]
+pub struct RecyclingAccountPool {
+    accounts: SegQueue<LocalAccount>,
+}
+
+impl RecyclingAccountPool {
+    pub fn new(accounts: Vec<LocalAccount>) -> Self {
+        let pool = Self::default();
+        pool.check_in(accounts);
+        pool
+    }
+
+    /// Up to num_accounts accounts, fewer if others are checked out, owned by the caller
+    /// until checked back in.
+    pub fn check_out(&self, num_accounts: usize) -> Vec<LocalAccount> {
+        (0..num_accounts)
+            .map_while(|_| self.accounts.pop())
+            .collect()
+    }
+
+    pub fn check_in(&self, accounts: Vec<LocalAccount>) {
+        for account in accounts {
+            self.accounts.push(account);
+        }
+    }
+
+    /// Accounts available to check out.
+    pub fn len(&self) -> usize {
+        self.accounts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+    }
+
+    /// All available accounts, e.g. once workers are done with them.
+    pub fn drain(&self) -> Vec<LocalAccount> {
+        std::iter::from_fn(|| self.accounts.pop()).collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::RecyclingAccountPool;
+    use aptos_sdk::types::LocalAccount;
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::{collections::HashSet, sync::Arc, thread};
+
+    #[test]
+    pub fn test_recycling_account_pool() {
+        let mut rng = StdRng::from_seed([0; 32]);
+        let accounts: Vec<_> = (0..10).map(|_| LocalAccount::generate(&mut rng)).collect();
+        let addresses: HashSet<_> = accounts.iter().map(LocalAccount::address).collect();
+        let pool = Arc::new(RecyclingAccountPool::new(accounts));
+
+        let mut checked_out = pool.check_out(8);
+        assert_eq!((checked_out.len(), pool.len()), (8, 2));
+        assert_eq!(pool.check_out(8).len(), 2);
+        assert!(pool.is_empty());
+        *checked_out[0].sequence_number_mut() = 5;
+        let address = checked_out[0].address();
+        pool.check_in(checked_out);
+        assert_eq!(pool.len(), 8);
+
+        // returned with its updated sequence number
+        let recycled = pool.check_out(1);
+        assert_eq!(recycled[0].address(), address);
+        assert_eq!(recycled[0].sequence_number(), 5);
+        pool.check_in(recycled);
+
+        let workers: Vec<_> = (0..4)
+            .map(|_| {
+                let pool = pool.clone();
+                thread::spawn(move || {
+                    for _ in 0..1000 {
+                        let mut accounts = pool.check_out(3);
+                        for account in &mut accounts {
+                            *account.sequence_number_mut() += 1;
+                        }
+                        pool.check_in(accounts);
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+        let accounts = pool.drain();
+        assert_eq!(accounts.len(), 8);
+        assert!(accounts
+            .iter()
+            .all(|account| addresses.contains(&account.address())));
+        assert!(pool.is_empty());
+    }
+}
@@ -0,0 +1,119 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::emitter::result_report::{ResultReport, StatsReport};
+use std::fmt;
+
+/// Thresholds on how much worse than a baseline run (from its result report) a run can
+/// be, for using the emitter as a performance regression gate.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RegressionCheck {
+    pub baseline: ResultReport,
+    /// Max drop of committed TPS, in percent of the baseline's
+    pub max_tps_drop_pct: Option<f64>,
+    /// Max increase of p99 latency, in percent of the baseline's
+    pub max_p99_latency_increase_pct: Option<f64>,
+}
+
+/// Ways a run regressed compared to the baseline.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Regressions {
+    pub regressions: Vec<String>,
+}
+
+impl fmt::Display for Regressions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Run regressed compared to the baseline: {}",
+            self.regressions.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for Regressions {}
+
+impl RegressionCheck {
+    /// Compares the total of the run, and each of its phases if it has as many as the
+    /// baseline, to the baseline.
+    pub fn find(&self, report: &ResultReport) -> Option<Regressions> {
+        let mut regressions = self.compare("total", &self.baseline.total, &report.total);
+        if self.baseline.phases.len() == report.phases.len() && report.phases.len() > 1 {
+            for (phase, (baseline, current)) in self
+                .baseline
+                .phases
+                .iter()
+                .zip(report.phases.iter())
+                .enumerate()
+            {
+                regressions.extend(self.compare(&format!("phase {}", phase), baseline, current));
+            }
+        }
+        (!regressions.is_empty()).then_some(Regressions { regressions })
+    }
+
+    fn compare(&self, part: &str, baseline: &StatsReport, current: &StatsReport) -> Vec<String> {
+        let change_pct = |baseline: u64, current: u64| {
+            (current as f64 - baseline as f64) * 100.0 / (baseline.max(1) as f64)
+        };
+        let mut regressions = vec![];
+        if let Some(max_tps_drop_pct) = self.max_tps_drop_pct {
+            let drop_pct = -change_pct(baseline.committed_tps, current.committed_tps);
+            if drop_pct > max_tps_drop_pct {
+                regressions.push(format!(
+                    "[{}] committed TPS dropped {:.1}% ({} -> {}), more than {}%",
+                    part, drop_pct, baseline.committed_tps, current.committed_tps, max_tps_drop_pct
+                ));
+            }
+        }
+        if let Some(max_increase_pct) = self.max_p99_latency_increase_pct {
+            let increase_pct = change_pct(baseline.latency_p99_ms, current.latency_p99_ms);
+            if increase_pct > max_increase_pct {
+                regressions.push(format!(
+                    "[{}] p99 latency rose {:.1}% ({} ms -> {} ms), more than {}%",
+                    part,
+                    increase_pct,
+                    baseline.latency_p99_ms,
+                    current.latency_p99_ms,
+                    max_increase_pct
+                ));
+            }
+        }
+        regressions
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::RegressionCheck;
+    use crate::emitter::result_report::{ResultReport, StatsReport};
+
+    #[test]
+    pub fn test_regression_check() {
+        let report = |committed_tps, latency_p99_ms| ResultReport {
+            total: StatsReport {
+                committed_tps,
+                latency_p99_ms,
+                ..StatsReport::default()
+            },
+            ..ResultReport::default()
+        };
+        let check = RegressionCheck {
+            baseline: report(1000, 2000),
+            max_tps_drop_pct: Some(10.0),
+            max_p99_latency_increase_pct: Some(25.0),
+        };
+        assert!(check.find(&report(950, 2400)).is_none());
+        assert!(check.find(&report(2000, 100)).is_none());
+        let regressions = check.find(&report(800, 3000)).unwrap();
+        assert_eq!(regressions.regressions, vec![
+            "[total] committed TPS dropped 20.0% (1000 -> 800), more than 10%".to_string(),
+            "[total] p99 latency rose 50.0% (2000 ms -> 3000 ms), more than 25%".to_string(),
+        ]);
+
+        // survives a round trip through the written report
+        let baseline: ResultReport =
+            serde_json::from_value(serde_json::to_value(&check.baseline).unwrap()).unwrap();
+        assert_eq!(baseline, check.baseline);
+    }
+}
@@ -0,0 +1,169 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::emitter::{sequence_audit::SequenceAudit, stats::TxnStats};
+use anyhow::{Context, Result};
+use aptos_rest_client::Client as RestClient;
+use aptos_sdk::types::LocalAccount;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, path::Path};
+
+/// Counts (and rates) of a part of the run, with latencies in milliseconds.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct StatsReport {
+    pub lasted_secs: f64,
+    pub submitted: u64,
+    pub committed: u64,
+    pub expired: u64,
+    pub failed_submission: u64,
+    pub submitted_tps: u64,
+    pub committed_tps: u64,
+    pub expired_tps: u64,
+    pub failed_submission_tps: u64,
+    pub latency_avg_ms: u64,
+    pub latency_p50_ms: u64,
+    pub latency_p90_ms: u64,
+    pub latency_p99_ms: u64,
+    pub latency_samples: u64,
+}
+
+impl From<&TxnStats> for StatsReport {
+    fn from(stats: &TxnStats) -> Self {
+        let rate = stats.rate();
+        Self {
+            lasted_secs: stats.lasted.as_secs_f64(),
+            submitted: stats.submitted,
+            committed: stats.committed,
+            expired: stats.expired,
+            failed_submission: stats.failed_submission,
+            submitted_tps: rate.submitted,
+            committed_tps: rate.committed,
+            expired_tps: rate.expired,
+            failed_submission_tps: rate.failed_submission,
+            latency_avg_ms: rate.latency,
+            latency_p50_ms: rate.p50_latency,
+            latency_p90_ms: rate.p90_latency,
+            latency_p99_ms: rate.p99_latency,
+            latency_samples: rate.latency_samples,
+        }
+    }
+}
+
+/// Coins spent by the accounts emitting transactions, i.e. their balance at the start
+/// of emitting minus the one at the end. Includes coins transferred outside of them.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct GasReport {
+    pub gas_price: u64,
+    pub coins_spent: u64,
+    pub coins_spent_per_committed_txn: u64,
+}
+
+impl GasReport {
+    pub fn new(gas_price: u64, initial_balance: u64, final_balance: u64, committed: u64) -> Self {
+        let coins_spent = initial_balance.saturating_sub(final_balance);
+        Self {
+            gas_price,
+            coins_spent,
+            coins_spent_per_committed_txn: coins_spent / committed.max(1),
+        }
+    }
+}
+
+/// Results of a run, written as JSON at the end of it, for CI pipelines and test
+/// harnesses to assert on, instead of parsing logs.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct ResultReport {
+    pub total: StatsReport,
+    pub phases: Vec<StatsReport>,
+    // empty unless running a mix of workloads
+    pub workloads: BTreeMap<String, StatsReport>,
+    pub endpoints: BTreeMap<String, StatsReport>,
+    // empty unless endpoints are grouped
+    pub groups: BTreeMap<String, StatsReport>,
+    // empty unless tracking per-worker stats
+    pub worker_imbalances: Vec<String>,
+    // none if balances couldn't be queried
+    pub gas: Option<GasReport>,
+    // none unless auditing sequence numbers
+    pub sequence_audit: Option<SequenceAudit>,
+}
+
+impl ResultReport {
+    pub fn new(stats_per_phase: &[TxnStats]) -> Self {
+        let total = stats_per_phase
+            .iter()
+            .fold(TxnStats::default(), |total, stats| &total + stats);
+        Self {
+            total: (&total).into(),
+            phases: stats_per_phase.iter().map(StatsReport::from).collect(),
+            ..Self::default()
+        }
+    }
+
+    /// Reads a report written by a previous run, e.g. as a baseline to compare against.
+    pub fn load(path: &Path) -> Result<Self> {
+        let bytes = fs::read(path)
+            .with_context(|| format!("Failed to read result report {}", path.display()))?;
+        serde_json::from_slice(&bytes)
+            .with_context(|| format!("Failed to parse result report {}", path.display()))
+    }
+
+    /// Writes to a temporary file first, so that readers never see a partial report.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, serde_json::to_vec_pretty(self)?)
+            .with_context(|| format!("Failed to write result report {}", tmp_path.display()))?;
+        fs::rename(&tmp_path, path)
+            .with_context(|| format!("Failed to write result report {}", path.display()))?;
+        Ok(())
+    }
+}
+
+pub async fn total_balance<'a, I>(client: &RestClient, accounts: I) -> Result<u64>
+where
+    I: Iterator<Item = &'a LocalAccount>,
+{
+    let balances = futures::stream::iter(accounts.map(|account| async move {
+        Ok(client
+            .get_account_balance(account.address())
+            .await?
+            .into_inner()
+            .get())
+    }))
+    .buffered(BALANCE_QUERY_PARALLELISM)
+    .collect::<Vec<Result<u64>>>()
+    .await;
+    balances.into_iter().sum()
+}
+
+const BALANCE_QUERY_PARALLELISM: usize = 100;
+
+#[cfg(test)]
+mod test {
+    use super::{GasReport, ResultReport};
+    use crate::emitter::stats::TxnStats;
+    use std::time::Duration;
+
+    #[test]
+    pub fn test_result_report() {
+        let stats = |committed, expired| TxnStats {
+            submitted: committed + expired,
+            committed,
+            expired,
+            lasted: Duration::from_secs(10),
+            ..TxnStats::default()
+        };
+        let mut report = ResultReport::new(&[stats(1000, 0), stats(3000, 100)]);
+        assert_eq!(report.phases[1].committed_tps, 300);
+        let total = &report.total;
+        assert_eq!(total.committed, 4000);
+        assert_eq!(total.expired, 100);
+        assert_eq!(total.committed_tps, 200);
+
+        report.gas = Some(GasReport::new(100, 1_000_000, 600_000, 4000));
+        let json: serde_json::Value = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["phases"][0]["submitted"], 1000);
+        assert_eq!(json["gas"]["coins_spent_per_committed_txn"], 100);
+    }
+}
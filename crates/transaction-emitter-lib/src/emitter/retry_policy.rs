@@ -0,0 +1,136 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::transaction_executor::RetryBackoff;
+use aptos_rest_client::error::RestError;
+use rand::thread_rng;
+use std::{future::Future, time::Duration};
+
+/// Decides how client calls that are necessary for the test to proceed
+/// (e.g. querying balances and sequence numbers) are retried.
+pub trait RetryPolicy: Sync + Send {
+    /// Maximum number of attempts, including the first one.
+    fn max_attempts(&self) -> usize;
+
+    /// Delay before the given retry, with retry 0 following the first failed attempt.
+    fn delay(&self, retry: usize) -> Duration;
+
+    /// Whether a call that failed with the given error should be attempted again.
+    fn is_retryable(&self, _error: &RestError) -> bool {
+        true
+    }
+}
+
+/// Retries all errors, with exponentially growing delay between attempts.
+#[derive(Clone, Debug)]
+pub struct ExponentialRetryPolicy {
+    pub max_retries: usize,
+    pub backoff: RetryBackoff,
+}
+
+impl ExponentialRetryPolicy {
+    pub fn new(max_retries: usize, initial_delay: Duration) -> Self {
+        Self {
+            max_retries,
+            backoff: RetryBackoff::Exponential {
+                initial_delay,
+                max_delay: Duration::MAX,
+                jitter: true,
+            },
+        }
+    }
+}
+
+impl RetryPolicy for ExponentialRetryPolicy {
+    fn max_attempts(&self) -> usize {
+        self.max_retries + 1
+    }
+
+    fn delay(&self, retry: usize) -> Duration {
+        self.backoff.delay_for_round(retry, &mut thread_rng())
+    }
+}
+
+/// Calls f until it succeeds, or until policy says the error shouldn't be retried.
+pub async fn retry<T, F, Fut>(policy: &dyn RetryPolicy, mut f: F) -> Result<T, RestError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, RestError>>,
+{
+    let mut retry = 0;
+    loop {
+        match f().await {
+            Ok(value) => return Ok(value),
+            Err(err) => {
+                if retry + 1 >= policy.max_attempts() || !policy.is_retryable(&err) {
+                    return Err(err);
+                }
+                tokio::time::sleep(policy.delay(retry)).await;
+                retry += 1;
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{retry, RetryPolicy};
+    use aptos_rest_client::error::RestError;
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    struct NoDelayPolicy {
+        max_attempts: usize,
+    }
+
+    impl RetryPolicy for NoDelayPolicy {
+        fn max_attempts(&self) -> usize {
+            self.max_attempts
+        }
+
+        fn delay(&self, _retry: usize) -> Duration {
+            Duration::ZERO
+        }
+
+        fn is_retryable(&self, error: &RestError) -> bool {
+            matches!(error, RestError::Timeout(_))
+        }
+    }
+
+    #[tokio::test]
+    pub async fn test_retry_policy() {
+        let policy = NoDelayPolicy { max_attempts: 3 };
+
+        let attempts = AtomicUsize::new(0);
+        let result: Result<(), _> = retry(&policy, || async {
+            attempts.fetch_add(1, Ordering::Relaxed);
+            Err(RestError::Timeout("timeout"))
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(attempts.load(Ordering::Relaxed), 3);
+
+        let attempts = AtomicUsize::new(0);
+        let result = retry(&policy, || async {
+            if attempts.fetch_add(1, Ordering::Relaxed) == 0 {
+                Err(RestError::Timeout("timeout"))
+            } else {
+                Ok(5)
+            }
+        })
+        .await;
+        assert_eq!(result.unwrap(), 5);
+        assert_eq!(attempts.load(Ordering::Relaxed), 2);
+
+        let attempts = AtomicUsize::new(0);
+        let result: Result<(), _> = retry(&policy, || async {
+            attempts.fetch_add(1, Ordering::Relaxed);
+            Err(RestError::Unknown(anyhow::anyhow!("not retryable")))
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(attempts.load(Ordering::Relaxed), 1);
+    }
+}
@@ -0,0 +1,171 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::{Context, Result};
+use aptos_crypto::{ed25519::Ed25519PrivateKey, ValidCryptoMaterialStringExt};
+use aptos_infallible::Mutex;
+use aptos_sdk::types::{AccountKey, LocalAccount};
+use aptos_transaction_generator_lib::TransactionExecutor;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+pub enum RunPhase {
+    #[default]
+    CreatingSeedAccounts,
+    CreatingAccounts,
+    Emitting,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct PersistedState {
+    phase: RunPhase,
+    // private keys of funded seed accounts
+    seed_accounts: Vec<String>,
+    // private keys of created (and funded) accounts
+    accounts: Vec<String>,
+}
+
+/// Progress of an emitter run, persisted to a file after every step of account
+/// creation, so that a rerun after a crash can resume where it left off, instead of
+/// creating and funding all the accounts again.
+/// Only private keys are persisted, sequence numbers are queried on resume.
+#[derive(Debug)]
+pub struct RunState {
+    path: PathBuf,
+    state: Mutex<PersistedState>,
+}
+
+impl RunState {
+    /// Loads state from path, or starts from scratch if it doesn't exist.
+    pub fn load_or_create(path: &Path) -> Result<Self> {
+        let state = if path.exists() {
+            let contents = fs::read_to_string(path)
+                .with_context(|| format!("Failed to read run state {}", path.display()))?;
+            serde_json::from_str(&contents)
+                .with_context(|| format!("Invalid run state {}", path.display()))?
+        } else {
+            PersistedState::default()
+        };
+        Ok(Self {
+            path: path.to_path_buf(),
+            state: Mutex::new(state),
+        })
+    }
+
+    pub fn phase(&self) -> RunPhase {
+        self.state.lock().phase
+    }
+
+    pub fn num_accounts(&self) -> usize {
+        self.state.lock().accounts.len()
+    }
+
+    pub fn set_phase(&self, phase: RunPhase) -> Result<()> {
+        let mut state = self.state.lock();
+        state.phase = phase;
+        self.save(&state)
+    }
+
+    /// Records funded seed accounts, moving to the CreatingAccounts phase.
+    pub fn record_seed_accounts(&self, seed_accounts: &[LocalAccount]) -> Result<()> {
+        let mut state = self.state.lock();
+        state.seed_accounts = encode_keys(seed_accounts)?;
+        state.phase = RunPhase::CreatingAccounts;
+        self.save(&state)
+    }
+
+    pub fn record_accounts(&self, accounts: &[LocalAccount]) -> Result<()> {
+        let mut state = self.state.lock();
+        state.accounts.append(&mut encode_keys(accounts)?);
+        self.save(&state)
+    }
+
+    pub async fn load_seed_accounts(
+        &self,
+        txn_executor: &dyn TransactionExecutor,
+    ) -> Result<Vec<LocalAccount>> {
+        let keys = self.state.lock().seed_accounts.clone();
+        load_accounts(&keys, txn_executor).await
+    }
+
+    pub async fn load_accounts(
+        &self,
+        txn_executor: &dyn TransactionExecutor,
+    ) -> Result<Vec<LocalAccount>> {
+        let keys = self.state.lock().accounts.clone();
+        load_accounts(&keys, txn_executor).await
+    }
+
+    /// Writes to a temporary file first, so that a crash while saving doesn't
+    /// corrupt the previous state.
+    fn save(&self, state: &PersistedState) -> Result<()> {
+        let tmp_path = self.path.with_extension("tmp");
+        fs::write(&tmp_path, serde_json::to_vec(state)?)
+            .with_context(|| format!("Failed to write run state {}", tmp_path.display()))?;
+        fs::rename(&tmp_path, &self.path)
+            .with_context(|| format!("Failed to write run state {}", self.path.display()))?;
+        Ok(())
+    }
+}
+
+fn encode_keys(accounts: &[LocalAccount]) -> Result<Vec<String>> {
+    accounts
+        .iter()
+        .map(|account| Ok(account.private_key().to_encoded_string()?))
+        .collect()
+}
+
+async fn load_accounts(
+    keys: &[String],
+    txn_executor: &dyn TransactionExecutor,
+) -> Result<Vec<LocalAccount>> {
+    let account_futures = keys.iter().map(|key| async move {
+        let account_key =
+            AccountKey::from_private_key(Ed25519PrivateKey::from_encoded_string(key)?);
+        let address = account_key.authentication_key().derived_address();
+        let sequence_number = txn_executor.query_sequence_number(address).await?;
+        Ok(LocalAccount::new(address, account_key, sequence_number))
+    });
+    futures::stream::iter(account_futures)
+        .buffered(LOAD_PARALLELISM)
+        .collect::<Vec<Result<_>>>()
+        .await
+        .into_iter()
+        .collect()
+}
+
+const LOAD_PARALLELISM: usize = 100;
+
+#[cfg(test)]
+mod test {
+    use super::{RunPhase, RunState};
+    use aptos_sdk::types::LocalAccount;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    pub fn test_run_state() {
+        let path = std::env::temp_dir().join(format!("run_state_{}.json", std::process::id()));
+        let mut rng = StdRng::from_seed([0; 32]);
+        let accounts: Vec<_> = (0..3).map(|_| LocalAccount::generate(&mut rng)).collect();
+
+        let state = RunState::load_or_create(&path).unwrap();
+        assert_eq!(state.phase(), RunPhase::CreatingSeedAccounts);
+        state.record_seed_accounts(&accounts[..1]).unwrap();
+        state.record_accounts(&accounts[1..]).unwrap();
+
+        let state = RunState::load_or_create(&path).unwrap();
+        assert_eq!(state.phase(), RunPhase::CreatingAccounts);
+        assert_eq!(state.num_accounts(), 2);
+        state.set_phase(RunPhase::Emitting).unwrap();
+        assert_eq!(
+            RunState::load_or_create(&path).unwrap().phase(),
+            RunPhase::Emitting
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+}
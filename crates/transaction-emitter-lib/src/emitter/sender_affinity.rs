@@ -0,0 +1,121 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use aptos_infallible::RwLock;
+use aptos_sdk::move_types::account_address::AccountAddress;
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Assignment {
+    pub client_index: usize,
+    /// Pinned assignments are kept on rebalance.
+    pub pinned: bool,
+}
+
+/// Tracks which client owns each sender, i.e. receives first submission of all of
+/// its transactions. Transactions from the same sender need to go to the same client,
+/// so that they are not placed in the parking lot, due to arriving out of order.
+/// Assignments are kept across batches, until rebalanced.
+#[derive(Debug, Default)]
+pub struct SenderAffinity {
+    assignments: RwLock<HashMap<AccountAddress, Assignment>>,
+}
+
+impl SenderAffinity {
+    pub fn get(&self, sender: &AccountAddress) -> Option<Assignment> {
+        self.assignments.read().get(sender).copied()
+    }
+
+    /// Returns the client owning the sender, assigning it with assign if sender is new.
+    pub fn get_or_assign<F>(&self, sender: AccountAddress, assign: F) -> usize
+    where
+        F: FnOnce() -> usize,
+    {
+        if let Some(assignment) = self.get(&sender) {
+            return assignment.client_index;
+        }
+        let client_index = assign();
+        self.assignments
+            .write()
+            .entry(sender)
+            .or_insert(Assignment {
+                client_index,
+                pinned: false,
+            })
+            .client_index
+    }
+
+    pub fn pin(&self, sender: AccountAddress, client_index: usize) {
+        self.assignments.write().insert(sender, Assignment {
+            client_index,
+            pinned: true,
+        });
+    }
+
+    /// Keeps the current assignment, but allows it to be rebalanced.
+    pub fn unpin(&self, sender: &AccountAddress) {
+        if let Some(assignment) = self.assignments.write().get_mut(sender) {
+            assignment.pinned = false;
+        }
+    }
+
+    /// Drops all assignments that are not pinned (or only ones to from_client, if given),
+    /// so that the senders get reassigned on their next transaction.
+    /// Returns number of dropped assignments.
+    pub fn rebalance(&self, from_client: Option<usize>) -> usize {
+        let mut assignments = self.assignments.write();
+        let before = assignments.len();
+        assignments.retain(|_, assignment| {
+            assignment.pinned
+                || from_client.map_or(false, |client_index| {
+                    assignment.client_index != client_index
+                })
+        });
+        before - assignments.len()
+    }
+
+    pub fn assignments(&self) -> HashMap<AccountAddress, Assignment> {
+        self.assignments.read().clone()
+    }
+
+    pub fn num_senders_per_client(&self, num_clients: usize) -> Vec<usize> {
+        let mut result = vec![0; num_clients];
+        for assignment in self.assignments.read().values() {
+            if let Some(num) = result.get_mut(assignment.client_index) {
+                *num += 1;
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SenderAffinity;
+    use aptos_sdk::move_types::account_address::AccountAddress;
+
+    #[test]
+    pub fn test_sender_affinity() {
+        let affinity = SenderAffinity::default();
+        let a = AccountAddress::from_hex_literal("0xa").unwrap();
+        let b = AccountAddress::from_hex_literal("0xb").unwrap();
+        let c = AccountAddress::from_hex_literal("0xc").unwrap();
+
+        assert_eq!(affinity.get_or_assign(a, || 0), 0);
+        // existing assignment is kept
+        assert_eq!(affinity.get_or_assign(a, || 1), 0);
+        assert_eq!(affinity.get_or_assign(b, || 1), 1);
+        affinity.pin(c, 1);
+        assert_eq!(affinity.num_senders_per_client(2), vec![1, 2]);
+
+        assert_eq!(affinity.rebalance(Some(1)), 1);
+        assert!(affinity.get(&b).is_none());
+        assert_eq!(affinity.get(&a).unwrap().client_index, 0);
+
+        assert_eq!(affinity.rebalance(None), 1);
+        assert_eq!(affinity.get(&c).unwrap().client_index, 1);
+        affinity.unpin(&c);
+        assert_eq!(affinity.rebalance(None), 1);
+        assert!(affinity.assignments().is_empty());
+    }
+}
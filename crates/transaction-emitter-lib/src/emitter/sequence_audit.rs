@@ -0,0 +1,124 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::emitter::query_sequence_numbers;
+use anyhow::Result;
+use aptos_rest_client::Client as RestClient;
+use aptos_sdk::{move_types::account_address::AccountAddress, types::LocalAccount};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Account whose sequence number tracked by the emitter differs from the one on chain.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub struct SequenceGap {
+    pub address: AccountAddress,
+    pub local: u64,
+    pub on_chain: u64,
+}
+
+impl fmt::Display for SequenceGap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.local > self.on_chain {
+            write!(
+                f,
+                "{}: {} transactions counted as committed never committed (local {}, on chain {})",
+                self.address,
+                self.local - self.on_chain,
+                self.local,
+                self.on_chain
+            )
+        } else {
+            write!(
+                f,
+                "{}: {} commits never observed (local {}, on chain {})",
+                self.address,
+                self.on_chain - self.local,
+                self.local,
+                self.on_chain
+            )
+        }
+    }
+}
+
+/// Comparison of the sequence numbers of the emitting accounts at the end of the run
+/// with the ones on chain. As the emitter rolls sequence numbers back to the committed
+/// ones after expirations, any gap means transactions were miscounted, either by the
+/// emitter itself, or because they were dropped (or committed) without it noticing.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+pub struct SequenceAudit {
+    pub accounts_checked: usize,
+    /// Transactions counted as committed, that are not on chain
+    pub never_committed: u64,
+    /// Transactions on chain, that were not counted as committed
+    pub never_observed: u64,
+    pub gaps: Vec<SequenceGap>,
+}
+
+impl SequenceAudit {
+    pub fn new(accounts: &[LocalAccount], on_chain: &[(AccountAddress, u64)]) -> Self {
+        let mut audit = Self {
+            accounts_checked: accounts.len(),
+            ..Self::default()
+        };
+        for (account, (_, on_chain)) in accounts.iter().zip(on_chain) {
+            let (local, on_chain) = (account.sequence_number(), *on_chain);
+            if local == on_chain {
+                continue;
+            }
+            if local > on_chain {
+                audit.never_committed += local - on_chain;
+            } else {
+                audit.never_observed += on_chain - local;
+            }
+            audit.gaps.push(SequenceGap {
+                address: account.address(),
+                local,
+                on_chain,
+            });
+        }
+        audit
+    }
+
+    /// Queries sequence numbers of all the accounts, in batches.
+    pub async fn run(client: &RestClient, accounts: &[LocalAccount]) -> Result<Self> {
+        let mut on_chain = Vec::with_capacity(accounts.len());
+        for batch in accounts.chunks(AUDIT_QUERY_BATCH_SIZE) {
+            let addresses = batch.iter().map(LocalAccount::address).collect::<Vec<_>>();
+            let (seq_nums, _) = query_sequence_numbers(client, addresses.iter()).await?;
+            on_chain.extend(seq_nums);
+        }
+        Ok(Self::new(accounts, &on_chain))
+    }
+}
+
+const AUDIT_QUERY_BATCH_SIZE: usize = 100;
+
+#[cfg(test)]
+mod test {
+    use super::SequenceAudit;
+    use aptos_sdk::types::LocalAccount;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    pub fn test_sequence_audit() {
+        let mut rng = StdRng::from_seed([0; 32]);
+        let mut accounts: Vec<_> = (0..3).map(|_| LocalAccount::generate(&mut rng)).collect();
+        for (account, seq_num) in accounts.iter_mut().zip([5, 7, 2]) {
+            *account.sequence_number_mut() = seq_num;
+        }
+        let on_chain: Vec<_> = accounts
+            .iter()
+            .map(LocalAccount::address)
+            .zip([5, 4, 6])
+            .collect();
+        let audit = SequenceAudit::new(&accounts, &on_chain);
+        assert_eq!(audit.accounts_checked, 3);
+        assert_eq!(audit.never_committed, 3);
+        assert_eq!(audit.never_observed, 4);
+        assert_eq!(audit.gaps.len(), 2);
+        assert_eq!(audit.gaps[0].address, accounts[1].address());
+        assert!(audit.gaps[1]
+            .to_string()
+            .contains("4 commits never observed (local 2, on chain 6)"));
+    }
+}
@@ -0,0 +1,76 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use aptos_infallible::RwLock;
+use aptos_sdk::{
+    move_types::account_address::AccountAddress, types::transaction::SignedTransaction,
+};
+use std::collections::HashMap;
+
+/// Next sequence number of accounts, as known from transactions confirmed
+/// through the executor, so that they don't need to be fetched again.
+#[derive(Debug, Default)]
+pub struct SequenceNumberCache {
+    sequence_numbers: RwLock<HashMap<AccountAddress, u64>>,
+}
+
+impl SequenceNumberCache {
+    pub fn get(&self, address: &AccountAddress) -> Option<u64> {
+        self.sequence_numbers.read().get(address).copied()
+    }
+
+    /// Records sequence number fetched from the chain.
+    pub fn insert(&self, address: AccountAddress, sequence_number: u64) {
+        self.sequence_numbers
+            .write()
+            .insert(address, sequence_number);
+    }
+
+    pub fn on_committed(&self, txn: &SignedTransaction) {
+        let next = txn.sequence_number() + 1;
+        self.sequence_numbers
+            .write()
+            .entry(txn.sender())
+            .and_modify(|sequence_number| *sequence_number = (*sequence_number).max(next))
+            .or_insert(next);
+    }
+
+    /// Forgets the sender's sequence number, as it is not known whether
+    /// its transaction got committed.
+    pub fn invalidate(&self, address: &AccountAddress) {
+        self.sequence_numbers.write().remove(address);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SequenceNumberCache;
+    use aptos_sdk::{
+        transaction_builder::{aptos_stdlib, TransactionFactory},
+        types::{chain_id::ChainId, LocalAccount},
+    };
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    pub fn test_sequence_number_cache() {
+        let mut rng = StdRng::from_seed([0u8; 32]);
+        let mut account = LocalAccount::generate(&mut rng);
+        *account.sequence_number_mut() = 5;
+        let address = account.address();
+        let txn_factory = TransactionFactory::new(ChainId::test());
+        let txn = account.sign_with_transaction_builder(
+            txn_factory.payload(aptos_stdlib::aptos_coin_transfer(address, 1)),
+        );
+
+        let cache = SequenceNumberCache::default();
+        assert_eq!(cache.get(&address), None);
+        cache.on_committed(&txn);
+        assert_eq!(cache.get(&address), Some(6));
+        // older commits don't move it back
+        cache.insert(address, 10);
+        cache.on_committed(&txn);
+        assert_eq!(cache.get(&address), Some(10));
+        cache.invalidate(&address);
+        assert_eq!(cache.get(&address), None);
+    }
+}
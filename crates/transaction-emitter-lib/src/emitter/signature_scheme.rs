@@ -0,0 +1,114 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::{bail, format_err, Result};
+use aptos_sdk::types::LocalAccount;
+use std::{fmt, str::FromStr};
+
+/// Signature scheme of the accounts emitting transactions, so that differences in
+/// signature verification cost show up in load tests.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SignatureScheme {
+    #[default]
+    Ed25519,
+    /// K-of-N multi-ed25519, signing with threshold of num_keys keys
+    MultiEd25519 { num_keys: u8, threshold: u8 },
+}
+
+impl SignatureScheme {
+    pub fn generate_account<R>(&self, rng: &mut R) -> LocalAccount
+    where
+        R: ::rand_core::RngCore + ::rand_core::CryptoRng,
+    {
+        match self {
+            SignatureScheme::Ed25519 => LocalAccount::generate(rng),
+            SignatureScheme::MultiEd25519 {
+                num_keys,
+                threshold,
+            } => LocalAccount::generate_multi_ed25519(rng, *num_keys, *threshold)
+                .expect("multi-ed25519 scheme is validated when parsed"),
+        }
+    }
+}
+
+impl FromStr for SignatureScheme {
+    type Err = anyhow::Error;
+
+    /// Parses `ed25519`, or `multi_ed25519:<threshold>:<num keys>`, e.g. `multi_ed25519:2:3`.
+    fn from_str(s: &str) -> Result<Self> {
+        if s == "ed25519" {
+            return Ok(SignatureScheme::Ed25519);
+        }
+        if s == "secp256k1" {
+            bail!("secp256k1 accounts are not supported, as transactions can only be authenticated with ed25519 and multi-ed25519 keys");
+        }
+        let (threshold, num_keys) = s
+            .strip_prefix("multi_ed25519:")
+            .and_then(|keys| keys.split_once(':'))
+            .ok_or_else(|| {
+                format_err!(
+                    "Expected ed25519 or multi_ed25519:<threshold>:<num keys>, got {}",
+                    s
+                )
+            })?;
+        let (threshold, num_keys): (u8, u8) = (threshold.parse()?, num_keys.parse()?);
+        if threshold == 0 || threshold > num_keys || num_keys > 32 {
+            bail!(
+                "Multi-ed25519 needs 1 <= threshold <= num keys <= 32, got {}-of-{}",
+                threshold,
+                num_keys
+            );
+        }
+        Ok(SignatureScheme::MultiEd25519 {
+            num_keys,
+            threshold,
+        })
+    }
+}
+
+impl fmt::Display for SignatureScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignatureScheme::Ed25519 => write!(f, "ed25519"),
+            SignatureScheme::MultiEd25519 {
+                num_keys,
+                threshold,
+            } => write!(f, "multi_ed25519:{}:{}", threshold, num_keys),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SignatureScheme;
+    use aptos_sdk::types::transaction::authenticator::AuthenticationKey;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    pub fn test_signature_scheme() {
+        let scheme: SignatureScheme = "multi_ed25519:2:3".parse().unwrap();
+        assert_eq!(scheme, SignatureScheme::MultiEd25519 {
+            num_keys: 3,
+            threshold: 2
+        });
+        assert_eq!(scheme.to_string(), "multi_ed25519:2:3");
+        assert_eq!(
+            "ed25519".parse::<SignatureScheme>().unwrap(),
+            SignatureScheme::Ed25519
+        );
+        assert!("multi_ed25519:3:2".parse::<SignatureScheme>().is_err());
+        assert!("secp256k1".parse::<SignatureScheme>().is_err());
+
+        let mut rng = StdRng::from_seed([0; 32]);
+        let account = scheme.generate_account(&mut rng);
+        let public_key = account.multi_ed25519_key().unwrap().public_key();
+        assert_eq!(
+            AuthenticationKey::multi_ed25519(public_key).derived_address(),
+            account.address()
+        );
+        assert!(SignatureScheme::Ed25519
+            .generate_account(&mut rng)
+            .multi_ed25519_key()
+            .is_none());
+    }
+}
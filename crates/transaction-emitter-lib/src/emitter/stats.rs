@@ -276,6 +276,10 @@ pub struct DynamicStatsTracking {
     num_phases: usize,
     cur_phase: Arc<AtomicUsize>,
     stats: Vec<StatsAccumulator>,
+    // name and stats of each workload, for the whole run, if tracked
+    workloads: Vec<(String, StatsAccumulator)>,
+    // for each phase, index into workloads, of each workload of the phase's transaction mix
+    workload_index_per_phase: Vec<Vec<usize>>,
 }
 
 impl DynamicStatsTracking {
@@ -287,9 +291,90 @@ impl DynamicStatsTracking {
             stats: (0..num_phases)
                 .map(|_| StatsAccumulator::default())
                 .collect(),
+            workloads: Vec::new(),
+            workload_index_per_phase: Vec::new(),
         }
     }
 
+    /// Also tracks stats of each workload, given names of the workloads in each
+    /// phase's transaction mix. Workloads with the same name are tracked together.
+    pub fn with_workloads(mut self, workload_names_per_phase: Vec<Vec<String>>) -> Self {
+        let mut names: Vec<String> = Vec::new();
+        self.workload_index_per_phase = workload_names_per_phase
+            .into_iter()
+            .map(|phase_names| {
+                phase_names
+                    .into_iter()
+                    .map(|name| {
+                        names.iter().position(|n| *n == name).unwrap_or_else(|| {
+                            names.push(name);
+                            names.len() - 1
+                        })
+                    })
+                    .collect()
+            })
+            .collect();
+        self.workloads = names
+            .into_iter()
+            .map(|name| (name, StatsAccumulator::default()))
+            .collect();
+        self
+    }
+
+    /// Also tracks stats of each generator phase, by label, with the ones of a workload
+    /// of the same name.
+    pub fn with_generator_phases(mut self, labels: Vec<String>) -> Self {
+        for label in labels {
+            if !self.workloads.iter().any(|(name, _)| *name == label) {
+                self.workloads.push((label, StatsAccumulator::default()));
+            }
+        }
+        self
+    }
+
+    /// Stats of the generator phase with the label, if tracked.
+    pub fn get_generator_phase(&self, label: &str) -> Option<&StatsAccumulator> {
+        self.workloads
+            .iter()
+            .find(|(name, _)| name == label)
+            .map(|(_, stats)| stats)
+    }
+
+    /// Stats of the workload at index in the transaction mix of the phase, if tracked.
+    pub fn get_workload(&self, phase: usize, index: usize) -> Option<&StatsAccumulator> {
+        let workload_index = *self.workload_index_per_phase.get(phase)?.get(index)?;
+        self.workloads.get(workload_index).map(|(_, stats)| stats)
+    }
+
+    pub fn accumulate_workloads(&self, lasted: Duration) -> Vec<(String, TxnStats)> {
+        self.workloads
+            .iter()
+            .map(|(name, stats)| (name.clone(), stats.accumulate(lasted)))
+            .collect()
+    }
+
+    /// Stats of all the phases so far, added together.
+    pub fn accumulate_total(&self, lasted: Duration) -> TxnStats {
+        let mut total = self
+            .stats
+            .iter()
+            .take(self.get_cur_phase() + 1)
+            .fold(TxnStats::default(), |total, stats| {
+                &total + &stats.accumulate(Duration::ZERO)
+            });
+        total.lasted = lasted;
+        total
+    }
+
+    /// Stats of each phase so far, without duration.
+    pub fn snapshot(&self) -> Vec<TxnStats> {
+        self.stats
+            .iter()
+            .take(self.get_cur_phase() + 1)
+            .map(|stats| stats.accumulate(Duration::ZERO))
+            .collect()
+    }
+
     pub fn start_next_phase(&self) -> usize {
         let cur_phase = self.cur_phase.fetch_add(1, Ordering::Relaxed) + 1;
         assert!(cur_phase < self.num_phases);
@@ -331,10 +416,10 @@ impl DynamicStatsTracking {
 #[cfg(test)]
 mod test {
     use crate::emitter::stats::{
-        AtomicHistogramAccumulator, AtomicHistogramSnapshot, TxnStats, DEFAULT_HISTOGRAM_CAPACITY,
-        DEFAULT_HISTOGRAM_STEP_WIDTH,
+        AtomicHistogramAccumulator, AtomicHistogramSnapshot, DynamicStatsTracking, TxnStats,
+        DEFAULT_HISTOGRAM_CAPACITY, DEFAULT_HISTOGRAM_STEP_WIDTH,
     };
-    use std::time::Duration;
+    use std::{sync::atomic::Ordering, time::Duration};
 
     #[test]
     pub fn test_default_atomic_histogram() {
@@ -406,4 +491,39 @@ mod test {
         let res = stat.latency_buckets.percentile(9, 10);
         assert_eq!(res, 900);
     }
+
+    #[test]
+    pub fn test_workload_stats() {
+        let stats = DynamicStatsTracking::new(2).with_workloads(vec![
+            vec!["CoinTransfer".to_string(), "NftMintAndTransfer".to_string()],
+            vec!["NftMintAndTransfer".to_string()],
+        ]);
+        stats
+            .get_workload(0, 1)
+            .unwrap()
+            .committed
+            .fetch_add(2, Ordering::Relaxed);
+        stats
+            .get_workload(1, 0)
+            .unwrap()
+            .committed
+            .fetch_add(3, Ordering::Relaxed);
+        assert!(stats.get_workload(1, 1).is_none());
+        let stats =
+            stats.with_generator_phases(vec!["mint".to_string(), "NftMintAndTransfer".to_string()]);
+        stats
+            .get_generator_phase("mint")
+            .unwrap()
+            .committed
+            .fetch_add(1, Ordering::Relaxed);
+        assert!(stats.get_generator_phase("trade").is_none());
+
+        let workloads = stats.accumulate_workloads(Duration::from_secs(1));
+        assert_eq!(workloads.len(), 3);
+        assert_eq!(workloads[0].1.committed, 0);
+        assert_eq!(workloads[1].0, "NftMintAndTransfer");
+        assert_eq!(workloads[1].1.committed, 5);
+        assert_eq!(workloads[2].0, "mint");
+        assert_eq!(workloads[2].1.committed, 1);
+    }
 }
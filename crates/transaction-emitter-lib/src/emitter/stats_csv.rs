@@ -0,0 +1,131 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::emitter::stats::{DynamicStatsTracking, TxnStats};
+use anyhow::{Context, Result};
+use aptos_logger::{sample, sample::SampleRate, warn};
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::time;
+
+const HEADER: &str = "elapsed_secs,phase,submitted_tps,committed_tps,expired_tps,failed_submission_tps,latency_avg_ms,latency_p50_ms,latency_p90_ms,latency_p99_ms";
+
+/// Writes throughput and latency of each interval of the run as a CSV row, so that
+/// runs can be plotted (and transient degradations seen) offline.
+#[derive(Debug)]
+pub struct StatsCsvWriter {
+    file: BufWriter<File>,
+    start: Instant,
+    // phase and accumulated stats of it, at the previous interval
+    prev: Option<(usize, TxnStats)>,
+}
+
+impl StatsCsvWriter {
+    pub fn create(path: &Path) -> Result<Self> {
+        let file = File::create(path)
+            .with_context(|| format!("Failed to create stats CSV {}", path.display()))?;
+        let mut writer = Self {
+            file: BufWriter::new(file),
+            start: Instant::now(),
+            prev: None,
+        };
+        writer.write_line(HEADER)?;
+        Ok(writer)
+    }
+
+    /// Writes row of the interval, given stats accumulated since the start of the phase.
+    pub fn write_interval(
+        &mut self,
+        phase: usize,
+        cur: TxnStats,
+        interval: Duration,
+    ) -> Result<()> {
+        let delta = match &self.prev {
+            Some((prev_phase, prev_stats)) if *prev_phase == phase => {
+                let mut delta = &cur - prev_stats;
+                delta.lasted = interval;
+                delta
+            },
+            _ => cur.clone(),
+        };
+        self.prev = Some((phase, cur));
+
+        let rate = delta.rate();
+        self.write_line(&format!(
+            "{},{},{},{},{},{},{},{},{},{}",
+            self.start.elapsed().as_secs(),
+            phase,
+            rate.submitted,
+            rate.committed,
+            rate.expired,
+            rate.failed_submission,
+            rate.latency,
+            rate.p50_latency,
+            rate.p90_latency,
+            rate.p99_latency,
+        ))
+    }
+
+    /// Rows are flushed right away, so that the CSV is complete up to the last
+    /// interval, even if the run crashes.
+    fn write_line(&mut self, line: &str) -> Result<()> {
+        writeln!(self.file, "{}", line)?;
+        self.file.flush()?;
+        Ok(())
+    }
+
+    /// Writes a row every interval, until aborted.
+    pub async fn run(mut self, interval: Duration, stats: Arc<DynamicStatsTracking>) {
+        loop {
+            time::sleep(interval).await;
+            let phase = stats.get_cur_phase();
+            let cur = stats.get_cur().accumulate(interval);
+            if let Err(e) = self.write_interval(phase, cur, interval) {
+                sample!(
+                    SampleRate::Duration(Duration::from_secs(60)),
+                    warn!("Failed to write stats CSV: {:?}", e)
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::StatsCsvWriter;
+    use crate::emitter::stats::TxnStats;
+    use std::time::Duration;
+
+    #[test]
+    pub fn test_stats_csv() {
+        let path = std::env::temp_dir().join(format!("stats_{}.csv", std::process::id()));
+        let stats = |committed| TxnStats {
+            submitted: committed,
+            committed,
+            lasted: Duration::from_secs(1),
+            ..TxnStats::default()
+        };
+        let interval = Duration::from_secs(1);
+        let mut writer = StatsCsvWriter::create(&path).unwrap();
+        writer.write_interval(0, stats(100), interval).unwrap();
+        writer.write_interval(0, stats(350), interval).unwrap();
+        // stats start from zero on phase change
+        writer.write_interval(1, stats(80), interval).unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let rows: Vec<Vec<&str>> = contents
+            .lines()
+            .map(|line| line.split(',').collect())
+            .collect();
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[0][3], "committed_tps");
+        let committed: Vec<_> = rows[1..].iter().map(|row| (row[1], row[3])).collect();
+        assert_eq!(committed, vec![("0", "100"), ("0", "250"), ("1", "80")]);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,181 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::emitter::stats::{DynamicStatsTracking, TxnStats};
+use std::{fmt, sync::Arc, time::Duration};
+use tokio::sync::Notify;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+// failure ratio is only checked once this many transactions were submitted,
+// so that a few failures at the start don't stop the run
+const MIN_SUBMITTED_FOR_FAILURE_RATIO: u64 = 1000;
+
+/// Conditions, other than duration, that stop the run as soon as any of them triggers.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StopConditions {
+    /// Maximum number of submitted transactions
+    pub max_txns: Option<u64>,
+    /// Maximum coins spent on gas, estimated from the number of committed transactions
+    /// with every transaction using expected_gas_per_txn at gas_price
+    pub max_coins_spent: Option<u64>,
+    /// Maximum ratio of submitted transactions that expired or failed submission
+    pub max_failure_ratio: Option<f64>,
+}
+
+/// Stop signals sent by the caller of the run, e.g. on ctrl-c, which stop it early like
+/// its stop conditions. Signals sent before the run waits for them aren't lost.
+#[derive(Clone, Debug, Default)]
+pub struct StopSignal(Arc<Notify>);
+
+impl StopSignal {
+    pub fn signal(&self) {
+        self.0.notify_one();
+    }
+
+    pub async fn received(&self) {
+        self.0.notified().await;
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum StopReason {
+    MaxTxns {
+        submitted: u64,
+    },
+    MaxCoinsSpent {
+        estimated_coins_spent: u64,
+    },
+    MaxFailureRatio {
+        failure_ratio: f64,
+    },
+    Signal,
+    /// Stopped through the handle of the job
+    Requested,
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StopReason::MaxTxns { submitted } => {
+                write!(
+                    f,
+                    "submitted {} transactions, reaching the limit",
+                    submitted
+                )
+            },
+            StopReason::MaxCoinsSpent {
+                estimated_coins_spent,
+            } => write!(
+                f,
+                "spent an estimated {} coins on gas, reaching the budget",
+                estimated_coins_spent
+            ),
+            StopReason::MaxFailureRatio { failure_ratio } => write!(
+                f,
+                "failure ratio {:.3} exceeded the threshold",
+                failure_ratio
+            ),
+            StopReason::Signal => write!(f, "received stop signal"),
+            StopReason::Requested => write!(f, "was requested to stop"),
+        }
+    }
+}
+
+impl StopConditions {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Checks conditions against stats of the whole run so far, given estimated coins
+    /// spent by each committed transaction.
+    pub fn check(&self, total: &TxnStats, coins_per_txn: u64) -> Option<StopReason> {
+        if let Some(max_txns) = self.max_txns {
+            if total.submitted >= max_txns {
+                return Some(StopReason::MaxTxns {
+                    submitted: total.submitted,
+                });
+            }
+        }
+        if let Some(max_coins_spent) = self.max_coins_spent {
+            let estimated_coins_spent = total.committed.saturating_mul(coins_per_txn);
+            if estimated_coins_spent >= max_coins_spent {
+                return Some(StopReason::MaxCoinsSpent {
+                    estimated_coins_spent,
+                });
+            }
+        }
+        if let Some(max_failure_ratio) = self.max_failure_ratio {
+            if total.submitted >= MIN_SUBMITTED_FOR_FAILURE_RATIO {
+                let failure_ratio =
+                    (total.expired + total.failed_submission) as f64 / total.submitted as f64;
+                if failure_ratio > max_failure_ratio {
+                    return Some(StopReason::MaxFailureRatio { failure_ratio });
+                }
+            }
+        }
+        None
+    }
+
+    /// Resolves once any of the conditions triggers, never if there are none.
+    pub async fn triggered(&self, stats: &DynamicStatsTracking, coins_per_txn: u64) -> StopReason {
+        if self.is_empty() {
+            return futures::future::pending().await;
+        }
+        loop {
+            tokio::time::sleep(CHECK_INTERVAL).await;
+            if let Some(reason) = self.check(&stats.accumulate_total(Duration::ZERO), coins_per_txn)
+            {
+                return reason;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{StopConditions, StopReason, StopSignal};
+    use crate::emitter::stats::TxnStats;
+
+    #[test]
+    pub fn test_stop_conditions() {
+        let stats = |submitted, committed| TxnStats {
+            submitted,
+            committed,
+            expired: submitted - committed,
+            ..TxnStats::default()
+        };
+        let conditions = StopConditions {
+            max_txns: Some(10_000),
+            max_coins_spent: Some(1_000_000),
+            max_failure_ratio: Some(0.1),
+        };
+        assert!(!conditions.is_empty());
+        assert_eq!(conditions.check(&stats(5000, 4900), 100), None);
+        assert_eq!(
+            conditions.check(&stats(10_000, 9900), 100),
+            Some(StopReason::MaxTxns { submitted: 10_000 })
+        );
+        assert_eq!(
+            conditions.check(&stats(5000, 4900), 500),
+            Some(StopReason::MaxCoinsSpent {
+                estimated_coins_spent: 2_450_000
+            })
+        );
+        assert!(matches!(
+            conditions.check(&stats(5000, 4000), 100),
+            Some(StopReason::MaxFailureRatio { .. })
+        ));
+        // too few transactions to check failure ratio
+        assert_eq!(conditions.check(&stats(500, 0), 100), None);
+    }
+
+    #[tokio::test]
+    pub async fn test_stop_signal() {
+        let stop_signal = StopSignal::default();
+        // sent before waiting for it
+        stop_signal.clone().signal();
+        tokio::time::timeout(std::time::Duration::from_secs(1), stop_signal.received())
+            .await
+            .unwrap();
+    }
+}
@@ -3,6 +3,11 @@
 
 use crate::{
     emitter::{
+        drain::InFlightDrain,
+        endpoint_discovery::DiscoveredEndpoints,
+        in_flight_limit::InFlightPermits,
+        publish_failures,
+        recycling_pool::RecyclingAccountPool,
         stats::{DynamicStatsTracking, StatsAccumulator},
         update_seq_num_and_get_num_expired, wait_for_accounts_sequence,
     },
@@ -12,13 +17,17 @@ use aptos_logger::{sample, sample::SampleRate, warn};
 use aptos_rest_client::Client as RestClient;
 use aptos_sdk::{
     move_types::account_address::AccountAddress,
-    types::{transaction::SignedTransaction, vm_status::StatusCode, LocalAccount},
+    types::{
+        transaction::{authenticator::TransactionAuthenticator, RawTransaction, SignedTransaction},
+        vm_status::StatusCode,
+        LocalAccount,
+    },
 };
 use aptos_transaction_generator_lib::TransactionGenerator;
 use core::{
     cmp::{max, min},
     result::Result::{Err, Ok},
-    sync::atomic::{AtomicBool, Ordering},
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
     time::Duration,
 };
 use futures::future::join_all;
@@ -26,6 +35,7 @@ use itertools::Itertools;
 use rand::seq::IteratorRandom;
 use std::{
     collections::HashMap,
+    ptr,
     sync::{atomic::AtomicU64, Arc},
     time::Instant,
 };
@@ -35,12 +45,30 @@ pub struct SubmissionWorker {
     pub(crate) accounts: Vec<LocalAccount>,
     client: RestClient,
     stop: Arc<AtomicBool>,
+    drain: Arc<InFlightDrain>,
     params: EmitModeParams,
     stats: Arc<DynamicStatsTracking>,
     txn_generator: Box<dyn TransactionGenerator>,
     start_sleep_duration: Duration,
     skip_latency_stats: bool,
+    // if set, worker only submits while its rank is below the number of active workers
+    activation: Option<(Arc<AtomicUsize>, usize)>,
+    // stats recorded to in addition to the ones of the phase and workload,
+    // e.g. of this worker alone, or of its endpoint
+    tracked_stats: Vec<Arc<StatsAccumulator>>,
     rng: ::rand::rngs::StdRng,
+    // if set, generated transactions with a different gas unit price are re-signed with it
+    gas_unit_price: Option<Arc<AtomicU64>>,
+    // if set, worker moves to its endpoint among the discovered ones whenever they change,
+    // with its index and the generation of the endpoints it is on
+    discovered_endpoints: Option<(Arc<DiscoveredEndpoints>, usize, u64)>,
+    // if set, transactions are only submitted with permits of their endpoint
+    in_flight_permits: Option<Arc<InFlightPermits>>,
+    // if set, worker only submits while its index is below the number of workers
+    // allowed by backlog throttling
+    backlog_throttle: Option<(Arc<AtomicUsize>, usize)>,
+    // if set, accounts of each round are checked out of it, instead of being the worker's
+    account_pool: Option<Arc<RecyclingAccountPool>>,
 }
 
 impl SubmissionWorker {
@@ -48,26 +76,88 @@ impl SubmissionWorker {
         accounts: Vec<LocalAccount>,
         client: RestClient,
         stop: Arc<AtomicBool>,
+        drain: Arc<InFlightDrain>,
         params: EmitModeParams,
         stats: Arc<DynamicStatsTracking>,
         txn_generator: Box<dyn TransactionGenerator>,
         start_sleep_duration: Duration,
         skip_latency_stats: bool,
+        activation: Option<(Arc<AtomicUsize>, usize)>,
+        tracked_stats: Vec<Arc<StatsAccumulator>>,
         rng: ::rand::rngs::StdRng,
+        gas_unit_price: Option<Arc<AtomicU64>>,
     ) -> Self {
         Self {
             accounts,
             client,
             stop,
+            drain,
             params,
             stats,
             txn_generator,
             start_sleep_duration,
             skip_latency_stats,
+            activation,
+            tracked_stats,
             rng,
+            gas_unit_price,
+            discovered_endpoints: None,
+            in_flight_permits: None,
+            backlog_throttle: None,
+            account_pool: None,
         }
     }
 
+    pub fn with_backlog_throttle(
+        mut self,
+        allowed_workers: Arc<AtomicUsize>,
+        worker_index: usize,
+    ) -> Self {
+        self.backlog_throttle = Some((allowed_workers, worker_index));
+        self
+    }
+
+    pub fn with_in_flight_permits(mut self, in_flight_permits: Arc<InFlightPermits>) -> Self {
+        self.in_flight_permits = Some(in_flight_permits);
+        self
+    }
+
+    /// Senders of each round are checked out of the pool, and returned to it once the
+    /// round is done, instead of being accounts of the worker alone.
+    pub fn with_account_pool(mut self, account_pool: Arc<RecyclingAccountPool>) -> Self {
+        self.account_pool = Some(account_pool);
+        self
+    }
+
+    pub fn with_discovered_endpoints(
+        mut self,
+        endpoints: Arc<DiscoveredEndpoints>,
+        worker_index: usize,
+    ) -> Self {
+        let generation = endpoints.generation();
+        self.discovered_endpoints = Some((endpoints, worker_index, generation));
+        self
+    }
+
+    fn switch_endpoint_if_changed(&mut self) {
+        if let Some((endpoints, worker_index, generation)) = &mut self.discovered_endpoints {
+            let current = endpoints.generation();
+            if current != *generation {
+                *generation = current;
+                self.client = endpoints.client_for(*worker_index);
+            }
+        }
+    }
+
+    fn is_active(&self) -> bool {
+        let below = |limit: &Option<(Arc<AtomicUsize>, usize)>| {
+            limit.as_ref().map_or(true, |(num_workers, rank)| {
+                *rank < num_workers.load(Ordering::Relaxed)
+            })
+        };
+        below(&self.activation) && below(&self.backlog_throttle)
+    }
+
     #[allow(clippy::collapsible_if)]
     pub(crate) async fn run(mut self) -> Vec<LocalAccount> {
         let start_time = Instant::now() + self.start_sleep_duration;
@@ -78,8 +168,21 @@ impl SubmissionWorker {
         let mut wait_until = start_time;
 
         while !self.stop.load(Ordering::Relaxed) {
+            self.switch_endpoint_if_changed();
             let stats_clone = self.stats.clone();
-            let loop_stats = stats_clone.get_cur();
+
+            // held until transactions of this round are committed or expired
+            let in_flight_permit = match &self.in_flight_permits {
+                Some(in_flight_permits) if self.is_active() => {
+                    in_flight_permits
+                        .acquire(
+                            &self.client,
+                            self.batch_size() * self.params.transactions_per_account,
+                        )
+                        .await
+                },
+                _ => None,
+            };
 
             let loop_start_time = Arc::new(Instant::now());
             if wait_duration.as_secs() > 0
@@ -97,7 +200,47 @@ impl SubmissionWorker {
             // always add expected cycle duration, to not drift from expected pace.
             wait_until += wait_duration;
 
+            if !self.is_active() {
+                // skip this cycle, keeping the pace for when the worker is activated
+                let now = Instant::now();
+                if wait_until > now {
+                    self.sleep_check_done(wait_until - now).await;
+                }
+                continue;
+            }
+
+            if let Some(account_pool) = &self.account_pool {
+                self.accounts = account_pool.check_out(self.batch_size());
+                if self.accounts.is_empty() {
+                    // all checked out by other workers, try again next cycle
+                    let now = Instant::now();
+                    if wait_until > now {
+                        self.sleep_check_done(wait_until - now).await;
+                    }
+                    continue;
+                }
+            }
             let requests = self.gen_requests();
+            // stats of the phase, of the workload and generator phase the requests came
+            // from, if tracked, and any additionally tracked ones
+            let tracked_stats = self.tracked_stats.clone();
+            let workload_stats = self
+                .txn_generator
+                .last_generated_workload()
+                .and_then(|(phase, index)| stats_clone.get_workload(phase, index));
+            let generator_phase_stats = self
+                .txn_generator
+                .last_generated_label()
+                .and_then(|label| stats_clone.get_generator_phase(label))
+                // unless tracked together with the workload, under the same name
+                .filter(|stats| {
+                    workload_stats.map_or(true, |workload_stats| !ptr::eq(*stats, workload_stats))
+                });
+            let loop_stats: Vec<&StatsAccumulator> = std::iter::once(stats_clone.get_cur())
+                .chain(workload_stats)
+                .chain(generator_phase_stats)
+                .chain(tracked_stats.iter().map(Arc::as_ref))
+                .collect();
 
             let mut account_to_start_and_end_seq_num = HashMap::new();
             for req in requests.iter() {
@@ -121,6 +264,13 @@ impl SubmissionWorker {
                 .max()
                 .unwrap_or(0);
 
+            // checked for execution failures once committed
+            let publish_hashes: Vec<_> = requests
+                .iter()
+                .filter(|txn| publish_failures::is_code_publish(txn))
+                .map(|txn| txn.clone().committed_hash())
+                .collect();
+
             let txn_offset_time = Arc::new(AtomicU64::new(0));
 
             join_all(
@@ -132,7 +282,7 @@ impl SubmissionWorker {
                             reqs,
                             loop_start_time.clone(),
                             txn_offset_time.clone(),
-                            loop_stats,
+                            &loop_stats,
                         )
                     }),
             )
@@ -163,9 +313,16 @@ impl SubmissionWorker {
                     if self.skip_latency_stats { 10 } else { 1 }
                         * self.params.check_account_sequence_sleep_millis,
                 ),
-                loop_stats,
+                &loop_stats,
             )
             .await;
+            drop(in_flight_permit);
+            if let Some(account_pool) = &self.account_pool {
+                account_pool.check_in(std::mem::take(&mut self.accounts));
+            }
+            if !publish_hashes.is_empty() {
+                publish_failures::record_execution_failures(&self.client, &publish_hashes).await;
+            }
 
             let now = Instant::now();
             if wait_until > now {
@@ -205,7 +362,7 @@ impl SubmissionWorker {
         skip_latency_stats: bool,
         txn_expiration_ts_secs: u64,
         check_account_sleep_duration: Duration,
-        loop_stats: &StatsAccumulator,
+        loop_stats: &[&StatsAccumulator],
     ) {
         let (latest_fetched_counts, sum_of_completion_timestamps_millis) =
             wait_for_accounts_sequence(
@@ -214,6 +371,7 @@ impl SubmissionWorker {
                 &account_to_start_and_end_seq_num,
                 txn_expiration_ts_secs,
                 check_account_sleep_duration,
+                &self.drain,
             )
             .await;
 
@@ -224,9 +382,11 @@ impl SubmissionWorker {
         );
 
         if num_expired > 0 {
-            loop_stats
-                .expired
-                .fetch_add(num_expired as u64, Ordering::Relaxed);
+            for stats in loop_stats {
+                stats
+                    .expired
+                    .fetch_add(num_expired as u64, Ordering::Relaxed);
+            }
             sample!(
                 SampleRate::Duration(Duration::from_secs(120)),
                 warn!(
@@ -245,38 +405,93 @@ impl SubmissionWorker {
             let sum_latency = sum_of_completion_timestamps_millis
                 - (avg_txn_offset_time as u128 * num_committed as u128);
             let avg_latency = (sum_latency / num_committed as u128) as u64;
-            loop_stats
-                .committed
-                .fetch_add(num_committed as u64, Ordering::Relaxed);
-
-            if !skip_latency_stats {
-                loop_stats
-                    .latency
-                    .fetch_add(sum_latency as u64, Ordering::Relaxed);
-                loop_stats
-                    .latency_samples
+            for stats in loop_stats {
+                stats
+                    .committed
                     .fetch_add(num_committed as u64, Ordering::Relaxed);
-                loop_stats
-                    .latencies
-                    .record_data_point(avg_latency, num_committed as u64);
+
+                if !skip_latency_stats {
+                    stats
+                        .latency
+                        .fetch_add(sum_latency as u64, Ordering::Relaxed);
+                    stats
+                        .latency_samples
+                        .fetch_add(num_committed as u64, Ordering::Relaxed);
+                    stats
+                        .latencies
+                        .record_data_point(avg_latency, num_committed as u64);
+                }
             }
         }
     }
 
-    fn gen_requests(&mut self) -> Vec<SignedTransaction> {
-        let batch_size = max(
+    /// Number of accounts submitting in each round.
+    fn batch_size(&self) -> usize {
+        let num_accounts = match &self.account_pool {
+            Some(_) => self.params.accounts_per_worker,
+            None => self.accounts.len(),
+        };
+        max(
             1,
             min(
                 self.params.max_submit_batch_size / self.params.transactions_per_account,
-                self.accounts.len(),
+                num_accounts,
             ),
-        );
+        )
+    }
+
+    fn gen_requests(&mut self) -> Vec<SignedTransaction> {
+        let batch_size = self.batch_size();
         let accounts = self
             .accounts
             .iter_mut()
             .choose_multiple(&mut self.rng, batch_size);
-        self.txn_generator
-            .generate_transactions(accounts, self.params.transactions_per_account)
+        let requests = self
+            .txn_generator
+            .generate_transactions(accounts, self.params.transactions_per_account);
+        match &self.gas_unit_price {
+            Some(gas_unit_price) => self.reprice(requests, gas_unit_price.load(Ordering::Relaxed)),
+            None => requests,
+        }
+    }
+
+    /// Re-signs transactions of the worker's accounts with the given gas unit price.
+    /// Multi-agent transactions, and ones of accounts not owned by the worker (e.g. from
+    /// the accounts pool), can't be re-signed, and are left as generated.
+    fn reprice(
+        &self,
+        requests: Vec<SignedTransaction>,
+        gas_unit_price: u64,
+    ) -> Vec<SignedTransaction> {
+        requests
+            .into_iter()
+            .map(|txn| {
+                if txn.gas_unit_price() == gas_unit_price
+                    || matches!(
+                        txn.authenticator(),
+                        TransactionAuthenticator::MultiAgent { .. }
+                    )
+                {
+                    return txn;
+                }
+                match self
+                    .accounts
+                    .iter()
+                    .find(|account| account.address() == txn.sender())
+                {
+                    Some(account) => account.sign_transaction(RawTransaction::new(
+                        txn.sender(),
+                        txn.sequence_number(),
+                        txn.payload().clone(),
+                        txn.max_gas_amount(),
+                        gas_unit_price,
+                        txn.expiration_timestamp_secs(),
+                        txn.chain_id(),
+                    )),
+                    None => txn,
+                }
+            })
+            .collect()
     }
 }
 
@@ -285,7 +500,7 @@ pub async fn submit_transactions(
     txns: &[SignedTransaction],
     loop_start_time: Arc<Instant>,
     txn_offset_time: Arc<AtomicU64>,
-    stats: &StatsAccumulator,
+    stats: &[&StatsAccumulator],
 ) {
     let cur_time = Instant::now();
     let offset = cur_time - *loop_start_time;
@@ -293,15 +508,19 @@ pub async fn submit_transactions(
         txns.len() as u64 * offset.as_millis() as u64,
         Ordering::Relaxed,
     );
-    stats
-        .submitted
-        .fetch_add(txns.len() as u64, Ordering::Relaxed);
+    for stats in stats {
+        stats
+            .submitted
+            .fetch_add(txns.len() as u64, Ordering::Relaxed);
+    }
 
     match client.submit_batch_bcs(txns).await {
         Err(e) => {
-            stats
-                .failed_submission
-                .fetch_add(txns.len() as u64, Ordering::Relaxed);
+            for stats in stats {
+                stats
+                    .failed_submission
+                    .fetch_add(txns.len() as u64, Ordering::Relaxed);
+            }
             sample!(
                 SampleRate::Duration(Duration::from_secs(60)),
                 warn!(
@@ -314,9 +533,22 @@ pub async fn submit_transactions(
         Ok(v) => {
             let failures = v.into_inner().transaction_failures;
 
-            stats
-                .failed_submission
-                .fetch_add(failures.len() as u64, Ordering::Relaxed);
+            for stats in stats {
+                stats
+                    .failed_submission
+                    .fetch_add(failures.len() as u64, Ordering::Relaxed);
+            }
+
+            for failure in &failures {
+                if publish_failures::is_code_publish(&txns[failure.transaction_index]) {
+                    publish_failures::record_rejected(
+                        failure
+                            .error
+                            .vm_error_code
+                            .and_then(|c| StatusCode::try_from(c).ok()),
+                    );
+                }
+            }
 
             let by_error = failures
                 .iter()
@@ -1,141 +1,1976 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-use super::RETRY_POLICY;
-use anyhow::{Context, Result};
-use aptos_logger::{debug, sample, sample::SampleRate, warn};
-use aptos_rest_client::Client as RestClient;
+use super::{
+    circuit_breaker::{CircuitBreaker, CircuitBreakerConfig},
+    confirmer::{BatchWaiter, ConfirmationReceiver, TransactionConfirmer},
+    counter_reporter::CounterReporter,
+    counters::{
+        EXECUTOR_ACCEPTED, EXECUTOR_HEDGED_SUBMITS, EXECUTOR_INJECTED_FAULTS,
+        EXECUTOR_ROUND_LATENCY, EXECUTOR_SEQUENCE_CHECK_HITS, EXECUTOR_SUBMIT_FAILURES,
+        EXECUTOR_SUCCESSES, EXECUTOR_WAIT_FAILURES,
+    },
+    fault_injection::{FaultInjection, InjectedFaults},
+    rate_limiter::{RateLimit, TokenBucket},
+    retry_policy::{retry, RetryPolicy},
+    sender_affinity::SenderAffinity,
+    sequence_number_cache::SequenceNumberCache,
+    RETRY_POLICY,
+};
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use aptos_config::config::DEFAULT_MAX_SUBMIT_TRANSACTION_BATCH_SIZE;
+use aptos_crypto::HashValue;
+use aptos_infallible::Mutex;
+use aptos_logger::{debug, info, sample, sample::SampleRate, warn};
+use aptos_rest_client::{
+    aptos_api_types::{AptosError, AptosErrorCode, TransactionsBatchSubmissionResult},
+    error::RestError,
+    Client as RestClient,
+};
 use aptos_sdk::{
-    move_types::account_address::AccountAddress, types::transaction::SignedTransaction,
+    crypto::ed25519::Ed25519Signature,
+    move_types::{account_address::AccountAddress, language_storage::StructTag},
+    types::transaction::{
+        authenticator::TransactionAuthenticator, RawTransaction, SignedTransaction,
+        TransactionStatus,
+    },
+};
+use aptos_transaction_generator_lib::{
+    view_request, ClientCounters, CounterState, FailureCategory, SimulationResult,
+    TransactionExecutor, TransactionReceipt, TxnExecutionError,
 };
-use aptos_transaction_generator_lib::{CounterState, TransactionExecutor};
 use async_trait::async_trait;
-use futures::future::join_all;
+use futures::{
+    future::{join_all, select, try_join_all, Either},
+    stream::FuturesUnordered,
+    StreamExt,
+};
 use rand::{rngs::StdRng, seq::SliceRandom, thread_rng, Rng, SeedableRng};
+use reqwest::StatusCode;
 use std::{
-    sync::atomic::AtomicUsize,
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
     time::{Duration, Instant},
 };
+use tracing::{debug_span, field, Instrument, Span};
+
+/// Delay to wait between consecutive retry rounds of a single transaction.
+#[derive(Clone, Debug, Default)]
+pub enum RetryBackoff {
+    /// Start next round as soon as previous one finished.
+    #[default]
+    NoDelay,
+    /// Delay doubles each round, starting from initial_delay, and is capped at max_delay.
+    /// With jitter, actual delay is uniformly picked from [delay / 2, delay], so that
+    /// many concurrent submitters don't retry in lockstep.
+    Exponential {
+        initial_delay: Duration,
+        max_delay: Duration,
+        jitter: bool,
+    },
+}
+
+impl RetryBackoff {
+    pub fn delay_for_round<R>(&self, round: usize, rng: &mut R) -> Duration
+    where
+        R: Rng + ?Sized,
+    {
+        match self {
+            RetryBackoff::NoDelay => Duration::ZERO,
+            RetryBackoff::Exponential {
+                initial_delay,
+                max_delay,
+                jitter,
+            } => {
+                let delay = initial_delay
+                    .checked_mul(1u32.checked_shl(round as u32).unwrap_or(u32::MAX))
+                    .unwrap_or(*max_delay)
+                    .min(*max_delay);
+                let delay_millis = delay.as_millis() as u64;
+                if *jitter && delay_millis > 1 {
+                    Duration::from_millis(rng.gen_range(delay_millis / 2, delay_millis + 1))
+                } else {
+                    delay
+                }
+            },
+        }
+    }
+}
+
+/// How commit of a submitted transaction is polled for.
+#[derive(Clone, Debug)]
+pub struct WaitStrategy {
+    /// Delay before the first poll, as transaction cannot be committed right after submission.
+    pub initial_delay: Duration,
+    pub poll_interval: Duration,
+    /// Each poll interval is this many times longer than the previous one.
+    pub poll_interval_growth: f64,
+    pub max_poll_interval: Duration,
+}
+
+impl Default for WaitStrategy {
+    /// Same polling as RestClient::wait_for_transaction_by_hash
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::ZERO,
+            poll_interval: Duration::from_millis(500),
+            poll_interval_growth: 1.0,
+            max_poll_interval: Duration::from_millis(500),
+        }
+    }
+}
+
+impl WaitStrategy {
+    /// Delay after the given (0-based) poll, before the next one.
+    pub fn delay_after_poll(&self, poll: usize) -> Duration {
+        let delay_secs = self.poll_interval.as_secs_f64()
+            * self
+                .poll_interval_growth
+                .powi(poll.min(i32::MAX as usize) as i32);
+        Duration::from_secs_f64(delay_secs.min(self.max_poll_interval.as_secs_f64()))
+    }
+}
+
+/// Re-signs a modified raw transaction on behalf of its sender, needed when
+/// retries change the transaction itself (e.g. its gas unit price).
+pub trait TransactionResigner: Sync + Send {
+    /// Returns None if the sender is not known to the resigner.
+    fn resign(&self, raw_txn: RawTransaction) -> Option<SignedTransaction>;
+
+    /// Re-signs a multi-agent transaction, with the given secondary signers.
+    /// By default they are not known, and multi-agent transactions are retried as is.
+    fn resign_multi_agent(
+        &self,
+        _raw_txn: RawTransaction,
+        _secondary_signer_addresses: Vec<AccountAddress>,
+    ) -> Option<SignedTransaction> {
+        None
+    }
+}
+
+impl<F> TransactionResigner for F
+where
+    F: Fn(RawTransaction) -> Option<SignedTransaction> + Sync + Send,
+{
+    fn resign(&self, raw_txn: RawTransaction) -> Option<SignedTransaction> {
+        self(raw_txn)
+    }
+}
+
+/// Bumps gas unit price on each retry round, so that transactions stuck in
+/// a congested mempool are replaced with ones that are prioritized higher.
+#[derive(Clone, Debug)]
+pub struct GasEscalation {
+    pub bump_factor: f64,
+    pub max_gas_unit_price: u64,
+}
+
+impl GasEscalation {
+    pub fn next_gas_unit_price(&self, gas_unit_price: u64) -> u64 {
+        // always bump by at least one, so that the replacement is accepted by mempool
+        ((gas_unit_price as f64 * self.bump_factor).ceil() as u64)
+            .max(gas_unit_price + 1)
+            .min(self.max_gas_unit_price)
+            .max(gas_unit_price)
+    }
+}
+
+/// How to react to submissions rejected because mempool is full
+/// (either globally, or for the sender account).
+#[derive(Clone, Debug)]
+pub struct MempoolFullBackoff {
+    /// How long to wait before retrying the rejected transaction.
+    pub delay: Duration,
+    /// Whether to pause all submissions through this executor for the delay,
+    /// not only the rejected transaction.
+    pub pause_all: bool,
+}
+
+/// How a client is picked for each submission round.
+#[derive(Clone, Copy, Debug, Default)]
+pub enum ClientSelection {
+    #[default]
+    Uniform,
+    /// Clients are weighted by their success rate and average latency,
+    /// as tracked in CounterState::by_client, so that slow or flaky
+    /// endpoints receive proportionally less load.
+    HealthWeighted,
+    /// Clients are weighted by the moving average of their submit and confirm latency,
+    /// as tracked in CounterState::by_client, so that faster endpoints are preferred.
+    /// Clients without measured latency are preferred, until they are measured.
+    LatencyWeighted,
+}
+
+// Lowest weight a client can get, so that unhealthy clients still get
+// an occasional request, and can recover.
+const MIN_HEALTH_WEIGHT: f64 = 0.01;
+
+fn health_weight(client_counters: &ClientCounters) -> f64 {
+    let successes = client_counters.successes.load(Ordering::Relaxed) as f64;
+    let failures = (client_counters.submit_failures.load(Ordering::Relaxed)
+        + client_counters.wait_failures.load(Ordering::Relaxed)) as f64;
+    // smoothed, so that clients without history start at 0.5
+    let success_rate = (successes + 1.0) / (successes + failures + 2.0);
+    let avg_latency_millis = if successes > 0.0 {
+        client_counters.latency_millis.load(Ordering::Relaxed) as f64 / successes
+    } else {
+        0.0
+    };
+    (success_rate * 1000.0 / (1000.0 + avg_latency_millis)).max(MIN_HEALTH_WEIGHT)
+}
+
+fn latency_weight(client_counters: &ClientCounters) -> f64 {
+    match client_counters.ewma_latency() {
+        Some(latency) => (1000.0 / (1000.0 + latency.as_millis() as f64)).max(MIN_HEALTH_WEIGHT),
+        None => 1.0,
+    }
+}
 
 // Reliable/retrying transaction executor, used for initializing
 pub struct RestApiTransactionExecutor {
     pub rest_clients: Vec<RestClient>,
     pub max_retries: usize,
     pub retry_after: Duration,
+    pub retry_backoff: RetryBackoff,
+    // used for balance and sequence number queries,
+    // submissions are retried based on max_retries and retry_backoff
+    pub retry_policy: Arc<dyn RetryPolicy>,
+    pub client_selection: ClientSelection,
+    // submit transactions for each client in a single batch request,
+    // instead of one request per transaction
+    pub batch_submit: bool,
+    // in batch_submit mode, wait for each batch by scanning committed transactions,
+    // instead of polling for each transaction of the batch
+    pub batch_wait: bool,
+    // gas_escalation and expiration_refresh are only applied if resigner is set,
+    // and not in batch_submit mode
+    pub gas_escalation: Option<GasEscalation>,
+    // if transaction expires during retries, it is re-signed to expire this long from now
+    pub expiration_refresh: Option<Duration>,
+    resigner: Option<Arc<dyn TransactionResigner>>,
+    // if set, mempool full rejections are not waited on, and are retried after a backoff
+    pub mempool_full_backoff: Option<MempoolFullBackoff>,
+    paused_until: Mutex<Option<Instant>>,
+    // one per rest_client, empty if circuit breaking is disabled
+    circuit_breakers: Vec<CircuitBreaker>,
+    // simulate each transaction before submitting it, and don't submit it
+    // if simulation shows it is going to fail
+    pub simulate_before_submit: bool,
+    // one per rest_client, empty if rate limiting is disabled
+    rate_limiters: Vec<Option<TokenBucket>>,
+    // if set, commits are confirmed through it instead of polling for each transaction,
+    // falling back to polling when it is unavailable
+    confirmer: Option<Arc<dyn TransactionConfirmer>>,
+    // tier of each of the rest_clients, empty if clients are not tiered
+    client_tiers: Vec<usize>,
+    // number of retry rounds a transaction is sent to a tier, before failing over to the next one
+    pub tier_failover_rounds: usize,
+    sender_affinity: SenderAffinity,
+    // if set, transaction is only considered committed once it is visible
+    // on this many of the rest_clients, including the one it was confirmed on
+    pub confirmation_quorum: Option<usize>,
+    // if not set, waiting polls the same as RestClient::wait_for_transaction_by_hash
+    wait_strategy: Option<WaitStrategy>,
+    counter_reporter: Option<CounterReporter>,
+    // if set, query_sequence_number is served from it, when possible
+    sequence_number_cache: Option<SequenceNumberCache>,
+    // if set, each execute_transactions_with_counter call is cancelled after this long
+    pub execution_budget: Option<Duration>,
+    // if set, transactions are not waited on individually, but all of them are submitted,
+    // and after this delay, their senders' sequence numbers are checked to find which
+    // ones need to be resubmitted
+    pub bulk_verify_delay: Option<Duration>,
+    // if set, and submission is not acknowledged within this delay, the same transaction
+    // is also submitted to another client. Only applies when not in batch_submit mode.
+    pub hedge_delay: Option<Duration>,
+    // if set, transactions are only submitted until accepted into mempool, without waiting
+    // for them to be committed. Takes precedence over bulk_verify_delay and batch_submit.
+    pub ack_only: bool,
+    // before each retry round, check sender's sequence number on chain, and count
+    // transaction as committed without retrying, if it was already used
+    pub sequence_check_before_retry: bool,
+    // if set, faults are injected into individual submissions
+    fault_injection: Option<FaultInjection>,
+}
+
+const DEFAULT_TIER_FAILOVER_ROUNDS: usize = 2;
+
+/// Why waiting for a submitted transaction to be committed failed.
+#[derive(Debug)]
+enum WaitFailure {
+    /// Transaction got committed but aborted, or expired, so its outcome is known
+    Resolved(TxnExecutionError),
+    /// Transaction was still pending after the given timeout, it might still succeed
+    Timeout(Duration, HashValue),
+}
+
+impl WaitFailure {
+    fn category(&self) -> FailureCategory {
+        match self {
+            WaitFailure::Resolved(_) => FailureCategory::Other,
+            WaitFailure::Timeout(..) => FailureCategory::Timeout,
+        }
+    }
+}
+
+impl Display for WaitFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WaitFailure::Resolved(error) => write!(f, "{}", error),
+            WaitFailure::Timeout(timeout, hash) => write!(
+                f,
+                "Timeout of {}s waiting for transaction reached. Warning, transaction ({}) might still succeed.",
+                timeout.as_secs(),
+                hash,
+            ),
+        }
+    }
+}
+
+impl From<WaitFailure> for anyhow::Error {
+    fn from(failure: WaitFailure) -> Self {
+        match failure {
+            WaitFailure::Resolved(error) => error.into(),
+            failure @ WaitFailure::Timeout(..) => anyhow!("{}", failure),
+        }
+    }
+}
+
+/// Client a transaction is sent to, together with helpers applied
+/// to the requests sent through it.
+#[derive(Clone, Copy)]
+struct SubmissionTarget<'a> {
+    rest_client: &'a RestClient,
+    rate_limiter: Option<&'a TokenBucket>,
+    confirmer: Option<&'a dyn TransactionConfirmer>,
+    wait_strategy: Option<&'a WaitStrategy>,
+    fault_injection: Option<&'a FaultInjection>,
+    // set if commit is to be waited for under a wrong hash, as an injected fault
+    corrupt_hash: bool,
+}
+
+impl SubmissionTarget<'_> {
+    async fn acquire(&self) {
+        if let Some(rate_limiter) = self.rate_limiter {
+            rate_limiter.acquire().await;
+        }
+    }
+
+    fn inject_faults(&self) -> InjectedFaults {
+        let faults = self
+            .fault_injection
+            .map_or_else(InjectedFaults::default, |fault_injection| {
+                fault_injection.sample(&mut thread_rng())
+            });
+        for name in faults.names() {
+            EXECUTOR_INJECTED_FAULTS.with_label_values(&[name]).inc();
+        }
+        faults
+    }
+
+    fn hash(&self, txn: &SignedTransaction) -> HashValue {
+        let hash = txn.clone().committed_hash();
+        if !self.corrupt_hash {
+            return hash;
+        }
+        let mut bytes = hash.to_vec();
+        bytes[0] ^= 0xFF;
+        HashValue::from_slice(&bytes).unwrap()
+    }
+
+    fn subscribe(&self, txn: &SignedTransaction) -> Option<ConfirmationReceiver> {
+        self.confirmer
+            .and_then(|confirmer| confirmer.subscribe(self.hash(txn)))
+    }
+
+    /// Polls until transaction is committed, expires, or timeout is reached.
+    async fn wait(
+        &self,
+        txn: &SignedTransaction,
+        timeout: Option<Duration>,
+    ) -> Result<(), WaitFailure> {
+        poll_for_transaction(
+            self.rest_client,
+            self.rate_limiter,
+            self.wait_strategy.cloned().unwrap_or_default(),
+            self.hash(txn),
+            txn.expiration_timestamp_secs(),
+            timeout,
+        )
+        .await
+    }
 }
 
 impl RestApiTransactionExecutor {
+    pub fn new(rest_clients: Vec<RestClient>, max_retries: usize, retry_after: Duration) -> Self {
+        Self {
+            rest_clients,
+            max_retries,
+            retry_after,
+            retry_backoff: RetryBackoff::default(),
+            retry_policy: Arc::new(RETRY_POLICY.clone()),
+            client_selection: ClientSelection::default(),
+            batch_submit: false,
+            batch_wait: false,
+            gas_escalation: None,
+            expiration_refresh: None,
+            resigner: None,
+            mempool_full_backoff: None,
+            paused_until: Mutex::new(None),
+            circuit_breakers: Vec::new(),
+            simulate_before_submit: false,
+            rate_limiters: Vec::new(),
+            confirmer: None,
+            client_tiers: Vec::new(),
+            tier_failover_rounds: DEFAULT_TIER_FAILOVER_ROUNDS,
+            sender_affinity: SenderAffinity::default(),
+            confirmation_quorum: None,
+            wait_strategy: None,
+            counter_reporter: None,
+            sequence_number_cache: None,
+            execution_budget: None,
+            bulk_verify_delay: None,
+            hedge_delay: None,
+            ack_only: false,
+            sequence_check_before_retry: false,
+            fault_injection: None,
+        }
+    }
+
+    /// Clients are given in tiers, in order of preference (e.g. validator fullnodes first,
+    /// and public fullnodes as a fallback). Transactions are sent to the next tier only
+    /// once they are retried tier_failover_rounds times on the previous one, or if none
+    /// of its clients is available (when circuit breaking is enabled).
+    pub fn new_tiered(
+        tiers: Vec<Vec<RestClient>>,
+        max_retries: usize,
+        retry_after: Duration,
+    ) -> Self {
+        assert!(
+            tiers.iter().all(|tier| !tier.is_empty()),
+            "Each client tier needs at least one client"
+        );
+        let client_tiers = tiers
+            .iter()
+            .enumerate()
+            .flat_map(|(tier, clients)| std::iter::repeat(tier).take(clients.len()))
+            .collect();
+        let mut executor = Self::new(
+            tiers.into_iter().flatten().collect(),
+            max_retries,
+            retry_after,
+        );
+        executor.client_tiers = client_tiers;
+        executor
+    }
+
+    pub fn with_tier_failover_rounds(mut self, tier_failover_rounds: usize) -> Self {
+        assert!(tier_failover_rounds > 0);
+        self.tier_failover_rounds = tier_failover_rounds;
+        self
+    }
+
+    /// Checks that all clients point to the same chain, and that none of them is
+    /// more than max_version_lag versions behind the most up to date one.
+    pub async fn validate_clients(&self, max_version_lag: u64) -> Result<()> {
+        let states = join_all(
+            self.rest_clients
+                .iter()
+                .map(|rest_client| rest_client.get_ledger_information()),
+        )
+        .await
+        .into_iter()
+        .zip(self.rest_clients.iter())
+        .map(|(result, rest_client)| {
+            result
+                .map(|response| response.into_inner())
+                .with_context(|| {
+                    format!(
+                        "[{}] Failed to get ledger information",
+                        rest_client.path_prefix_string()
+                    )
+                })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+        let (first_state, first_client) = match states.first() {
+            Some(state) => (state, &self.rest_clients[0]),
+            None => return Ok(()),
+        };
+        let max_version = states.iter().map(|state| state.version).max().unwrap();
+        for (state, rest_client) in states.iter().zip(self.rest_clients.iter()) {
+            ensure!(
+                state.chain_id == first_state.chain_id,
+                "[{}] is on chain {}, while [{}] is on chain {}",
+                rest_client.path_prefix_string(),
+                state.chain_id,
+                first_client.path_prefix_string(),
+                first_state.chain_id,
+            );
+            ensure!(
+                max_version - state.version <= max_version_lag,
+                "[{}] is {} versions behind the most up to date client, more than allowed {}",
+                rest_client.path_prefix_string(),
+                max_version - state.version,
+                max_version_lag,
+            );
+        }
+        Ok(())
+    }
+
+    /// Which client owns each sender, can be used to inspect, pin or rebalance assignments.
+    /// Client indices refer to rest_clients.
+    pub fn sender_affinity(&self) -> &SenderAffinity {
+        &self.sender_affinity
+    }
+
+    fn num_tiers(&self) -> usize {
+        self.client_tiers
+            .iter()
+            .max()
+            .map_or(1, |max_tier| max_tier + 1)
+    }
+
+    fn client_tier(&self, client_index: usize) -> usize {
+        self.client_tiers.get(client_index).copied().unwrap_or(0)
+    }
+
+    pub fn with_retry_backoff(mut self, retry_backoff: RetryBackoff) -> Self {
+        self.retry_backoff = retry_backoff;
+        self
+    }
+
+    pub fn with_retry_policy(mut self, retry_policy: Arc<dyn RetryPolicy>) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    pub fn with_client_selection(mut self, client_selection: ClientSelection) -> Self {
+        self.client_selection = client_selection;
+        self
+    }
+
+    pub fn with_batch_submit(mut self, batch_submit: bool) -> Self {
+        self.batch_submit = batch_submit;
+        self
+    }
+
+    pub fn with_batch_wait(mut self, batch_wait: bool) -> Self {
+        self.batch_wait = batch_wait;
+        self
+    }
+
+    pub fn with_resigner(mut self, resigner: Arc<dyn TransactionResigner>) -> Self {
+        self.resigner = Some(resigner);
+        self
+    }
+
+    pub fn with_gas_escalation(mut self, gas_escalation: GasEscalation) -> Self {
+        self.gas_escalation = Some(gas_escalation);
+        self
+    }
+
+    pub fn with_expiration_refresh(mut self, expiration_window: Duration) -> Self {
+        self.expiration_refresh = Some(expiration_window);
+        self
+    }
+
+    pub fn with_mempool_full_backoff(mut self, mempool_full_backoff: MempoolFullBackoff) -> Self {
+        self.mempool_full_backoff = Some(mempool_full_backoff);
+        self
+    }
+
+    pub fn with_circuit_breaker(mut self, config: CircuitBreakerConfig) -> Self {
+        self.circuit_breakers = self
+            .rest_clients
+            .iter()
+            .map(|_| CircuitBreaker::new(config.clone()))
+            .collect();
+        self
+    }
+
+    pub fn with_simulate_before_submit(mut self, simulate_before_submit: bool) -> Self {
+        self.simulate_before_submit = simulate_before_submit;
+        self
+    }
+
+    /// Limits requests (submissions and wait polling) to each of the clients.
+    pub fn with_rate_limit(mut self, rate_limit: RateLimit) -> Self {
+        self.rate_limiters = self
+            .rest_clients
+            .iter()
+            .map(|_| Some(TokenBucket::new(rate_limit.clone())))
+            .collect();
+        self
+    }
+
+    /// Same as with_rate_limit, but with separate limit for each client,
+    /// in the same order as rest_clients. None leaves the client unlimited.
+    pub fn with_client_rate_limits(mut self, rate_limits: Vec<Option<RateLimit>>) -> Self {
+        assert_eq!(
+            rate_limits.len(),
+            self.rest_clients.len(),
+            "Rate limits need to be provided for each client"
+        );
+        self.rate_limiters = rate_limits
+            .into_iter()
+            .map(|rate_limit| rate_limit.map(TokenBucket::new))
+            .collect();
+        self
+    }
+
+    pub fn with_confirmer(mut self, confirmer: Arc<dyn TransactionConfirmer>) -> Self {
+        self.confirmer = Some(confirmer);
+        self
+    }
+
+    pub fn with_confirmation_quorum(mut self, confirmation_quorum: usize) -> Self {
+        assert!(
+            confirmation_quorum > 0 && confirmation_quorum <= self.rest_clients.len(),
+            "Confirmation quorum needs to be between 1 and number of clients"
+        );
+        self.confirmation_quorum = Some(confirmation_quorum);
+        self
+    }
+
+    pub fn with_wait_strategy(mut self, wait_strategy: WaitStrategy) -> Self {
+        self.wait_strategy = Some(wait_strategy);
+        self
+    }
+
+    /// Reports counters passed to execute_transactions_with_counter, while it is running.
+    pub fn with_counter_reporter(mut self, counter_reporter: CounterReporter) -> Self {
+        self.counter_reporter = Some(counter_reporter);
+        self
+    }
+
+    /// Caches sequence numbers of accounts, updated on confirmed commits, and
+    /// invalidated when it is not known whether transactions got committed.
+    pub fn with_sequence_number_cache(mut self) -> Self {
+        self.sequence_number_cache = Some(SequenceNumberCache::default());
+        self
+    }
+
+    /// Bounds how long each execution of a batch can take in total, across all retries.
+    /// Once reached, remaining work is cancelled, and execution fails with
+    /// TxnExecutionError::Unconfirmed, listing transactions that didn't get committed.
+    pub fn with_execution_budget(mut self, execution_budget: Duration) -> Self {
+        self.execution_budget = Some(execution_budget);
+        self
+    }
+
+    /// Submits whole batch at once, and verifies which transactions got committed
+    /// with a single pass over sender sequence numbers, verify_delay after submission,
+    /// resubmitting only the missing ones.
+    pub fn with_bulk_verify(mut self, verify_delay: Duration) -> Self {
+        self.bulk_verify_delay = Some(verify_delay);
+        self
+    }
+
+    /// If a submission is not acknowledged within hedge_delay, the transaction is also
+    /// submitted to a second client. Duplicates are deduplicated by hash, so whichever
+    /// client gets it into mempool first wins.
+    pub fn with_hedged_submit(mut self, hedge_delay: Duration) -> Self {
+        self.hedge_delay = Some(hedge_delay);
+        self
+    }
+
+    /// Only wait for transactions to be accepted into mempool, counting them in
+    /// CounterState::accepted, for when commits are measured elsewhere.
+    pub fn with_ack_only(mut self, ack_only: bool) -> Self {
+        self.ack_only = ack_only;
+        self
+    }
+
+    /// Skip retry rounds of transactions whose sequence number was already used on chain,
+    /// i.e. they (or another transaction with the same sequence number) got committed
+    /// while waiting on a timed out response. Costs one account query per retry round.
+    pub fn with_sequence_check_before_retry(mut self, sequence_check_before_retry: bool) -> Self {
+        self.sequence_check_before_retry = sequence_check_before_retry;
+        self
+    }
+
+    /// Inject faults into transactions submitted individually and waited on (i.e. not in
+    /// batch_submit, ack_only or bulk verify modes), to test retries and accounting.
+    pub fn with_fault_injection(mut self, fault_injection: FaultInjection) -> Self {
+        self.fault_injection = Some(fault_injection);
+        self
+    }
+
+    fn rate_limiter(&self, client_index: usize) -> Option<&TokenBucket> {
+        self.rate_limiters
+            .get(client_index)
+            .and_then(Option::as_ref)
+    }
+
+    fn submission_target(&self, client_index: usize) -> SubmissionTarget<'_> {
+        SubmissionTarget {
+            rest_client: &self.rest_clients[client_index],
+            rate_limiter: self.rate_limiter(client_index),
+            confirmer: self.confirmer.as_deref(),
+            wait_strategy: self.wait_strategy.as_ref(),
+            fault_injection: self.fault_injection.as_ref(),
+            corrupt_hash: false,
+        }
+    }
+
     fn random_rest_client(&self) -> &RestClient {
         let mut rng = thread_rng();
-        self.rest_clients.choose(&mut rng).unwrap()
+        &self.rest_clients[self.random_client_index_from_rng(&mut rng, None, 0)]
     }
 
-    fn random_rest_client_from_rng<R>(&self, rng: &mut R) -> &RestClient
-    where
-        R: Rng + ?Sized,
-    {
-        self.rest_clients.choose(rng).unwrap()
+    /// Picks a random client among the ones with available circuit, from the first
+    /// tier starting at min_tier that has one, or among all clients of min_tier,
+    /// if none is available.
+    /// If counters are provided, they are used for health-weighted selection.
+    fn random_client_index_from_rng<R>(
+        &self,
+        rng: &mut R,
+        counters: Option<&CounterState>,
+        min_tier: usize,
+    ) -> usize
+    where
+        R: Rng + ?Sized,
+    {
+        let min_tier = min_tier.min(self.num_tiers() - 1);
+        let mut candidates = (min_tier..self.num_tiers())
+            .chain(0..min_tier)
+            .map(|tier| {
+                (0..self.rest_clients.len())
+                    .filter(|i| self.client_tier(*i) == tier && self.is_client_available(*i))
+                    .collect::<Vec<_>>()
+            })
+            .find(|tier_candidates| !tier_candidates.is_empty())
+            .unwrap_or_default();
+        if candidates.is_empty() {
+            candidates = (0..self.rest_clients.len())
+                .filter(|i| self.client_tier(*i) == min_tier)
+                .collect();
+        }
+
+        let weight: Option<fn(&ClientCounters) -> f64> = match self.client_selection {
+            ClientSelection::Uniform => None,
+            ClientSelection::HealthWeighted => Some(health_weight),
+            ClientSelection::LatencyWeighted => Some(latency_weight),
+        };
+        if let (Some(weight), Some(counters)) = (weight, counters) {
+            if !counters.by_client.is_empty() {
+                let weighted = candidates.choose_weighted(rng, |i| {
+                    counters
+                        .by_client
+                        .get(&self.rest_clients[*i].path_prefix_string())
+                        .map_or(MIN_HEALTH_WEIGHT, weight)
+                });
+                if let Ok(index) = weighted {
+                    return *index;
+                }
+            }
+        }
+        *candidates.choose(rng).unwrap()
+    }
+
+    /// Returns the target to hedge submissions to client_index with,
+    /// if hedging is enabled and there is another available client.
+    fn hedge_target(&self, client_index: usize) -> Option<(SubmissionTarget<'_>, Duration)> {
+        let hedge_delay = self.hedge_delay?;
+        let candidates = (0..self.rest_clients.len())
+            .filter(|index| *index != client_index && self.is_client_available(*index))
+            .collect::<Vec<_>>();
+        let hedge_index = *candidates.choose(&mut thread_rng())?;
+        Some((self.submission_target(hedge_index), hedge_delay))
+    }
+
+    fn is_client_available(&self, client_index: usize) -> bool {
+        self.circuit_breakers
+            .get(client_index)
+            .map_or(true, |breaker| breaker.is_available())
+    }
+
+    fn record_client_result(&self, client_index: usize, success: bool) {
+        if let Some(breaker) = self.circuit_breakers.get(client_index) {
+            let rest_client = &self.rest_clients[client_index];
+            if success {
+                if breaker.record_success() {
+                    info!(
+                        "[{}] Circuit closed, client is healthy again",
+                        rest_client.path_prefix_string()
+                    );
+                }
+            } else if breaker.record_failure() {
+                warn!(
+                    "[{}] Circuit opened, rerouting requests to other clients",
+                    rest_client.path_prefix_string()
+                );
+            }
+        }
+    }
+
+    async fn sleep_before_round(&self, round: usize) {
+        if round > 0 {
+            let delay = self
+                .retry_backoff
+                .delay_for_round(round - 1, &mut thread_rng());
+            if !delay.is_zero() {
+                tokio::time::sleep(delay)
+                    .instrument(debug_span!("retry_backoff", round))
+                    .await;
+            }
+        }
+    }
+
+    async fn wait_if_paused(&self) {
+        let paused_until = *self.paused_until.lock();
+        if let Some(paused_until) = paused_until {
+            let now = Instant::now();
+            if paused_until > now {
+                tokio::time::sleep(paused_until - now).await;
+            }
+        }
+    }
+
+    async fn backoff_on_mempool_full(&self, counters: &CounterState) {
+        if let Some(backoff) = &self.mempool_full_backoff {
+            counters
+                .mempool_full_backoffs
+                .fetch_add(1, Ordering::Relaxed);
+            if backoff.pause_all {
+                let until = Instant::now() + backoff.delay;
+                let mut paused_until = self.paused_until.lock();
+                if paused_until.map_or(true, |cur| cur < until) {
+                    *paused_until = Some(until);
+                }
+            }
+            sample!(
+                SampleRate::Duration(Duration::from_secs(60)),
+                warn!(
+                    "Mempool is full, backing off for {}ms{}",
+                    backoff.delay.as_millis(),
+                    if backoff.pause_all {
+                        ", pausing all submissions"
+                    } else {
+                        ""
+                    }
+                )
+            );
+            tokio::time::sleep(backoff.delay)
+                .instrument(debug_span!("mempool_full_backoff"))
+                .await;
+        }
+    }
+
+    fn select_client_for_round(
+        &self,
+        txn: &SignedTransaction,
+        round: usize,
+        run_seed: u64,
+        counters: &CounterState,
+    ) -> usize {
+        // All transactions from the same sender, need to be submitted to the same client
+        // in the same retry round, so that they are not placed in parking lot.
+        // First round goes to the client owning the sender, if it is available.
+        if round == 0 {
+            let owner = self.sender_affinity.get_or_assign(txn.sender(), || {
+                self.seeded_client_index(txn, round, run_seed, counters)
+            });
+            if owner < self.rest_clients.len() && self.is_client_available(owner) {
+                return owner;
+            }
+        }
+        self.seeded_client_index(txn, round, run_seed, counters)
+    }
+
+    /// Selects a client via seeded random selection, so that it is the same
+    /// for all transactions of the sender, within the same run and round.
+    fn seeded_client_index(
+        &self,
+        txn: &SignedTransaction,
+        round: usize,
+        run_seed: u64,
+        counters: &CounterState,
+    ) -> usize {
+        let seed = [
+            round.to_le_bytes().to_vec(),
+            run_seed.to_le_bytes().to_vec(),
+            txn.sender().to_vec(),
+        ]
+        .concat();
+        let mut seeded_rng = StdRng::from_seed(*aptos_crypto::HashValue::sha3_256_of(&seed));
+        self.random_client_index_from_rng(
+            &mut seeded_rng,
+            Some(counters),
+            round / self.tier_failover_rounds,
+        )
+    }
+
+    fn update_counters(
+        &self,
+        counters: &CounterState,
+        round: usize,
+        client_index: usize,
+        failed_submit: Option<FailureCategory>,
+        failed_wait: Option<FailureCategory>,
+        success_latency: Option<Duration>,
+        error: Option<&dyn Display>,
+    ) {
+        self.record_client_result(client_index, success_latency.is_some());
+        let client_name = self.rest_clients[client_index].path_prefix_string();
+        let client_counters = counters.by_client.get(&client_name);
+        let tier_counters = counters.by_tier.get(self.client_tier(client_index));
+        let round_label = round.to_string();
+
+        if let Some(category) = failed_submit.or(failed_wait) {
+            // category alone, if the error isn't known
+            let error = error.map_or_else(|| category.name().to_owned(), |err| err.to_string());
+            for client_counters in client_counters.iter().chain(tier_counters.iter()) {
+                client_counters.record_error(error.clone());
+            }
+        }
+        if let Some(category) = failed_submit {
+            EXECUTOR_SUBMIT_FAILURES
+                .with_label_values(&[&client_name, &round_label, category.name()])
+                .inc();
+            counters.record_failure_category(category);
+            counters.submit_failures[round.min(counters.submit_failures.len() - 1)]
+                .fetch_add(1, Ordering::Relaxed);
+            for client_counters in client_counters.iter().chain(tier_counters.iter()) {
+                client_counters
+                    .submit_failures
+                    .fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        if let Some(category) = failed_wait {
+            EXECUTOR_WAIT_FAILURES
+                .with_label_values(&[&client_name, &round_label, category.name()])
+                .inc();
+            counters.record_failure_category(category);
+            counters.wait_failures[round.min(counters.wait_failures.len() - 1)]
+                .fetch_add(1, Ordering::Relaxed);
+            for client_counters in client_counters.iter().chain(tier_counters.iter()) {
+                client_counters
+                    .wait_failures
+                    .fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        if let Some(latency) = success_latency {
+            EXECUTOR_SUCCESSES.with_label_values(&[&client_name]).inc();
+            EXECUTOR_ROUND_LATENCY
+                .with_label_values(&[&client_name])
+                .observe(latency.as_secs_f64());
+            counters.successes.fetch_add(1, Ordering::Relaxed);
+            counters.record_success_round(round);
+            for client_counters in client_counters.iter().chain(tier_counters.iter()) {
+                client_counters.record_success(latency);
+            }
+        }
+    }
+
+    /// Returns the transaction re-signed for the given retry round, with bumped
+    /// gas unit price (if gas escalation is configured), and with fresh expiration
+    /// (if expiration refresh is configured and transaction has expired).
+    /// Returns None if transaction doesn't need to change.
+    fn resign_for_round(&self, txn: &SignedTransaction, round: usize) -> Option<SignedTransaction> {
+        let resigner = self.resigner.as_ref()?;
+
+        let gas_unit_price = match &self.gas_escalation {
+            Some(gas_escalation) if round > 0 => {
+                gas_escalation.next_gas_unit_price(txn.gas_unit_price())
+            },
+            _ => txn.gas_unit_price(),
+        };
+        let expiration_timestamp_secs = match self.expiration_refresh {
+            Some(expiration_window) => {
+                let now_secs = aptos_infallible::duration_since_epoch().as_secs();
+                if txn.expiration_timestamp_secs() <= now_secs {
+                    now_secs + expiration_window.as_secs()
+                } else {
+                    txn.expiration_timestamp_secs()
+                }
+            },
+            None => txn.expiration_timestamp_secs(),
+        };
+
+        if gas_unit_price == txn.gas_unit_price()
+            && expiration_timestamp_secs == txn.expiration_timestamp_secs()
+        {
+            return None;
+        }
+        sample!(
+            SampleRate::Duration(Duration::from_secs(60)),
+            debug!(
+                "Re-signing transaction for {} in round {}, gas unit price {} -> {}, expiration {} -> {}",
+                txn.sender(),
+                round,
+                txn.gas_unit_price(),
+                gas_unit_price,
+                txn.expiration_timestamp_secs(),
+                expiration_timestamp_secs,
+            )
+        );
+        let raw_txn = RawTransaction::new(
+            txn.sender(),
+            txn.sequence_number(),
+            txn.payload().clone(),
+            txn.max_gas_amount(),
+            gas_unit_price,
+            expiration_timestamp_secs,
+            txn.chain_id(),
+        );
+        match txn.authenticator() {
+            TransactionAuthenticator::MultiAgent {
+                secondary_signer_addresses,
+                ..
+            } => resigner.resign_multi_agent(raw_txn, secondary_signer_addresses.clone()),
+            _ => resigner.resign(raw_txn),
+        }
+    }
+
+    /// Runs the transaction through the simulate API, recording its expected gas.
+    /// Returns an error only if the transaction executed and failed in simulation.
+    /// If simulation itself couldn't be done (e.g. transaction depends on another
+    /// one from the same sender, which is not yet committed), it is ignored.
+    async fn check_in_simulation(
+        &self,
+        txn: &SignedTransaction,
+        counters: &CounterState,
+    ) -> Result<()> {
+        let unsigned_txn = match unsigned_for_simulation(txn) {
+            Some(unsigned_txn) => unsigned_txn,
+            None => return Ok(()),
+        };
+        let rest_client = self.random_rest_client();
+        let simulated = match rest_client.simulate(&unsigned_txn).await {
+            Ok(response) => response.into_inner(),
+            Err(err) => {
+                sample!(
+                    SampleRate::Duration(Duration::from_secs(60)),
+                    debug!(
+                        "[{}] Failed simulating transaction: {}",
+                        rest_client.path_prefix_string(),
+                        err,
+                    )
+                );
+                return Ok(());
+            },
+        };
+        let info = match simulated.first() {
+            Some(user_txn) => &user_txn.info,
+            None => return Ok(()),
+        };
+
+        counters.simulated.fetch_add(1, Ordering::Relaxed);
+        counters
+            .simulated_gas_used
+            .fetch_add(info.gas_used.0, Ordering::Relaxed);
+        if !info.success {
+            counters.simulation_failures.fetch_add(1, Ordering::Relaxed);
+            sample!(
+                SampleRate::Duration(Duration::from_secs(60)),
+                warn!(
+                    "[{}] Transaction failed in simulation, not submitting it: {}",
+                    rest_client.path_prefix_string(),
+                    info.vm_status,
+                )
+            );
+            return Err(TxnExecutionError::SimulationFailed {
+                vm_status: info.vm_status.clone(),
+            })
+            .with_context(|| {
+                format!(
+                    "Transaction from {} with sequence number {}",
+                    txn.sender(),
+                    txn.sequence_number()
+                )
+            });
+        }
+        Ok(())
+    }
+
+    /// How long to wait on a submission round, bounded by the deadline.
+    /// Returns None if deadline was already reached.
+    fn round_wait_duration(&self, deadline: Option<Instant>) -> Option<Duration> {
+        match deadline {
+            Some(deadline) => {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                (!remaining.is_zero()).then(|| remaining.min(self.retry_after))
+            },
+            None => Some(self.retry_after),
+        }
+    }
+
+    /// Returns whether sequence_check_before_retry is enabled, and transaction's
+    /// sequence number was already used on chain. Query failures are treated as unused.
+    async fn committed_before_retry(&self, txn: &SignedTransaction, round: usize) -> bool {
+        if !self.sequence_check_before_retry || round == 0 {
+            return false;
+        }
+        let client_index = self.random_client_index_from_rng(&mut thread_rng(), None, 0);
+        let target = self.submission_target(client_index);
+        target.acquire().await;
+        let committed = match target.rest_client.get_account_bcs(txn.sender()).await {
+            Ok(account) => account.into_inner().sequence_number() > txn.sequence_number(),
+            Err(_) => false,
+        };
+        if committed {
+            EXECUTOR_SEQUENCE_CHECK_HITS
+                .with_label_values(&[&round.to_string()])
+                .inc();
+        }
+        committed
+    }
+
+    async fn submit_check_and_retry(
+        &self,
+        txn: &SignedTransaction,
+        counters: &CounterState,
+        run_seed: u64,
+        deadline: Option<Instant>,
+    ) -> Result<()> {
+        if self.simulate_before_submit {
+            self.check_in_simulation(txn, counters).await?;
+        }
+        let first_submit = Instant::now();
+        // latest re-signed version of the transaction, if it was modified on retries
+        let mut resigned: Option<SignedTransaction> = None;
+        let mut rounds = 0;
+        for i in 0..self.max_retries {
+            self.sleep_before_round(i).await;
+            let wait_duration = match self.round_wait_duration(deadline) {
+                Some(wait_duration) => wait_duration,
+                None => break,
+            };
+            if self
+                .committed_before_retry(resigned.as_ref().unwrap_or(txn), i)
+                .await
+            {
+                counters.successes.fetch_add(1, Ordering::Relaxed);
+                counters.record_success_round(i - 1);
+                counters.latencies.record(first_submit.elapsed());
+                return Ok(());
+            }
+            rounds += 1;
+            if let Some(updated) = self.resign_for_round(resigned.as_ref().unwrap_or(txn), i) {
+                resigned = Some(updated);
+            }
+            let txn = resigned.as_ref().unwrap_or(txn);
+            self.wait_if_paused().await;
+            sample!(
+                SampleRate::Duration(Duration::from_secs(60)),
+                debug!(
+                    "Running reliable/retriable fetching, current state: {}",
+                    counters.show_detailed()
+                )
+            );
+
+            let client_index = self.select_client_for_round(txn, i, run_seed, counters);
+            let rest_client = &self.rest_clients[client_index];
+            if let Some(breaker) = self.circuit_breakers.get(client_index) {
+                breaker.on_selected();
+            }
+            let mut failed_submit = None;
+            let mut failed_wait = None;
+            let start = Instant::now();
+            let result = submit_and_check(
+                self.submission_target(client_index),
+                self.hedge_target(client_index),
+                txn,
+                wait_duration,
+                self.mempool_full_backoff.is_some(),
+                &mut failed_submit,
+                &mut failed_wait,
+            )
+            .instrument(round_span(txn, i, rest_client))
+            .await;
+            let result = match result {
+                Ok(()) => {
+                    self.confirm_quorum(txn, client_index, &mut failed_wait)
+                        .await
+                },
+                Err(err) => Err(err),
+            };
+
+            self.update_counters(
+                counters,
+                i,
+                client_index,
+                failed_submit,
+                failed_wait,
+                result.as_ref().ok().map(|_| start.elapsed()),
+                result.as_ref().err().map(|err| err as &dyn Display),
+            );
+            if result.is_ok() {
+                counters.latencies.record(first_submit.elapsed());
+                return Ok(());
+            };
+            if failed_submit == Some(FailureCategory::MempoolFull) {
+                self.backoff_on_mempool_full(counters).await;
+            }
+        }
+
+        // if submission timeouts, it might still get committed:
+        let txn = resigned.as_ref().unwrap_or(txn);
+        let client_index = self
+            .wait_for_signed_transaction(txn, rounds, deadline)
+            .await?;
+        self.confirm_quorum(txn, client_index, &mut None).await?;
+
+        counters.successes.fetch_add(1, Ordering::Relaxed);
+        counters.record_success_round(rounds);
+        counters.latencies.record(first_submit.elapsed());
+        Ok(())
+    }
+
+    /// Submits transaction until some client accepts it into mempool, without
+    /// waiting for it to be committed. Rejection due to the sequence number being
+    /// already used is not retried, as the transaction was most likely accepted before.
+    async fn submit_until_accepted(
+        &self,
+        txn: &SignedTransaction,
+        counters: &CounterState,
+        run_seed: u64,
+        deadline: Option<Instant>,
+    ) -> Result<()> {
+        if self.simulate_before_submit {
+            self.check_in_simulation(txn, counters).await?;
+        }
+        let mut rounds = 0;
+        let mut last_error = None;
+        for i in 0..self.max_retries {
+            self.sleep_before_round(i).await;
+            if deadline.map_or(false, |deadline| Instant::now() >= deadline) {
+                break;
+            }
+            rounds += 1;
+            self.wait_if_paused().await;
+
+            let client_index = self.select_client_for_round(txn, i, run_seed, counters);
+            if let Some(breaker) = self.circuit_breakers.get(client_index) {
+                breaker.on_selected();
+            }
+            let target = self.submission_target(client_index);
+            target.acquire().await;
+            let result = submit_hedged(target.rest_client, self.hedge_target(client_index), txn)
+                .instrument(round_span(txn, i, target.rest_client))
+                .await;
+            match result {
+                Ok(()) => {
+                    self.record_client_result(client_index, true);
+                    EXECUTOR_ACCEPTED
+                        .with_label_values(&[&target.rest_client.path_prefix_string()])
+                        .inc();
+                    counters.accepted.fetch_add(1, Ordering::Relaxed);
+                    return Ok(());
+                },
+                Err(err) => {
+                    let category = classify_rest_error(&err);
+                    self.update_counters(
+                        counters,
+                        i,
+                        client_index,
+                        Some(category),
+                        None,
+                        None,
+                        Some(&err),
+                    );
+                    match category {
+                        FailureCategory::SequenceNumberTooOld => return Ok(()),
+                        FailureCategory::MempoolFull => {
+                            self.backoff_on_mempool_full(counters).await
+                        },
+                        _ => {},
+                    }
+                    last_error = Some(err);
+                },
+            }
+        }
+        bail!(
+            "Transaction not accepted into mempool after {} submission rounds: {}",
+            rounds,
+            last_error.map_or_else(|| "deadline reached".to_string(), |err| err.to_string())
+        )
+    }
+
+    async fn execute_transactions_until(
+        &self,
+        txns: &[SignedTransaction],
+        counters: &CounterState,
+        deadline: Option<Instant>,
+    ) -> Result<()> {
+        let run_seed: u64 = thread_rng().gen();
+        let deadline = match (deadline, self.execution_budget) {
+            (Some(deadline), Some(budget)) => Some(deadline.min(Instant::now() + budget)),
+            (deadline, budget) => deadline.or_else(|| budget.map(|budget| Instant::now() + budget)),
+        };
+
+        let execute = async {
+            if self.ack_only {
+                join_all(txns.iter().map(|txn| {
+                    self.submit_until_accepted(txn, counters, run_seed, deadline)
+                        .instrument(transaction_span(txn))
+                }))
+                .await
+                .into_iter()
+                .collect::<Result<Vec<()>, anyhow::Error>>()
+                .map(|_| ())
+            } else if let Some(verify_delay) = self.bulk_verify_delay {
+                self.submit_verify_and_retry(txns, counters, run_seed, verify_delay, deadline)
+                    .await
+            } else if self.batch_submit {
+                self.submit_batch_check_and_retry(txns, counters, run_seed, deadline)
+                    .await
+            } else {
+                join_all(txns.iter().map(|txn| {
+                    self.submit_check_and_retry(txn, counters, run_seed, deadline)
+                        .instrument(transaction_span(txn))
+                }))
+                .await
+                .into_iter()
+                .collect::<Result<Vec<()>, anyhow::Error>>()
+                .map(|_| ())
+            }
+        };
+        let execute = async {
+            match &self.counter_reporter {
+                Some(counter_reporter) => counter_reporter.report_while(counters, execute).await,
+                None => execute.await,
+            }
+        };
+        let result = match deadline {
+            Some(deadline) => match tokio::time::timeout_at(deadline.into(), execute).await {
+                Ok(result) => result,
+                Err(_) => self.check_unconfirmed(txns).await,
+            },
+            None => execute.await,
+        };
+        if let Some(cache) = &self.sequence_number_cache {
+            for txn in txns {
+                if result.is_ok() {
+                    cache.on_committed(txn);
+                } else {
+                    cache.invalidate(&txn.sender());
+                }
+            }
+        }
+        result.with_context(|| {
+            format!(
+                "Tried executing {} txns, request counters: {:?}",
+                txns.len(),
+                counters.show_detailed()
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Each round submits all pending transactions (in batches, if batch_submit is set),
+    /// and after verify_delay, checks which of them got committed, based on
+    /// their senders' sequence numbers.
+    async fn submit_verify_and_retry(
+        &self,
+        txns: &[SignedTransaction],
+        counters: &CounterState,
+        run_seed: u64,
+        verify_delay: Duration,
+        deadline: Option<Instant>,
+    ) -> Result<()> {
+        let first_submit = Instant::now();
+        let mut pending = txns.iter().collect::<Vec<_>>();
+        for i in 0..self.max_retries {
+            if pending.is_empty() {
+                return Ok(());
+            }
+            self.sleep_before_round(i).await;
+            if self.round_wait_duration(deadline).is_none() {
+                break;
+            }
+            self.wait_if_paused().await;
+
+            let mut by_client: HashMap<usize, Vec<&SignedTransaction>> = HashMap::new();
+            for txn in pending.iter().copied() {
+                by_client
+                    .entry(self.select_client_for_round(txn, i, run_seed, counters))
+                    .or_default()
+                    .push(txn);
+            }
+            let start = Instant::now();
+            let submitted = join_all(by_client.iter().map(|(client_index, group)| async move {
+                let target = self.submission_target(*client_index);
+                if let Some(breaker) = self.circuit_breakers.get(*client_index) {
+                    breaker.on_selected();
+                }
+                let failed_submit = if self.batch_submit {
+                    let mut failed_submit = Vec::with_capacity(group.len());
+                    for chunk in group.chunks(DEFAULT_MAX_SUBMIT_TRANSACTION_BATCH_SIZE) {
+                        target.acquire().await;
+                        failed_submit.extend(submit_batch(target.rest_client, chunk).await);
+                    }
+                    failed_submit
+                } else {
+                    join_all(group.iter().map(|txn| async move {
+                        target.acquire().await;
+                        target
+                            .rest_client
+                            .submit_bcs(txn)
+                            .await
+                            .err()
+                            .map(|err| classify_rest_error(&err))
+                    }))
+                    .await
+                };
+                (*client_index, failed_submit)
+            }))
+            .await;
+
+            tokio::time::sleep(verify_delay).await;
+            let unconfirmed_txns = self.find_unconfirmed(&pending).await?;
+            let unconfirmed = unconfirmed_txns
+                .iter()
+                .map(|txn| (txn.sender(), txn.sequence_number()))
+                .collect::<HashSet<_>>();
+
+            for (client_index, failed_submit) in submitted {
+                for (txn, failed_submit) in by_client[&client_index].iter().zip(failed_submit) {
+                    let committed = !unconfirmed.contains(&(txn.sender(), txn.sequence_number()));
+                    self.update_counters(
+                        counters,
+                        i,
+                        client_index,
+                        failed_submit,
+                        (!committed).then_some(FailureCategory::Timeout),
+                        committed.then(|| start.elapsed()),
+                        None,
+                    );
+                    if committed {
+                        counters.latencies.record(first_submit.elapsed());
+                    }
+                }
+            }
+            // resubmitted in the next round
+            pending = unconfirmed_txns;
+        }
+        if pending.is_empty() {
+            return Ok(());
+        }
+        Err(TxnExecutionError::Unconfirmed {
+            transactions: pending
+                .iter()
+                .map(|txn| (txn.sender(), txn.sequence_number()))
+                .collect(),
+        }
+        .into())
+    }
+
+    /// Returns transactions that are not committed, based on their senders'
+    /// sequence numbers on chain.
+    async fn find_unconfirmed<'a>(
+        &self,
+        txns: &[&'a SignedTransaction],
+    ) -> Result<Vec<&'a SignedTransaction>> {
+        let senders = txns
+            .iter()
+            .map(|txn| txn.sender())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+        let sequence_numbers = try_join_all(senders.iter().map(|sender| async move {
+            retry(&*self.retry_policy, || {
+                self.random_rest_client().get_account_bcs(*sender)
+            })
+            .await
+            .map(|response| (*sender, response.into_inner().sequence_number()))
+        }))
+        .await?
+        .into_iter()
+        .collect::<HashMap<_, _>>();
+        Ok(unconfirmed_by_sequence_number(txns, &sequence_numbers))
+    }
+
+    /// Called when execution is cancelled at deadline, fails reporting which
+    /// transactions are unconfirmed, if any.
+    async fn check_unconfirmed(&self, txns: &[SignedTransaction]) -> Result<()> {
+        match self
+            .find_unconfirmed(&txns.iter().collect::<Vec<_>>())
+            .await
+        {
+            Ok(unconfirmed) if unconfirmed.is_empty() => Ok(()),
+            Ok(unconfirmed) => Err(TxnExecutionError::Unconfirmed {
+                transactions: unconfirmed
+                    .iter()
+                    .map(|txn| (txn.sender(), txn.sequence_number()))
+                    .collect(),
+            }
+            .into()),
+            Err(err) => Err(
+                anyhow::Error::new(TxnExecutionError::DeadlineReached).context(format!(
+                    "Failed checking which transactions are unconfirmed: {:?}",
+                    err
+                )),
+            ),
+        }
+    }
+
+    /// Same as submit_check_and_retry, but for each round, groups transactions
+    /// by the selected client, and submits each group in a single batch request.
+    async fn submit_batch_check_and_retry(
+        &self,
+        txns: &[SignedTransaction],
+        counters: &CounterState,
+        run_seed: u64,
+        deadline: Option<Instant>,
+    ) -> Result<()> {
+        let mut pending = txns.iter().collect::<Vec<_>>();
+        let mut num_failed_simulation = 0;
+        if self.simulate_before_submit {
+            let simulation_results = join_all(
+                pending
+                    .iter()
+                    .map(|txn| self.check_in_simulation(txn, counters)),
+            )
+            .await;
+            pending = pending
+                .into_iter()
+                .zip(simulation_results.into_iter())
+                .filter_map(|(txn, result)| result.ok().map(|_| txn))
+                .collect();
+            num_failed_simulation = txns.len() - pending.len();
+        }
+        let first_submit = Instant::now();
+        let mut rounds = 0;
+        for i in 0..self.max_retries {
+            if pending.is_empty() {
+                break;
+            }
+            self.sleep_before_round(i).await;
+            let wait_duration = match self.round_wait_duration(deadline) {
+                Some(wait_duration) => wait_duration,
+                None => break,
+            };
+            if self.sequence_check_before_retry && i > 0 {
+                let committed = join_all(
+                    pending
+                        .iter()
+                        .map(|txn| self.committed_before_retry(txn, i)),
+                )
+                .await;
+                pending = pending
+                    .into_iter()
+                    .zip(committed.into_iter())
+                    .filter_map(|(txn, committed)| {
+                        if committed {
+                            counters.successes.fetch_add(1, Ordering::Relaxed);
+                            counters.record_success_round(i - 1);
+                            counters.latencies.record(first_submit.elapsed());
+                            None
+                        } else {
+                            Some(txn)
+                        }
+                    })
+                    .collect();
+                if pending.is_empty() {
+                    break;
+                }
+            }
+            rounds += 1;
+            self.wait_if_paused().await;
+
+            let mut by_client: HashMap<usize, Vec<&SignedTransaction>> = HashMap::new();
+            for txn in pending {
+                by_client
+                    .entry(self.select_client_for_round(txn, i, run_seed, counters))
+                    .or_default()
+                    .push(txn);
+            }
+
+            pending = join_all(by_client.iter().flat_map(|(client_index, group)| {
+                group
+                    .chunks(DEFAULT_MAX_SUBMIT_TRANSACTION_BATCH_SIZE)
+                    .map(|chunk| {
+                        self.submit_batch_and_check(
+                            *client_index,
+                            chunk,
+                            i,
+                            wait_duration,
+                            first_submit,
+                            counters,
+                        )
+                        .instrument(debug_span!(
+                            "submission_round",
+                            round = i,
+                            client = %self.rest_clients[*client_index].path_prefix_string(),
+                            batch_size = chunk.len(),
+                        ))
+                    })
+            }))
+            .await
+            .into_iter()
+            .flatten()
+            .collect();
+        }
+
+        // if submission timeouts, they might still get committed:
+        join_all(pending.iter().map(|txn| async move {
+            let result = match self
+                .wait_for_signed_transaction(txn, rounds, deadline)
+                .await
+            {
+                Ok(client_index) => self.confirm_quorum(txn, client_index, &mut None).await,
+                Err(err) => Err(err),
+            };
+            if result.is_ok() {
+                counters.successes.fetch_add(1, Ordering::Relaxed);
+                counters.record_success_round(rounds);
+                counters.latencies.record(first_submit.elapsed());
+            }
+            result
+        }))
+        .await
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()?;
+
+        if num_failed_simulation > 0 {
+            bail!(
+                "{} transactions failed in simulation, and were not submitted",
+                num_failed_simulation
+            );
+        }
+        Ok(())
     }
 
-    async fn submit_check_and_retry(
+    /// Waits on a random client, until transaction is committed or expires,
+    /// or deadline is reached. Returns the client it was confirmed on.
+    /// Called after all (given number of) submission rounds failed.
+    async fn wait_for_signed_transaction(
         &self,
         txn: &SignedTransaction,
-        counters: &CounterState,
-        run_seed: u64,
-    ) -> Result<()> {
-        for i in 0..self.max_retries {
-            sample!(
-                SampleRate::Duration(Duration::from_secs(60)),
-                debug!(
-                    "Running reliable/retriable fetching, current state: {}",
-                    counters.show_detailed()
-                )
-            );
+        rounds: usize,
+        deadline: Option<Instant>,
+    ) -> Result<usize> {
+        let client_index = self.random_client_index_from_rng(&mut thread_rng(), None, 0);
+        let timeout = deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+        if let Err(err) = self
+            .submission_target(client_index)
+            .wait(txn, timeout)
+            .await
+        {
+            let error = match err {
+                WaitFailure::Resolved(error) => error,
+                WaitFailure::Timeout(..) if !self.is_client_available(client_index) => {
+                    TxnExecutionError::EndpointUnhealthy {
+                        client: self.rest_clients[client_index].path_prefix_string(),
+                    }
+                },
+                WaitFailure::Timeout(..)
+                    if deadline.map_or(false, |deadline| Instant::now() >= deadline) =>
+                {
+                    TxnExecutionError::DeadlineReached
+                },
+                err @ WaitFailure::Timeout(..) => TxnExecutionError::SubmitExhausted {
+                    rounds,
+                    last_error: err.to_string(),
+                },
+            };
+            return Err(error.into());
+        }
+        Ok(client_index)
+    }
 
-            // All transactions from the same sender, need to be submitted to the same client
-            // in the same retry round, so that they are not placed in parking lot.
-            // Do so by selecting a client via seeded random selection.
-            let seed = [
-                i.to_le_bytes().to_vec(),
-                run_seed.to_le_bytes().to_vec(),
-                txn.sender().to_vec(),
-            ]
-            .concat();
-            let mut seeded_rng = StdRng::from_seed(*aptos_crypto::HashValue::sha3_256_of(&seed));
-            let rest_client = self.random_rest_client_from_rng(&mut seeded_rng);
-            let mut failed_submit = false;
-            let mut failed_wait = false;
-            let result = submit_and_check(
-                rest_client,
-                txn,
-                self.retry_after,
-                &mut failed_submit,
-                &mut failed_wait,
+    /// Fetches receipt of the committed transaction with the sender and sequence number
+    /// of txn, which is txn itself, unless it was re-signed on retries.
+    async fn fetch_receipt(&self, txn: &SignedTransaction) -> Result<TransactionReceipt> {
+        let client_index = self.random_client_index_from_rng(&mut thread_rng(), None, 0);
+        let target = self.submission_target(client_index);
+        let committed = retry(&*self.retry_policy, || async move {
+            target.acquire().await;
+            target
+                .rest_client
+                .get_account_transactions_bcs(txn.sender(), Some(txn.sequence_number()), Some(1))
+                .await
+        })
+        .await?
+        .into_inner()
+        .into_iter()
+        .next()
+        .ok_or_else(|| {
+            anyhow!(
+                "[{}] Committed transaction with sequence number {} not found for {}",
+                target.rest_client.path_prefix_string(),
+                txn.sequence_number(),
+                txn.sender(),
             )
-            .await;
+        })?;
+        Ok(TransactionReceipt {
+            hash: committed.info.transaction_hash(),
+            version: Some(committed.version),
+            gas_used: Some(committed.info.gas_used()),
+            vm_status: Some(format!("{:?}", committed.info.status())),
+            confirmed_by: Some(target.rest_client.path_prefix_string()),
+        })
+    }
 
-            if failed_submit {
-                counters.submit_failures[i.min(counters.submit_failures.len() - 1)]
-                    .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-                if !counters.by_client.is_empty() {
-                    counters
-                        .by_client
-                        .get(&rest_client.path_prefix_string())
-                        .map(|(_, submit_failures, _)| {
-                            submit_failures.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
-                        });
-                }
-            }
-            if failed_wait {
-                counters.wait_failures[i.min(counters.wait_failures.len() - 1)]
-                    .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-                if !counters.by_client.is_empty() {
-                    counters
-                        .by_client
-                        .get(&rest_client.path_prefix_string())
-                        .map(|(_, _, wait_failures)| {
-                            wait_failures.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
-                        });
+    /// Waits until transaction, already confirmed on confirmed_on client, is visible
+    /// on enough other clients to reach the confirmation_quorum, for at most retry_after.
+    async fn confirm_quorum(
+        &self,
+        txn: &SignedTransaction,
+        confirmed_on: usize,
+        failed_wait: &mut Option<FailureCategory>,
+    ) -> Result<()> {
+        let quorum = match self.confirmation_quorum {
+            Some(quorum) if quorum > 1 => quorum,
+            _ => return Ok(()),
+        };
+        let mut confirmations = 1;
+        let mut waits = (0..self.rest_clients.len())
+            .filter(|client_index| *client_index != confirmed_on)
+            .map(|client_index| async move {
+                self.submission_target(client_index)
+                    .wait(txn, Some(self.retry_after))
+                    .await
+            })
+            .collect::<FuturesUnordered<_>>();
+        while let Some(result) = waits.next().await {
+            if result.is_ok() {
+                confirmations += 1;
+                if confirmations >= quorum {
+                    return Ok(());
                 }
             }
+        }
+        *failed_wait = Some(FailureCategory::Timeout);
+        sample!(
+            SampleRate::Duration(Duration::from_secs(60)),
+            warn!(
+                "Transaction from {} confirmed only on {} out of required {} clients",
+                txn.sender(),
+                confirmations,
+                quorum,
+            )
+        );
+        Err(TxnExecutionError::QuorumNotReached {
+            confirmations,
+            quorum,
+        }
+        .into())
+    }
 
-            if result.is_ok() {
-                counters
-                    .successes
-                    .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-                if !counters.by_client.is_empty() {
-                    counters
-                        .by_client
-                        .get(&rest_client.path_prefix_string())
-                        .map(|(successes, _, _)| {
-                            successes.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
-                        });
+    /// Returns transactions that didn't get committed.
+    async fn submit_batch_and_check<'a>(
+        &self,
+        client_index: usize,
+        txns: &[&'a SignedTransaction],
+        round: usize,
+        wait_duration: Duration,
+        first_submit: Instant,
+        counters: &CounterState,
+    ) -> Vec<&'a SignedTransaction> {
+        let target = self.submission_target(client_index);
+        let rest_client = target.rest_client;
+        if let Some(breaker) = self.circuit_breakers.get(client_index) {
+            breaker.on_selected();
+        }
+        let start = Instant::now();
+        // ledger version before submission, from which commits of the batch are scanned for
+        let start_version = if self.batch_wait {
+            target.acquire().await;
+            rest_client
+                .get_ledger_information()
+                .await
+                .ok()
+                .map(|response| response.into_inner().version + 1)
+        } else {
+            None
+        };
+        let mut batch_waiter = BatchWaiter::default();
+        target.acquire().await;
+        let confirmations = txns
+            .iter()
+            .map(|txn| {
+                target
+                    .subscribe(txn)
+                    .or_else(|| start_version.map(|_| batch_waiter.subscribe(txn)))
+            })
+            .collect::<Vec<_>>();
+        let failed_submit = submit_batch(rest_client, txns).await;
+        let mempool_full = failed_submit.contains(&Some(FailureCategory::MempoolFull));
+        let check_all = join_all(
+            txns.iter()
+                .zip(failed_submit.iter())
+                .zip(confirmations.into_iter())
+                .map(|((txn, failed_submit), confirmation)| async move {
+                    let mut failed_wait = None;
+                    let result = check_submitted(
+                        target,
+                        txn,
+                        *failed_submit,
+                        self.mempool_full_backoff.is_some(),
+                        wait_duration.saturating_sub(start.elapsed()),
+                        confirmation,
+                        &mut failed_wait,
+                    )
+                    .await;
+                    let result = match result {
+                        Ok(()) => {
+                            self.confirm_quorum(txn, client_index, &mut failed_wait)
+                                .await
+                        },
+                        Err(err) => Err(err),
+                    };
+                    (result, failed_wait)
+                }),
+        );
+        let check_results = match start_version {
+            Some(start_version) if !batch_waiter.is_empty() => {
+                let scan = batch_waiter.run(
+                    rest_client,
+                    target.rate_limiter,
+                    start_version,
+                    wait_duration.saturating_sub(start.elapsed()),
+                );
+                futures::join!(check_all, scan).0
+            },
+            _ => check_all.await,
+        };
+
+        let failed = txns
+            .iter()
+            .zip(failed_submit.iter().zip(check_results.into_iter()))
+            .filter_map(|(txn, (failed_submit, (result, failed_wait)))| {
+                self.update_counters(
+                    counters,
+                    round,
+                    client_index,
+                    *failed_submit,
+                    failed_wait,
+                    result.as_ref().ok().map(|_| start.elapsed()),
+                    result.as_ref().err().map(|err| err as &dyn Display),
+                );
+                if result.is_ok() {
+                    counters.latencies.record(first_submit.elapsed());
                 }
-                return Ok(());
-            };
+                result.err().map(|_| *txn)
+            })
+            .collect::<Vec<_>>();
+
+        // in batch mode, whole round waits for the backoff
+        if mempool_full {
+            self.backoff_on_mempool_full(counters).await;
         }
+        failed
+    }
+}
 
-        // if submission timeouts, it might still get committed:
-        self.random_rest_client()
-            .wait_for_signed_transaction_bcs(txn)
-            .await?;
+/// Span covering all retry rounds of a single transaction.
+fn transaction_span(txn: &SignedTransaction) -> Span {
+    debug_span!(
+        "submit_check_and_retry",
+        sender = %txn.sender(),
+        sequence_number = txn.sequence_number(),
+    )
+}
 
-        counters
-            .successes
-            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-        Ok(())
+/// Span covering submission and wait of a single round.
+/// Hash is recorded per round, as transaction can be re-signed between rounds.
+fn round_span(txn: &SignedTransaction, round: usize, rest_client: &RestClient) -> Span {
+    let span = debug_span!(
+        "submission_round",
+        round,
+        client = %rest_client.path_prefix_string(),
+        txn_hash = field::Empty,
+    );
+    // avoid computing the hash, if there is nobody to record it
+    if !span.is_disabled() {
+        span.record("txn_hash", &field::display(txn.clone().committed_hash()));
+    }
+    span
+}
+
+/// Transactions that are not committed, given the sequence numbers of all their
+/// senders on chain, as ones below it are.
+fn unconfirmed_by_sequence_number<'a>(
+    txns: &[&'a SignedTransaction],
+    sequence_numbers: &HashMap<AccountAddress, u64>,
+) -> Vec<&'a SignedTransaction> {
+    txns.iter()
+        .filter(|txn| sequence_numbers[&txn.sender()] <= txn.sequence_number())
+        .copied()
+        .collect()
+}
+
+fn classify_aptos_error(error: &AptosError) -> FailureCategory {
+    match error.error_code {
+        AptosErrorCode::MempoolIsFull => FailureCategory::MempoolFull,
+        AptosErrorCode::SequenceNumberTooOld => FailureCategory::SequenceNumberTooOld,
+        AptosErrorCode::VmError | AptosErrorCode::InvalidTransactionUpdate => {
+            FailureCategory::VmValidation
+        },
+        _ => FailureCategory::Other,
+    }
+}
+
+fn classify_rest_error(error: &RestError) -> FailureCategory {
+    match error {
+        RestError::Api(response) => {
+            if response.status_code == StatusCode::TOO_MANY_REQUESTS {
+                FailureCategory::RateLimited
+            } else {
+                classify_aptos_error(&response.error)
+            }
+        },
+        RestError::Http(status_code, _) => match *status_code {
+            StatusCode::TOO_MANY_REQUESTS => FailureCategory::RateLimited,
+            StatusCode::REQUEST_TIMEOUT | StatusCode::GATEWAY_TIMEOUT => FailureCategory::Timeout,
+            _ => FailureCategory::Connection,
+        },
+        RestError::Timeout(_) => FailureCategory::Timeout,
+        RestError::Unknown(error) => {
+            if let Some(reqwest_error) = error.downcast_ref::<reqwest::Error>() {
+                if reqwest_error.is_timeout() {
+                    FailureCategory::Timeout
+                } else {
+                    FailureCategory::Connection
+                }
+            } else if error.to_string().contains("Timeout") {
+                // wait_for_transaction_by_hash reports reaching timeout_from_call this way
+                FailureCategory::Timeout
+            } else {
+                FailureCategory::Other
+            }
+        },
+        RestError::Bcs(_) | RestError::Json(_) | RestError::UrlParse(_) => FailureCategory::Other,
     }
 }
 
 async fn submit_and_check(
-    rest_client: &RestClient,
+    target: SubmissionTarget<'_>,
+    hedge: Option<(SubmissionTarget<'_>, Duration)>,
     txn: &SignedTransaction,
     wait_duration: Duration,
-    failed_submit: &mut bool,
-    failed_wait: &mut bool,
+    skip_wait_if_mempool_full: bool,
+    failed_submit: &mut Option<FailureCategory>,
+    failed_wait: &mut Option<FailureCategory>,
 ) -> Result<()> {
+    let rest_client = target.rest_client;
     let start = Instant::now();
-    if let Err(err) = rest_client.submit_bcs(txn).await {
+    let faults = target.inject_faults();
+    let target = SubmissionTarget {
+        corrupt_hash: faults.corrupt_hash,
+        ..target
+    };
+    if let Some(delay) = faults.delay {
+        tokio::time::sleep(delay).await;
+    }
+    target.acquire().await;
+    let confirmation = target.subscribe(txn);
+    if faults.drop {
+        // handled as if the request got lost on the way
+        *failed_submit = Some(FailureCategory::Connection);
+    } else if let Err(err) = submit_hedged(rest_client, hedge, txn)
+        .instrument(debug_span!("submit"))
+        .await
+    {
         sample!(
             SampleRate::Duration(Duration::from_secs(60)),
             warn!(
@@ -144,18 +1979,189 @@ async fn submit_and_check(
                 err,
             )
         );
-        *failed_submit = true;
-        // even if txn fails submitting, it might get committed, so wait to see if that is the case.
+        *failed_submit = Some(classify_rest_error(&err));
     }
-    if let Err(err) = rest_client
-        .wait_for_transaction_by_hash(
-            txn.clone().committed_hash(),
-            txn.expiration_timestamp_secs(),
-            None,
-            Some(wait_duration.saturating_sub(start.elapsed())),
-        )
+    if faults.duplicate && !faults.drop {
+        // outcome of the duplicate doesn't matter, only how the original is handled
+        let _ = rest_client.submit_bcs(txn).await;
+    }
+    check_submitted(
+        target,
+        txn,
+        *failed_submit,
+        skip_wait_if_mempool_full,
+        wait_duration.saturating_sub(start.elapsed()),
+        confirmation,
+        failed_wait,
+    )
+    .await
+}
+
+/// Submits transaction to rest_client, and if it doesn't respond within the hedge delay,
+/// also to the hedge target. Succeeds if either of them accepts the transaction.
+async fn submit_hedged(
+    rest_client: &RestClient,
+    hedge: Option<(SubmissionTarget<'_>, Duration)>,
+    txn: &SignedTransaction,
+) -> Result<(), RestError> {
+    let submit = rest_client.submit_bcs(txn);
+    let (hedge_target, hedge_delay) = match hedge {
+        Some(hedge) => hedge,
+        None => return submit.await.map(|_| ()),
+    };
+    tokio::pin!(submit);
+    if let Ok(result) = tokio::time::timeout(hedge_delay, &mut submit).await {
+        return result.map(|_| ());
+    }
+
+    EXECUTOR_HEDGED_SUBMITS
+        .with_label_values(&[&rest_client.path_prefix_string()])
+        .inc();
+    let hedged_submit = async {
+        hedge_target.acquire().await;
+        hedge_target.rest_client.submit_bcs(txn).await
+    };
+    tokio::pin!(hedged_submit);
+    match select(submit, hedged_submit).await {
+        Either::Left((Ok(_), _)) | Either::Right((Ok(_), _)) => Ok(()),
+        Either::Left((Err(_), hedged_submit)) => hedged_submit.await.map(|_| ()),
+        Either::Right((Err(_), submit)) => submit.await.map(|_| ()),
+    }
+}
+
+/// Submits transactions in a single batch request, returning why each of them failed to submit.
+async fn submit_batch(
+    rest_client: &RestClient,
+    txns: &[&SignedTransaction],
+) -> Vec<Option<FailureCategory>> {
+    let batch = txns.iter().map(|txn| (*txn).clone()).collect::<Vec<_>>();
+    let result = rest_client
+        .submit_batch_bcs(&batch)
         .await
-    {
+        .map(|response| response.into_inner());
+    if let Err(err) = &result {
+        sample!(
+            SampleRate::Duration(Duration::from_secs(60)),
+            warn!(
+                "[{}] Failed submitting batch of {} transactions: {}",
+                rest_client.path_prefix_string(),
+                txns.len(),
+                err,
+            )
+        );
+    }
+    batch_submit_failures(txns.len(), &result)
+}
+
+/// Why each of the num_txns transactions of a batch failed submission, if it did.
+/// All of them fail if the request does, and otherwise the ones reported by index.
+fn batch_submit_failures(
+    num_txns: usize,
+    result: &Result<TransactionsBatchSubmissionResult, RestError>,
+) -> Vec<Option<FailureCategory>> {
+    let mut failed_submit = vec![None; num_txns];
+    match result {
+        Err(err) => {
+            let category = classify_rest_error(err);
+            failed_submit
+                .iter_mut()
+                .for_each(|failed| *failed = Some(category));
+        },
+        Ok(result) => {
+            for failure in &result.transaction_failures {
+                if let Some(failed) = failed_submit.get_mut(failure.transaction_index) {
+                    *failed = Some(classify_aptos_error(&failure.error));
+                }
+            }
+        },
+    }
+    failed_submit
+}
+
+/// Checks whether submitted transaction got committed, waiting for it if needed.
+/// Based on why the submission failed, outcome can be known without waiting.
+async fn check_submitted(
+    target: SubmissionTarget<'_>,
+    txn: &SignedTransaction,
+    failed_submit: Option<FailureCategory>,
+    skip_wait_if_mempool_full: bool,
+    wait_duration: Duration,
+    confirmation: Option<ConfirmationReceiver>,
+    failed_wait: &mut Option<FailureCategory>,
+) -> Result<()> {
+    let rest_client = target.rest_client;
+    match failed_submit {
+        Some(FailureCategory::SequenceNumberTooOld) => {
+            // Sequence number was already used, so transaction (or its replacement)
+            // almost certainly got committed already, confirm via account sequence number.
+            target.acquire().await;
+            let sequence_number = match rest_client.get_account_bcs(txn.sender()).await {
+                Ok(account) => account.into_inner().sequence_number(),
+                Err(err) => {
+                    // outcome is unknown, so counted as a failed wait of the round
+                    *failed_wait = Some(classify_rest_error(&err));
+                    return Err(anyhow::Error::new(err).context(format!(
+                        "[{}] Failed checking sequence number of {} after sequence number too old",
+                        rest_client.path_prefix_string(),
+                        txn.sender(),
+                    )));
+                },
+            };
+            if sequence_number > txn.sequence_number() {
+                sample!(
+                    SampleRate::Duration(Duration::from_secs(60)),
+                    debug!(
+                        "[{}] Sequence number {} for {} already used, counting as committed",
+                        rest_client.path_prefix_string(),
+                        txn.sequence_number(),
+                        txn.sender(),
+                    )
+                );
+                return Ok(());
+            }
+            bail!(
+                "Transaction rejected with sequence number too old, but account {} is still at sequence number {} <= {}",
+                txn.sender(),
+                sequence_number,
+                txn.sequence_number()
+            );
+        },
+        Some(FailureCategory::MempoolFull) if skip_wait_if_mempool_full => {
+            bail!(
+                "[{}] Transaction rejected, mempool is full",
+                rest_client.path_prefix_string()
+            );
+        },
+        // even if txn fails submitting, it might get committed, so wait to see if that is the case.
+        _ => {},
+    }
+    let start = Instant::now();
+    let wait_result: Result<(), WaitFailure> = async {
+        if let Some(confirmation) = confirmation {
+            match tokio::time::timeout(wait_duration, confirmation).await {
+                Ok(Ok(true)) => return Ok(()),
+                Ok(Ok(false)) => {
+                    // confirmer only reports that it aborted, not its status
+                    return Err(WaitFailure::Resolved(
+                        TxnExecutionError::CommittedButAborted {
+                            vm_status: "unknown".to_string(),
+                        },
+                    ));
+                },
+                Err(_) => {
+                    return Err(WaitFailure::Timeout(wait_duration, target.hash(txn)));
+                },
+                // confirmer stopped tracking the transaction, poll for the remaining time
+                Ok(Err(_)) => {},
+            }
+        }
+        target
+            .wait(txn, Some(wait_duration.saturating_sub(start.elapsed())))
+            .await
+    }
+    .instrument(debug_span!("wait"))
+    .await;
+    if let Err(err) = wait_result {
         sample!(
             SampleRate::Duration(Duration::from_secs(60)),
             warn!(
@@ -164,31 +2170,209 @@ async fn submit_and_check(
                 err,
             )
         );
-        *failed_wait = true;
-        Err(err)?;
+        *failed_wait = Some(err.category());
+        return Err(err.into());
     }
     Ok(())
 }
 
+/// Same as RestClient::wait_for_transaction_by_hash, polling until transaction
+/// is committed, expires, or timeout is reached, but polling as configured by
+/// wait_strategy, taking a token from the rate limiter (if any) before each request,
+/// and reporting why it failed as a WaitFailure.
+async fn poll_for_transaction(
+    rest_client: &RestClient,
+    rate_limiter: Option<&TokenBucket>,
+    wait_strategy: WaitStrategy,
+    hash: HashValue,
+    expiration_timestamp_secs: u64,
+    timeout: Option<Duration>,
+) -> Result<(), WaitFailure> {
+    let start = Instant::now();
+    if !wait_strategy.initial_delay.is_zero() {
+        tokio::time::sleep(timeout.map_or(wait_strategy.initial_delay, |timeout| {
+            timeout.min(wait_strategy.initial_delay)
+        }))
+        .await;
+    }
+    let mut poll = 0;
+    loop {
+        if let Some(rate_limiter) = rate_limiter {
+            rate_limiter.acquire().await;
+        }
+        let chain_timestamp_usecs = match rest_client.get_transaction_by_hash(hash).await {
+            Ok(response) => {
+                let (transaction, state) = response.into_parts();
+                if !transaction.is_pending() {
+                    if transaction.success() {
+                        return Ok(());
+                    }
+                    return Err(WaitFailure::Resolved(
+                        TxnExecutionError::CommittedButAborted {
+                            vm_status: transaction.vm_status(),
+                        },
+                    ));
+                }
+                Some(state.timestamp_usecs)
+            },
+            Err(RestError::Api(response)) if response.status_code == StatusCode::NOT_FOUND => {
+                response.state.map(|state| state.timestamp_usecs)
+            },
+            Err(err) => {
+                debug!("Fetching error, will retry: {}", err);
+                None
+            },
+        };
+
+        if let Some(timestamp_usecs) = chain_timestamp_usecs {
+            if expiration_timestamp_secs <= timestamp_usecs / 1_000_000 {
+                return Err(WaitFailure::Resolved(TxnExecutionError::Expired));
+            }
+        }
+        if let Some(timeout) = timeout {
+            if start.elapsed() > timeout {
+                return Err(WaitFailure::Timeout(timeout, hash));
+            }
+        }
+        tokio::time::sleep(wait_strategy.delay_after_poll(poll)).await;
+        poll += 1;
+    }
+}
+
+const APTOS_COIN_TYPE: &str = "0x1::aptos_coin::AptosCoin";
+// balances queried concurrently, across all clients
+const BALANCE_QUERY_PARALLELISM: usize = 100;
+
+/// txn with its signature zeroed, as the simulate API rejects transactions with a valid
+/// signature, or None if it isn't signed by a single ed25519 key.
+fn unsigned_for_simulation(txn: &SignedTransaction) -> Option<SignedTransaction> {
+    match txn.authenticator() {
+        TransactionAuthenticator::Ed25519 { public_key, .. } => Some(SignedTransaction::new(
+            txn.clone().into_raw_transaction(),
+            public_key,
+            Ed25519Signature::try_from([0u8; 64].as_ref()).unwrap(),
+        )),
+        _ => None,
+    }
+}
+
 #[async_trait]
 impl TransactionExecutor for RestApiTransactionExecutor {
     async fn get_account_balance(&self, account_address: AccountAddress) -> Result<u64> {
-        Ok(RETRY_POLICY
-            .retry(move || {
+        Ok(retry(&*self.retry_policy, move || {
+            self.random_rest_client()
+                .get_account_balance(account_address)
+        })
+        .await?
+        .into_inner()
+        .get())
+    }
+
+    async fn get_account_balances(&self, account_addresses: &[AccountAddress]) -> Result<Vec<u64>> {
+        futures::stream::iter(account_addresses.iter().map(|account_address| async move {
+            Ok(retry(&*self.retry_policy, move || {
                 self.random_rest_client()
-                    .get_account_balance(account_address)
+                    .get_account_balance_bcs(*account_address, APTOS_COIN_TYPE)
             })
             .await?
-            .into_inner()
-            .get())
+            .into_inner())
+        }))
+        .buffered(BALANCE_QUERY_PARALLELISM)
+        .collect::<Vec<Result<u64>>>()
+        .await
+        .into_iter()
+        .collect()
     }
 
     async fn query_sequence_number(&self, account_address: AccountAddress) -> Result<u64> {
-        Ok(RETRY_POLICY
-            .retry(move || self.random_rest_client().get_account_bcs(account_address))
-            .await?
-            .into_inner()
-            .sequence_number())
+        if let Some(sequence_number) = self
+            .sequence_number_cache
+            .as_ref()
+            .and_then(|cache| cache.get(&account_address))
+        {
+            return Ok(sequence_number);
+        }
+        let sequence_number = retry(&*self.retry_policy, move || {
+            self.random_rest_client().get_account_bcs(account_address)
+        })
+        .await?
+        .into_inner()
+        .sequence_number();
+        if let Some(cache) = &self.sequence_number_cache {
+            cache.insert(account_address, sequence_number);
+        }
+        Ok(sequence_number)
+    }
+
+    async fn get_account_resource(
+        &self,
+        account_address: AccountAddress,
+        resource_type: &str,
+    ) -> Result<Option<serde_json::Value>> {
+        Ok(retry(&*self.retry_policy, move || {
+            self.random_rest_client()
+                .get_account_resource(account_address, resource_type)
+        })
+        .await?
+        .into_inner()
+        .map(|resource| resource.data))
+    }
+
+    async fn get_resource_bytes(
+        &self,
+        account_address: AccountAddress,
+        resource_type: &StructTag,
+    ) -> Result<Option<Vec<u8>>> {
+        let resource_type = resource_type.to_string();
+        // not found isn't retried
+        Ok(retry(&*self.retry_policy, || async {
+            match self
+                .random_rest_client()
+                .get_account_resource_bytes(account_address, &resource_type)
+                .await
+            {
+                Ok(response) => Ok(Some(response.into_inner())),
+                Err(RestError::Api(response)) if response.status_code == StatusCode::NOT_FOUND => {
+                    Ok(None)
+                },
+                Err(err) => Err(err),
+            }
+        })
+        .await?)
+    }
+
+    async fn view(
+        &self,
+        function: &str,
+        type_args: &[String],
+        args: Vec<serde_json::Value>,
+    ) -> Result<Vec<serde_json::Value>> {
+        let request = view_request(function, type_args, args)?;
+        Ok(retry(&*self.retry_policy, || {
+            self.random_rest_client().view(&request, None)
+        })
+        .await?
+        .into_inner())
+    }
+
+    async fn simulate(&self, txn: &SignedTransaction) -> Result<SimulationResult> {
+        let unsigned_txn = unsigned_for_simulation(txn).with_context(|| {
+            format!(
+                "Only ed25519 transactions can be simulated, not the one from {}",
+                txn.sender()
+            )
+        })?;
+        let simulated = retry(&*self.retry_policy, || {
+            self.random_rest_client().simulate_bcs(&unsigned_txn)
+        })
+        .await?
+        .into_inner();
+        Ok(SimulationResult::new(
+            simulated.info.gas_used(),
+            &TransactionStatus::Keep(simulated.info.status().clone()),
+            &simulated.changes,
+            simulated.events.len(),
+        ))
     }
 
     async fn execute_transactions_with_counter(
@@ -196,24 +2380,31 @@ impl TransactionExecutor for RestApiTransactionExecutor {
         txns: &[SignedTransaction],
         counters: &CounterState,
     ) -> Result<()> {
-        let run_seed: u64 = thread_rng().gen();
+        self.execute_transactions_until(txns, counters, None).await
+    }
 
-        join_all(
-            txns.iter()
-                .map(|txn| self.submit_check_and_retry(txn, counters, run_seed)),
-        )
-        .await
-        .into_iter()
-        .collect::<Result<Vec<()>, anyhow::Error>>()
-        .with_context(|| {
-            format!(
-                "Tried executing {} txns, request counters: {:?}",
-                txns.len(),
-                counters.show_detailed()
-            )
-        })?;
+    /// Stops starting new retry rounds once deadline is reached,
+    /// and bounds waits on submitted transactions by it.
+    async fn execute_transactions_with_deadline(
+        &self,
+        txns: &[SignedTransaction],
+        counters: &CounterState,
+        deadline: Instant,
+    ) -> Result<()> {
+        self.execute_transactions_until(txns, counters, Some(deadline))
+            .await
+    }
 
-        Ok(())
+    /// Receipts are fetched from a random client after all transactions are committed,
+    /// from the account's transaction with the sequence number of each transaction.
+    async fn execute_transactions_with_receipts(
+        &self,
+        txns: &[SignedTransaction],
+        counters: &CounterState,
+    ) -> Result<Vec<TransactionReceipt>> {
+        self.execute_transactions_with_counter(txns, counters)
+            .await?;
+        try_join_all(txns.iter().map(|txn| self.fetch_receipt(txn))).await
     }
 
     fn create_counter_state(&self) -> CounterState {
@@ -224,21 +2415,405 @@ impl TransactionExecutor for RestApiTransactionExecutor {
             wait_failures: std::iter::repeat_with(|| AtomicUsize::new(0))
                 .take(self.max_retries)
                 .collect(),
-            successes: AtomicUsize::new(0),
+            // one more than rounds, for transactions committed only after all of them
+            successes_by_round: std::iter::repeat_with(|| AtomicUsize::new(0))
+                .take(self.max_retries + 1)
+                .collect(),
             by_client: self
                 .rest_clients
                 .iter()
-                .map(|client| {
-                    (
-                        client.path_prefix_string(),
-                        (
-                            AtomicUsize::new(0),
-                            AtomicUsize::new(0),
-                            AtomicUsize::new(0),
-                        ),
-                    )
-                })
+                .map(|client| (client.path_prefix_string(), ClientCounters::default()))
                 .collect(),
+            by_tier: if self.client_tiers.is_empty() {
+                Vec::new()
+            } else {
+                (0..self.num_tiers())
+                    .map(|_| ClientCounters::default())
+                    .collect()
+            },
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        batch_submit_failures, classify_rest_error, health_weight, unconfirmed_by_sequence_number,
+        ClientSelection, GasEscalation, RestApiTransactionExecutor, RetryBackoff, WaitFailure,
+        WaitStrategy, MIN_HEALTH_WEIGHT,
+    };
+    use anyhow::anyhow;
+    use aptos_crypto::HashValue;
+    use aptos_rest_client::{
+        aptos_api_types::{
+            AptosError, AptosErrorCode, TransactionsBatchSingleSubmissionFailure,
+            TransactionsBatchSubmissionResult,
+        },
+        error::RestError,
+        Client as RestClient,
+    };
+    use aptos_sdk::{
+        transaction_builder::{aptos_stdlib, TransactionFactory},
+        types::{chain_id::ChainId, transaction::SignedTransaction, LocalAccount},
+    };
+    use aptos_transaction_generator_lib::{
+        ClientCounters, FailureCategory, TransactionExecutor, TxnExecutionError,
+    };
+    use rand::{rngs::StdRng, SeedableRng};
+    use reqwest::StatusCode;
+    use std::{
+        collections::HashMap,
+        sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+        time::Duration,
+    };
+    use url::Url;
+
+    // transfers of each account to itself, with consecutive sequence numbers
+    fn self_transfers(
+        accounts: &mut [LocalAccount],
+        txns_per_account: usize,
+    ) -> Vec<SignedTransaction> {
+        let txn_factory = TransactionFactory::new(ChainId::test());
+        accounts
+            .iter_mut()
+            .flat_map(|account| {
+                (0..txns_per_account)
+                    .map(|_| {
+                        let address = account.address();
+                        account.sign_with_transaction_builder(
+                            txn_factory.payload(aptos_stdlib::aptos_account_transfer(address, 1)),
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    fn api_error(error_code: AptosErrorCode, status_code: StatusCode) -> RestError {
+        (
+            AptosError::new_with_error_code("error", error_code),
+            None,
+            status_code,
+        )
+            .into()
+    }
+
+    fn http_error(status_code: StatusCode) -> RestError {
+        // any reqwest::Error, here one of building a request to an invalid url
+        let error = reqwest::Client::new().get("not a url").build().unwrap_err();
+        RestError::Http(status_code, error)
+    }
+
+    #[test]
+    pub fn test_classify_rest_error() {
+        let cases = [
+            (
+                api_error(AptosErrorCode::MempoolIsFull, StatusCode::TOO_MANY_REQUESTS),
+                FailureCategory::RateLimited,
+            ),
+            (
+                api_error(AptosErrorCode::MempoolIsFull, StatusCode::BAD_REQUEST),
+                FailureCategory::MempoolFull,
+            ),
+            (
+                api_error(
+                    AptosErrorCode::SequenceNumberTooOld,
+                    StatusCode::BAD_REQUEST,
+                ),
+                FailureCategory::SequenceNumberTooOld,
+            ),
+            (
+                api_error(AptosErrorCode::VmError, StatusCode::BAD_REQUEST),
+                FailureCategory::VmValidation,
+            ),
+            (
+                api_error(
+                    AptosErrorCode::InvalidTransactionUpdate,
+                    StatusCode::BAD_REQUEST,
+                ),
+                FailureCategory::VmValidation,
+            ),
+            (
+                api_error(
+                    AptosErrorCode::InternalError,
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                ),
+                FailureCategory::Other,
+            ),
+            (
+                http_error(StatusCode::TOO_MANY_REQUESTS),
+                FailureCategory::RateLimited,
+            ),
+            (
+                http_error(StatusCode::REQUEST_TIMEOUT),
+                FailureCategory::Timeout,
+            ),
+            (
+                http_error(StatusCode::GATEWAY_TIMEOUT),
+                FailureCategory::Timeout,
+            ),
+            (
+                http_error(StatusCode::BAD_GATEWAY),
+                FailureCategory::Connection,
+            ),
+            (RestError::Timeout("txn"), FailureCategory::Timeout),
+            (
+                RestError::Unknown(anyhow!("Timeout reached")),
+                FailureCategory::Timeout,
+            ),
+            (
+                RestError::Unknown(
+                    reqwest::Client::new()
+                        .get("not a url")
+                        .build()
+                        .unwrap_err()
+                        .into(),
+                ),
+                FailureCategory::Connection,
+            ),
+            (
+                RestError::Unknown(anyhow!("something else")),
+                FailureCategory::Other,
+            ),
+        ];
+        for (error, category) in cases {
+            assert_eq!(classify_rest_error(&error), category, "{:?}", error);
+        }
+    }
+
+    #[test]
+    pub fn test_wait_failure() {
+        let aborted = WaitFailure::Resolved(TxnExecutionError::CommittedButAborted {
+            vm_status: "Move abort".to_string(),
+        });
+        assert_eq!(aborted.category(), FailureCategory::Other);
+        let error: anyhow::Error = aborted.into();
+        assert!(matches!(
+            TxnExecutionError::from_error(&error),
+            Some(TxnExecutionError::CommittedButAborted { vm_status }) if vm_status == "Move abort"
+        ));
+
+        let timeout = WaitFailure::Timeout(Duration::from_secs(5), HashValue::zero());
+        assert_eq!(timeout.category(), FailureCategory::Timeout);
+        let error: anyhow::Error = timeout.into();
+        assert!(TxnExecutionError::from_error(&error).is_none());
+        assert!(error.to_string().contains("might still succeed"));
+    }
+
+    #[test]
+    pub fn test_batch_submit_failures() {
+        let failure = |transaction_index, error_code| TransactionsBatchSingleSubmissionFailure {
+            error: AptosError::new_with_error_code("error", error_code),
+            transaction_index,
+        };
+        let result = Ok(TransactionsBatchSubmissionResult {
+            transaction_failures: vec![
+                failure(1, AptosErrorCode::MempoolIsFull),
+                failure(3, AptosErrorCode::SequenceNumberTooOld),
+                // not in the batch
+                failure(10, AptosErrorCode::VmError),
+            ],
+        });
+        assert_eq!(batch_submit_failures(4, &result), vec![
+            None,
+            Some(FailureCategory::MempoolFull),
+            None,
+            Some(FailureCategory::SequenceNumberTooOld),
+        ]);
+        assert_eq!(
+            batch_submit_failures(
+                2,
+                &Ok(TransactionsBatchSubmissionResult {
+                    transaction_failures: vec![]
+                })
+            ),
+            vec![None, None]
+        );
+
+        // failed request fails the whole batch
+        assert_eq!(
+            batch_submit_failures(3, &Err(RestError::Timeout("batch"))),
+            vec![Some(FailureCategory::Timeout); 3]
+        );
+    }
+
+    #[test]
+    pub fn test_health_weight() {
+        // clients without history get an even chance
+        assert_eq!(health_weight(&ClientCounters::default()), 0.5);
+        let healthy = ClientCounters {
+            successes: AtomicUsize::new(9),
+            submit_failures: AtomicUsize::new(1),
+            latency_millis: AtomicU64::new(900),
+            ..ClientCounters::default()
+        };
+        // 10 / 12 success rate, at 100ms each
+        assert!((health_weight(&healthy) - 10.0 / 12.0 * 1000.0 / 1100.0).abs() < 1e-9);
+        let slow = ClientCounters {
+            successes: AtomicUsize::new(9),
+            submit_failures: AtomicUsize::new(1),
+            latency_millis: AtomicU64::new(9000),
+            ..ClientCounters::default()
+        };
+        assert!(health_weight(&slow) < health_weight(&healthy));
+        let failing = ClientCounters {
+            wait_failures: AtomicUsize::new(1000),
+            ..ClientCounters::default()
+        };
+        assert_eq!(health_weight(&failing), MIN_HEALTH_WEIGHT);
+    }
+
+    #[test]
+    pub fn test_health_weighted_selection() {
+        let executor = RestApiTransactionExecutor::new(
+            vec![
+                RestClient::new(Url::parse("http://healthy:8080").unwrap()),
+                RestClient::new(Url::parse("http://failing:8080").unwrap()),
+            ],
+            3,
+            Duration::from_millis(10),
+        )
+        .with_client_selection(ClientSelection::HealthWeighted);
+        let counters = executor.create_counter_state();
+        let client_counters = executor
+            .rest_clients
+            .iter()
+            .map(|client| &counters.by_client[&client.path_prefix_string()])
+            .collect::<Vec<_>>();
+        for _ in 0..100 {
+            client_counters[0].record_success(Duration::from_millis(10));
+            client_counters[1]
+                .submit_failures
+                .fetch_add(1, Ordering::Relaxed);
+        }
+
+        let mut rng = StdRng::from_seed([0u8; 32]);
+        let mut selected = [0; 2];
+        for _ in 0..1000 {
+            selected[executor.random_client_index_from_rng(&mut rng, Some(&counters), 0)] += 1;
+        }
+        // expected 1% of selections go to the failing client, as it's at MIN_HEALTH_WEIGHT
+        assert!(selected[1] < 50, "{:?}", selected);
+
+        // without counters, selection is uniform
+        let mut selected = [0; 2];
+        for _ in 0..1000 {
+            selected[executor.random_client_index_from_rng(&mut rng, None, 0)] += 1;
+        }
+        assert!(selected[1] > 400, "{:?}", selected);
+    }
+
+    #[test]
+    pub fn test_unconfirmed_by_sequence_number() {
+        let mut rng = StdRng::from_seed([0u8; 32]);
+        let mut accounts = vec![
+            LocalAccount::generate(&mut rng),
+            LocalAccount::generate(&mut rng),
+        ];
+        let txns = self_transfers(&mut accounts, 3);
+        let txns = txns.iter().collect::<Vec<_>>();
+        // first two of the first sender, and none of the second, are committed
+        let sequence_numbers =
+            HashMap::from([(accounts[0].address(), 2), (accounts[1].address(), 0)]);
+        let unconfirmed = unconfirmed_by_sequence_number(&txns, &sequence_numbers);
+        assert_eq!(
+            unconfirmed
+                .iter()
+                .map(|txn| (txn.sender(), txn.sequence_number()))
+                .collect::<Vec<_>>(),
+            vec![
+                (accounts[0].address(), 2),
+                (accounts[1].address(), 0),
+                (accounts[1].address(), 1),
+                (accounts[1].address(), 2),
+            ]
+        );
+
+        // the next round resubmits only those, and all of them once the senders catch up
+        let sequence_numbers =
+            HashMap::from([(accounts[0].address(), 3), (accounts[1].address(), 2)]);
+        let unconfirmed = unconfirmed_by_sequence_number(&unconfirmed, &sequence_numbers);
+        assert_eq!(unconfirmed, vec![txns[5]]);
+        let sequence_numbers = HashMap::from([(accounts[1].address(), 3)]);
+        assert!(unconfirmed_by_sequence_number(&unconfirmed, &sequence_numbers).is_empty());
+    }
+
+    #[test]
+    pub fn test_exponential_backoff() {
+        let mut rng = StdRng::from_seed([0u8; 32]);
+        let backoff = RetryBackoff::Exponential {
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(1),
+            jitter: false,
+        };
+        assert_eq!(
+            backoff.delay_for_round(0, &mut rng),
+            Duration::from_millis(100)
+        );
+        assert_eq!(
+            backoff.delay_for_round(2, &mut rng),
+            Duration::from_millis(400)
+        );
+        assert_eq!(backoff.delay_for_round(4, &mut rng), Duration::from_secs(1));
+        assert_eq!(
+            backoff.delay_for_round(100, &mut rng),
+            Duration::from_secs(1)
+        );
+
+        let jittered = RetryBackoff::Exponential {
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(1),
+            jitter: true,
+        };
+        for round in 0..10 {
+            let delay = jittered.delay_for_round(round, &mut rng);
+            let expected = backoff.delay_for_round(round, &mut rng);
+            assert!(delay <= expected);
+            assert!(delay >= expected / 2);
         }
     }
+
+    #[test]
+    pub fn test_gas_escalation() {
+        let gas_escalation = GasEscalation {
+            bump_factor: 1.5,
+            max_gas_unit_price: 300,
+        };
+        assert_eq!(gas_escalation.next_gas_unit_price(100), 150);
+        assert_eq!(gas_escalation.next_gas_unit_price(1), 2);
+        assert_eq!(gas_escalation.next_gas_unit_price(250), 300);
+        assert_eq!(gas_escalation.next_gas_unit_price(300), 300);
+        assert_eq!(gas_escalation.next_gas_unit_price(400), 400);
+    }
+
+    #[test]
+    pub fn test_wait_strategy() {
+        let wait_strategy = WaitStrategy {
+            initial_delay: Duration::from_millis(300),
+            poll_interval: Duration::from_millis(100),
+            poll_interval_growth: 2.0,
+            max_poll_interval: Duration::from_millis(500),
+        };
+        assert_eq!(
+            wait_strategy.delay_after_poll(0),
+            Duration::from_millis(100)
+        );
+        assert_eq!(
+            wait_strategy.delay_after_poll(2),
+            Duration::from_millis(400)
+        );
+        assert_eq!(
+            wait_strategy.delay_after_poll(3),
+            Duration::from_millis(500)
+        );
+        assert_eq!(
+            wait_strategy.delay_after_poll(1000),
+            Duration::from_millis(500)
+        );
+        assert_eq!(
+            WaitStrategy::default().delay_after_poll(5),
+            Duration::from_millis(500)
+        );
+    }
 }
@@ -0,0 +1,121 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::emitter::stats::{StatsAccumulator, TxnStats};
+use std::{fmt, sync::Arc, time::Duration};
+
+/// Worker whose count of a metric deviates from the mean across workers by more
+/// than the imbalance factor.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WorkerImbalance {
+    pub worker: String,
+    pub metric: &'static str,
+    pub value: u64,
+    pub mean: f64,
+}
+
+impl fmt::Display for WorkerImbalance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Worker {} {} {} transactions, while mean across workers is {:.1}",
+            self.worker, self.metric, self.value, self.mean
+        )
+    }
+}
+
+/// Stats of each worker, to detect ones stalling (or doing much more than others).
+#[derive(Debug)]
+pub struct WorkerStats {
+    imbalance_factor: f64,
+    workers: Vec<(String, Arc<StatsAccumulator>)>,
+}
+
+impl WorkerStats {
+    pub fn new(imbalance_factor: f64) -> Self {
+        assert!(
+            imbalance_factor > 1.0,
+            "Imbalance factor needs to be larger than 1"
+        );
+        Self {
+            imbalance_factor,
+            workers: Vec::new(),
+        }
+    }
+
+    /// Returns stats the new worker needs to record to.
+    pub fn add_worker(&mut self, name: String) -> Arc<StatsAccumulator> {
+        let stats = Arc::new(StatsAccumulator::default());
+        self.workers.push((name, stats.clone()));
+        stats
+    }
+
+    pub fn accumulate(&self, lasted: Duration) -> Vec<(String, TxnStats)> {
+        self.workers
+            .iter()
+            .map(|(name, stats)| (name.clone(), stats.accumulate(lasted)))
+            .collect()
+    }
+
+    /// Submitted and committed counts are checked in both directions, expired
+    /// counts only for workers expiring more than others.
+    pub fn imbalances(&self) -> Vec<WorkerImbalance> {
+        let stats = self.accumulate(Duration::ZERO);
+        let mut result = self.find_imbalances(&stats, "submitted", true, |s| s.submitted);
+        result.append(&mut self.find_imbalances(&stats, "committed", true, |s| s.committed));
+        result.append(&mut self.find_imbalances(&stats, "expired", false, |s| s.expired));
+        result
+    }
+
+    fn find_imbalances<F>(
+        &self,
+        stats: &[(String, TxnStats)],
+        metric: &'static str,
+        check_below: bool,
+        value_fn: F,
+    ) -> Vec<WorkerImbalance>
+    where
+        F: Fn(&TxnStats) -> u64,
+    {
+        let mean =
+            stats.iter().map(|(_, s)| value_fn(s)).sum::<u64>() as f64 / stats.len().max(1) as f64;
+        if mean < 1.0 {
+            return vec![];
+        }
+        stats
+            .iter()
+            .filter(|(_, s)| {
+                let value = value_fn(s) as f64;
+                value > mean * self.imbalance_factor
+                    || (check_below && value < mean / self.imbalance_factor)
+            })
+            .map(|(worker, s)| WorkerImbalance {
+                worker: worker.clone(),
+                metric,
+                value: value_fn(s),
+                mean,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::WorkerStats;
+    use std::sync::atomic::Ordering;
+
+    #[test]
+    pub fn test_worker_imbalances() {
+        let mut worker_stats = WorkerStats::new(2.0);
+        for (i, committed) in [100, 100, 100, 10].iter().enumerate() {
+            let stats = worker_stats.add_worker(format!("worker {}", i));
+            stats.submitted.fetch_add(100, Ordering::Relaxed);
+            stats.committed.fetch_add(*committed, Ordering::Relaxed);
+        }
+        let imbalances = worker_stats.imbalances();
+        assert_eq!(imbalances.len(), 1);
+        assert_eq!(imbalances[0].worker, "worker 3");
+        assert_eq!(imbalances[0].metric, "committed");
+        assert_eq!(imbalances[0].value, 10);
+    }
+}
@@ -1,6 +1,7 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::args::HttpClientArgs;
 use aptos_rest_client::Client as RestClient;
 use reqwest::Url;
 use std::fmt;
@@ -10,6 +11,7 @@ pub struct Instance {
     peer_name: String,
     url: Url,
     inspection_service_port: Option<u32>,
+    http_client_args: HttpClientArgs,
 }
 
 impl Instance {
@@ -18,9 +20,15 @@ impl Instance {
             peer_name,
             url,
             inspection_service_port,
+            http_client_args: HttpClientArgs::default(),
         }
     }
 
+    pub fn with_http_client_args(mut self, http_client_args: HttpClientArgs) -> Self {
+        self.http_client_args = http_client_args;
+        self
+    }
+
     pub fn peer_name(&self) -> &String {
         &self.peer_name
     }
@@ -34,7 +42,7 @@ impl Instance {
     }
 
     pub fn rest_client(&self) -> RestClient {
-        RestClient::new(self.api_url())
+        self.http_client_args.rest_client(self.api_url())
     }
 }
 
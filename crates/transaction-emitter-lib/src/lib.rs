@@ -7,15 +7,24 @@ mod args;
 mod cluster;
 pub mod emitter;
 mod instance;
+mod local_testnet;
+mod run_config;
 mod wrappers;
 
 // These are the top level things you should need to run the emitter.
-pub use args::{ClusterArgs, CoinSourceArgs, EmitArgs};
+pub use args::{
+    ClusterArgs, CoinSourceArgs, EmitArgs, HttpClientArgs, TargetHeader, TargetProxy,
+    TargetProxySetting,
+};
 // We export these if you want finer grained control.
 pub use cluster::Cluster;
 pub use emitter::{
+    job_builder::{EmitCallbacks, EmitJobBuilder, EmitJobHandle},
     query_sequence_number, query_sequence_numbers,
     stats::{TxnStats, TxnStatsRate},
-    EmitJob, EmitJobMode, EmitJobRequest, EmitModeParams, TxnEmitter,
+    stop_conditions::StopSignal,
+    EmitJob, EmitJobMode, EmitJobRequest, EmitModeParams, TpsSegment, TxnEmitter,
 };
+pub use local_testnet::LocalTestnet;
+pub use run_config::RunConfig;
 pub use wrappers::{emit_transactions, emit_transactions_with_cluster};
@@ -0,0 +1,171 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{cluster::Cluster, HttpClientArgs};
+use anyhow::{format_err, Context, Result};
+use aptos::common::types::EncodingType;
+use aptos_config::config::NodeConfig;
+use aptos_crypto::ed25519::Ed25519PrivateKey;
+use aptos_logger::{error, info};
+use aptos_rest_client::Client as RestClient;
+use aptos_sdk::types::chain_id::ChainId;
+use aptos_temppath::TempPath;
+use rand::{rngs::StdRng, SeedableRng};
+use std::{
+    convert::TryFrom,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant},
+};
+use url::Url;
+
+// genesis is built before the node starts, which takes a while
+const START_TIMEOUT: Duration = Duration::from_secs(120);
+const WAIT_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Local single-node testnet, started in-process (or already running from its
+/// directory), for smoke-testing generators and the emitter without an external
+/// network. Accounts are funded from the root account of the testnet.
+#[derive(Debug)]
+pub struct LocalTestnet {
+    test_dir: PathBuf,
+    rest_url: Url,
+    root_key: Ed25519PrivateKey,
+    // removed once the testnet is dropped, if the directory wasn't given
+    _temp_dir: Option<TempPath>,
+}
+
+impl LocalTestnet {
+    /// Starts a node in a background thread, from the test directory, or from a
+    /// temporary one. Config and genesis already in the directory are reused.
+    /// The node keeps running until the process exits.
+    pub async fn start(test_dir: Option<PathBuf>) -> Result<Self> {
+        let (test_dir, temp_dir) = match test_dir {
+            Some(test_dir) => (test_dir, None),
+            None => {
+                let temp_dir = TempPath::new();
+                (temp_dir.path().to_path_buf(), Some(temp_dir))
+            },
+        };
+        info!("Starting a local testnet in {:?}", test_dir);
+        let node_dir = test_dir.clone();
+        thread::spawn(move || {
+            let result = aptos_node::setup_test_environment_and_start_node(
+                None,
+                Some(node_dir),
+                true,
+                false,
+                &aptos_cached_packages::head_release_bundle(),
+                StdRng::from_entropy(),
+            );
+            error!("Local testnet node stopped unexpectedly: {:?}", result);
+        });
+
+        let start = Instant::now();
+        loop {
+            match Self::attach(&test_dir).await {
+                Ok(mut testnet) => {
+                    info!("Local testnet is up at {}", testnet.rest_url);
+                    testnet._temp_dir = temp_dir;
+                    return Ok(testnet);
+                },
+                Err(e) if start.elapsed() > START_TIMEOUT => {
+                    return Err(e.context("Local testnet failed to start"));
+                },
+                Err(_) => tokio::time::sleep(WAIT_INTERVAL).await,
+            }
+        }
+    }
+
+    /// Attaches to the node already running from the test directory, e.g. one
+    /// started with `aptos node run-local-testnet --test-dir`.
+    pub async fn attach(test_dir: &Path) -> Result<Self> {
+        let config = NodeConfig::load_from_path(test_dir.join("0").join("node.yaml"))
+            .map_err(|e| format_err!("Failed to load the node config: {:?}", e))?;
+        let rest_url = Url::parse(&format!("http://127.0.0.1:{}", config.api.address.port()))?;
+        RestClient::new(rest_url.clone())
+            .get_ledger_information()
+            .await
+            .with_context(|| format!("Local testnet at {} is not reachable", rest_url))?;
+        let root_key = EncodingType::BCS
+            .load_key::<Ed25519PrivateKey>("mint key pair", &test_dir.join("mint.key"))?;
+        Ok(Self {
+            test_dir: test_dir.to_path_buf(),
+            rest_url,
+            root_key,
+            _temp_dir: None,
+        })
+    }
+
+    /// Attaches to the node running from the test directory if there is one, and
+    /// starts one otherwise.
+    pub async fn start_or_attach(test_dir: Option<PathBuf>) -> Result<Self> {
+        if let Some(test_dir) = &test_dir {
+            if let Ok(testnet) = Self::attach(test_dir).await {
+                info!("Attached to the local testnet at {}", testnet.rest_url);
+                return Ok(testnet);
+            }
+        }
+        Self::start(test_dir).await
+    }
+
+    pub fn test_dir(&self) -> &Path {
+        &self.test_dir
+    }
+
+    pub fn rest_url(&self) -> &Url {
+        &self.rest_url
+    }
+
+    /// Cluster of the node, with the root account as the coin source.
+    pub async fn cluster(&self, http_client_args: &HttpClientArgs) -> Result<Cluster> {
+        let root_key = Ed25519PrivateKey::try_from(self.root_key.to_bytes().as_slice())?;
+        Cluster::from_host_port(
+            vec![self.rest_url.clone()],
+            root_key,
+            true,
+            ChainId::test(),
+            http_client_args,
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::LocalTestnet;
+    use crate::{
+        emitter::{job_builder::EmitJobBuilder, EmitJobMode},
+        instance::Instance,
+        HttpClientArgs,
+    };
+    use aptos_sdk::transaction_builder::TransactionFactory;
+    use std::time::Duration;
+
+    #[tokio::test(flavor = "multi_thread")]
+    pub async fn test_emit_to_local_testnet() {
+        let testnet = LocalTestnet::start(None).await.unwrap();
+        let cluster = testnet.cluster(&HttpClientArgs::default()).await.unwrap();
+        let source_account = cluster
+            .load_coin_source_account(&cluster.random_instance().rest_client())
+            .await
+            .unwrap();
+        let txn_factory = TransactionFactory::new(cluster.chain_id)
+            .with_gas_unit_price(aptos_global_constants::GAS_UNIT_PRICE);
+        let (_, stats) = EmitJobBuilder::new(
+            txn_factory,
+            cluster.all_instances().map(Instance::rest_client).collect(),
+        )
+        .request(|request| {
+            request.mode(EmitJobMode::MaxLoad {
+                mempool_backlog: 100,
+            })
+        })
+        .duration(Duration::from_secs(10))
+        .start(source_account)
+        .join()
+        .await
+        .unwrap();
+        assert!(stats[0].committed > 0);
+    }
+}
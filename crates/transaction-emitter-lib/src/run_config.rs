@@ -0,0 +1,135 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::args::{ClusterArgs, EmitArgs};
+use anyhow::{bail, ensure, format_err, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
+use std::{fs, path::Path};
+
+/// Whole emitter run (endpoints, workload mix, TPS, accounts, gas), as described in a
+/// config file, so that complex runs can be reproduced and reviewed. Fields are the
+/// same as the ones of the emit-tx command, in snake case.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct RunConfig {
+    pub cluster_args: ClusterArgs,
+    pub emit_args: EmitArgs,
+}
+
+impl RunConfig {
+    /// Loads config from a YAML file, or a TOML one if path has `.toml` extension,
+    /// with overrides (see `parse`) applied on top.
+    pub fn load(path: &Path, overrides: &[String]) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read run config {}", path.display()))?;
+        let is_toml = path.extension().map_or(false, |ext| ext == "toml");
+        Self::parse(&contents, is_toml, overrides)
+            .with_context(|| format!("Invalid run config {}", path.display()))
+    }
+
+    /// Each override is `<dot separated path>=<value>`, e.g. `emit_args.target_tps=1000`,
+    /// with value parsed as YAML, replacing the one from the config.
+    pub fn parse(contents: &str, is_toml: bool, overrides: &[String]) -> Result<Self> {
+        let mut value: Value = if is_toml {
+            serde_yaml::to_value(toml::from_str::<toml::Value>(contents)?)?
+        } else {
+            serde_yaml::from_str(contents)?
+        };
+        for config_override in overrides {
+            apply_override(&mut value, config_override)?;
+        }
+        let config: Self = serde_yaml::from_value(value)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Checks the requirements that clap checks for the emit-tx command.
+    fn validate(&self) -> Result<()> {
+        ensure!(
+            self.cluster_args.targets.is_some() || self.cluster_args.targets_file.is_some(),
+            "Either targets or targets_file needs to be set"
+        );
+        let emit_args = &self.emit_args;
+        let num_modes = [
+            emit_args.mempool_backlog.is_some(),
+            emit_args.target_tps.is_some(),
+            !emit_args.tps_schedule.is_empty(),
+        ]
+        .iter()
+        .filter(|set| **set)
+        .count();
+        ensure!(
+            num_modes == 1,
+            "Exactly one of mempool_backlog, target_tps or tps_schedule needs to be set"
+        );
+        Ok(())
+    }
+}
+
+fn apply_override(value: &mut Value, config_override: &str) -> Result<()> {
+    let (path, override_value) = config_override
+        .split_once('=')
+        .ok_or_else(|| format_err!("Expected <path>=<value>, got {}", config_override))?;
+    let mut cur = value;
+    for key in path.split('.') {
+        if cur.is_null() {
+            *cur = Value::Mapping(Mapping::new());
+        }
+        cur = match cur {
+            Value::Mapping(mapping) => {
+                let key = Value::String(key.to_string());
+                if !mapping.contains_key(&key) {
+                    mapping.insert(key.clone(), Value::Null);
+                }
+                mapping.get_mut(&key).unwrap()
+            },
+            _ => bail!("Cannot override {}, {} is not a mapping", path, key),
+        };
+    }
+    *cur = serde_yaml::from_str(override_value)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::RunConfig;
+    use std::time::Duration;
+
+    #[test]
+    pub fn test_run_config() {
+        let yaml = r#"
+cluster_args:
+  targets: ["http://localhost:8080"]
+  chain_id: TESTING
+emit_args:
+  tps_schedule: ["60:100", "30:1000"]
+  duration: 90
+  txn_expiration_time_secs: 30
+  transaction_type: [CoinTransfer]
+  transaction_weights: []
+  transaction_phases: []
+"#;
+        let config = RunConfig::parse(yaml, false, &[]).unwrap();
+        assert_eq!(config.emit_args.tps_schedule.len(), 2);
+        assert_eq!(
+            config.emit_args.tps_schedule[1].duration,
+            Duration::from_secs(30)
+        );
+        assert_eq!(config.emit_args.burst_interval_secs, 60);
+
+        let config = RunConfig::parse(yaml, false, &[
+            "emit_args.gas_price=200".to_string(),
+            "cluster_args.http_client_args.pool_max_idle_per_host=4".to_string(),
+        ])
+        .unwrap();
+        assert_eq!(config.emit_args.gas_price, Some(200));
+        assert_eq!(
+            config.cluster_args.http_client_args.pool_max_idle_per_host,
+            Some(4)
+        );
+
+        // two modes set
+        assert!(RunConfig::parse(yaml, false, &["emit_args.target_tps=100".to_string()]).is_err());
+    }
+}
@@ -2,28 +2,60 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    args::{ClusterArgs, EmitArgs},
+    args::{target_matches, ClusterArgs, EmitArgs},
     cluster::Cluster,
-    emitter::{stats::TxnStats, EmitJobMode, EmitJobRequest, TxnEmitter},
+    emitter::{
+        account_pool_file::AccountPoolFile,
+        backlog_throttle::BacklogTarget,
+        burst_schedule::BurstSchedule,
+        endpoint_groups::EndpointGroups,
+        faucet_funding::FaucetFunding,
+        fault_injection::FaultInjection,
+        funding_tree::FundingTree,
+        gas_price_refresh::GasPriceRefresh,
+        health_check::HealthCheck,
+        in_flight_limit::InFlightLimits,
+        latency_controller::LatencyTarget,
+        measurement_window::MeasurementWindow,
+        phase_plan::{PlannedPhase, SuccessCriteria},
+        pushgateway::PushgatewayConfig,
+        regression_check::RegressionCheck,
+        result_report::ResultReport,
+        signature_scheme::SignatureScheme,
+        stats::TxnStats,
+        stop_conditions::{StopConditions, StopSignal},
+        EmitJobMode, EmitJobRequest, TxnEmitter,
+    },
     instance::Instance,
 };
-use anyhow::{bail, Context, Result};
+use anyhow::{bail, ensure, format_err, Context, Result};
 use aptos_logger::{error, info};
 use aptos_sdk::transaction_builder::TransactionFactory;
-use aptos_transaction_generator_lib::TransactionType;
-use rand::{rngs::StdRng, SeedableRng};
-use std::time::{Duration, Instant};
+use aptos_transaction_generator_lib::entry_function_generator::EntryFunctionTemplate;
+use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
+use std::{
+    path::Path,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
 
+/// Runs the emitter against the cluster, stopping early on signals of stop_signal, if
+/// given.
 pub async fn emit_transactions(
     cluster_args: &ClusterArgs,
     emit_args: &EmitArgs,
+    stop_signal: Option<StopSignal>,
 ) -> Result<TxnStats> {
     if emit_args.coordination_delay_between_instances.is_none() {
         let cluster = Cluster::try_from_cluster_args(cluster_args)
             .await
             .context("Failed to build cluster")?;
-        return emit_transactions_with_cluster(&cluster, emit_args, cluster_args.reuse_accounts)
-            .await;
+        return emit_transactions_with_cluster(
+            &cluster,
+            emit_args,
+            cluster_args.reuse_accounts,
+            stop_signal,
+        )
+        .await;
     } else {
         let initial_delay_after_minting = emit_args.coordination_delay_between_instances.unwrap();
         let start_time = Instant::now();
@@ -54,6 +86,7 @@ pub async fn emit_transactions(
                 &cluster,
                 &cur_emit_args,
                 cluster_args.reuse_accounts,
+                stop_signal.clone(),
             )
             .await;
             match result {
@@ -67,74 +100,129 @@ pub async fn emit_transactions(
     }
 }
 
+fn endpoint_groups(cluster: &Cluster, args: &EmitArgs) -> Result<EndpointGroups> {
+    let mut group_of_target = vec![];
+    for endpoint_group in &args.endpoint_group {
+        let (target, group) = endpoint_group
+            .split_once('=')
+            .filter(|(target, group)| !target.is_empty() && !group.is_empty())
+            .with_context(|| format!("Expected <target>=<group>, got {}", endpoint_group))?;
+        group_of_target.push((target, group));
+    }
+    let groups = cluster
+        .all_instances()
+        .map(|instance| {
+            group_of_target
+                .iter()
+                .rev()
+                .find(|(target, _)| target_matches(target, &instance.api_url()))
+                .map_or("default", |(_, group)| *group)
+                .to_string()
+        })
+        .collect();
+    let mut sender_shares = vec![];
+    for sender_share in &args.group_sender_share {
+        let (group, share) = sender_share
+            .split_once('=')
+            .with_context(|| format!("Expected <group>=<share>, got {}", sender_share))?;
+        sender_shares.push((group.to_string(), share.parse()?));
+    }
+    EndpointGroups::new(groups, sender_shares)
+}
+
+fn in_flight_limits(args: &EmitArgs) -> Result<InFlightLimits> {
+    let mut per_endpoint = vec![];
+    for endpoint_max in &args.endpoint_max_in_flight {
+        let (target, max) = endpoint_max
+            .split_once('=')
+            .filter(|(target, _)| !target.is_empty())
+            .with_context(|| format!("Expected <target>=<max>, got {}", endpoint_max))?;
+        per_endpoint.push((target.to_string(), max.parse()?));
+    }
+    let limits = InFlightLimits {
+        default: args.max_in_flight_per_endpoint,
+        per_endpoint,
+    };
+    ensure!(
+        limits.default != Some(0) && limits.per_endpoint.iter().all(|(_, max)| *max > 0),
+        "Max transactions in flight needs to be non zero"
+    );
+    Ok(limits)
+}
+
 pub async fn emit_transactions_with_cluster(
     cluster: &Cluster,
     args: &EmitArgs,
     reuse_accounts: bool,
+    stop_signal: Option<StopSignal>,
 ) -> Result<TxnStats> {
-    let emitter_mode = EmitJobMode::create(args.mempool_backlog, args.target_tps);
+    let emitter_mode = if !args.tps_schedule.is_empty() {
+        EmitJobMode::TpsSchedule {
+            segments: args.tps_schedule.clone(),
+        }
+    } else if let Some(burst_tps) = args.burst_tps {
+        EmitJobMode::BurstTps {
+            schedule: BurstSchedule::new(
+                args.target_tps
+                    .expect("Need to set target_tps as the baseline for bursts"),
+                burst_tps,
+                Duration::from_secs(args.burst_duration_secs),
+                Duration::from_secs(args.burst_interval_secs),
+            ),
+        }
+    } else if let Some(target_p99_latency_ms) = args.target_p99_latency_ms {
+        EmitJobMode::LatencyTargeted {
+            target: LatencyTarget {
+                target_p99_latency: Duration::from_millis(target_p99_latency_ms),
+                initial_tps: args
+                    .target_tps
+                    .expect("Need to set target_tps as the initial TPS when targeting latency"),
+                max_tps: args
+                    .max_tps
+                    .expect("Need to set max_tps when targeting latency"),
+                adjust_interval: Duration::from_secs(args.latency_adjust_interval_secs),
+            },
+        }
+    } else {
+        EmitJobMode::create(args.mempool_backlog, args.target_tps)
+    };
 
     let duration = Duration::from_secs(args.duration);
     let client = cluster.random_instance().rest_client();
     let mut coin_source_account = cluster.load_coin_source_account(&client).await?;
+    let seed = args.seed.unwrap_or_else(|| thread_rng().gen());
+    info!(
+        "Emitting with seed {} (can be passed in to reproduce the run)",
+        seed
+    );
     let emitter = TxnEmitter::new(
         TransactionFactory::new(cluster.chain_id)
             .with_transaction_expiration_time(args.txn_expiration_time_secs)
             .with_gas_unit_price(aptos_global_constants::GAS_UNIT_PRICE),
-        StdRng::from_entropy(),
+        StdRng::seed_from_u64(seed),
     );
 
-    let arg_transaction_types = args
-        .transaction_type
+    let workload_mix = args.workload_mix()?;
+    ensure!(
+        workload_mix.num_entry_functions() == args.entry_function.len(),
+        "Each entry-function transaction type needs an entry function, got {} types and {} functions",
+        workload_mix.num_entry_functions(),
+        args.entry_function.len()
+    );
+    let entry_functions = args
+        .entry_function
         .iter()
-        .map(|t| t.materialize())
-        .collect::<Vec<_>>();
-
-    let arg_transaction_weights = if args.transaction_weights.is_empty() {
-        vec![1; arg_transaction_types.len()]
-    } else {
-        assert_eq!(
-            args.transaction_weights.len(),
-            arg_transaction_types.len(),
-            "Transaction types and weights need to be the same length"
-        );
-        args.transaction_weights.clone()
-    };
-    let arg_transaction_phases = if args.transaction_phases.is_empty() {
-        vec![0; arg_transaction_types.len()]
-    } else {
-        assert_eq!(
-            args.transaction_phases.len(),
-            arg_transaction_types.len(),
-            "Transaction types and phases need to be the same length"
-        );
-        args.transaction_phases.clone()
-    };
-
-    let mut transaction_mix_per_phase: Vec<Vec<(TransactionType, usize)>> = Vec::new();
-    for (transaction_type, (weight, phase)) in arg_transaction_types.into_iter().zip(
-        arg_transaction_weights
-            .into_iter()
-            .zip(arg_transaction_phases.into_iter()),
-    ) {
-        assert!(
-            phase <= transaction_mix_per_phase.len(),
-            "cannot skip phases ({})",
-            transaction_mix_per_phase.len()
-        );
-        if phase == transaction_mix_per_phase.len() {
-            transaction_mix_per_phase.push(Vec::new());
-        }
-        transaction_mix_per_phase
-            .get_mut(phase)
-            .unwrap()
-            .push((transaction_type, weight));
-    }
+        .map(EntryFunctionTemplate::parse)
+        .collect::<Result<Vec<_>>>()?;
+    let transaction_mix_per_phase = workload_mix.transaction_mix_per_phase()?;
+    let workload_txn_factory_overrides = workload_mix.txn_factory_overrides_per_phase()?;
+    let workload_generator_phases = workload_mix.generator_phases_per_phase()?;
 
     let mut emit_job_request =
         EmitJobRequest::new(cluster.all_instances().map(Instance::rest_client).collect())
             .mode(emitter_mode)
             .transaction_mix_per_phase(transaction_mix_per_phase)
+            .entry_functions(entry_functions)
             .txn_expiration_time_secs(args.txn_expiration_time_secs)
             .coordination_delay_between_instances(Duration::from_secs(
                 args.coordination_delay_between_instances.unwrap_or(0),
@@ -159,6 +247,68 @@ pub async fn emit_transactions_with_cluster(
         emit_job_request = emit_job_request.init_gas_price_multiplier(init_gas_price_multiplier);
     }
 
+    if args.funding_fan_out.is_some() || !args.funding_level_amounts.is_empty() {
+        let funding_tree = FundingTree {
+            fan_out: args.funding_fan_out,
+            depth: args.funding_depth.unwrap_or(1),
+            level_amounts: args.funding_level_amounts.clone(),
+        };
+        funding_tree.validate()?;
+        emit_job_request = emit_job_request.funding_tree(funding_tree);
+    }
+
+    if args.derive_accounts_from_source {
+        emit_job_request = emit_job_request.derive_accounts_from_source();
+    }
+    if let Some(account_pool_seed) = args.account_pool_seed {
+        emit_job_request = emit_job_request.account_pool_seed(account_pool_seed);
+    }
+    if args.recycle_accounts {
+        emit_job_request = emit_job_request.recycle_accounts();
+    }
+
+    if let Some(faucet_url) = &args.faucet_url {
+        let mut faucet_funding = FaucetFunding::new(faucet_url.clone());
+        if let Some(parallelism) = args.faucet_parallelism {
+            ensure!(parallelism > 0, "faucet_parallelism needs to be non zero");
+            faucet_funding.parallelism = parallelism;
+        }
+        if let Some(max_attempts) = args.faucet_max_attempts {
+            ensure!(max_attempts > 0, "faucet_max_attempts needs to be non zero");
+            faucet_funding.max_attempts = max_attempts;
+        }
+        faucet_funding.max_amount_per_request = args.faucet_max_amount_per_request;
+        emit_job_request = emit_job_request.faucet_funding(faucet_funding);
+    }
+
+    if let Some(gas_price_refresh) = &args.gas_price_refresh {
+        emit_job_request =
+            emit_job_request.gas_price_refresh(GasPriceRefresh::parse(gas_price_refresh)?);
+    }
+    if let Some(refresh_interval_secs) = args.targets_refresh_interval_secs {
+        let endpoint_discovery = cluster.endpoint_discovery.clone().ok_or_else(|| {
+            format_err!("targets_refresh_interval_secs needs targets to be discovered")
+        })?;
+        ensure!(
+            refresh_interval_secs > 0,
+            "targets_refresh_interval_secs needs to be non zero"
+        );
+        emit_job_request = emit_job_request.endpoint_refresh(
+            endpoint_discovery,
+            Duration::from_secs(refresh_interval_secs),
+        );
+    }
+    if args.pre_run_health_check {
+        let mut health_check = HealthCheck {
+            required_version: args.required_version,
+            ..HealthCheck::default()
+        };
+        if let Some(max_ledger_age_secs) = args.max_ledger_age_secs {
+            health_check.max_ledger_age = Duration::from_secs(max_ledger_age_secs);
+        }
+        emit_job_request = emit_job_request.health_check(health_check);
+    }
+
     if let Some(expected_max_txns) = args.expected_max_txns {
         emit_job_request = emit_job_request.expected_max_txns(expected_max_txns);
     }
@@ -168,6 +318,154 @@ pub async fn emit_transactions_with_cluster(
     if !cluster.coin_source_is_root {
         emit_job_request = emit_job_request.prompt_before_spending();
     }
+    if let Some(run_state_file) = &args.run_state_file {
+        emit_job_request = emit_job_request.run_state_file(run_state_file.into());
+    }
+    if !args.phase_duration_secs.is_empty() {
+        let mut phase_plan: Vec<_> = args
+            .phase_duration_secs
+            .iter()
+            .map(|duration_secs| PlannedPhase {
+                duration: Duration::from_secs(*duration_secs),
+                success_criteria: None,
+            })
+            .collect();
+        for phase_criteria in &args.phase_success_criteria {
+            let (phase, criteria) = phase_criteria
+                .split_once(':')
+                .with_context(|| format!("Expected <phase>:<criteria>, got {}", phase_criteria))?;
+            let phase: usize = phase.parse()?;
+            phase_plan
+                .get_mut(phase)
+                .with_context(|| format!("Success criteria for unplanned phase {}", phase))?
+                .success_criteria = Some(SuccessCriteria::parse(criteria)?);
+        }
+        emit_job_request = emit_job_request.phase_plan(phase_plan);
+    }
+    if let Some(stats_csv_file) = &args.stats_csv_file {
+        ensure!(
+            args.stats_csv_interval_secs > 0,
+            "stats_csv_interval_secs needs to be positive"
+        );
+        emit_job_request = emit_job_request.stats_csv(
+            stats_csv_file.into(),
+            Duration::from_secs(args.stats_csv_interval_secs),
+        );
+    }
+    if let Some(pushgateway_url) = &args.pushgateway_url {
+        let run_id = args.pushgateway_run_id.clone().unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs()
+                .to_string()
+        });
+        emit_job_request = emit_job_request.pushgateway(PushgatewayConfig::new(
+            pushgateway_url.clone(),
+            run_id,
+            Duration::from_secs(args.pushgateway_interval_secs),
+        )?);
+    }
+    emit_job_request = emit_job_request.stop_conditions(StopConditions {
+        max_txns: args.stop_after_txns,
+        max_coins_spent: args.stop_after_coins_spent,
+        max_failure_ratio: args.stop_on_failure_ratio,
+    });
+    if let Some(stop_signal) = stop_signal {
+        emit_job_request = emit_job_request.stop_signal(stop_signal);
+    }
+    emit_job_request = emit_job_request.drain_timeout(Duration::from_secs(args.drain_timeout_secs));
+    if args.audit_sequence_numbers {
+        emit_job_request = emit_job_request.audit_sequence_numbers();
+    }
+    if args.warm_up_secs.is_some() || args.cool_down_secs.is_some() {
+        emit_job_request = emit_job_request.measurement_window(MeasurementWindow {
+            warm_up: Duration::from_secs(args.warm_up_secs.unwrap_or(0)),
+            cool_down: Duration::from_secs(args.cool_down_secs.unwrap_or(0)),
+        });
+    }
+    if let Some(init_fault_injection) = &args.init_fault_injection {
+        emit_job_request =
+            emit_job_request.init_fault_injection(FaultInjection::parse(init_fault_injection)?);
+    }
+    if let Some(result_report_file) = &args.result_report_file {
+        emit_job_request = emit_job_request.result_report_file(result_report_file.into());
+    }
+    if let Some(baseline_report_file) = &args.baseline_report_file {
+        ensure!(
+            args.max_tps_drop_pct.is_some() || args.max_p99_latency_increase_pct.is_some(),
+            "Comparing to a baseline needs max_tps_drop_pct or max_p99_latency_increase_pct"
+        );
+        emit_job_request = emit_job_request.regression_check(RegressionCheck {
+            baseline: ResultReport::load(Path::new(baseline_report_file))?,
+            max_tps_drop_pct: args.max_tps_drop_pct,
+            max_p99_latency_increase_pct: args.max_p99_latency_increase_pct,
+        });
+    }
+    if workload_mix.has_txn_factory_overrides() {
+        emit_job_request =
+            emit_job_request.workload_txn_factory_overrides(workload_txn_factory_overrides);
+    }
+    if workload_generator_phases.iter().any(Option::is_some) {
+        emit_job_request = emit_job_request.workload_generator_phases(workload_generator_phases);
+    }
+    if args.max_in_flight_per_endpoint.is_some() || !args.endpoint_max_in_flight.is_empty() {
+        emit_job_request = emit_job_request.in_flight_limits(in_flight_limits(args)?);
+    }
+    if let Some(max_backlog) = args.max_backlog {
+        let sample_interval_secs = args.backlog_sample_interval_secs.unwrap_or(5);
+        ensure!(
+            sample_interval_secs > 0,
+            "backlog_sample_interval_secs needs to be non zero"
+        );
+        emit_job_request = emit_job_request.backlog_throttle(BacklogTarget {
+            max_backlog,
+            sample_interval: Duration::from_secs(sample_interval_secs),
+        });
+    }
+    if !args.endpoint_group.is_empty() {
+        emit_job_request = emit_job_request.endpoint_groups(endpoint_groups(cluster, args)?);
+    }
+    if let Some(worker_imbalance_factor) = args.worker_imbalance_factor {
+        ensure!(
+            worker_imbalance_factor > 1.0,
+            "worker_imbalance_factor needs to be larger than 1"
+        );
+        emit_job_request = emit_job_request.worker_imbalance_factor(worker_imbalance_factor);
+    }
+    if let Some(account_pool_file) = &args.account_pool_file {
+        emit_job_request = emit_job_request.account_pool_file(AccountPoolFile::new(
+            account_pool_file.into(),
+            args.account_pool_password
+                .clone()
+                .context("account_pool_password needs to be set with account_pool_file")?,
+        ));
+    }
+    if let Some(account_signature_scheme) = &args.account_signature_scheme {
+        emit_job_request = emit_job_request
+            .account_signature_scheme(account_signature_scheme.parse::<SignatureScheme>()?);
+    }
+    if !args.tps_schedule.is_empty() || !args.phase_duration_secs.is_empty() {
+        let stats_per_phase = emitter
+            .emit_txn_for_with_phase_stats(
+                &mut coin_source_account,
+                emit_job_request,
+                duration,
+                (args.duration / 10).clamp(1, 10),
+            )
+            .await?;
+        for (segment, stats) in args.tps_schedule.iter().zip(stats_per_phase.iter()) {
+            info!(
+                "Segment of {}s at {} TPS: {}",
+                segment.duration.as_secs(),
+                segment.tps,
+                stats.rate()
+            );
+        }
+        return Ok(stats_per_phase
+            .iter()
+            .fold(TxnStats::default(), |total, stats| &total + stats));
+    }
     let stats = emitter
         .emit_txn_for_with_stats(
             &mut coin_source_account,
@@ -6,9 +6,13 @@ mod diag;
 
 use anyhow::{Context, Result};
 use aptos_logger::{Level, Logger};
-use aptos_transaction_emitter_lib::{emit_transactions, Cluster, ClusterArgs, EmitArgs};
+use aptos_push_metrics::MetricsPusher;
+use aptos_transaction_emitter_lib::{
+    emit_transactions, Cluster, ClusterArgs, EmitArgs, RunConfig, StopSignal, TxnStats,
+};
 use clap::{Parser, Subcommand};
 use diag::diag;
+use std::path::PathBuf;
 
 #[derive(Parser, Debug)]
 struct Args {
@@ -23,6 +27,9 @@ enum TxnEmitterCommand {
     /// recording stats as we go.
     EmitTx(EmitTx),
 
+    /// Same as emit-tx, with the cluster and emit args loaded from a run config file.
+    EmitTxFromConfig(EmitTxFromConfig),
+
     /// This runs the transaction emitter in diag mode, where the focus is on
     /// FullNodes instead of ValidatorNodes. This performs a simple health check.
     Diag(Diag),
@@ -41,6 +48,18 @@ struct EmitTx {
     emit_args: EmitArgs,
 }
 
+#[derive(Parser, Debug)]
+struct EmitTxFromConfig {
+    /// YAML (or TOML, with `.toml` extension) file, with `cluster_args` and `emit_args`
+    /// sections, having the same fields as the emit-tx args.
+    #[clap(long)]
+    config: PathBuf,
+
+    /// Overrides of config values, as `<path>=<value>`, e.g. `--set emit_args.target_tps=1000`
+    #[clap(long = "set")]
+    overrides: Vec<String>,
+}
+
 #[derive(Parser, Debug)]
 struct PingEndPoints {
     #[clap(flatten)]
@@ -56,18 +75,33 @@ struct Diag {
 #[tokio::main]
 pub async fn main() -> Result<()> {
     Logger::builder().level(Level::Info).build();
+    // pushes metrics only if PUSH_METRICS_ENDPOINT is set
+    let _mp = MetricsPusher::start(vec![]);
 
     let args = Args::parse();
 
     // TODO: Check if I need DisplayChain here in the error case.
     match args.command {
         TxnEmitterCommand::EmitTx(args) => {
-            let stats = emit_transactions(&args.cluster_args, &args.emit_args)
-                .await
-                .map_err(|e| panic!("Emit transactions failed {:?}", e))
-                .unwrap();
-            println!("Total stats: {}", stats);
-            println!("Average rate: {}", stats.rate());
+            let stats =
+                emit_transactions(&args.cluster_args, &args.emit_args, Some(stop_on_ctrl_c()))
+                    .await
+                    .map_err(|e| panic!("Emit transactions failed {:?}", e))
+                    .unwrap();
+            print_stats(&stats);
+            Ok(())
+        },
+        TxnEmitterCommand::EmitTxFromConfig(args) => {
+            let config = RunConfig::load(&args.config, &args.overrides)?;
+            let stats = emit_transactions(
+                &config.cluster_args,
+                &config.emit_args,
+                Some(stop_on_ctrl_c()),
+            )
+            .await
+            .map_err(|e| panic!("Emit transactions failed {:?}", e))
+            .unwrap();
+            print_stats(&stats);
             Ok(())
         },
         TxnEmitterCommand::Diag(args) => {
@@ -85,3 +119,31 @@ pub async fn main() -> Result<()> {
         },
     }
 }
+
+/// Signals the run to stop on ctrl-c, instead of the process being killed by it, so that
+/// it still reports its stats. The second ctrl-c cuts short waiting for in-flight
+/// transactions, and the third one exits without waiting for the run at all.
+fn stop_on_ctrl_c() -> StopSignal {
+    let stop_signal = StopSignal::default();
+    tokio::spawn({
+        let stop_signal = stop_signal.clone();
+        async move {
+            for _ in 0..2 {
+                if tokio::signal::ctrl_c().await.is_err() {
+                    return;
+                }
+                stop_signal.signal();
+            }
+            if tokio::signal::ctrl_c().await.is_ok() {
+                eprintln!("Received another stop signal, exiting without waiting");
+                std::process::exit(130);
+            }
+        }
+    });
+    stop_signal
+}
+
+fn print_stats(stats: &TxnStats) {
+    println!("Total stats: {}", stats);
+    println!("Average rate: {}", stats.rate());
+}
@@ -43,6 +43,10 @@ impl TransactionGenerator for AccountsPoolWrapperGenerator {
         self.creator
             .generate_transactions(accounts_to_burn.iter_mut().collect(), 1)
     }
+
+    fn last_generated_label(&self) -> Option<&str> {
+        self.creator.last_generated_label()
+    }
 }
 
 pub struct AccountsPoolWrapperCreator {
@@ -1,7 +1,7 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{EntryPoints, TransactionType};
+use crate::{hot_spot::HotSpot, script_generator::ScriptKind, EntryPoints, TransactionType};
 use clap::{ArgEnum, Parser};
 use serde::{Deserialize, Serialize};
 
@@ -10,17 +10,53 @@ use serde::{Deserialize, Serialize};
 pub enum TransactionTypeArg {
     CoinTransfer,
     CoinTransferWithInvalid,
+    /// Coin transfers, half of them to the same few receivers
+    CoinTransferHotSpot,
     AccountGeneration,
     AccountGenerationLargePool,
+    BatchTransfer,
+    BatchTransferLarge,
     NftMintAndTransfer,
+    MultiAgentTokenTransfer,
+    MultisigTransactions,
+    TokenV2MintTransferBurn,
+    TokenV2MintTransferBurnManyCollections,
+    /// Fungible asset transfers between primary stores
+    FungibleAssetTransfer,
+    ObjectResourceGroup,
+    ObjectResourceGroupLarge,
+    StakePoolOperations,
+    DelegationPoolOperations,
+    GovernanceVoting,
+    /// Table inserts, updates and reads, 10 of each per transaction
+    TableOperations,
+    KeyRotation,
+    /// Transactions writing 10 resources of 1KB each
+    LargeWriteSet,
+    /// Transactions writing 32 resources of 8KB each
+    LargeWriteSetMany,
     PublishPackage,
+    PublishAndUpgrade,
+    /// Calls of an entry function given with --entry-function, the n-th entry-function
+    /// transaction type calling the n-th one
+    EntryFunction,
+    ScriptNoop,
+    ScriptTransfer,
+    EmitEvents,
+    EmitEventsLarge,
     CustomFunctionLargeModuleWorkingSet,
     CreateNewResource,
     ModifyGlobalResource,
     ModifyTenGlobalResources,
     NoOp,
+    /// Calls of a published function looping COMPUTE_LOOP_COUNT times, for execution
+    /// dominated workloads
+    ComputeLoop,
 }
 
+/// Iterations of each compute-loop call, unless given with --loop-count
+pub const COMPUTE_LOOP_COUNT: u64 = 10_000;
+
 impl Default for TransactionTypeArg {
     fn default() -> Self {
         TransactionTypeArg::CoinTransfer
@@ -33,10 +69,17 @@ impl TransactionTypeArg {
             TransactionTypeArg::CoinTransfer => TransactionType::CoinTransfer {
                 invalid_transaction_ratio: 0,
                 sender_use_account_pool: false,
+                hot_spot: HotSpot::none(),
             },
             TransactionTypeArg::CoinTransferWithInvalid => TransactionType::CoinTransfer {
                 invalid_transaction_ratio: 10,
                 sender_use_account_pool: false,
+                hot_spot: HotSpot::none(),
+            },
+            TransactionTypeArg::CoinTransferHotSpot => TransactionType::CoinTransfer {
+                invalid_transaction_ratio: 0,
+                sender_use_account_pool: false,
+                hot_spot: HotSpot::new(50),
             },
             TransactionTypeArg::AccountGeneration => TransactionType::default_account_generation(),
             TransactionTypeArg::AccountGenerationLargePool => TransactionType::AccountGeneration {
@@ -44,10 +87,79 @@ impl TransactionTypeArg {
                 max_account_working_set: 50_000_000,
                 creation_balance: 200_000_000,
             },
+            TransactionTypeArg::BatchTransfer => TransactionType::BatchTransfer {
+                num_recipients: 10,
+                hot_spot: HotSpot::none(),
+            },
+            TransactionTypeArg::BatchTransferLarge => TransactionType::BatchTransfer {
+                num_recipients: 100,
+                hot_spot: HotSpot::none(),
+            },
             TransactionTypeArg::NftMintAndTransfer => TransactionType::NftMintAndTransfer,
+            TransactionTypeArg::MultiAgentTokenTransfer => TransactionType::MultiAgentTokenTransfer,
+            TransactionTypeArg::MultisigTransactions => TransactionType::MultisigTransactions {
+                num_owners: 3,
+                num_signatures_required: 2,
+            },
+            TransactionTypeArg::TokenV2MintTransferBurn => {
+                TransactionType::TokenV2MintTransferBurn {
+                    num_collections: 1,
+                    tokens_per_collection: 100,
+                }
+            },
+            TransactionTypeArg::TokenV2MintTransferBurnManyCollections => {
+                TransactionType::TokenV2MintTransferBurn {
+                    num_collections: 20,
+                    tokens_per_collection: 10,
+                }
+            },
+            TransactionTypeArg::FungibleAssetTransfer => TransactionType::FungibleAssetTransfer,
+            TransactionTypeArg::ObjectResourceGroup => TransactionType::ObjectResourceGroup {
+                num_properties: 4,
+                property_size: 32,
+            },
+            TransactionTypeArg::ObjectResourceGroupLarge => TransactionType::ObjectResourceGroup {
+                num_properties: 32,
+                property_size: 1024,
+            },
+            TransactionTypeArg::StakePoolOperations => TransactionType::StakingOperations {
+                delegation_pool: false,
+            },
+            TransactionTypeArg::DelegationPoolOperations => TransactionType::StakingOperations {
+                delegation_pool: true,
+            },
+            TransactionTypeArg::GovernanceVoting => TransactionType::GovernanceVoting,
+            TransactionTypeArg::TableOperations => TransactionType::TableOperations {
+                inserts: 10,
+                updates: 10,
+                reads: 10,
+            },
+            TransactionTypeArg::KeyRotation => TransactionType::KeyRotation,
+            TransactionTypeArg::LargeWriteSet => TransactionType::LargeWriteSet {
+                num_resources: 10,
+                total_bytes: 10 * 1024,
+            },
+            TransactionTypeArg::LargeWriteSetMany => TransactionType::LargeWriteSet {
+                num_resources: 32,
+                total_bytes: 32 * 8 * 1024,
+            },
             TransactionTypeArg::PublishPackage => TransactionType::PublishPackage {
                 use_account_pool: false,
             },
+            TransactionTypeArg::PublishAndUpgrade => TransactionType::PublishAndUpgrade,
+            TransactionTypeArg::EntryFunction => TransactionType::CustomEntryFunction { index: 0 },
+            TransactionTypeArg::ScriptNoop => TransactionType::Script {
+                kind: ScriptKind::Noop,
+            },
+            TransactionTypeArg::ScriptTransfer => TransactionType::Script {
+                kind: ScriptKind::Transfer,
+            },
+            TransactionTypeArg::EmitEvents => TransactionType::Script {
+                kind: ScriptKind::EmitEvents { num_events: 10 },
+            },
+            TransactionTypeArg::EmitEventsLarge => TransactionType::Script {
+                kind: ScriptKind::EmitEvents { num_events: 1000 },
+            },
             TransactionTypeArg::CustomFunctionLargeModuleWorkingSet => {
                 TransactionType::CallCustomModules {
                     entry_point: EntryPoints::Nop,
@@ -77,6 +189,13 @@ impl TransactionTypeArg {
                 num_modules: 1,
                 use_account_pool: false,
             },
+            TransactionTypeArg::ComputeLoop => TransactionType::CallCustomModules {
+                entry_point: EntryPoints::Loopy {
+                    loop_count: Some(COMPUTE_LOOP_COUNT),
+                },
+                num_modules: 1,
+                use_account_pool: false,
+            },
         }
     }
 }
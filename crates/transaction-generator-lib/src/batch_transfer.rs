@@ -0,0 +1,167 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{hot_spot::HotSpot, TransactionGenerator, TransactionGeneratorCreator};
+use aptos_infallible::RwLock;
+use aptos_sdk::{
+    move_types::account_address::AccountAddress,
+    transaction_builder::{aptos_stdlib, TransactionFactory},
+    types::{transaction::SignedTransaction, LocalAccount},
+};
+use rand::{prelude::SliceRandom, rngs::StdRng, SeedableRng};
+use std::sync::Arc;
+
+/// Transfers to num_recipients distinct accounts in each transaction, with
+/// aptos_account::batch_transfer, to compare throughput of fewer larger transactions
+/// to the one of many single transfers.
+pub struct BatchTransferGenerator {
+    rng: StdRng,
+    txn_factory: TransactionFactory,
+    send_amount: u64,
+    num_recipients: usize,
+    all_addresses: Arc<RwLock<Vec<AccountAddress>>>,
+    hot_spot: HotSpot,
+}
+
+impl BatchTransferGenerator {
+    pub fn new(
+        rng: StdRng,
+        txn_factory: TransactionFactory,
+        send_amount: u64,
+        num_recipients: usize,
+        all_addresses: Arc<RwLock<Vec<AccountAddress>>>,
+        hot_spot: HotSpot,
+    ) -> Self {
+        Self {
+            rng,
+            txn_factory,
+            send_amount,
+            num_recipients,
+            all_addresses,
+            hot_spot,
+        }
+    }
+}
+
+impl TransactionGenerator for BatchTransferGenerator {
+    fn generate_transactions(
+        &mut self,
+        accounts: Vec<&mut LocalAccount>,
+        transactions_per_account: usize,
+    ) -> Vec<SignedTransaction> {
+        let all_addresses = self.all_addresses.read();
+        let mut requests = Vec::with_capacity(accounts.len() * transactions_per_account);
+        for account in accounts {
+            for _ in 0..transactions_per_account {
+                // fewer if there aren't enough accounts
+                let recipients: Vec<_> = all_addresses
+                    .choose_multiple(&mut self.rng, self.num_recipients)
+                    .copied()
+                    .collect();
+                // no longer distinct, with a hot spot
+                let recipients: Vec<_> = recipients
+                    .into_iter()
+                    .map(|recipient| {
+                        self.hot_spot
+                            .receiver(&mut self.rng, &all_addresses, recipient)
+                    })
+                    .collect();
+                let amounts = vec![self.send_amount; recipients.len()];
+                requests.push(
+                    account.sign_with_transaction_builder(self.txn_factory.payload(
+                        aptos_stdlib::aptos_account_batch_transfer(recipients, amounts),
+                    )),
+                );
+            }
+        }
+        requests
+    }
+}
+
+pub struct BatchTransferGeneratorCreator {
+    rng: StdRng,
+    txn_factory: TransactionFactory,
+    send_amount: u64,
+    num_recipients: usize,
+    all_addresses: Arc<RwLock<Vec<AccountAddress>>>,
+    hot_spot: HotSpot,
+}
+
+impl BatchTransferGeneratorCreator {
+    pub fn new(
+        rng: StdRng,
+        txn_factory: TransactionFactory,
+        send_amount: u64,
+        num_recipients: usize,
+        all_addresses: Arc<RwLock<Vec<AccountAddress>>>,
+        hot_spot: HotSpot,
+    ) -> Self {
+        Self {
+            rng,
+            txn_factory,
+            send_amount,
+            num_recipients,
+            all_addresses,
+            hot_spot,
+        }
+    }
+}
+
+impl TransactionGeneratorCreator for BatchTransferGeneratorCreator {
+    fn create_transaction_generator(&mut self) -> Box<dyn TransactionGenerator> {
+        Box::new(BatchTransferGenerator::new(
+            StdRng::from_rng(&mut self.rng).unwrap(),
+            self.txn_factory.clone(),
+            self.send_amount,
+            self.num_recipients,
+            self.all_addresses.clone(),
+            self.hot_spot,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::BatchTransferGenerator;
+    use crate::{hot_spot::HotSpot, TransactionGenerator};
+    use aptos_infallible::RwLock;
+    use aptos_sdk::{
+        bcs,
+        move_types::account_address::AccountAddress,
+        transaction_builder::TransactionFactory,
+        types::{chain_id::ChainId, transaction::TransactionPayload, LocalAccount},
+    };
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::{collections::HashSet, sync::Arc};
+
+    #[test]
+    pub fn test_batch_transfer() {
+        let addresses: Vec<_> = (0..20).map(|_| AccountAddress::random()).collect();
+        let mut generator = BatchTransferGenerator::new(
+            StdRng::from_seed([0; 32]),
+            TransactionFactory::new(ChainId::test()),
+            1,
+            10,
+            Arc::new(RwLock::new(addresses.clone())),
+            HotSpot::none(),
+        );
+        let mut account = LocalAccount::generate(&mut StdRng::from_seed([0; 32]));
+        let txns = generator.generate_transactions(vec![&mut account], 2);
+        assert_eq!(txns.len(), 2);
+        for txn in txns {
+            match txn.payload() {
+                TransactionPayload::EntryFunction(entry_function) => {
+                    assert_eq!(entry_function.function().as_str(), "batch_transfer");
+                    let recipients: Vec<AccountAddress> =
+                        bcs::from_bytes(&entry_function.args()[0]).unwrap();
+                    let amounts: Vec<u64> = bcs::from_bytes(&entry_function.args()[1]).unwrap();
+                    assert_eq!(recipients.len(), 10);
+                    assert_eq!(recipients.iter().collect::<HashSet<_>>().len(), 10);
+                    assert!(recipients.iter().all(|r| addresses.contains(r)));
+                    assert_eq!(amounts, vec![1; 10]);
+                },
+                _ => panic!("Expected an entry function payload"),
+            }
+        }
+    }
+}
@@ -0,0 +1,177 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use aptos_sdk::move_types::{account_address::AccountAddress, identifier::Identifier};
+use move_binary_format::{
+    file_format::{
+        AbilitySet, AddressIdentifierIndex, Bytecode, CodeUnit, CompiledModule, CompiledScript,
+        FieldHandle, FunctionDefinition, FunctionHandle, FunctionHandleIndex,
+        FunctionInstantiation, FunctionInstantiationIndex, IdentifierIndex, ModuleHandle,
+        ModuleHandleIndex, Signature, SignatureIndex, SignatureToken, StructDefinition,
+        StructHandle, StructHandleIndex, StructTypeParameter,
+    },
+    file_format_common::VERSION_5,
+};
+
+// index of item in pool, added if it's not there yet, as the pools of compiled code can't
+// have duplicates
+pub(crate) fn pool_index<T: PartialEq>(pool: &mut Vec<T>, item: T) -> u16 {
+    let index = pool
+        .iter()
+        .position(|other| *other == item)
+        .unwrap_or_else(|| {
+            pool.push(item);
+            pool.len() - 1
+        });
+    index as u16
+}
+
+/// Pools of Move bytecode assembled in code, for workloads calling functions no
+/// published package of the generators has. Handles to functions and structs of other
+/// modules need their declared abilities and type parameters, as they are checked when
+/// linking.
+#[derive(Default)]
+pub(crate) struct BytecodeBuilder {
+    identifiers: Vec<Identifier>,
+    address_identifiers: Vec<AccountAddress>,
+    module_handles: Vec<ModuleHandle>,
+    struct_handles: Vec<StructHandle>,
+    function_handles: Vec<FunctionHandle>,
+    function_instantiations: Vec<FunctionInstantiation>,
+    signatures: Vec<Signature>,
+}
+
+impl BytecodeBuilder {
+    pub(crate) fn identifier(&mut self, name: &str) -> IdentifierIndex {
+        IdentifierIndex(pool_index(
+            &mut self.identifiers,
+            Identifier::new(name).unwrap(),
+        ))
+    }
+
+    pub(crate) fn signature(&mut self, tokens: Vec<SignatureToken>) -> SignatureIndex {
+        SignatureIndex(pool_index(&mut self.signatures, Signature(tokens)))
+    }
+
+    pub(crate) fn module(&mut self, address: AccountAddress, name: &str) -> ModuleHandleIndex {
+        let address = AddressIdentifierIndex(pool_index(&mut self.address_identifiers, address));
+        let name = self.identifier(name);
+        ModuleHandleIndex(pool_index(&mut self.module_handles, ModuleHandle {
+            address,
+            name,
+        }))
+    }
+
+    pub(crate) fn struct_handle(
+        &mut self,
+        module: ModuleHandleIndex,
+        name: &str,
+        abilities: AbilitySet,
+        type_parameters: Vec<StructTypeParameter>,
+    ) -> StructHandleIndex {
+        let handle = StructHandle {
+            module,
+            name: self.identifier(name),
+            abilities,
+            type_parameters,
+        };
+        StructHandleIndex(pool_index(&mut self.struct_handles, handle))
+    }
+
+    pub(crate) fn function(
+        &mut self,
+        module: ModuleHandleIndex,
+        name: &str,
+        parameters: Vec<SignatureToken>,
+        return_: Vec<SignatureToken>,
+        type_parameters: Vec<AbilitySet>,
+    ) -> FunctionHandleIndex {
+        let handle = FunctionHandle {
+            module,
+            name: self.identifier(name),
+            parameters: self.signature(parameters),
+            return_: self.signature(return_),
+            type_parameters,
+        };
+        FunctionHandleIndex(pool_index(&mut self.function_handles, handle))
+    }
+
+    pub(crate) fn instantiation(
+        &mut self,
+        handle: FunctionHandleIndex,
+        type_parameters: Vec<SignatureToken>,
+    ) -> FunctionInstantiationIndex {
+        let instantiation = FunctionInstantiation {
+            handle,
+            type_parameters: self.signature(type_parameters),
+        };
+        FunctionInstantiationIndex(pool_index(&mut self.function_instantiations, instantiation))
+    }
+
+    /// Serialized script of main with the given parameters, locals (after the
+    /// parameters) and code, in bytecode version 5.
+    pub(crate) fn into_script(
+        mut self,
+        parameters: Vec<SignatureToken>,
+        locals: Vec<SignatureToken>,
+        code: Vec<Bytecode>,
+    ) -> Vec<u8> {
+        let parameters = self.signature(parameters);
+        let locals = self.signature(locals);
+        let script = CompiledScript {
+            version: VERSION_5,
+            module_handles: self.module_handles,
+            struct_handles: self.struct_handles,
+            function_handles: self.function_handles,
+            function_instantiations: self.function_instantiations,
+            signatures: self.signatures,
+            identifiers: self.identifiers,
+            address_identifiers: self.address_identifiers,
+            constant_pool: vec![],
+            metadata: vec![],
+            code: CodeUnit { locals, code },
+            type_parameters: vec![],
+            parameters,
+        };
+        let mut serialized = vec![];
+        script
+            .serialize_for_version(Some(VERSION_5), &mut serialized)
+            .expect("Script must serialize");
+        serialized
+    }
+
+    /// Serialized module of self_module, with the given definitions of its structs and
+    /// functions, in bytecode version 5.
+    pub(crate) fn into_module(
+        self,
+        self_module: ModuleHandleIndex,
+        field_handles: Vec<FieldHandle>,
+        struct_defs: Vec<StructDefinition>,
+        function_defs: Vec<FunctionDefinition>,
+    ) -> Vec<u8> {
+        let module = CompiledModule {
+            version: VERSION_5,
+            self_module_handle_idx: self_module,
+            module_handles: self.module_handles,
+            struct_handles: self.struct_handles,
+            function_handles: self.function_handles,
+            field_handles,
+            friend_decls: vec![],
+            struct_def_instantiations: vec![],
+            function_instantiations: self.function_instantiations,
+            field_instantiations: vec![],
+            signatures: self.signatures,
+            identifiers: self.identifiers,
+            address_identifiers: self.address_identifiers,
+            constant_pool: vec![],
+            metadata: vec![],
+            struct_defs,
+            function_defs,
+        };
+        let mut serialized = vec![];
+        module
+            .serialize_for_version(Some(VERSION_5), &mut serialized)
+            .expect("Module must serialize");
+        serialized
+    }
+}
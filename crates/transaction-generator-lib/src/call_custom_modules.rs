@@ -67,6 +67,7 @@ impl TransactionGenerator for CallCustomModulesGenerator {
 }
 
 pub struct CallCustomModulesCreator {
+    rng: StdRng,
     txn_factory: TransactionFactory,
     packages: Arc<Vec<(Package, AccountAddress)>>,
     entry_point: EntryPoints,
@@ -74,6 +75,7 @@ pub struct CallCustomModulesCreator {
 
 impl CallCustomModulesCreator {
     pub async fn new(
+        mut rng: StdRng,
         txn_factory: TransactionFactory,
         init_txn_factory: TransactionFactory,
         accounts: &mut [LocalAccount],
@@ -81,7 +83,6 @@ impl CallCustomModulesCreator {
         entry_point: EntryPoints,
         num_modules: usize,
     ) -> Self {
-        let mut rng = StdRng::from_entropy();
         assert!(accounts.len() >= num_modules);
         let mut requests = Vec::with_capacity(accounts.len());
         let mut package_handler = PackageHandler::new();
@@ -97,6 +98,7 @@ impl CallCustomModulesCreator {
         info!("Done publishing {} packages", requests.len());
 
         Self {
+            rng,
             txn_factory,
             packages: Arc::new(packages),
             entry_point,
@@ -107,7 +109,7 @@ impl CallCustomModulesCreator {
 impl TransactionGeneratorCreator for CallCustomModulesCreator {
     fn create_transaction_generator(&mut self) -> Box<dyn TransactionGenerator> {
         Box::new(CallCustomModulesGenerator::new(
-            StdRng::from_entropy(),
+            StdRng::from_rng(&mut self.rng).unwrap(),
             self.txn_factory.clone(),
             self.packages.clone(),
             self.entry_point,
@@ -0,0 +1,404 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{TransactionGenerator, TransactionGeneratorCreator};
+use anyhow::{bail, format_err, Context, Result};
+use aptos_infallible::RwLock;
+use aptos_sdk::{
+    bcs,
+    move_types::{
+        account_address::AccountAddress,
+        identifier::Identifier,
+        language_storage::{ModuleId, TypeTag},
+        parser::parse_type_tag,
+    },
+    transaction_builder::TransactionFactory,
+    types::{
+        transaction::{EntryFunction, SignedTransaction, TransactionPayload},
+        LocalAccount,
+    },
+};
+use rand::{
+    distributions::{Alphanumeric, Uniform},
+    prelude::SliceRandom,
+    rngs::StdRng,
+    Rng, SeedableRng,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Call of an entry function, as given in the run config, e.g.
+/// ```yaml
+/// function: 0xcafe::counter::increment_by
+/// type_args: ["0x1::aptos_coin::AptosCoin"]
+/// args: ["address:{receiver}", "u64:{random:1:100}", "string:hello"]
+/// ```
+/// Each argument is `<type>:<value>`, with type one of bool, u8, u16, u32, u64, u128,
+/// address, string (UTF-8 `vector<u8>`) and bytes (hex `vector<u8>`). Value is either
+/// a literal, or a placeholder filled in for each transaction: `{sender}` and
+/// `{receiver}` (a random account of the run) for addresses, `{random}` or
+/// `{random:<min>:<max>}` (inclusive) for integers, and `{random:<len>}` for strings
+/// and bytes.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct EntryFunctionCall {
+    pub function: String,
+    #[serde(default)]
+    pub type_args: Vec<String>,
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum IntType {
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+}
+
+impl IntType {
+    fn max(&self) -> u128 {
+        match self {
+            IntType::U8 => u8::MAX as u128,
+            IntType::U16 => u16::MAX as u128,
+            IntType::U32 => u32::MAX as u128,
+            IntType::U64 => u64::MAX as u128,
+            IntType::U128 => u128::MAX,
+        }
+    }
+
+    fn encode(&self, value: u128) -> Vec<u8> {
+        let encoded = match self {
+            IntType::U8 => bcs::to_bytes(&(value as u8)),
+            IntType::U16 => bcs::to_bytes(&(value as u16)),
+            IntType::U32 => bcs::to_bytes(&(value as u32)),
+            IntType::U64 => bcs::to_bytes(&(value as u64)),
+            IntType::U128 => bcs::to_bytes(&value),
+        };
+        encoded.expect("integers must serialize")
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum ArgTemplate {
+    // already BCS encoded
+    Literal(Vec<u8>),
+    Sender,
+    Receiver,
+    RandomInt(IntType, u128, u128),
+    RandomString(usize),
+    RandomBytes(usize),
+}
+
+impl ArgTemplate {
+    fn parse(arg: &str) -> Result<Self> {
+        let (arg_type, value) = arg
+            .split_once(':')
+            .ok_or_else(|| format_err!("Expected <type>:<value>, got {}", arg))?;
+        let placeholder = value
+            .strip_prefix('{')
+            .and_then(|value| value.strip_suffix('}'));
+        let int_type = match arg_type {
+            "u8" => Some(IntType::U8),
+            "u16" => Some(IntType::U16),
+            "u32" => Some(IntType::U32),
+            "u64" => Some(IntType::U64),
+            "u128" => Some(IntType::U128),
+            _ => None,
+        };
+        if let Some(int_type) = int_type {
+            return Self::parse_int(int_type, arg_type, value, placeholder);
+        }
+        Ok(match (arg_type, placeholder) {
+            ("address", Some("sender")) => ArgTemplate::Sender,
+            ("address", Some("receiver")) => ArgTemplate::Receiver,
+            ("address", None) => {
+                ArgTemplate::Literal(bcs::to_bytes(&AccountAddress::from_hex_literal(value)?)?)
+            },
+            ("bool", None) => ArgTemplate::Literal(bcs::to_bytes(&value.parse::<bool>()?)?),
+            ("string", None) => ArgTemplate::Literal(bcs::to_bytes(value)?),
+            ("bytes", None) => ArgTemplate::Literal(bcs::to_bytes(&hex::decode(
+                value.strip_prefix("0x").unwrap_or(value),
+            )?)?),
+            ("string", Some(random)) | ("bytes", Some(random)) => {
+                let len = random
+                    .strip_prefix("random:")
+                    .ok_or_else(|| format_err!("Expected {{random:<len>}}, got {}", value))?
+                    .parse()?;
+                if arg_type == "string" {
+                    ArgTemplate::RandomString(len)
+                } else {
+                    ArgTemplate::RandomBytes(len)
+                }
+            },
+            _ => bail!("Unsupported argument {}", arg),
+        })
+    }
+
+    fn parse_int(
+        int_type: IntType,
+        arg_type: &str,
+        value: &str,
+        placeholder: Option<&str>,
+    ) -> Result<Self> {
+        let random = match placeholder {
+            Some(random) => random,
+            None => {
+                let value: u128 = value.parse()?;
+                if value > int_type.max() {
+                    bail!("{} is out of range of {}", value, arg_type);
+                }
+                return Ok(ArgTemplate::Literal(int_type.encode(value)));
+            },
+        };
+        let (min, max) = match random.strip_prefix("random") {
+            Some("") => (0, int_type.max()),
+            Some(range) => {
+                let (min, max) = range
+                    .strip_prefix(':')
+                    .and_then(|range| range.split_once(':'))
+                    .ok_or_else(|| format_err!("Expected {{random:<min>:<max>}}, got {}", value))?;
+                (min.parse()?, max.parse()?)
+            },
+            None => bail!("Unknown placeholder {} for {}", value, arg_type),
+        };
+        if min > max || max > int_type.max() {
+            bail!("Invalid range {}..={} of {}", min, max, arg_type);
+        }
+        Ok(ArgTemplate::RandomInt(int_type, min, max))
+    }
+
+    fn fill(&self, rng: &mut StdRng, sender: AccountAddress, receiver: AccountAddress) -> Vec<u8> {
+        let encoded = match self {
+            ArgTemplate::Literal(encoded) => return encoded.clone(),
+            ArgTemplate::Sender => bcs::to_bytes(&sender),
+            ArgTemplate::Receiver => bcs::to_bytes(&receiver),
+            ArgTemplate::RandomInt(int_type, min, max) => {
+                return int_type.encode(rng.sample(Uniform::new_inclusive(*min, *max)));
+            },
+            ArgTemplate::RandomString(len) => bcs::to_bytes(
+                &(0..*len)
+                    .map(|_| rng.sample(Alphanumeric))
+                    .collect::<String>(),
+            ),
+            ArgTemplate::RandomBytes(len) => {
+                bcs::to_bytes(&(0..*len).map(|_| rng.gen()).collect::<Vec<u8>>())
+            },
+        };
+        encoded.expect("arguments must serialize")
+    }
+}
+
+/// EntryFunctionCall, parsed and checked, with arguments to be filled in.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EntryFunctionTemplate {
+    module: ModuleId,
+    function: Identifier,
+    ty_args: Vec<TypeTag>,
+    args: Vec<ArgTemplate>,
+}
+
+impl EntryFunctionTemplate {
+    pub fn parse(call: &EntryFunctionCall) -> Result<Self> {
+        let parts: Vec<_> = call.function.split("::").collect();
+        if parts.len() != 3 {
+            bail!(
+                "Expected <address>::<module>::<function>, got {}",
+                call.function
+            );
+        }
+        let module = ModuleId::new(
+            AccountAddress::from_hex_literal(parts[0])?,
+            Identifier::new(parts[1])?,
+        );
+        let function = Identifier::new(parts[2])?;
+        let ty_args = call
+            .type_args
+            .iter()
+            .map(|ty_arg| parse_type_tag(ty_arg))
+            .collect::<Result<_>>()
+            .with_context(|| format!("Invalid type arguments of {}", call.function))?;
+        let args = call
+            .args
+            .iter()
+            .map(|arg| ArgTemplate::parse(arg))
+            .collect::<Result<_>>()
+            .with_context(|| format!("Invalid arguments of {}", call.function))?;
+        Ok(Self {
+            module,
+            function,
+            ty_args,
+            args,
+        })
+    }
+
+    fn payload(
+        &self,
+        rng: &mut StdRng,
+        sender: AccountAddress,
+        receiver: AccountAddress,
+    ) -> TransactionPayload {
+        TransactionPayload::EntryFunction(EntryFunction::new(
+            self.module.clone(),
+            self.function.clone(),
+            self.ty_args.clone(),
+            self.args
+                .iter()
+                .map(|arg| arg.fill(rng, sender, receiver))
+                .collect(),
+        ))
+    }
+}
+
+/// Calls of an entry function given in the run config, so that contracts can be
+/// load-tested without writing a generator for them.
+pub struct EntryFunctionGenerator {
+    rng: StdRng,
+    txn_factory: TransactionFactory,
+    template: Arc<EntryFunctionTemplate>,
+    all_addresses: Arc<RwLock<Vec<AccountAddress>>>,
+}
+
+impl EntryFunctionGenerator {
+    pub fn new(
+        rng: StdRng,
+        txn_factory: TransactionFactory,
+        template: Arc<EntryFunctionTemplate>,
+        all_addresses: Arc<RwLock<Vec<AccountAddress>>>,
+    ) -> Self {
+        Self {
+            rng,
+            txn_factory,
+            template,
+            all_addresses,
+        }
+    }
+}
+
+impl TransactionGenerator for EntryFunctionGenerator {
+    fn generate_transactions(
+        &mut self,
+        accounts: Vec<&mut LocalAccount>,
+        transactions_per_account: usize,
+    ) -> Vec<SignedTransaction> {
+        let all_addresses = self.all_addresses.read();
+        let mut requests = Vec::with_capacity(accounts.len() * transactions_per_account);
+        for account in accounts {
+            for _ in 0..transactions_per_account {
+                let sender = account.address();
+                let receiver = all_addresses
+                    .choose(&mut self.rng)
+                    .copied()
+                    .unwrap_or(sender);
+                let payload = self.template.payload(&mut self.rng, sender, receiver);
+                requests
+                    .push(account.sign_with_transaction_builder(self.txn_factory.payload(payload)));
+            }
+        }
+        requests
+    }
+}
+
+pub struct EntryFunctionGeneratorCreator {
+    rng: StdRng,
+    txn_factory: TransactionFactory,
+    template: Arc<EntryFunctionTemplate>,
+    all_addresses: Arc<RwLock<Vec<AccountAddress>>>,
+}
+
+impl EntryFunctionGeneratorCreator {
+    pub fn new(
+        rng: StdRng,
+        txn_factory: TransactionFactory,
+        template: EntryFunctionTemplate,
+        all_addresses: Arc<RwLock<Vec<AccountAddress>>>,
+    ) -> Self {
+        Self {
+            rng,
+            txn_factory,
+            template: Arc::new(template),
+            all_addresses,
+        }
+    }
+}
+
+impl TransactionGeneratorCreator for EntryFunctionGeneratorCreator {
+    fn create_transaction_generator(&mut self) -> Box<dyn TransactionGenerator> {
+        Box::new(EntryFunctionGenerator::new(
+            StdRng::from_rng(&mut self.rng).unwrap(),
+            self.txn_factory.clone(),
+            self.template.clone(),
+            self.all_addresses.clone(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{EntryFunctionCall, EntryFunctionGenerator, EntryFunctionTemplate};
+    use crate::TransactionGenerator;
+    use aptos_infallible::RwLock;
+    use aptos_sdk::{
+        bcs,
+        move_types::account_address::AccountAddress,
+        transaction_builder::TransactionFactory,
+        types::{chain_id::ChainId, transaction::TransactionPayload, LocalAccount},
+    };
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::sync::Arc;
+
+    #[test]
+    pub fn test_entry_function_generator() {
+        let call = |args: &[&str]| EntryFunctionCall {
+            function: "0xcafe::counter::increment_by".to_string(),
+            type_args: vec!["0x1::aptos_coin::AptosCoin".to_string()],
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+        };
+        assert!(EntryFunctionTemplate::parse(&call(&["u8:256"])).is_err());
+        assert!(EntryFunctionTemplate::parse(&call(&["u64:{random:10:1}"])).is_err());
+        assert!(EntryFunctionTemplate::parse(&call(&["address:{someone}"])).is_err());
+        assert!(EntryFunctionTemplate::parse(&EntryFunctionCall {
+            function: "counter::increment_by".to_string(),
+            ..call(&[])
+        })
+        .is_err());
+
+        let template = EntryFunctionTemplate::parse(&call(&[
+            "address:{sender}",
+            "address:{receiver}",
+            "u64:{random:5:7}",
+            "string:hello",
+            "bytes:{random:4}",
+        ]))
+        .unwrap();
+        let receiver = AccountAddress::random();
+        let mut generator = EntryFunctionGenerator::new(
+            StdRng::from_seed([0; 32]),
+            TransactionFactory::new(ChainId::test()),
+            Arc::new(template),
+            Arc::new(RwLock::new(vec![receiver])),
+        );
+        let mut rng = StdRng::from_seed([0; 32]);
+        let mut account = LocalAccount::generate(&mut rng);
+        let txns = generator.generate_transactions(vec![&mut account], 3);
+        assert_eq!(txns.len(), 3);
+        for txn in txns {
+            match txn.payload() {
+                TransactionPayload::EntryFunction(entry_function) => {
+                    assert_eq!(entry_function.function().as_str(), "increment_by");
+                    assert_eq!(entry_function.ty_args().len(), 1);
+                    let args = entry_function.args();
+                    assert_eq!(args[0], bcs::to_bytes(&txn.sender()).unwrap());
+                    assert_eq!(args[1], bcs::to_bytes(&receiver).unwrap());
+                    let amount: u64 = bcs::from_bytes(&args[2]).unwrap();
+                    assert!((5..=7).contains(&amount));
+                    assert_eq!(args[3], bcs::to_bytes("hello").unwrap());
+                    assert_eq!(bcs::from_bytes::<Vec<u8>>(&args[4]).unwrap().len(), 4);
+                },
+                _ => panic!("Expected an entry function payload"),
+            }
+        }
+    }
+}
@@ -0,0 +1,393 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    bytecode_builder::BytecodeBuilder, TransactionExecutor, TransactionGenerator,
+    TransactionGeneratorCreator, SEND_AMOUNT,
+};
+use aptos_logger::info;
+use aptos_sdk::{
+    bcs,
+    move_types::{
+        account_address::AccountAddress,
+        ident_str,
+        language_storage::{ModuleId, StructTag, TypeTag},
+    },
+    transaction_builder::TransactionFactory,
+    types::{
+        account_address::create_object_address,
+        transaction::{
+            EntryFunction, Script, SignedTransaction, TransactionArgument, TransactionPayload,
+        },
+        LocalAccount,
+    },
+};
+use move_binary_format::file_format::{
+    Ability, AbilitySet, Bytecode, SignatureToken, StructTypeParameter,
+};
+use rand::{rngs::StdRng, seq::SliceRandom, RngCore, SeedableRng};
+use std::sync::Arc;
+
+// minted for each source account, which only ever sends SEND_AMOUNT at a time
+const AMOUNT_PER_ACCOUNT: u64 = 1_000_000_000;
+
+/// `fun main(creator: signer, seed: vector<u8>, name: vector<u8>, symbol: vector<u8>,
+/// decimals: u8, amount: u64)`, creating the metadata of a fungible asset as the named
+/// object of seed under the creator, with primary stores enabled and unlimited supply,
+/// and minting amount of it to the primary store of the creator. A script, as the
+/// ConstructorRef and MintRef this needs can't be passed to entry functions.
+pub fn create_and_mint_script() -> Vec<u8> {
+    use SignatureToken::*;
+
+    let mut builder = BytecodeBuilder::default();
+    let object = builder.module(AccountAddress::ONE, "object");
+    let fungible_asset = builder.module(AccountAddress::ONE, "fungible_asset");
+    let primary_fungible_store = builder.module(AccountAddress::ONE, "primary_fungible_store");
+    let option = builder.module(AccountAddress::ONE, "option");
+    let string = builder.module(AccountAddress::ONE, "string");
+    let signer = builder.module(AccountAddress::ONE, "signer");
+
+    let copy_drop_store = AbilitySet::EMPTY | Ability::Copy | Ability::Drop | Ability::Store;
+    let constructor_ref_type = builder.struct_handle(
+        object,
+        "ConstructorRef",
+        AbilitySet::EMPTY | Ability::Drop,
+        vec![],
+    );
+    let mint_ref_type = builder.struct_handle(
+        fungible_asset,
+        "MintRef",
+        AbilitySet::EMPTY | Ability::Drop | Ability::Store,
+        vec![],
+    );
+    let fungible_asset_type =
+        builder.struct_handle(fungible_asset, "FungibleAsset", AbilitySet::EMPTY, vec![]);
+    let string_type = builder.struct_handle(string, "String", copy_drop_store, vec![]);
+    let option_type = builder.struct_handle(option, "Option", copy_drop_store, vec![
+        StructTypeParameter {
+            constraints: AbilitySet::EMPTY,
+            is_phantom: false,
+        },
+    ]);
+    let bytes = || Vector(Box::new(U8));
+    let max_supply = || StructInstantiation(option_type, vec![U128]);
+
+    let create_named_object = builder.function(
+        object,
+        "create_named_object",
+        vec![Reference(Box::new(Signer)), bytes()],
+        vec![Struct(constructor_ref_type)],
+        vec![],
+    );
+    let none = builder.function(
+        option,
+        "none",
+        vec![],
+        vec![StructInstantiation(option_type, vec![TypeParameter(0)])],
+        vec![AbilitySet::EMPTY],
+    );
+    let none_max_supply = builder.instantiation(none, vec![max_supply()]);
+    let utf8 = builder.function(
+        string,
+        "utf8",
+        vec![bytes()],
+        vec![Struct(string_type)],
+        vec![],
+    );
+    let create_fungible_asset = builder.function(
+        primary_fungible_store,
+        "create_primary_store_enabled_fungible_asset",
+        vec![
+            Reference(Box::new(Struct(constructor_ref_type))),
+            StructInstantiation(option_type, vec![max_supply()]),
+            Struct(string_type),
+            Struct(string_type),
+            U8,
+        ],
+        vec![],
+        vec![],
+    );
+    let generate_mint_ref = builder.function(
+        fungible_asset,
+        "generate_mint_ref",
+        vec![Reference(Box::new(Struct(constructor_ref_type)))],
+        vec![Struct(mint_ref_type)],
+        vec![],
+    );
+    let address_of = builder.function(
+        signer,
+        "address_of",
+        vec![Reference(Box::new(Signer))],
+        vec![Address],
+        vec![],
+    );
+    let mint = builder.function(
+        fungible_asset,
+        "mint",
+        vec![Reference(Box::new(Struct(mint_ref_type))), U64],
+        vec![Struct(fungible_asset_type)],
+        vec![],
+    );
+    let deposit = builder.function(
+        primary_fungible_store,
+        "deposit",
+        vec![Address, Struct(fungible_asset_type)],
+        vec![],
+        vec![],
+    );
+
+    builder.into_script(
+        vec![Signer, bytes(), bytes(), bytes(), U8, U64],
+        // locals 6 and 7, after the parameters
+        vec![Struct(constructor_ref_type), Struct(mint_ref_type)],
+        vec![
+            Bytecode::ImmBorrowLoc(0),
+            Bytecode::MoveLoc(1),
+            Bytecode::Call(create_named_object),
+            Bytecode::StLoc(6),
+            Bytecode::ImmBorrowLoc(6),
+            Bytecode::CallGeneric(none_max_supply),
+            Bytecode::MoveLoc(2),
+            Bytecode::Call(utf8),
+            Bytecode::MoveLoc(3),
+            Bytecode::Call(utf8),
+            Bytecode::MoveLoc(4),
+            Bytecode::Call(create_fungible_asset),
+            Bytecode::ImmBorrowLoc(6),
+            Bytecode::Call(generate_mint_ref),
+            Bytecode::StLoc(7),
+            Bytecode::ImmBorrowLoc(0),
+            Bytecode::Call(address_of),
+            Bytecode::ImmBorrowLoc(7),
+            Bytecode::MoveLoc(5),
+            Bytecode::Call(mint),
+            Bytecode::Call(deposit),
+            Bytecode::Ret,
+        ],
+    )
+}
+
+fn metadata_type() -> TypeTag {
+    TypeTag::Struct(Box::new(StructTag {
+        address: AccountAddress::ONE,
+        module: ident_str!("fungible_asset").to_owned(),
+        name: ident_str!("Metadata").to_owned(),
+        type_params: vec![],
+    }))
+}
+
+/// 0x1::primary_fungible_store::transfer of amount of the fungible asset of metadata.
+pub fn transfer_payload(
+    metadata: AccountAddress,
+    recipient: AccountAddress,
+    amount: u64,
+) -> TransactionPayload {
+    TransactionPayload::EntryFunction(EntryFunction::new(
+        ModuleId::new(
+            AccountAddress::ONE,
+            ident_str!("primary_fungible_store").to_owned(),
+        ),
+        ident_str!("transfer").to_owned(),
+        vec![metadata_type()],
+        vec![
+            bcs::to_bytes(&metadata).unwrap(),
+            bcs::to_bytes(&recipient).unwrap(),
+            bcs::to_bytes(&amount).unwrap(),
+        ],
+    ))
+}
+
+/// Transfers of a fungible asset between the primary stores of the source accounts, so
+/// the fungible asset counterpart of coin transfers.
+pub struct FungibleAssetTransferGenerator {
+    rng: StdRng,
+    txn_factory: TransactionFactory,
+    metadata: AccountAddress,
+    recipients: Arc<Vec<AccountAddress>>,
+}
+
+impl FungibleAssetTransferGenerator {
+    pub fn new(
+        rng: StdRng,
+        txn_factory: TransactionFactory,
+        metadata: AccountAddress,
+        recipients: Arc<Vec<AccountAddress>>,
+    ) -> Self {
+        Self {
+            rng,
+            txn_factory,
+            metadata,
+            recipients,
+        }
+    }
+}
+
+impl TransactionGenerator for FungibleAssetTransferGenerator {
+    fn generate_transactions(
+        &mut self,
+        accounts: Vec<&mut LocalAccount>,
+        transactions_per_account: usize,
+    ) -> Vec<SignedTransaction> {
+        let mut requests = Vec::with_capacity(accounts.len() * transactions_per_account);
+        for account in accounts {
+            for _ in 0..transactions_per_account {
+                let recipient = *self.recipients.choose(&mut self.rng).unwrap();
+                requests.push(account.sign_with_transaction_builder(
+                    self.txn_factory.payload(transfer_payload(
+                        self.metadata,
+                        recipient,
+                        SEND_AMOUNT,
+                    )),
+                ));
+            }
+        }
+        requests
+    }
+}
+
+pub struct FungibleAssetTransferCreator {
+    rng: StdRng,
+    txn_factory: TransactionFactory,
+    metadata: AccountAddress,
+    recipients: Arc<Vec<AccountAddress>>,
+}
+
+impl FungibleAssetTransferCreator {
+    /// Creates the fungible asset from the first source account, under a random seed so
+    /// that runs reusing the accounts each create their own, and funds the other source
+    /// accounts with it, which the generators then transfer between.
+    pub async fn new(
+        mut rng: StdRng,
+        txn_factory: TransactionFactory,
+        init_txn_factory: TransactionFactory,
+        source_accounts: &mut [LocalAccount],
+        txn_executor: &dyn TransactionExecutor,
+    ) -> Self {
+        let recipients: Vec<_> = source_accounts.iter().map(LocalAccount::address).collect();
+        let (creator, funded) = source_accounts
+            .split_first_mut()
+            .expect("Fungible asset transfers need source accounts");
+        let mut seed = vec![0u8; 32];
+        rng.fill_bytes(&mut seed);
+        let metadata = create_object_address(creator.address(), &seed);
+        let create_txn = creator.sign_with_transaction_builder(init_txn_factory.payload(
+            TransactionPayload::Script(Script::new(create_and_mint_script(), vec![], vec![
+                TransactionArgument::U8Vector(seed),
+                TransactionArgument::U8Vector(b"Fungible Asset".to_vec()),
+                TransactionArgument::U8Vector(b"FA".to_vec()),
+                TransactionArgument::U8(8),
+                TransactionArgument::U64(AMOUNT_PER_ACCOUNT * recipients.len() as u64),
+            ])),
+        ));
+        info!("Creating fungible asset {}", metadata);
+        txn_executor
+            .execute_transactions(&[create_txn])
+            .await
+            .unwrap();
+
+        let fund_txns: Vec<_> =
+            funded
+                .iter()
+                .map(|account| {
+                    creator.sign_with_transaction_builder(init_txn_factory.payload(
+                        transfer_payload(metadata, account.address(), AMOUNT_PER_ACCOUNT),
+                    ))
+                })
+                .collect();
+        info!(
+            "Funding {} accounts with fungible asset {}",
+            fund_txns.len(),
+            metadata
+        );
+        // all from the creator, so executed in chunks within the per account limit of
+        // mempool
+        for chunk in fund_txns.chunks(100) {
+            txn_executor.execute_transactions(chunk).await.unwrap();
+        }
+
+        Self {
+            rng,
+            txn_factory,
+            metadata,
+            recipients: Arc::new(recipients),
+        }
+    }
+}
+
+impl TransactionGeneratorCreator for FungibleAssetTransferCreator {
+    fn create_transaction_generator(&mut self) -> Box<dyn TransactionGenerator> {
+        Box::new(FungibleAssetTransferGenerator::new(
+            StdRng::from_rng(&mut self.rng).unwrap(),
+            self.txn_factory.clone(),
+            self.metadata,
+            self.recipients.clone(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{create_and_mint_script, FungibleAssetTransferGenerator};
+    use crate::{TransactionGenerator, SEND_AMOUNT};
+    use aptos_sdk::{
+        bcs,
+        move_types::account_address::AccountAddress,
+        transaction_builder::TransactionFactory,
+        types::{chain_id::ChainId, transaction::TransactionPayload, LocalAccount},
+    };
+    use move_binary_format::file_format::CompiledScript;
+    use move_bytecode_verifier::verify_script;
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::sync::Arc;
+
+    #[test]
+    pub fn test_create_and_mint_script() {
+        let script = CompiledScript::deserialize(&create_and_mint_script()).unwrap();
+        assert_eq!(script.version, 5);
+        assert_eq!(script.address_identifiers, vec![AccountAddress::ONE]);
+        assert_eq!(script.module_handles.len(), 6);
+        assert_eq!(script.struct_handles.len(), 5);
+        assert_eq!(script.function_handles.len(), 8);
+        assert_eq!(script.function_instantiations.len(), 1);
+        verify_script(&script).unwrap();
+    }
+
+    #[test]
+    pub fn test_fungible_asset_transfer() {
+        let mut rng = StdRng::from_seed([0; 32]);
+        let metadata = AccountAddress::random();
+        let recipients: Vec<_> = (0..3).map(|_| AccountAddress::random()).collect();
+        let mut generator = FungibleAssetTransferGenerator::new(
+            StdRng::from_seed([1; 32]),
+            TransactionFactory::new(ChainId::test()),
+            metadata,
+            Arc::new(recipients.clone()),
+        );
+        let mut account = LocalAccount::generate(&mut rng);
+        let txns = generator.generate_transactions(vec![&mut account], 3);
+        assert_eq!(txns.len(), 3);
+        for txn in txns {
+            match txn.payload() {
+                TransactionPayload::EntryFunction(entry_function) => {
+                    assert_eq!(entry_function.module().address(), &AccountAddress::ONE);
+                    assert_eq!(
+                        entry_function.module().name().as_str(),
+                        "primary_fungible_store"
+                    );
+                    assert_eq!(entry_function.function().as_str(), "transfer");
+                    assert_eq!(
+                        entry_function.ty_args()[0].to_string(),
+                        "0x1::fungible_asset::Metadata"
+                    );
+                    let args = entry_function.args();
+                    assert_eq!(args[0], metadata.to_vec());
+                    let recipient: AccountAddress = bcs::from_bytes(&args[1]).unwrap();
+                    assert!(recipients.contains(&recipient));
+                    assert_eq!(args[2], bcs::to_bytes(&SEND_AMOUNT).unwrap());
+                },
+                _ => panic!("Expected an entry function"),
+            }
+        }
+        assert_eq!(account.sequence_number(), 3);
+    }
+}
@@ -0,0 +1,297 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::TransactionExecutor;
+use crate::{
+    nft_mint_and_transfer::create_and_fund_account_request, TransactionGenerator,
+    TransactionGeneratorCreator,
+};
+use anyhow::{anyhow, Result};
+use aptos_crypto::HashValue;
+use aptos_infallible::RwLock;
+use aptos_logger::info;
+use aptos_sdk::{
+    transaction_builder::{aptos_stdlib, TransactionFactory},
+    types::{
+        account_address::AccountAddress,
+        transaction::{SignedTransaction, TransactionPayload},
+        LocalAccount,
+    },
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{cmp::min, collections::HashMap, ops::Range, sync::Arc};
+
+// stake of the pool of each voter, its voting power
+const VOTER_STAKE: u64 = 1_000;
+// proposals created for the workload, each voter voting once on each of them
+const NUM_PROPOSALS: u64 = 100;
+const VOTING_FORUM_TYPE: &str =
+    "0x1::voting::VotingForum<0x1::governance_proposal::GovernanceProposal>";
+
+/// Payload of the step-th transaction of voter, None once it voted on all proposals.
+/// Voters first initialize a stake pool, with themselves as its voter, and lock it up
+/// (for longer than the voting duration of proposals, as required at genesis), and
+/// then vote on each proposal once, as following votes from the pool would abort.
+pub fn voter_payload(
+    voter: AccountAddress,
+    step: u64,
+    proposal_ids: &Range<u64>,
+    should_pass: bool,
+) -> Option<TransactionPayload> {
+    match step {
+        0 => Some(aptos_stdlib::stake_initialize_stake_owner(
+            VOTER_STAKE,
+            voter,
+            voter,
+        )),
+        1 => Some(aptos_stdlib::stake_increase_lockup()),
+        _ => {
+            let proposal_id = proposal_ids.start + step - 2;
+            (proposal_id < proposal_ids.end)
+                .then(|| aptos_stdlib::aptos_governance_vote(voter, proposal_id, should_pass))
+        },
+    }
+}
+
+/// Governance votes, for and against at random, on proposals created when the
+/// workload is, from accounts taken from the accounts pool (e.g. filled by account
+/// generation) while generating and returned to it after, or from the given accounts
+/// when the pool is empty. Votes count the stake of pools not in the validator set,
+/// so validator set changes need to be allowed on chain. The aptos_governance module
+/// of this framework has no partial voting, so each vote is of the whole pool.
+pub struct GovernanceVoting {
+    rng: StdRng,
+    txn_factory: TransactionFactory,
+    proposal_ids: Range<u64>,
+    accounts_pool: Arc<RwLock<Vec<LocalAccount>>>,
+    steps: Arc<RwLock<HashMap<AccountAddress, u64>>>,
+}
+
+impl GovernanceVoting {
+    pub fn new(
+        rng: StdRng,
+        txn_factory: TransactionFactory,
+        proposal_ids: Range<u64>,
+        accounts_pool: Arc<RwLock<Vec<LocalAccount>>>,
+        steps: Arc<RwLock<HashMap<AccountAddress, u64>>>,
+    ) -> Self {
+        Self {
+            rng,
+            txn_factory,
+            proposal_ids,
+            accounts_pool,
+            steps,
+        }
+    }
+
+    fn generate_for_account(
+        &mut self,
+        account: &mut LocalAccount,
+        transactions_per_account: usize,
+        requests: &mut Vec<SignedTransaction>,
+    ) {
+        let mut steps = self.steps.write();
+        let step = steps.entry(account.address()).or_insert(0);
+        for _ in 0..transactions_per_account {
+            let payload =
+                match voter_payload(account.address(), *step, &self.proposal_ids, self.rng.gen()) {
+                    Some(payload) => payload,
+                    None => break,
+                };
+            requests.push(account.sign_with_transaction_builder(self.txn_factory.payload(payload)));
+            *step += 1;
+        }
+    }
+}
+
+impl TransactionGenerator for GovernanceVoting {
+    fn generate_transactions(
+        &mut self,
+        accounts: Vec<&mut LocalAccount>,
+        transactions_per_account: usize,
+    ) -> Vec<SignedTransaction> {
+        let mut requests = Vec::with_capacity(accounts.len() * transactions_per_account);
+        // taken out of the pool, so that no other worker uses them meanwhile
+        let mut pool_accounts: Vec<_> = {
+            let mut accounts_pool = self.accounts_pool.write();
+            let needed = min(accounts.len(), accounts_pool.len());
+            (0..needed)
+                .map(|_| {
+                    let index = self.rng.gen_range(0, accounts_pool.len());
+                    accounts_pool.swap_remove(index)
+                })
+                .collect()
+        };
+        if pool_accounts.is_empty() {
+            for account in accounts {
+                self.generate_for_account(account, transactions_per_account, &mut requests);
+            }
+        } else {
+            for account in pool_accounts.iter_mut() {
+                self.generate_for_account(account, transactions_per_account, &mut requests);
+            }
+            self.accounts_pool.write().extend(pool_accounts);
+        }
+        requests
+    }
+}
+
+pub struct GovernanceVotingCreator {
+    rng: StdRng,
+    txn_factory: TransactionFactory,
+    proposal_ids: Range<u64>,
+    accounts_pool: Arc<RwLock<Vec<LocalAccount>>>,
+    steps: Arc<RwLock<HashMap<AccountAddress, u64>>>,
+}
+
+impl GovernanceVotingCreator {
+    /// Creates NUM_PROPOSALS proposals, backed by the stake pool of a new proposer,
+    /// staking a twentieth of the balance of the first source account, which needs to
+    /// be at least the required proposer stake.
+    pub async fn new(
+        mut rng: StdRng,
+        txn_factory: TransactionFactory,
+        init_txn_factory: TransactionFactory,
+        source_accounts: &mut [LocalAccount],
+        txn_executor: &dyn TransactionExecutor,
+        accounts_pool: Arc<RwLock<Vec<LocalAccount>>>,
+    ) -> Self {
+        let source_account = source_accounts.get_mut(0).unwrap();
+        let mut proposer = LocalAccount::generate(&mut rng);
+        let proposer_address = proposer.address();
+        let balance = txn_executor
+            .get_account_balance(source_account.address())
+            .await
+            .unwrap();
+        txn_executor
+            .execute_transactions(&[create_and_fund_account_request(
+                source_account,
+                balance / 10,
+                proposer.public_key(),
+                &init_txn_factory,
+            )])
+            .await
+            .unwrap();
+        txn_executor
+            .execute_transactions(&[
+                proposer.sign_with_transaction_builder(init_txn_factory.payload(
+                    aptos_stdlib::stake_initialize_stake_owner(
+                        balance / 20,
+                        proposer_address,
+                        proposer_address,
+                    ),
+                )),
+                proposer.sign_with_transaction_builder(
+                    init_txn_factory.payload(aptos_stdlib::stake_increase_lockup()),
+                ),
+            ])
+            .await
+            .unwrap();
+
+        let txns: Vec<_> = (0..NUM_PROPOSALS)
+            .map(|index| {
+                proposer.sign_with_transaction_builder(init_txn_factory.payload(
+                    aptos_stdlib::aptos_governance_create_proposal(
+                        proposer_address,
+                        HashValue::sha3_256_of(&index.to_le_bytes()).to_vec(),
+                        vec![],
+                        vec![],
+                    ),
+                ))
+            })
+            .collect();
+        // per account limit is 100
+        for chunk in txns.chunks(100) {
+            txn_executor.execute_transactions(chunk).await.unwrap();
+        }
+        // ids are assigned in order, and no other proposals are expected meanwhile
+        let next_proposal_id = query_next_proposal_id(txn_executor).await.unwrap();
+        let proposal_ids = next_proposal_id - NUM_PROPOSALS..next_proposal_id;
+        info!("Created governance proposals {:?}", proposal_ids);
+
+        Self {
+            rng,
+            txn_factory,
+            proposal_ids,
+            accounts_pool,
+            steps: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+}
+
+async fn query_next_proposal_id(txn_executor: &dyn TransactionExecutor) -> Result<u64> {
+    let voting_forum = txn_executor
+        .get_account_resource(AccountAddress::ONE, VOTING_FORUM_TYPE)
+        .await?
+        .ok_or_else(|| anyhow!("No governance voting forum"))?;
+    // u64s are strings in json
+    Ok(voting_forum["next_proposal_id"]
+        .as_str()
+        .ok_or_else(|| anyhow!("No next_proposal_id in {}", voting_forum))?
+        .parse()?)
+}
+
+impl TransactionGeneratorCreator for GovernanceVotingCreator {
+    fn create_transaction_generator(&mut self) -> Box<dyn TransactionGenerator> {
+        Box::new(GovernanceVoting::new(
+            StdRng::from_rng(&mut self.rng).unwrap(),
+            self.txn_factory.clone(),
+            self.proposal_ids.clone(),
+            self.accounts_pool.clone(),
+            self.steps.clone(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::GovernanceVoting;
+    use crate::TransactionGenerator;
+    use aptos_infallible::RwLock;
+    use aptos_sdk::{
+        bcs,
+        transaction_builder::TransactionFactory,
+        types::{chain_id::ChainId, transaction::TransactionPayload, LocalAccount},
+    };
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::{collections::HashMap, sync::Arc};
+
+    #[test]
+    pub fn test_governance_voting() {
+        let mut generator = GovernanceVoting::new(
+            StdRng::from_seed([0; 32]),
+            TransactionFactory::new(ChainId::test()),
+            5..7,
+            Arc::new(RwLock::new(vec![])),
+            Arc::new(RwLock::new(HashMap::new())),
+        );
+        let mut account = LocalAccount::generate(&mut StdRng::from_seed([0; 32]));
+        let txns = generator.generate_transactions(vec![&mut account], 10);
+        let functions: Vec<_> = txns
+            .iter()
+            .map(|txn| match txn.payload() {
+                TransactionPayload::EntryFunction(entry_function) => {
+                    entry_function.function().to_string()
+                },
+                _ => panic!("Expected an entry function payload"),
+            })
+            .collect();
+        // a vote on each of the proposals, after the stake pool is set up
+        assert_eq!(functions, vec![
+            "initialize_stake_owner",
+            "increase_lockup",
+            "vote",
+            "vote"
+        ]);
+        match txns[3].payload() {
+            TransactionPayload::EntryFunction(entry_function) => {
+                assert_eq!(entry_function.args()[1], bcs::to_bytes(&6u64).unwrap());
+            },
+            _ => unreachable!(),
+        }
+        assert!(generator
+            .generate_transactions(vec![&mut account], 1)
+            .is_empty());
+        assert_eq!(account.sequence_number(), 4);
+    }
+}
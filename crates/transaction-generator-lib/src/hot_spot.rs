@@ -0,0 +1,80 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use aptos_sdk::move_types::account_address::AccountAddress;
+use rand::{rngs::StdRng, Rng};
+
+// receivers of the hot spot, unless given otherwise
+pub const DEFAULT_NUM_HOT_SPOT_RECEIVERS: usize = 10;
+
+/// Contention of transfer workloads: ratio percent of transfers go to a small set of
+/// num_receivers receivers shared by all workers, the first accounts of the addresses
+/// pool, so that they conflict with each other when executed. 0 keeps all receivers
+/// random, and 100 with a single receiver has all transfers write the same resource.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct HotSpot {
+    pub ratio: usize,
+    pub num_receivers: usize,
+}
+
+impl HotSpot {
+    pub fn new(ratio: usize) -> Self {
+        Self {
+            ratio,
+            num_receivers: DEFAULT_NUM_HOT_SPOT_RECEIVERS,
+        }
+    }
+
+    pub fn none() -> Self {
+        Self::new(0)
+    }
+
+    /// Receiver of a transfer otherwise going to receiver.
+    pub fn receiver(
+        &self,
+        rng: &mut StdRng,
+        all_addresses: &[AccountAddress],
+        receiver: AccountAddress,
+    ) -> AccountAddress {
+        let num_receivers = self.num_receivers.min(all_addresses.len());
+        if self.ratio == 0 || num_receivers == 0 || rng.gen_range(0, 100) >= self.ratio {
+            return receiver;
+        }
+        all_addresses[rng.gen_range(0, num_receivers)]
+    }
+}
+
+impl Default for HotSpot {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::HotSpot;
+    use aptos_sdk::move_types::account_address::AccountAddress;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    pub fn test_hot_spot() {
+        let mut rng = StdRng::from_seed([0; 32]);
+        let addresses: Vec<_> = (0..100).map(|_| AccountAddress::random()).collect();
+        let receiver = AccountAddress::random();
+        let mut hot_receivers = |hot_spot: HotSpot| {
+            (0..1000)
+                .filter(|_| hot_spot.receiver(&mut rng, &addresses, receiver) != receiver)
+                .count()
+        };
+        assert_eq!(hot_receivers(HotSpot::none()), 0);
+        assert_eq!(hot_receivers(HotSpot::new(100)), 1000);
+        let num_hot = hot_receivers(HotSpot::new(50));
+        assert!(num_hot > 400 && num_hot < 600, "{}", num_hot);
+
+        let single = HotSpot {
+            ratio: 100,
+            num_receivers: 1,
+        };
+        assert!((0..100).all(|_| single.receiver(&mut rng, &addresses, receiver) == addresses[0]));
+    }
+}
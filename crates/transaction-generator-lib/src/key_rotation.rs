@@ -0,0 +1,236 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{TransactionGenerator, TransactionGeneratorCreator, SEND_AMOUNT};
+use aptos_crypto::{ed25519::Ed25519PrivateKey, SigningKey, Uniform};
+use aptos_infallible::RwLock;
+use aptos_sdk::{
+    bcs,
+    transaction_builder::{aptos_stdlib, TransactionFactory},
+    types::{
+        account_address::AccountAddress, transaction::SignedTransaction, AccountKey, LocalAccount,
+    },
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::Serialize;
+use std::{cmp::min, sync::Arc};
+
+// 0x1::account::ED25519_SCHEME
+const ED25519_SCHEME: u8 = 0;
+
+/// 0x1::account::RotationProofChallenge, in the 0x1::signer::SignedMessage it's signed
+/// in, so starting with its type info.
+#[derive(Serialize)]
+struct RotationProofChallenge {
+    account_address: AccountAddress,
+    module_name: String,
+    struct_name: String,
+    sequence_number: u64,
+    originator: AccountAddress,
+    current_auth_key: AccountAddress,
+    new_public_key: Vec<u8>,
+}
+
+/// Transaction rotating the authentication key of account to new_key, with proofs of
+/// knowledge of both the current and the new key, after which account signs with
+/// new_key.
+pub fn rotate_key_request(
+    account: &mut LocalAccount,
+    new_key: Ed25519PrivateKey,
+    txn_factory: &TransactionFactory,
+) -> SignedTransaction {
+    let new_key = AccountKey::from_private_key(new_key);
+    let new_public_key = new_key.public_key().to_bytes().to_vec();
+    let challenge = bcs::to_bytes(&RotationProofChallenge {
+        account_address: AccountAddress::ONE,
+        module_name: "account".to_owned(),
+        struct_name: "RotationProofChallenge".to_owned(),
+        // of the rotation itself, as it's not incremented until after it's executed
+        sequence_number: account.sequence_number(),
+        originator: account.address(),
+        current_auth_key: account.authentication_key().derived_address(),
+        new_public_key: new_public_key.clone(),
+    })
+    .unwrap();
+    let txn = account.sign_with_transaction_builder(
+        txn_factory.payload(aptos_stdlib::account_rotate_authentication_key(
+            ED25519_SCHEME,
+            account.public_key().to_bytes().to_vec(),
+            ED25519_SCHEME,
+            new_public_key,
+            account
+                .private_key()
+                .sign_arbitrary_message(&challenge)
+                .to_bytes()
+                .to_vec(),
+            new_key
+                .private_key()
+                .sign_arbitrary_message(&challenge)
+                .to_bytes()
+                .to_vec(),
+        )),
+    );
+    account.rotate_key(new_key);
+    txn
+}
+
+/// Authentication key rotations of accounts taken from the accounts pool (e.g. filled
+/// by account generation) while generating and returned to it after, each followed by
+/// transfers to itself signed with the new key. The worker's own accounts are never
+/// rotated, as later runs derive them again from their keys.
+/// Multi-ed25519 accounts only send the transfers.
+pub struct KeyRotation {
+    rng: StdRng,
+    txn_factory: TransactionFactory,
+    accounts_pool: Arc<RwLock<Vec<LocalAccount>>>,
+}
+
+impl KeyRotation {
+    pub fn new(
+        rng: StdRng,
+        txn_factory: TransactionFactory,
+        accounts_pool: Arc<RwLock<Vec<LocalAccount>>>,
+    ) -> Self {
+        Self {
+            rng,
+            txn_factory,
+            accounts_pool,
+        }
+    }
+}
+
+impl TransactionGenerator for KeyRotation {
+    fn generate_transactions(
+        &mut self,
+        accounts: Vec<&mut LocalAccount>,
+        transactions_per_account: usize,
+    ) -> Vec<SignedTransaction> {
+        // taken out of the pool, so that no other worker uses them meanwhile
+        let mut pool_accounts: Vec<_> = {
+            let mut accounts_pool = self.accounts_pool.write();
+            let needed = min(accounts.len(), accounts_pool.len());
+            (0..needed)
+                .map(|_| {
+                    let index = self.rng.gen_range(0, accounts_pool.len());
+                    accounts_pool.swap_remove(index)
+                })
+                .collect()
+        };
+        let mut requests = Vec::with_capacity(pool_accounts.len() * transactions_per_account);
+        for account in pool_accounts.iter_mut() {
+            for index in 0..transactions_per_account {
+                requests.push(
+                    if index == 0 && account.multi_ed25519_key().is_none() {
+                        let new_key = Ed25519PrivateKey::generate(
+                            &mut StdRng::from_rng(&mut self.rng).unwrap(),
+                        );
+                        rotate_key_request(account, new_key, &self.txn_factory)
+                    } else {
+                        let address = account.address();
+                        account.sign_with_transaction_builder(
+                            self.txn_factory
+                                .payload(aptos_stdlib::aptos_account_transfer(
+                                    address,
+                                    SEND_AMOUNT,
+                                )),
+                        )
+                    },
+                );
+            }
+        }
+        self.accounts_pool.write().extend(pool_accounts);
+        requests
+    }
+}
+
+pub struct KeyRotationCreator {
+    rng: StdRng,
+    txn_factory: TransactionFactory,
+    accounts_pool: Arc<RwLock<Vec<LocalAccount>>>,
+}
+
+impl KeyRotationCreator {
+    pub fn new(
+        rng: StdRng,
+        txn_factory: TransactionFactory,
+        accounts_pool: Arc<RwLock<Vec<LocalAccount>>>,
+    ) -> Self {
+        Self {
+            rng,
+            txn_factory,
+            accounts_pool,
+        }
+    }
+}
+
+impl TransactionGeneratorCreator for KeyRotationCreator {
+    fn create_transaction_generator(&mut self) -> Box<dyn TransactionGenerator> {
+        Box::new(KeyRotation::new(
+            StdRng::from_rng(&mut self.rng).unwrap(),
+            self.txn_factory.clone(),
+            self.accounts_pool.clone(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::KeyRotation;
+    use crate::TransactionGenerator;
+    use aptos_infallible::RwLock;
+    use aptos_sdk::{
+        bcs,
+        transaction_builder::TransactionFactory,
+        types::{chain_id::ChainId, transaction::TransactionPayload, LocalAccount},
+    };
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::sync::Arc;
+
+    #[test]
+    pub fn test_key_rotation() {
+        let mut rng = StdRng::from_seed([0; 32]);
+        let pool_account = LocalAccount::generate(&mut rng);
+        let address = pool_account.address();
+        let old_public_key = pool_account.public_key().clone();
+        let accounts_pool = Arc::new(RwLock::new(vec![pool_account]));
+        let mut generator = KeyRotation::new(
+            StdRng::from_seed([0; 32]),
+            TransactionFactory::new(ChainId::test()),
+            accounts_pool.clone(),
+        );
+        let mut account = LocalAccount::generate(&mut rng);
+        let txns = generator.generate_transactions(vec![&mut account], 2);
+        assert_eq!(txns.len(), 2);
+        assert!(txns.iter().all(|txn| txn.sender() == address));
+        assert_eq!(account.sequence_number(), 0);
+
+        // the rotation is signed with the old key, and the transfer after it with the new one
+        let new_public_key = match txns[0].payload() {
+            TransactionPayload::EntryFunction(entry_function) => {
+                assert_eq!(
+                    entry_function.function().as_str(),
+                    "rotate_authentication_key"
+                );
+                let new_public_key: Vec<u8> = bcs::from_bytes(&entry_function.args()[3]).unwrap();
+                new_public_key
+            },
+            _ => panic!("Expected an entry function payload"),
+        };
+        assert_eq!(
+            txns[0].authenticator().sender().public_key_bytes(),
+            old_public_key.to_bytes().to_vec()
+        );
+        assert_eq!(
+            txns[1].authenticator().sender().public_key_bytes(),
+            new_public_key
+        );
+        assert!(txns.into_iter().all(|txn| txn.check_signature().is_ok()));
+
+        let pool_account = accounts_pool.write().pop().unwrap();
+        assert_eq!(
+            pool_account.public_key().to_bytes().to_vec(),
+            new_public_key
+        );
+        assert_eq!(pool_account.sequence_number(), 2);
+    }
+}
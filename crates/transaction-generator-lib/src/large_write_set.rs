@@ -0,0 +1,234 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    bytecode_builder::BytecodeBuilder, nft_mint_and_transfer::create_and_fund_accounts,
+    publishing::publish_util::PackageHandler, TransactionExecutor, TransactionGenerator,
+    TransactionGeneratorCreator,
+};
+use aptos_logger::info;
+use aptos_sdk::{
+    move_types::language_storage::ModuleId,
+    transaction_builder::TransactionFactory,
+    types::{
+        transaction::{Script, SignedTransaction, TransactionArgument, TransactionPayload},
+        LocalAccount,
+    },
+};
+use move_binary_format::file_format::{Bytecode, SignatureToken};
+use rand::{rngs::StdRng, RngCore, SeedableRng};
+use std::sync::Arc;
+
+/// `fun main(sender: signer, data: vector<u8>)`, calling bytes_make_or_change of each of
+/// modules (published instances of the simple package) with data, so that it writes a
+/// ByteResource of each of them under the sender. In bytecode version 5, like the
+/// scripts of ScriptKind.
+pub fn write_resources_script(modules: &[ModuleId]) -> Vec<u8> {
+    use SignatureToken::*;
+
+    let mut builder = BytecodeBuilder::default();
+    let bytes = || Vector(Box::new(U8));
+    let mut code = Vec::with_capacity(modules.len() * 3 + 1);
+    for module in modules {
+        let module = builder.module(*module.address(), module.name().as_str());
+        let bytes_make_or_change = builder.function(
+            module,
+            "bytes_make_or_change",
+            vec![Reference(Box::new(Signer)), bytes()],
+            vec![],
+            vec![],
+        );
+        code.extend([
+            Bytecode::ImmBorrowLoc(0),
+            Bytecode::CopyLoc(1),
+            Bytecode::Call(bytes_make_or_change),
+        ]);
+    }
+    code.push(Bytecode::Ret);
+    builder.into_script(vec![Signer, bytes()], vec![], code)
+}
+
+/// Transactions writing bytes_per_resource random bytes to each of the resources written
+/// by its script, so that the cost of storage writes and of updating their proofs can be
+/// measured as a function of write-set size. The first transaction of each sender
+/// creates the resources, and the next ones overwrite them.
+pub struct LargeWriteSetGenerator {
+    rng: StdRng,
+    txn_factory: TransactionFactory,
+    code: Arc<Vec<u8>>,
+    bytes_per_resource: usize,
+}
+
+impl LargeWriteSetGenerator {
+    pub fn new(
+        rng: StdRng,
+        txn_factory: TransactionFactory,
+        code: Arc<Vec<u8>>,
+        bytes_per_resource: usize,
+    ) -> Self {
+        Self {
+            rng,
+            txn_factory,
+            code,
+            bytes_per_resource,
+        }
+    }
+}
+
+impl TransactionGenerator for LargeWriteSetGenerator {
+    fn generate_transactions(
+        &mut self,
+        accounts: Vec<&mut LocalAccount>,
+        transactions_per_account: usize,
+    ) -> Vec<SignedTransaction> {
+        let mut requests = Vec::with_capacity(accounts.len() * transactions_per_account);
+        for account in accounts {
+            for _ in 0..transactions_per_account {
+                let mut data = vec![0u8; self.bytes_per_resource];
+                self.rng.fill_bytes(&mut data);
+                let payload =
+                    TransactionPayload::Script(Script::new((*self.code).clone(), vec![], vec![
+                        TransactionArgument::U8Vector(data),
+                    ]));
+                requests
+                    .push(account.sign_with_transaction_builder(self.txn_factory.payload(payload)));
+            }
+        }
+        requests
+    }
+}
+
+pub struct LargeWriteSetCreator {
+    rng: StdRng,
+    txn_factory: TransactionFactory,
+    code: Arc<Vec<u8>>,
+    bytes_per_resource: usize,
+}
+
+impl LargeWriteSetCreator {
+    /// Publishes num_resources instances of the simple package, each from its own new
+    /// account, whose resources each transaction writes total_bytes across, split evenly.
+    pub async fn new(
+        mut rng: StdRng,
+        txn_factory: TransactionFactory,
+        init_txn_factory: TransactionFactory,
+        source_accounts: &mut [LocalAccount],
+        txn_executor: &dyn TransactionExecutor,
+        num_resources: usize,
+        total_bytes: usize,
+    ) -> Self {
+        assert!(
+            num_resources > 0,
+            "Large write sets need resources to write"
+        );
+        let mut publishers = create_and_fund_accounts(
+            &mut rng,
+            &init_txn_factory,
+            source_accounts,
+            txn_executor,
+            num_resources,
+        )
+        .await;
+        let mut package_handler = PackageHandler::new();
+        let mut modules = Vec::with_capacity(num_resources);
+        let mut requests = Vec::with_capacity(num_resources);
+        for publisher in publishers.iter_mut() {
+            let package = package_handler.pick_package(&mut rng, publisher);
+            requests.push(package.publish_transaction(publisher, &init_txn_factory));
+            modules.push(package.module_id());
+        }
+        info!(
+            "Publishing {} packages for large write sets",
+            requests.len()
+        );
+        txn_executor.execute_transactions(&requests).await.unwrap();
+
+        Self {
+            rng,
+            txn_factory,
+            code: Arc::new(write_resources_script(&modules)),
+            bytes_per_resource: total_bytes / num_resources,
+        }
+    }
+}
+
+impl TransactionGeneratorCreator for LargeWriteSetCreator {
+    fn create_transaction_generator(&mut self) -> Box<dyn TransactionGenerator> {
+        Box::new(LargeWriteSetGenerator::new(
+            StdRng::from_rng(&mut self.rng).unwrap(),
+            self.txn_factory.clone(),
+            self.code.clone(),
+            self.bytes_per_resource,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{write_resources_script, LargeWriteSetGenerator};
+    use crate::TransactionGenerator;
+    use aptos_sdk::{
+        move_types::{
+            account_address::AccountAddress, identifier::Identifier, language_storage::ModuleId,
+        },
+        transaction_builder::TransactionFactory,
+        types::{
+            chain_id::ChainId,
+            transaction::{TransactionArgument, TransactionPayload},
+            LocalAccount,
+        },
+    };
+    use move_binary_format::file_format::CompiledScript;
+    use move_bytecode_verifier::verify_script;
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::sync::Arc;
+
+    #[test]
+    pub fn test_large_write_set() {
+        let publisher = AccountAddress::random();
+        let modules: Vec<_> = (0..3)
+            .map(|index| {
+                ModuleId::new(
+                    publisher,
+                    Identifier::new(format!("simple{}", index)).unwrap(),
+                )
+            })
+            .chain(std::iter::once(ModuleId::new(
+                AccountAddress::random(),
+                Identifier::new("simple3").unwrap(),
+            )))
+            .collect();
+        let code = write_resources_script(&modules);
+        let script = CompiledScript::deserialize(&code).unwrap();
+        assert_eq!(script.version, 5);
+        assert_eq!(script.function_handles.len(), 4);
+        assert_eq!(script.address_identifiers.len(), 2);
+        verify_script(&script).unwrap();
+
+        let mut generator = LargeWriteSetGenerator::new(
+            StdRng::from_seed([0; 32]),
+            TransactionFactory::new(ChainId::test()),
+            Arc::new(code.clone()),
+            100,
+        );
+        let mut account = LocalAccount::generate(&mut StdRng::from_seed([0; 32]));
+        let txns = generator.generate_transactions(vec![&mut account], 2);
+        assert_eq!(txns.len(), 2);
+        let data: Vec<_> = txns
+            .iter()
+            .map(|txn| match txn.payload() {
+                TransactionPayload::Script(script) => {
+                    assert_eq!(script.code(), code.as_slice());
+                    match script.args() {
+                        [TransactionArgument::U8Vector(data)] => data.clone(),
+                        args => panic!("Unexpected args {:?}", args),
+                    }
+                },
+                _ => panic!("Expected a script payload"),
+            })
+            .collect();
+        assert!(data.iter().all(|data| data.len() == 100));
+        // each transaction writes new data
+        assert_ne!(data[0], data[1]);
+    }
+}
@@ -0,0 +1,106 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+// Each power of two is split into this many buckets, so relative error is at most 1/8.
+const SUB_BUCKETS_BITS: u64 = 3;
+const SUB_BUCKETS: u64 = 1 << SUB_BUCKETS_BITS;
+// Enough to cover latencies up to u32::MAX millis, larger ones land in the last bucket.
+const NUM_BUCKETS: usize = ((33 - SUB_BUCKETS_BITS) * SUB_BUCKETS) as usize;
+
+/// Log-linear histogram of latencies in milliseconds, which can be
+/// concurrently recorded into, with small and fixed memory footprint.
+#[derive(Debug)]
+pub struct LatencyHistogram {
+    buckets: Vec<AtomicU64>,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self {
+            buckets: (0..NUM_BUCKETS).map(|_| AtomicU64::new(0)).collect(),
+        }
+    }
+}
+
+fn bucket_index(millis: u64) -> usize {
+    if millis < SUB_BUCKETS {
+        return millis as usize;
+    }
+    let exp = 63 - millis.leading_zeros() as u64;
+    let sub_bucket = (millis >> (exp - SUB_BUCKETS_BITS)) & (SUB_BUCKETS - 1);
+    (((exp - SUB_BUCKETS_BITS + 1) * SUB_BUCKETS + sub_bucket) as usize).min(NUM_BUCKETS - 1)
+}
+
+// Largest value that lands in the given bucket.
+fn bucket_upper_bound(index: usize) -> u64 {
+    let index = index as u64;
+    if index < SUB_BUCKETS {
+        return index;
+    }
+    let shift = index / SUB_BUCKETS - 1;
+    ((SUB_BUCKETS + index % SUB_BUCKETS + 1) << shift) - 1
+}
+
+impl LatencyHistogram {
+    pub fn record(&self, latency: Duration) {
+        self.buckets[bucket_index(latency.as_millis() as u64)].fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.buckets
+            .iter()
+            .map(|bucket| bucket.load(Ordering::Relaxed))
+            .sum()
+    }
+
+    /// Returns (an upper bound of) the given percentile latency in milliseconds,
+    /// or 0 if nothing was recorded.
+    pub fn percentile(&self, numerator: u64, denominator: u64) -> u64 {
+        let p_count = self.count() * numerator / denominator;
+        let mut counter = 0u64;
+        for (index, bucket) in self.buckets.iter().enumerate() {
+            counter += bucket.load(Ordering::Relaxed);
+            if counter >= p_count && counter > 0 {
+                return bucket_upper_bound(index);
+            }
+        }
+        0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{bucket_index, bucket_upper_bound, LatencyHistogram, NUM_BUCKETS};
+    use std::time::Duration;
+
+    #[test]
+    pub fn test_bucket_bounds() {
+        for millis in [0, 1, 7, 8, 9, 15, 16, 17, 100, 1000, 12345, 1 << 20] {
+            let index = bucket_index(millis);
+            assert!(bucket_upper_bound(index) >= millis);
+            assert!(index == 0 || bucket_upper_bound(index - 1) < millis);
+            assert!(bucket_upper_bound(index) - millis <= millis / 8);
+        }
+        assert_eq!(bucket_index(u64::MAX), NUM_BUCKETS - 1);
+    }
+
+    #[test]
+    pub fn test_latency_percentiles() {
+        let histogram = LatencyHistogram::default();
+        assert_eq!(histogram.percentile(50, 100), 0);
+
+        for i in 1..101 {
+            histogram.record(Duration::from_millis(i * 10));
+        }
+        assert_eq!(histogram.count(), 100);
+        let p50 = histogram.percentile(50, 100);
+        assert!((500..=500 + 500 / 8).contains(&p50));
+        let p99 = histogram.percentile(99, 100);
+        assert!((990..=990 + 990 / 8).contains(&p99));
+    }
+}
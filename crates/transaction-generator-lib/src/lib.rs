@@ -3,41 +3,91 @@
 
 #![forbid(unsafe_code)]
 
-use anyhow::Result;
-use aptos_infallible::RwLock;
+use anyhow::{bail, Result};
+use aptos_crypto::HashValue;
+use aptos_infallible::{Mutex, RwLock};
 use aptos_logger::{sample, sample::SampleRate, warn};
+use aptos_rest_client::aptos_api_types::ViewRequest;
 use aptos_sdk::{
-    move_types::account_address::AccountAddress,
+    bcs,
+    move_types::{
+        account_address::AccountAddress, language_storage::StructTag, move_resource::MoveResource,
+    },
     transaction_builder::TransactionFactory,
-    types::{transaction::SignedTransaction, LocalAccount},
+    types::{
+        transaction::{ExecutionStatus, SignedTransaction, TransactionStatus},
+        write_set::WriteSet,
+        LocalAccount,
+    },
 };
 use async_trait::async_trait;
+use rand::{rngs::StdRng, SeedableRng};
+use serde::Serialize;
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     sync::{
-        atomic::{AtomicUsize, Ordering},
+        atomic::{AtomicU64, AtomicUsize, Ordering},
         Arc,
     },
-    time::Duration,
+    time::{Duration, Instant},
 };
+use thiserror::Error;
 
 pub mod account_generator;
 pub mod accounts_pool_wrapper;
 pub mod args;
+pub mod batch_transfer;
+mod bytecode_builder;
 pub mod call_custom_modules;
+pub mod entry_function_generator;
+pub mod fungible_asset;
+pub mod governance_voting;
+pub mod hot_spot;
+pub mod key_rotation;
+pub mod large_write_set;
+pub mod latency_histogram;
+pub mod multi_agent_token_transfer;
+pub mod multisig_transactions;
 pub mod nft_mint_and_transfer;
+pub mod object_resource_group;
 pub mod p2p_transaction_generator;
+pub mod phased_generator;
 pub mod publish_modules;
 mod publishing;
+pub mod script_generator;
+pub mod staking_operations;
+pub mod table_operations;
+pub mod token_v2_mint_transfer_burn;
 pub mod transaction_mix_generator;
+pub mod txn_factory_overrides;
+pub mod workload_mix;
 use self::{
-    account_generator::AccountGeneratorCreator, call_custom_modules::CallCustomModulesCreator,
+    account_generator::AccountGeneratorCreator,
+    batch_transfer::BatchTransferGeneratorCreator,
+    call_custom_modules::CallCustomModulesCreator,
+    entry_function_generator::{EntryFunctionGeneratorCreator, EntryFunctionTemplate},
+    fungible_asset::FungibleAssetTransferCreator,
+    governance_voting::GovernanceVotingCreator,
+    hot_spot::HotSpot,
+    key_rotation::KeyRotationCreator,
+    large_write_set::LargeWriteSetCreator,
+    multi_agent_token_transfer::MultiAgentTokenTransferCreator,
+    multisig_transactions::MultisigTransactionsCreator,
     nft_mint_and_transfer::NFTMintAndTransferGeneratorCreator,
+    object_resource_group::ObjectResourceGroupCreator,
     p2p_transaction_generator::P2PTransactionGeneratorCreator,
-    publish_modules::PublishPackageCreator,
+    phased_generator::{GeneratorPhase, PhasedTransactionGeneratorCreator},
+    publish_modules::{PublishAndUpgradeCreator, PublishPackageCreator},
+    script_generator::{ScriptGeneratorCreator, ScriptKind},
+    staking_operations::StakingOperationsCreator,
+    table_operations::TableOperationsCreator,
+    token_v2_mint_transfer_burn::TokenV2MintTransferBurnCreator,
     transaction_mix_generator::PhasedTxnMixGeneratorCreator,
+    txn_factory_overrides::TxnFactoryOverrides,
+};
+use crate::{
+    accounts_pool_wrapper::AccountsPoolWrapperCreator, latency_histogram::LatencyHistogram,
 };
-use crate::accounts_pool_wrapper::AccountsPoolWrapperCreator;
 pub use publishing::module_simple::EntryPoints;
 
 pub const SEND_AMOUNT: u64 = 1;
@@ -47,16 +97,76 @@ pub enum TransactionType {
     CoinTransfer {
         invalid_transaction_ratio: usize,
         sender_use_account_pool: bool,
+        hot_spot: HotSpot,
     },
     AccountGeneration {
         add_created_accounts_to_pool: bool,
         max_account_working_set: usize,
         creation_balance: u64,
     },
+    /// Coin transfers to num_recipients accounts in each transaction
+    BatchTransfer {
+        num_recipients: usize,
+        hot_spot: HotSpot,
+    },
     NftMintAndTransfer,
+    MultiAgentTokenTransfer,
+    /// Transfers from multisig accounts with num_owners owners, proposed and approved
+    /// by num_signatures_required of them before being executed
+    MultisigTransactions {
+        num_owners: usize,
+        num_signatures_required: usize,
+    },
+    /// Token V2 digital assets minted, transferred and burned, from num_collections
+    /// collections for each worker, with up to tokens_per_collection tokens alive
+    TokenV2MintTransferBurn {
+        num_collections: usize,
+        tokens_per_collection: usize,
+    },
+    /// Transfers of a fungible asset created for the workload, between the primary
+    /// stores of the source accounts
+    FungibleAssetTransfer,
+    /// Objects with num_properties properties of property_size bytes in their resource
+    /// group, created and then mutated
+    ObjectResourceGroup {
+        num_properties: usize,
+        property_size: usize,
+    },
+    /// Stake pool operations by each account on its own stake pool, or delegation pool
+    /// operations by each account on a shared delegation pool
+    StakingOperations {
+        delegation_pool: bool,
+    },
+    /// Votes of accounts on governance proposals created for the workload
+    GovernanceVoting,
+    /// Calls of a module published for the workload, each doing inserts into, updates
+    /// of and reads from a table of the sender
+    TableOperations {
+        inserts: usize,
+        updates: usize,
+        reads: usize,
+    },
+    /// Authentication key rotations of accounts from the accounts pool
+    KeyRotation,
+    /// Transactions writing total_bytes bytes, split evenly across num_resources
+    /// resources of the sender, of as many modules published for the workload
+    LargeWriteSet {
+        num_resources: usize,
+        total_bytes: usize,
+    },
     PublishPackage {
         use_account_pool: bool,
     },
+    /// Packages published and repeatedly upgraded by accounts from the accounts pool
+    PublishAndUpgrade,
+    /// Calls of the entry function at index among the ones given to the run
+    CustomEntryFunction {
+        index: usize,
+    },
+    /// Move script payloads, of the given script
+    Script {
+        kind: ScriptKind,
+    },
     CallCustomModules {
         entry_point: EntryPoints,
         num_modules: usize,
@@ -69,6 +179,7 @@ impl TransactionType {
         Self::CoinTransfer {
             invalid_transaction_ratio: 0,
             sender_use_account_pool: false,
+            hot_spot: HotSpot::none(),
         }
     }
 
@@ -95,6 +206,67 @@ impl TransactionType {
             use_account_pool: false,
         }
     }
+
+    /// Same transaction type, with ratio percent of its transfers going to the hot spot,
+    /// for transfer types.
+    pub fn with_hot_spot_ratio(self, ratio: usize) -> Self {
+        match self {
+            Self::CoinTransfer {
+                invalid_transaction_ratio,
+                sender_use_account_pool,
+                hot_spot,
+            } => Self::CoinTransfer {
+                invalid_transaction_ratio,
+                sender_use_account_pool,
+                hot_spot: HotSpot { ratio, ..hot_spot },
+            },
+            Self::BatchTransfer {
+                num_recipients,
+                hot_spot,
+            } => Self::BatchTransfer {
+                num_recipients,
+                hot_spot: HotSpot { ratio, ..hot_spot },
+            },
+            transaction_type => transaction_type,
+        }
+    }
+
+    /// Same transaction type, with its writes split across num_resources resources and
+    /// adding up to total_bytes, where given, for write-set types.
+    pub fn with_write_set(
+        self,
+        new_num_resources: Option<usize>,
+        new_total_bytes: Option<usize>,
+    ) -> Self {
+        match self {
+            Self::LargeWriteSet {
+                num_resources,
+                total_bytes,
+            } => Self::LargeWriteSet {
+                num_resources: new_num_resources.unwrap_or(num_resources),
+                total_bytes: new_total_bytes.unwrap_or(total_bytes),
+            },
+            transaction_type => transaction_type,
+        }
+    }
+
+    /// Same transaction type, with loop_count iterations in each call, for loop calls.
+    pub fn with_loop_count(self, loop_count: u64) -> Self {
+        match self {
+            Self::CallCustomModules {
+                entry_point: EntryPoints::Loopy { .. },
+                num_modules,
+                use_account_pool,
+            } => Self::CallCustomModules {
+                entry_point: EntryPoints::Loopy {
+                    loop_count: Some(loop_count),
+                },
+                num_modules,
+                use_account_pool,
+            },
+            transaction_type => transaction_type,
+        }
+    }
 }
 
 impl Default for TransactionType {
@@ -109,6 +281,18 @@ pub trait TransactionGenerator: Sync + Send {
         accounts: Vec<&mut LocalAccount>,
         transactions_per_account: usize,
     ) -> Vec<SignedTransaction>;
+
+    /// For generators running a mix of workloads, phase and index (in the transaction
+    /// mix of that phase) of the workload the last generated transactions came from.
+    fn last_generated_workload(&self) -> Option<(usize, usize)> {
+        None
+    }
+
+    /// For generators switching between phases of their own, label of the phase the last
+    /// generated transactions came from.
+    fn last_generated_label(&self) -> Option<&str> {
+        None
+    }
 }
 
 #[async_trait]
@@ -116,26 +300,352 @@ pub trait TransactionGeneratorCreator: Sync + Send {
     fn create_transaction_generator(&mut self) -> Box<dyn TransactionGenerator>;
 }
 
+#[derive(Default)]
+pub struct ClientCounters {
+    pub successes: AtomicUsize,
+    pub submit_failures: AtomicUsize,
+    pub wait_failures: AtomicUsize,
+    // sum of latencies of successful requests
+    pub latency_millis: AtomicU64,
+    // exponentially weighted moving average of latencies of successful requests,
+    // 0 if there were none
+    pub ewma_latency_micros: AtomicU64,
+    // of successful requests
+    pub latencies: LatencyHistogram,
+    // of the last failed request
+    pub last_error: Mutex<Option<String>>,
+}
+
+// Weight of the newest sample in ClientCounters::ewma_latency_micros
+const EWMA_LATENCY_ALPHA: f64 = 0.2;
+
+impl ClientCounters {
+    /// Records the latency of a successful request.
+    pub fn record_success(&self, latency: Duration) {
+        self.successes.fetch_add(1, Ordering::Relaxed);
+        self.latency_millis
+            .fetch_add(latency.as_millis() as u64, Ordering::Relaxed);
+        self.record_ewma_latency(latency);
+        self.latencies.record(latency);
+    }
+
+    pub fn record_error(&self, error: String) {
+        *self.last_error.lock() = Some(error);
+    }
+
+    pub fn record_ewma_latency(&self, latency: Duration) {
+        let sample = (latency.as_micros() as u64).max(1);
+        let _ =
+            self.ewma_latency_micros
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |ewma| {
+                    Some(
+                        if ewma == 0 {
+                            sample
+                        } else {
+                            ((1.0 - EWMA_LATENCY_ALPHA) * ewma as f64
+                                + EWMA_LATENCY_ALPHA * sample as f64)
+                                as u64
+                        },
+                    )
+                });
+    }
+
+    /// Returns None if no latency was recorded yet.
+    pub fn ewma_latency(&self) -> Option<Duration> {
+        match self.ewma_latency_micros.load(Ordering::Relaxed) {
+            0 => None,
+            micros => Some(Duration::from_micros(micros)),
+        }
+    }
+
+    pub fn snapshot(&self) -> ClientCountersSnapshot {
+        ClientCountersSnapshot {
+            successes: self.successes.load(Ordering::Relaxed),
+            submit_failures: self.submit_failures.load(Ordering::Relaxed),
+            wait_failures: self.wait_failures.load(Ordering::Relaxed),
+            latency_millis: self.latency_millis.load(Ordering::Relaxed),
+            ewma_latency_millis: self.ewma_latency_micros.load(Ordering::Relaxed) / 1000,
+            latency_p50_ms: self.latencies.percentile(50, 100),
+            latency_p99_ms: self.latencies.percentile(99, 100),
+            last_error: self.last_error.lock().clone(),
+        }
+    }
+
+    /// As (successes, submit failures, wait failures).
+    fn show(&self) -> String {
+        format!(
+            "({}, {}, {})",
+            self.successes.load(Ordering::Relaxed),
+            self.submit_failures.load(Ordering::Relaxed),
+            self.wait_failures.load(Ordering::Relaxed),
+        )
+    }
+
+    /// Latencies and the last error, if any, each after a comma.
+    fn show_latencies_and_error(&self) -> String {
+        let mut shown = String::new();
+        if self.latencies.count() > 0 {
+            shown.push_str(&format!(
+                ", p50 {} ms, p99 {} ms",
+                self.latencies.percentile(50, 100),
+                self.latencies.percentile(99, 100),
+            ));
+        }
+        if let Some(error) = self.last_error.lock().as_ref() {
+            shown.push_str(&format!(", last error: {}", error));
+        }
+        shown
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct ClientCountersSnapshot {
+    pub successes: usize,
+    pub submit_failures: usize,
+    pub wait_failures: usize,
+    pub latency_millis: u64,
+    pub ewma_latency_millis: u64,
+    pub latency_p50_ms: u64,
+    pub latency_p99_ms: u64,
+    pub last_error: Option<String>,
+}
+
+/// Classification of failed requests, to tell apart network and chain bottlenecks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FailureCategory {
+    Connection,
+    RateLimited,
+    MempoolFull,
+    SequenceNumberTooOld,
+    VmValidation,
+    Timeout,
+    Other,
+}
+
+impl FailureCategory {
+    pub const ALL: [FailureCategory; NUM_FAILURE_CATEGORIES] = [
+        FailureCategory::Connection,
+        FailureCategory::RateLimited,
+        FailureCategory::MempoolFull,
+        FailureCategory::SequenceNumberTooOld,
+        FailureCategory::VmValidation,
+        FailureCategory::Timeout,
+        FailureCategory::Other,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FailureCategory::Connection => "connection",
+            FailureCategory::RateLimited => "rate_limited",
+            FailureCategory::MempoolFull => "mempool_full",
+            FailureCategory::SequenceNumberTooOld => "seq_num_too_old",
+            FailureCategory::VmValidation => "vm_validation",
+            FailureCategory::Timeout => "timeout",
+            FailureCategory::Other => "other",
+        }
+    }
+}
+
+pub const NUM_FAILURE_CATEGORIES: usize = 7;
+
+/// Outcome of a committed transaction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TransactionReceipt {
+    /// Hash of the committed transaction, which can differ from the submitted one,
+    /// if executor re-signed it.
+    pub hash: HashValue,
+    // fields are None if not known to the executor
+    pub version: Option<u64>,
+    pub gas_used: Option<u64>,
+    pub vm_status: Option<String>,
+    /// Client that confirmed the commit.
+    pub confirmed_by: Option<String>,
+}
+
+impl TransactionReceipt {
+    /// Receipt for a committed transaction, without any details of its commit.
+    pub fn committed(txn: &SignedTransaction) -> Self {
+        Self {
+            hash: txn.clone().committed_hash(),
+            version: None,
+            gas_used: None,
+            vm_status: None,
+            confirmed_by: None,
+        }
+    }
+}
+
+/// Outcome of simulating a transaction, which isn't committed, see
+/// TransactionExecutor::simulate.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SimulationResult {
+    pub gas_used: u64,
+    pub success: bool,
+    pub vm_status: String,
+    /// State items created or modified by the transaction
+    pub num_writes: usize,
+    /// State items deleted by the transaction
+    pub num_deletions: usize,
+    pub num_events: usize,
+}
+
+impl SimulationResult {
+    pub fn new(
+        gas_used: u64,
+        status: &TransactionStatus,
+        write_set: &WriteSet,
+        num_events: usize,
+    ) -> Self {
+        let num_deletions = write_set
+            .iter()
+            .filter(|(_, write_op)| write_op.is_deletion())
+            .count();
+        Self {
+            gas_used,
+            success: matches!(status, TransactionStatus::Keep(ExecutionStatus::Success)),
+            vm_status: format!("{:?}", status),
+            num_writes: write_set.iter().count() - num_deletions,
+            num_deletions,
+            num_events,
+        }
+    }
+}
+
+/// Why executing a transaction failed, for callers to branch on.
+/// TransactionExecutor errors can be downcast to it, via TxnExecutionError::from_error.
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum TxnExecutionError {
+    #[error("Transaction not committed after {rounds} submission rounds: {last_error}")]
+    SubmitExhausted { rounds: usize, last_error: String },
+    #[error("Transaction expired, it is guaranteed it will not be committed")]
+    Expired,
+    #[error("Transaction committed on chain, but failed execution: {vm_status}")]
+    CommittedButAborted { vm_status: String },
+    #[error("Transaction failed in simulation, and was not submitted: {vm_status}")]
+    SimulationFailed { vm_status: String },
+    #[error("[{client}] Endpoint is unhealthy")]
+    EndpointUnhealthy { client: String },
+    #[error("Transaction confirmed only on {confirmations} out of required {quorum} clients")]
+    QuorumNotReached { confirmations: usize, quorum: usize },
+    #[error("Deadline reached before transaction was committed")]
+    DeadlineReached,
+    /// Execution of a batch was cancelled at deadline, with these (sender, sequence number)
+    /// transactions not committed.
+    #[error("Deadline reached with {} transactions unconfirmed", .transactions.len())]
+    Unconfirmed {
+        transactions: Vec<(AccountAddress, u64)>,
+    },
+}
+
+impl TxnExecutionError {
+    pub fn from_error(error: &anyhow::Error) -> Option<&TxnExecutionError> {
+        error.chain().find_map(|cause| cause.downcast_ref())
+    }
+}
+
+#[derive(Default)]
 pub struct CounterState {
     pub submit_failures: Vec<AtomicUsize>,
     pub wait_failures: Vec<AtomicUsize>,
     pub successes: AtomicUsize,
-    // (success, submit_fail, wait_fail)
-    pub by_client: HashMap<String, (AtomicUsize, AtomicUsize, AtomicUsize)>,
+    // successes by the (0-based) submission round the transaction got committed from,
+    // transactions only found committed after their last round are counted in the
+    // following entry, and last entry also counts all later rounds
+    pub successes_by_round: Vec<AtomicUsize>,
+    // populated only if executor doesn't wait for commit, and counts
+    // transactions accepted into mempool instead of successes
+    pub accepted: AtomicUsize,
+    pub by_client: HashMap<String, ClientCounters>,
+    // empty if clients are not tiered
+    pub by_tier: Vec<ClientCounters>,
+    // indexed by FailureCategory
+    pub failures_by_category: [AtomicUsize; NUM_FAILURE_CATEGORIES],
+    pub mempool_full_backoffs: AtomicUsize,
+    // populated only if transactions are simulated before submission
+    pub simulated: AtomicUsize,
+    pub simulated_gas_used: AtomicU64,
+    pub simulation_failures: AtomicUsize,
+    // from first submission, until transaction is seen committed, across all retries
+    pub latencies: LatencyHistogram,
+}
+
+/// Point in time copy of CounterState, which can be serialized for external monitoring.
+#[derive(Clone, Debug, Serialize)]
+pub struct CounterStateSnapshot {
+    pub successes: usize,
+    // trimmed of trailing zeros
+    pub successes_by_round: Vec<usize>,
+    pub accepted: usize,
+    // per round, trimmed of trailing zeros
+    pub submit_failures: Vec<usize>,
+    pub wait_failures: Vec<usize>,
+    pub by_client: BTreeMap<String, ClientCountersSnapshot>,
+    pub by_tier: Vec<ClientCountersSnapshot>,
+    pub failures_by_category: BTreeMap<&'static str, usize>,
+    pub mempool_full_backoffs: usize,
+    pub simulated: usize,
+    pub simulated_gas_used: u64,
+    pub simulation_failures: usize,
+    pub latency_p50_ms: u64,
+    pub latency_p90_ms: u64,
+    pub latency_p99_ms: u64,
 }
 
 #[async_trait]
 pub trait TransactionExecutor: Sync + Send {
     async fn get_account_balance(&self, account_address: AccountAddress) -> Result<u64>;
 
+    /// Balances of the accounts, in the same order. By default, they're queried one by
+    /// one.
+    async fn get_account_balances(&self, account_addresses: &[AccountAddress]) -> Result<Vec<u64>> {
+        let mut balances = Vec::with_capacity(account_addresses.len());
+        for account_address in account_addresses {
+            balances.push(self.get_account_balance(*account_address).await?);
+        }
+        Ok(balances)
+    }
+
     async fn query_sequence_number(&self, account_address: AccountAddress) -> Result<u64>;
 
+    /// Data of the resource_type resource of the account, if it has one.
+    async fn get_account_resource(
+        &self,
+        account_address: AccountAddress,
+        resource_type: &str,
+    ) -> Result<Option<serde_json::Value>>;
+
+    /// BCS bytes of the resource_type resource of the account, if it has one, see
+    /// get_resource.
+    async fn get_resource_bytes(
+        &self,
+        account_address: AccountAddress,
+        resource_type: &StructTag,
+    ) -> Result<Option<Vec<u8>>>;
+
+    /// Return values of function (e.g. `0x1::coin::balance`), called as a view function
+    /// with type_args and args, all as in the JSON of the view API.
+    async fn view(
+        &self,
+        function: &str,
+        type_args: &[String],
+        args: Vec<serde_json::Value>,
+    ) -> Result<Vec<serde_json::Value>>;
+
+    /// Executes txn against the latest state, without committing it, e.g. to find
+    /// the gas of a payload before a run. Not all executors support it.
+    async fn simulate(&self, txn: &SignedTransaction) -> Result<SimulationResult> {
+        bail!(
+            "Simulation not supported, for transaction from {}",
+            txn.sender()
+        )
+    }
+
     async fn execute_transactions(&self, txns: &[SignedTransaction]) -> Result<()> {
         self.execute_transactions_with_counter(txns, &CounterState {
             submit_failures: vec![AtomicUsize::new(0)],
             wait_failures: vec![AtomicUsize::new(0)],
-            successes: AtomicUsize::new(0),
-            by_client: HashMap::new(),
+            successes_by_round: vec![AtomicUsize::new(0)],
+            ..Default::default()
         })
         .await
     }
@@ -146,9 +656,71 @@ pub trait TransactionExecutor: Sync + Send {
         state: &CounterState,
     ) -> Result<()>;
 
+    /// Same as execute_transactions_with_counter, but fails if transactions are not
+    /// committed by the deadline, instead of the executor deciding on its own how long
+    /// to keep retrying. By default, execution is abandoned once the deadline is reached.
+    async fn execute_transactions_with_deadline(
+        &self,
+        txns: &[SignedTransaction],
+        state: &CounterState,
+        deadline: Instant,
+    ) -> Result<()> {
+        tokio::time::timeout_at(
+            deadline.into(),
+            self.execute_transactions_with_counter(txns, state),
+        )
+        .await
+        .map_err(|_| {
+            anyhow::Error::new(TxnExecutionError::DeadlineReached)
+                .context(format!("Tried executing {} txns", txns.len()))
+        })?
+    }
+
+    /// Same as execute_transactions_with_counter, but returns a receipt for each of
+    /// the transactions, in the same order. By default, receipts have no details.
+    async fn execute_transactions_with_receipts(
+        &self,
+        txns: &[SignedTransaction],
+        state: &CounterState,
+    ) -> Result<Vec<TransactionReceipt>> {
+        self.execute_transactions_with_counter(txns, state).await?;
+        Ok(txns.iter().map(TransactionReceipt::committed).collect())
+    }
+
     fn create_counter_state(&self) -> CounterState;
 }
 
+// generic, so not in the trait, which executors are used as trait objects of
+impl dyn TransactionExecutor + '_ {
+    /// The T resource of the account, if it has one.
+    pub async fn get_resource<T: MoveResource>(
+        &self,
+        account_address: AccountAddress,
+    ) -> Result<Option<T>> {
+        self.get_resource_bytes(account_address, &T::struct_tag())
+            .await?
+            .map(|bytes| bcs::from_bytes(&bytes))
+            .transpose()
+            .map_err(anyhow::Error::from)
+    }
+}
+
+/// Request to the view API, for TransactionExecutor::view.
+pub fn view_request(
+    function: &str,
+    type_args: &[String],
+    args: Vec<serde_json::Value>,
+) -> Result<ViewRequest> {
+    Ok(ViewRequest {
+        function: function.parse()?,
+        type_arguments: type_args
+            .iter()
+            .map(|type_arg| type_arg.parse())
+            .collect::<Result<_>>()?,
+        arguments: args,
+    })
+}
+
 fn failed_requests_to_trimmed_vec(failed_requests: &[AtomicUsize]) -> Vec<usize> {
     let mut result = failed_requests
         .iter()
@@ -161,27 +733,145 @@ fn failed_requests_to_trimmed_vec(failed_requests: &[AtomicUsize]) -> Vec<usize>
 }
 
 impl CounterState {
+    pub fn record_failure_category(&self, category: FailureCategory) {
+        self.failures_by_category[category as usize].fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_success_round(&self, round: usize) {
+        if let Some(last) = self.successes_by_round.len().checked_sub(1) {
+            self.successes_by_round[round.min(last)].fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn snapshot(&self) -> CounterStateSnapshot {
+        CounterStateSnapshot {
+            successes: self.successes.load(Ordering::Relaxed),
+            successes_by_round: failed_requests_to_trimmed_vec(&self.successes_by_round),
+            accepted: self.accepted.load(Ordering::Relaxed),
+            submit_failures: failed_requests_to_trimmed_vec(&self.submit_failures),
+            wait_failures: failed_requests_to_trimmed_vec(&self.wait_failures),
+            by_client: self
+                .by_client
+                .iter()
+                .map(|(name, client_counters)| (name.clone(), client_counters.snapshot()))
+                .collect(),
+            by_tier: self.by_tier.iter().map(ClientCounters::snapshot).collect(),
+            failures_by_category: FailureCategory::ALL
+                .iter()
+                .map(|category| {
+                    (
+                        category.name(),
+                        self.failures_by_category[*category as usize].load(Ordering::Relaxed),
+                    )
+                })
+                .collect(),
+            mempool_full_backoffs: self.mempool_full_backoffs.load(Ordering::Relaxed),
+            simulated: self.simulated.load(Ordering::Relaxed),
+            simulated_gas_used: self.simulated_gas_used.load(Ordering::Relaxed),
+            simulation_failures: self.simulation_failures.load(Ordering::Relaxed),
+            latency_p50_ms: self.latencies.percentile(50, 100),
+            latency_p90_ms: self.latencies.percentile(90, 100),
+            latency_p99_ms: self.latencies.percentile(99, 100),
+        }
+    }
+
     pub fn show_simple(&self) -> String {
         format!(
-            "success {}, failed submit {:?}, failed wait {:?}",
+            "success {}, {}failed submit {:?}, failed wait {:?}",
             self.successes.load(Ordering::Relaxed),
+            self.show_accepted(),
             failed_requests_to_trimmed_vec(&self.submit_failures),
             failed_requests_to_trimmed_vec(&self.wait_failures)
         )
     }
 
+    fn show_success_rounds(&self) -> String {
+        let successes_by_round = failed_requests_to_trimmed_vec(&self.successes_by_round);
+        if successes_by_round.len() <= 1 {
+            return String::new();
+        }
+        format!("success by round: {:?}, ", successes_by_round)
+    }
+
+    fn show_accepted(&self) -> String {
+        match self.accepted.load(Ordering::Relaxed) {
+            0 => String::new(),
+            accepted => format!("accepted {}, ", accepted),
+        }
+    }
+
+    fn show_latencies(&self) -> String {
+        if self.latencies.count() == 0 {
+            return String::new();
+        }
+        format!(
+            "latency p50: {} ms, p90: {} ms, p99: {} ms, ",
+            self.latencies.percentile(50, 100),
+            self.latencies.percentile(90, 100),
+            self.latencies.percentile(99, 100),
+        )
+    }
+
+    fn show_tiers(&self) -> String {
+        if self.by_tier.is_empty() {
+            return String::new();
+        }
+        format!(
+            "by tier: [{}], ",
+            self.by_tier
+                .iter()
+                .map(ClientCounters::show)
+                .collect::<Vec<_>>()
+                .join(", "),
+        )
+    }
+
+    fn show_simulated(&self) -> String {
+        let simulated = self.simulated.load(Ordering::Relaxed);
+        if simulated == 0 {
+            return String::new();
+        }
+        format!(
+            "simulated {} (avg gas {}, failed {}), ",
+            simulated,
+            self.simulated_gas_used.load(Ordering::Relaxed) / simulated as u64,
+            self.simulation_failures.load(Ordering::Relaxed),
+        )
+    }
+
     pub fn show_detailed(&self) -> String {
         format!(
-            "{}, by client: {}",
+            "{}, {}{}by category: [{}], mempool full backoffs: {}, {}{}by client: {}",
             self.show_simple(),
+            self.show_success_rounds(),
+            self.show_latencies(),
+            FailureCategory::ALL
+                .iter()
+                .flat_map(|category| {
+                    let num = self.failures_by_category[*category as usize].load(Ordering::Relaxed);
+                    if num > 0 {
+                        Some(format!("{}: {}", category.name(), num))
+                    } else {
+                        None
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(", "),
+            self.mempool_full_backoffs.load(Ordering::Relaxed),
+            self.show_simulated(),
+            self.show_tiers(),
             self.by_client
                 .iter()
-                .flat_map(|(name, (success, fail_submit, fail_wait))| {
-                    let num_s = success.load(Ordering::Relaxed);
-                    let num_fs = fail_submit.load(Ordering::Relaxed);
-                    let num_fw = fail_wait.load(Ordering::Relaxed);
+                .flat_map(|(name, client_counters)| {
+                    let num_fs = client_counters.submit_failures.load(Ordering::Relaxed);
+                    let num_fw = client_counters.wait_failures.load(Ordering::Relaxed);
                     if num_fs + num_fw > 0 {
-                        Some(format!("[({}, {}, {}): {}]", num_s, num_fs, num_fw, name))
+                        Some(format!(
+                            "[{}: {}{}]",
+                            client_counters.show(),
+                            name,
+                            client_counters.show_latencies_and_error()
+                        ))
                     } else {
                         None
                     }
@@ -200,6 +890,14 @@ pub async fn create_txn_generator_creator(
     txn_executor: &dyn TransactionExecutor,
     txn_factory: &TransactionFactory,
     init_txn_factory: &TransactionFactory,
+    txn_factory_overrides_per_phase: &[Vec<TxnFactoryOverrides>],
+    // phases given generator phases run their workloads one after another, in them,
+    // instead of as a weighted mix
+    generator_phases_per_phase: &[Option<Vec<GeneratorPhase>>],
+    entry_functions: &[EntryFunctionTemplate],
+    // rngs of all creators and generators are derived from it, so that their
+    // transactions are reproducible from its seed
+    mut rng: StdRng,
     cur_phase: Arc<AtomicUsize>,
 ) -> (
     Box<dyn TransactionGeneratorCreator>,
@@ -231,21 +929,32 @@ pub async fn create_txn_generator_creator(
         }
     }
 
-    for transaction_mix in transaction_mix_per_phase {
+    for (phase, transaction_mix) in transaction_mix_per_phase.iter().enumerate() {
         let mut txn_generator_creator_mix: Vec<(Box<dyn TransactionGeneratorCreator>, usize)> =
             Vec::new();
-        for (transaction_type, weight) in transaction_mix {
+        for (index, (transaction_type, weight)) in transaction_mix.iter().enumerate() {
+            // transactions of the workload take its own gas and expiration, if it has them
+            let txn_factory = &match txn_factory_overrides_per_phase
+                .get(phase)
+                .and_then(|per_workload| per_workload.get(index))
+            {
+                Some(overrides) => overrides.apply(txn_factory),
+                None => txn_factory.clone(),
+            };
             let txn_generator_creator: Box<dyn TransactionGeneratorCreator> = match transaction_type
             {
                 TransactionType::CoinTransfer {
                     invalid_transaction_ratio,
                     sender_use_account_pool,
+                    hot_spot,
                 } => wrap_accounts_pool(
                     Box::new(P2PTransactionGeneratorCreator::new(
+                        StdRng::from_rng(&mut rng).unwrap(),
                         txn_factory.clone(),
                         SEND_AMOUNT,
                         addresses_pool.clone(),
                         *invalid_transaction_ratio,
+                        *hot_spot,
                     )),
                     *sender_use_account_pool,
                     accounts_pool.clone(),
@@ -255,6 +964,7 @@ pub async fn create_txn_generator_creator(
                     max_account_working_set,
                     creation_balance,
                 } => Box::new(AccountGeneratorCreator::new(
+                    StdRng::from_rng(&mut rng).unwrap(),
                     txn_factory.clone(),
                     addresses_pool.clone(),
                     accounts_pool.clone(),
@@ -262,8 +972,20 @@ pub async fn create_txn_generator_creator(
                     *max_account_working_set,
                     *creation_balance,
                 )),
+                TransactionType::BatchTransfer {
+                    num_recipients,
+                    hot_spot,
+                } => Box::new(BatchTransferGeneratorCreator::new(
+                    StdRng::from_rng(&mut rng).unwrap(),
+                    txn_factory.clone(),
+                    SEND_AMOUNT,
+                    *num_recipients,
+                    addresses_pool.clone(),
+                    *hot_spot,
+                )),
                 TransactionType::NftMintAndTransfer => Box::new(
                     NFTMintAndTransferGeneratorCreator::new(
+                        StdRng::from_rng(&mut rng).unwrap(),
                         txn_factory.clone(),
                         init_txn_factory.clone(),
                         source_accounts.get_mut(0).unwrap(),
@@ -272,11 +994,165 @@ pub async fn create_txn_generator_creator(
                     )
                     .await,
                 ),
+                TransactionType::MultiAgentTokenTransfer => Box::new(
+                    MultiAgentTokenTransferCreator::new(
+                        StdRng::from_rng(&mut rng).unwrap(),
+                        txn_factory.clone(),
+                        init_txn_factory.clone(),
+                        source_accounts,
+                        accounts_pool.clone(),
+                        txn_executor,
+                    )
+                    .await,
+                ),
+                TransactionType::MultisigTransactions {
+                    num_owners,
+                    num_signatures_required,
+                } => Box::new(
+                    MultisigTransactionsCreator::new(
+                        StdRng::from_rng(&mut rng).unwrap(),
+                        txn_factory.clone(),
+                        init_txn_factory.clone(),
+                        source_accounts,
+                        txn_executor,
+                        num_workers,
+                        *num_owners,
+                        *num_signatures_required,
+                    )
+                    .await,
+                ),
+                TransactionType::TokenV2MintTransferBurn {
+                    num_collections,
+                    tokens_per_collection,
+                } => Box::new(
+                    TokenV2MintTransferBurnCreator::new(
+                        StdRng::from_rng(&mut rng).unwrap(),
+                        txn_factory.clone(),
+                        init_txn_factory.clone(),
+                        source_accounts,
+                        txn_executor,
+                        num_workers,
+                        *num_collections,
+                        *tokens_per_collection,
+                    )
+                    .await,
+                ),
+                TransactionType::FungibleAssetTransfer => Box::new(
+                    FungibleAssetTransferCreator::new(
+                        StdRng::from_rng(&mut rng).unwrap(),
+                        txn_factory.clone(),
+                        init_txn_factory.clone(),
+                        source_accounts,
+                        txn_executor,
+                    )
+                    .await,
+                ),
+                TransactionType::ObjectResourceGroup {
+                    num_properties,
+                    property_size,
+                } => Box::new(
+                    ObjectResourceGroupCreator::new(
+                        StdRng::from_rng(&mut rng).unwrap(),
+                        txn_factory.clone(),
+                        init_txn_factory.clone(),
+                        source_accounts,
+                        txn_executor,
+                        num_workers,
+                        *num_properties,
+                        *property_size,
+                    )
+                    .await,
+                ),
+                TransactionType::StakingOperations { delegation_pool } => Box::new(
+                    StakingOperationsCreator::new(
+                        StdRng::from_rng(&mut rng).unwrap(),
+                        txn_factory.clone(),
+                        init_txn_factory.clone(),
+                        source_accounts,
+                        txn_executor,
+                        accounts_pool.clone(),
+                        *delegation_pool,
+                    )
+                    .await,
+                ),
+                TransactionType::GovernanceVoting => Box::new(
+                    GovernanceVotingCreator::new(
+                        StdRng::from_rng(&mut rng).unwrap(),
+                        txn_factory.clone(),
+                        init_txn_factory.clone(),
+                        source_accounts,
+                        txn_executor,
+                        accounts_pool.clone(),
+                    )
+                    .await,
+                ),
+                TransactionType::TableOperations {
+                    inserts,
+                    updates,
+                    reads,
+                } => Box::new(
+                    TableOperationsCreator::new(
+                        txn_factory.clone(),
+                        init_txn_factory.clone(),
+                        source_accounts,
+                        txn_executor,
+                        *inserts,
+                        *updates,
+                        *reads,
+                    )
+                    .await,
+                ),
+                TransactionType::KeyRotation => Box::new(KeyRotationCreator::new(
+                    StdRng::from_rng(&mut rng).unwrap(),
+                    txn_factory.clone(),
+                    accounts_pool.clone(),
+                )),
+                TransactionType::LargeWriteSet {
+                    num_resources,
+                    total_bytes,
+                } => Box::new(
+                    LargeWriteSetCreator::new(
+                        StdRng::from_rng(&mut rng).unwrap(),
+                        txn_factory.clone(),
+                        init_txn_factory.clone(),
+                        source_accounts,
+                        txn_executor,
+                        *num_resources,
+                        *total_bytes,
+                    )
+                    .await,
+                ),
                 TransactionType::PublishPackage { use_account_pool } => wrap_accounts_pool(
-                    Box::new(PublishPackageCreator::new(txn_factory.clone())),
+                    Box::new(PublishPackageCreator::new(
+                        StdRng::from_rng(&mut rng).unwrap(),
+                        txn_factory.clone(),
+                    )),
                     *use_account_pool,
                     accounts_pool.clone(),
                 ),
+                TransactionType::PublishAndUpgrade => Box::new(PublishAndUpgradeCreator::new(
+                    StdRng::from_rng(&mut rng).unwrap(),
+                    txn_factory.clone(),
+                    accounts_pool.clone(),
+                )),
+                TransactionType::CustomEntryFunction { index } => {
+                    Box::new(EntryFunctionGeneratorCreator::new(
+                        StdRng::from_rng(&mut rng).unwrap(),
+                        txn_factory.clone(),
+                        entry_functions
+                            .get(*index)
+                            .unwrap_or_else(|| panic!("No entry function {} given", index))
+                            .clone(),
+                        addresses_pool.clone(),
+                    ))
+                },
+                TransactionType::Script { kind } => Box::new(ScriptGeneratorCreator::new(
+                    StdRng::from_rng(&mut rng).unwrap(),
+                    txn_factory.clone(),
+                    *kind,
+                    SEND_AMOUNT,
+                    addresses_pool.clone(),
+                )),
                 TransactionType::CallCustomModules {
                     entry_point,
                     num_modules,
@@ -284,6 +1160,7 @@ pub async fn create_txn_generator_creator(
                 } => wrap_accounts_pool(
                     Box::new(
                         CallCustomModulesCreator::new(
+                            StdRng::from_rng(&mut rng).unwrap(),
                             txn_factory.clone(),
                             init_txn_factory.clone(),
                             source_accounts,
@@ -299,11 +1176,33 @@ pub async fn create_txn_generator_creator(
             };
             txn_generator_creator_mix.push((txn_generator_creator, *weight));
         }
+        if let Some(Some(generator_phases)) = generator_phases_per_phase.get(phase) {
+            assert_eq!(
+                generator_phases.len(),
+                txn_generator_creator_mix.len(),
+                "Each workload of phase {} needs a generator phase",
+                phase
+            );
+            let phased_creator: Box<dyn TransactionGeneratorCreator> =
+                Box::new(PhasedTransactionGeneratorCreator::new(
+                    generator_phases
+                        .iter()
+                        .cloned()
+                        .zip(
+                            txn_generator_creator_mix
+                                .into_iter()
+                                .map(|(creator, _)| creator),
+                        )
+                        .collect(),
+                ));
+            txn_generator_creator_mix = vec![(phased_creator, 1)];
+        }
         txn_generator_creator_mix_per_phase.push(txn_generator_creator_mix)
     }
 
     (
         Box::new(PhasedTxnMixGeneratorCreator::new(
+            rng,
             txn_generator_creator_mix_per_phase,
             cur_phase,
         )),
@@ -329,3 +1228,112 @@ fn get_account_to_burn_from_pool(
         .drain((num_in_pool - needed)..)
         .collect::<Vec<_>>()
 }
+
+#[cfg(test)]
+mod test {
+    use super::{ClientCounters, CounterState, SimulationResult, TxnExecutionError};
+    use anyhow::Context;
+    use aptos_sdk::types::{
+        state_store::state_key::StateKey,
+        transaction::{ExecutionStatus, TransactionStatus},
+        write_set::{WriteOp, WriteSetMut},
+    };
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    #[test]
+    pub fn test_ewma_latency() {
+        let client_counters = ClientCounters::default();
+        assert_eq!(client_counters.ewma_latency(), None);
+        client_counters.record_ewma_latency(Duration::from_millis(100));
+        assert_eq!(
+            client_counters.ewma_latency(),
+            Some(Duration::from_millis(100))
+        );
+        client_counters.record_ewma_latency(Duration::from_millis(600));
+        assert_eq!(
+            client_counters.ewma_latency(),
+            Some(Duration::from_millis(200))
+        );
+    }
+
+    #[test]
+    pub fn test_client_counters() {
+        let mut counters = CounterState::default();
+        let client_counters = ClientCounters::default();
+        client_counters.record_success(Duration::from_millis(7));
+        client_counters
+            .submit_failures
+            .fetch_add(1, Ordering::Relaxed);
+        client_counters.record_error("mempool is full".to_owned());
+        counters
+            .by_client
+            .insert("client".to_owned(), client_counters);
+
+        let snapshot = counters.snapshot().by_client["client"].clone();
+        assert_eq!((snapshot.successes, snapshot.submit_failures), (1, 1));
+        assert_eq!(snapshot.latency_p50_ms, 7);
+        assert_eq!(snapshot.last_error, Some("mempool is full".to_owned()));
+        assert!(counters
+            .show_detailed()
+            .ends_with("[(1, 1, 0): client, p50 7 ms, p99 7 ms, last error: mempool is full]"));
+    }
+
+    #[test]
+    pub fn test_record_success_round() {
+        let counters = CounterState {
+            successes_by_round: (0..3).map(|_| AtomicUsize::new(0)).collect(),
+            ..CounterState::default()
+        };
+        counters.record_success_round(0);
+        counters.record_success_round(1);
+        counters.record_success_round(5);
+        assert_eq!(counters.snapshot().successes_by_round, vec![1, 1, 1]);
+    }
+
+    #[test]
+    pub fn test_txn_execution_error_from_error() {
+        let error = Err::<(), _>(TxnExecutionError::Expired)
+            .context("Tried executing 1 txns")
+            .context("Failed to create accounts")
+            .unwrap_err();
+        assert_eq!(
+            TxnExecutionError::from_error(&error),
+            Some(&TxnExecutionError::Expired)
+        );
+        assert_eq!(
+            TxnExecutionError::from_error(&anyhow::anyhow!("Other error")),
+            None
+        );
+    }
+
+    #[test]
+    pub fn test_simulation_result() {
+        let write_set = WriteSetMut::new(vec![
+            (StateKey::raw(vec![0]), WriteOp::Creation(vec![0])),
+            (StateKey::raw(vec![1]), WriteOp::Modification(vec![1])),
+            (StateKey::raw(vec![2]), WriteOp::Deletion),
+        ])
+        .freeze()
+        .unwrap();
+        let result = SimulationResult::new(
+            10,
+            &TransactionStatus::Keep(ExecutionStatus::Success),
+            &write_set,
+            1,
+        );
+        assert!(result.success);
+        assert_eq!((result.num_writes, result.num_deletions), (2, 1));
+        assert!(
+            !SimulationResult::new(
+                10,
+                &TransactionStatus::Keep(ExecutionStatus::OutOfGas),
+                &write_set,
+                0
+            )
+            .success
+        );
+    }
+}
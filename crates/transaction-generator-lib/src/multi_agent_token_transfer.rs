@@ -0,0 +1,237 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::TransactionExecutor;
+use crate::{
+    nft_mint_and_transfer::{create_nft_transfer_request, initialize_nft_collection},
+    TransactionGenerator, TransactionGeneratorCreator,
+};
+use aptos_infallible::RwLock;
+use aptos_logger::info;
+use aptos_sdk::{
+    transaction_builder::{aptos_stdlib::aptos_token_stdlib, TransactionFactory},
+    types::{account_address::AccountAddress, transaction::SignedTransaction, LocalAccount},
+};
+use rand::{prelude::SliceRandom, rngs::StdRng, Rng, SeedableRng};
+use std::sync::Arc;
+
+// tokens each sender gets, each transaction transferring one of them
+const TOKENS_PER_ACCOUNT: u64 = 1_000_000;
+
+/// Multi-agent transfers of a token, from each account (as the primary sender) to a
+/// secondary signer, exercising the multi-agent prologue. Secondary signers are drawn
+/// from the accounts pool (e.g. filled by account generation), falling back to other
+/// accounts given to the generator when the pool is empty.
+pub struct MultiAgentTokenTransfer {
+    rng: StdRng,
+    txn_factory: TransactionFactory,
+    creator_address: AccountAddress,
+    collection_name: Vec<u8>,
+    token_name: Vec<u8>,
+    accounts_pool: Arc<RwLock<Vec<LocalAccount>>>,
+}
+
+impl MultiAgentTokenTransfer {
+    pub fn new(
+        rng: StdRng,
+        txn_factory: TransactionFactory,
+        creator_address: AccountAddress,
+        collection_name: Vec<u8>,
+        token_name: Vec<u8>,
+        accounts_pool: Arc<RwLock<Vec<LocalAccount>>>,
+    ) -> Self {
+        Self {
+            rng,
+            txn_factory,
+            creator_address,
+            collection_name,
+            token_name,
+            accounts_pool,
+        }
+    }
+}
+
+impl TransactionGenerator for MultiAgentTokenTransfer {
+    fn generate_transactions(
+        &mut self,
+        mut accounts: Vec<&mut LocalAccount>,
+        transactions_per_account: usize,
+    ) -> Vec<SignedTransaction> {
+        let accounts_pool = self.accounts_pool.read();
+        if accounts_pool.is_empty() && accounts.len() < 2 {
+            return Vec::new();
+        }
+        let mut requests = Vec::with_capacity(accounts.len() * transactions_per_account);
+        for sender_index in 0..accounts.len() {
+            for _ in 0..transactions_per_account {
+                let sender = &mut accounts[sender_index];
+                let raw_txn = self
+                    .txn_factory
+                    .payload(aptos_token_stdlib::token_direct_transfer_script(
+                        self.creator_address,
+                        self.collection_name.clone(),
+                        self.token_name.clone(),
+                        0,
+                        1,
+                    ))
+                    .sender(sender.address())
+                    .sequence_number(sender.sequence_number())
+                    .build();
+                *sender.sequence_number_mut() += 1;
+
+                let secondary_signer = match accounts_pool.choose(&mut self.rng) {
+                    Some(pool_account) => pool_account,
+                    None => {
+                        let offset = self.rng.gen_range(1, accounts.len());
+                        &*accounts[(sender_index + offset) % accounts.len()]
+                    },
+                };
+                requests.push(
+                    accounts[sender_index]
+                        .sign_multi_agent_transaction(vec![secondary_signer], raw_txn),
+                );
+            }
+        }
+        requests
+    }
+}
+
+pub struct MultiAgentTokenTransferCreator {
+    rng: StdRng,
+    txn_factory: TransactionFactory,
+    creator_address: AccountAddress,
+    collection_name: Vec<u8>,
+    token_name: Vec<u8>,
+    accounts_pool: Arc<RwLock<Vec<LocalAccount>>>,
+}
+
+impl MultiAgentTokenTransferCreator {
+    /// Creates the token, and gives each of the source accounts some of it.
+    pub async fn new(
+        mut rng: StdRng,
+        txn_factory: TransactionFactory,
+        init_txn_factory: TransactionFactory,
+        source_accounts: &mut [LocalAccount],
+        accounts_pool: Arc<RwLock<Vec<LocalAccount>>>,
+        txn_executor: &dyn TransactionExecutor,
+    ) -> Self {
+        let mut creator_account = LocalAccount::generate(&mut rng);
+        let creator_address = creator_account.address();
+        let collection_name = "multi-agent collection".to_owned().into_bytes();
+        let token_name = "multi-agent token".to_owned().into_bytes();
+        initialize_nft_collection(
+            txn_executor,
+            source_accounts.get_mut(0).unwrap(),
+            &mut creator_account,
+            &init_txn_factory,
+            &collection_name,
+            &token_name,
+        )
+        .await;
+
+        let txns: Vec<_> = source_accounts
+            .iter()
+            .map(|account| {
+                create_nft_transfer_request(
+                    &mut creator_account,
+                    account,
+                    creator_address,
+                    &collection_name,
+                    &token_name,
+                    &init_txn_factory,
+                    TOKENS_PER_ACCOUNT,
+                )
+            })
+            .collect();
+        info!("Giving tokens to {} accounts", txns.len());
+        // per account limit is 100
+        for chunk in txns.chunks(100) {
+            txn_executor.execute_transactions(chunk).await.unwrap();
+        }
+        info!("Done giving tokens to {} accounts", txns.len());
+
+        Self {
+            rng,
+            txn_factory,
+            creator_address,
+            collection_name,
+            token_name,
+            accounts_pool,
+        }
+    }
+}
+
+impl TransactionGeneratorCreator for MultiAgentTokenTransferCreator {
+    fn create_transaction_generator(&mut self) -> Box<dyn TransactionGenerator> {
+        Box::new(MultiAgentTokenTransfer::new(
+            StdRng::from_rng(&mut self.rng).unwrap(),
+            self.txn_factory.clone(),
+            self.creator_address,
+            self.collection_name.clone(),
+            self.token_name.clone(),
+            self.accounts_pool.clone(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::MultiAgentTokenTransfer;
+    use crate::TransactionGenerator;
+    use aptos_infallible::RwLock;
+    use aptos_sdk::{
+        transaction_builder::TransactionFactory,
+        types::{
+            account_address::AccountAddress, chain_id::ChainId,
+            transaction::authenticator::TransactionAuthenticator, LocalAccount,
+        },
+    };
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::sync::Arc;
+
+    #[test]
+    pub fn test_multi_agent_token_transfer() {
+        let mut rng = StdRng::from_seed([0; 32]);
+        let accounts_pool = Arc::new(RwLock::new(vec![]));
+        let mut generator = MultiAgentTokenTransfer::new(
+            StdRng::from_seed([0; 32]),
+            TransactionFactory::new(ChainId::test()),
+            AccountAddress::random(),
+            b"collection".to_vec(),
+            b"token".to_vec(),
+            accounts_pool.clone(),
+        );
+        let mut accounts: Vec<_> = (0..2).map(|_| LocalAccount::generate(&mut rng)).collect();
+        let addresses: Vec<_> = accounts.iter().map(LocalAccount::address).collect();
+        // with an empty pool, the other account co-signs
+        let txns = generator.generate_transactions(accounts.iter_mut().collect(), 3);
+        assert_eq!(txns.len(), 6);
+        for txn in &txns {
+            match txn.authenticator() {
+                TransactionAuthenticator::MultiAgent {
+                    secondary_signer_addresses,
+                    ..
+                } => {
+                    assert_eq!(secondary_signer_addresses.len(), 1);
+                    assert_ne!(secondary_signer_addresses[0], txn.sender());
+                    assert!(addresses.contains(&secondary_signer_addresses[0]));
+                },
+                _ => panic!("Expected a multi-agent transaction"),
+            }
+        }
+        assert!(accounts
+            .iter()
+            .all(|account| account.sequence_number() == 3));
+
+        let pool_account = LocalAccount::generate(&mut rng);
+        let pool_address = pool_account.address();
+        accounts_pool.write().push(pool_account);
+        let txns = generator.generate_transactions(accounts.iter_mut().take(1).collect(), 1);
+        assert!(matches!(
+            txns[0].authenticator(),
+            TransactionAuthenticator::MultiAgent { secondary_signer_addresses, .. }
+                if secondary_signer_addresses == vec![pool_address]
+        ));
+        assert!(txns[0].clone().check_signature().is_ok());
+    }
+}
@@ -0,0 +1,314 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::TransactionExecutor;
+use crate::{
+    nft_mint_and_transfer::create_and_fund_accounts, TransactionGenerator,
+    TransactionGeneratorCreator, SEND_AMOUNT,
+};
+use aptos_sdk::{
+    bcs,
+    transaction_builder::{aptos_stdlib, TransactionFactory},
+    types::{
+        account_address::{create_resource_address, AccountAddress},
+        transaction::{
+            Multisig, MultisigTransactionPayload, SignedTransaction, TransactionPayload,
+        },
+        LocalAccount,
+    },
+};
+use rand::rngs::StdRng;
+
+// multisig accounts of each worker
+const MULTISIG_ACCOUNTS_PER_WORKER: usize = 10;
+// given to each multisig account when it's created, to pay the transfers it executes
+const MULTISIG_ACCOUNT_BALANCE: u64 = 10_000_000;
+// 0x1::multisig_account::DOMAIN_SEPARATOR
+const MULTISIG_DOMAIN_SEPARATOR: &[u8] = b"aptos_framework::multisig_account";
+
+/// Address of the multisig account created by creator with its sequence_number-th
+/// transaction, as by 0x1::multisig_account::get_next_multisig_account_address.
+pub fn multisig_account_address(creator: AccountAddress, sequence_number: u64) -> AccountAddress {
+    let seed = [
+        MULTISIG_DOMAIN_SEPARATOR,
+        &bcs::to_bytes(&sequence_number).unwrap(),
+    ]
+    .concat();
+    create_resource_address(creator, &seed)
+}
+
+/// Next step of a multisig account, each depending on the previous one being committed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Step {
+    Create,
+    Propose,
+    Approve,
+    Execute,
+}
+
+/// Multisig account, with the owners it's operated by, the first of them its creator.
+pub struct MultisigGroup {
+    owners: Vec<LocalAccount>,
+    num_signatures_required: usize,
+    multisig_address: AccountAddress,
+    step: Step,
+    // of the multisig transaction being proposed, approved and executed
+    sequence_number: u64,
+}
+
+impl MultisigGroup {
+    pub fn new(owners: Vec<LocalAccount>, num_signatures_required: usize) -> Self {
+        assert!(num_signatures_required > 0 && num_signatures_required <= owners.len());
+        // created with the next transaction of the creator
+        let multisig_address =
+            multisig_account_address(owners[0].address(), owners[0].sequence_number());
+        Self {
+            owners,
+            num_signatures_required,
+            multisig_address,
+            step: Step::Create,
+            sequence_number: 1,
+        }
+    }
+
+    /// Transactions of the current step, moving to the next one.
+    fn next_transactions(&mut self, txn_factory: &TransactionFactory) -> Vec<SignedTransaction> {
+        let creator_address = self.owners[0].address();
+        let multisig_address = self.multisig_address;
+        match self.step {
+            Step::Create => {
+                let additional_owners =
+                    self.owners[1..].iter().map(LocalAccount::address).collect();
+                let creator = &mut self.owners[0];
+                let txns = vec![
+                    creator.sign_with_transaction_builder(txn_factory.payload(
+                        aptos_stdlib::multisig_account_create_with_owners(
+                            additional_owners,
+                            self.num_signatures_required as u64,
+                            vec![],
+                            vec![],
+                        ),
+                    )),
+                    creator.sign_with_transaction_builder(txn_factory.payload(
+                        aptos_stdlib::aptos_account_transfer(
+                            multisig_address,
+                            MULTISIG_ACCOUNT_BALANCE,
+                        ),
+                    )),
+                ];
+                self.step = Step::Propose;
+                txns
+            },
+            Step::Propose => {
+                // approved by its creator
+                let payload = bcs::to_bytes(&MultisigTransactionPayload::EntryFunction(
+                    match aptos_stdlib::aptos_account_transfer(creator_address, SEND_AMOUNT) {
+                        TransactionPayload::EntryFunction(entry_function) => entry_function,
+                        _ => unreachable!(),
+                    },
+                ))
+                .unwrap();
+                self.step = if self.num_signatures_required > 1 {
+                    Step::Approve
+                } else {
+                    Step::Execute
+                };
+                vec![
+                    self.owners[0].sign_with_transaction_builder(txn_factory.payload(
+                        aptos_stdlib::multisig_account_create_transaction(
+                            multisig_address,
+                            payload,
+                        ),
+                    )),
+                ]
+            },
+            Step::Approve => {
+                let sequence_number = self.sequence_number;
+                self.step = Step::Execute;
+                self.owners[1..self.num_signatures_required]
+                    .iter_mut()
+                    .map(|owner| {
+                        owner.sign_with_transaction_builder(txn_factory.payload(
+                            aptos_stdlib::multisig_account_approve_transaction(
+                                multisig_address,
+                                sequence_number,
+                            ),
+                        ))
+                    })
+                    .collect()
+            },
+            Step::Execute => {
+                self.sequence_number += 1;
+                self.step = Step::Propose;
+                // the payload is stored on chain, from the proposal
+                vec![
+                    self.owners[0].sign_with_transaction_builder(txn_factory.payload(
+                        TransactionPayload::Multisig(Multisig {
+                            multisig_address,
+                            transaction_payload: None,
+                        }),
+                    )),
+                ]
+            },
+        }
+    }
+}
+
+/// Multisig (v2) transaction flow: multisig accounts are created, and then transfers
+/// from them are proposed, approved by as many owners as required, and executed, in
+/// turn. Each step is done in a batch of its own, once the previous one is committed.
+/// Owners are accounts created with the workload, held by the generator throughout,
+/// and all transactions are theirs, as many as the accounts given would send.
+/// Multisig accounts need to be enabled on chain.
+pub struct MultisigTransactions {
+    txn_factory: TransactionFactory,
+    groups: Vec<MultisigGroup>,
+    next_group: usize,
+}
+
+impl MultisigTransactions {
+    pub fn new(txn_factory: TransactionFactory, groups: Vec<MultisigGroup>) -> Self {
+        Self {
+            txn_factory,
+            groups,
+            next_group: 0,
+        }
+    }
+}
+
+impl TransactionGenerator for MultisigTransactions {
+    fn generate_transactions(
+        &mut self,
+        accounts: Vec<&mut LocalAccount>,
+        transactions_per_account: usize,
+    ) -> Vec<SignedTransaction> {
+        let num_transactions = accounts.len() * transactions_per_account;
+        let mut requests = Vec::with_capacity(num_transactions);
+        // at most one step of each group in a batch
+        for _ in 0..self.groups.len() {
+            if requests.len() >= num_transactions {
+                break;
+            }
+            let index = self.next_group;
+            self.next_group = (index + 1) % self.groups.len();
+            requests.append(&mut self.groups[index].next_transactions(&self.txn_factory));
+        }
+        requests
+    }
+}
+
+pub struct MultisigTransactionsCreator {
+    txn_factory: TransactionFactory,
+    // multisig accounts of each worker
+    groups_per_worker: Vec<Vec<MultisigGroup>>,
+}
+
+impl MultisigTransactionsCreator {
+    /// Creates the owners of MULTISIG_ACCOUNTS_PER_WORKER multisig accounts for each of
+    /// the workers, each account with num_owners owners.
+    pub async fn new(
+        mut rng: StdRng,
+        txn_factory: TransactionFactory,
+        init_txn_factory: TransactionFactory,
+        source_accounts: &mut [LocalAccount],
+        txn_executor: &dyn TransactionExecutor,
+        num_workers: usize,
+        num_owners: usize,
+        num_signatures_required: usize,
+    ) -> Self {
+        let mut owners = create_and_fund_accounts(
+            &mut rng,
+            &init_txn_factory,
+            source_accounts,
+            txn_executor,
+            num_workers * MULTISIG_ACCOUNTS_PER_WORKER * num_owners,
+        )
+        .await;
+        let mut groups_per_worker = Vec::with_capacity(num_workers);
+        for _ in 0..num_workers {
+            groups_per_worker.push(
+                (0..MULTISIG_ACCOUNTS_PER_WORKER)
+                    .map(|_| {
+                        MultisigGroup::new(
+                            owners.split_off(owners.len() - num_owners),
+                            num_signatures_required,
+                        )
+                    })
+                    .collect(),
+            );
+        }
+        Self {
+            txn_factory,
+            groups_per_worker,
+        }
+    }
+}
+
+impl TransactionGeneratorCreator for MultisigTransactionsCreator {
+    fn create_transaction_generator(&mut self) -> Box<dyn TransactionGenerator> {
+        Box::new(MultisigTransactions::new(
+            self.txn_factory.clone(),
+            self.groups_per_worker.pop().unwrap(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{multisig_account_address, MultisigGroup, MultisigTransactions};
+    use crate::TransactionGenerator;
+    use aptos_sdk::{
+        transaction_builder::TransactionFactory,
+        types::{chain_id::ChainId, transaction::TransactionPayload, LocalAccount},
+    };
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    pub fn test_multisig_transactions() {
+        let mut rng = StdRng::from_seed([0; 32]);
+        let owners: Vec<_> = (0..3).map(|_| LocalAccount::generate(&mut rng)).collect();
+        let addresses: Vec<_> = owners.iter().map(LocalAccount::address).collect();
+        let multisig_address = multisig_account_address(addresses[0], 0);
+        let mut generator =
+            MultisigTransactions::new(TransactionFactory::new(ChainId::test()), vec![
+                MultisigGroup::new(owners, 2),
+            ]);
+        let mut account = LocalAccount::generate(&mut rng);
+        let mut next_batch = || {
+            generator
+                .generate_transactions(vec![&mut account], 1)
+                .into_iter()
+                .map(|txn| {
+                    let function = match txn.payload() {
+                        TransactionPayload::EntryFunction(entry_function) => {
+                            entry_function.function().to_string()
+                        },
+                        TransactionPayload::Multisig(multisig) => {
+                            assert_eq!(multisig.multisig_address, multisig_address);
+                            "multisig".to_owned()
+                        },
+                        _ => panic!("Unexpected payload"),
+                    };
+                    (txn.sender(), function)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(next_batch(), vec![
+            (addresses[0], "create_with_owners".to_owned()),
+            (addresses[0], "transfer".to_owned())
+        ]);
+        for _ in 0..2 {
+            assert_eq!(next_batch(), vec![(
+                addresses[0],
+                "create_transaction".to_owned()
+            )]);
+            // one more approval than the proposer's is required
+            assert_eq!(next_batch(), vec![(
+                addresses[1],
+                "approve_transaction".to_owned()
+            )]);
+            assert_eq!(next_batch(), vec![(addresses[0], "multisig".to_owned())]);
+        }
+    }
+}
@@ -16,7 +16,7 @@ use aptos_sdk::{
         LocalAccount,
     },
 };
-use rand::{rngs::StdRng, thread_rng, SeedableRng};
+use rand::rngs::StdRng;
 use std::collections::HashMap;
 
 const INITIAL_NFT_BALANCE: u64 = 50_000;
@@ -214,13 +214,13 @@ pub struct NFTMintAndTransferGeneratorCreator {
 
 impl NFTMintAndTransferGeneratorCreator {
     pub async fn new(
+        mut rng: StdRng,
         txn_factory: TransactionFactory,
         init_txn_factory: TransactionFactory,
         root_account: &mut LocalAccount,
         txn_executor: &dyn TransactionExecutor,
         num_workers: usize,
     ) -> Self {
-        let mut rng = StdRng::from_entropy();
         let mut creator_account = LocalAccount::generate(&mut rng);
         let creator_address = creator_account.address();
         let collection_name = "collection name".to_owned().into_bytes();
@@ -239,7 +239,7 @@ impl NFTMintAndTransferGeneratorCreator {
         let mut txns = Vec::new();
 
         for _ in 0..num_workers {
-            let distribution_account = LocalAccount::generate(&mut thread_rng());
+            let distribution_account = LocalAccount::generate(&mut rng);
             txns.push(create_nft_transfer_request(
                 &mut creator_account,
                 &distribution_account,
@@ -281,7 +281,7 @@ impl TransactionGeneratorCreator for NFTMintAndTransferGeneratorCreator {
     }
 }
 
-fn create_and_fund_account_request(
+pub fn create_and_fund_account_request(
     creation_account: &mut LocalAccount,
     amount: u64,
     pubkey: &Ed25519PublicKey,
@@ -293,3 +293,51 @@ fn create_and_fund_account_request(
         aptos_stdlib::aptos_account_transfer(auth_key.derived_address(), amount),
     ))
 }
+
+/// Fresh accounts, funded by the source accounts with half of their balance, e.g. for
+/// generators to hold on to, or to know the guids and objects they create.
+pub async fn create_and_fund_accounts(
+    rng: &mut StdRng,
+    init_txn_factory: &TransactionFactory,
+    source_accounts: &mut [LocalAccount],
+    txn_executor: &dyn TransactionExecutor,
+    num_accounts: usize,
+) -> Vec<LocalAccount> {
+    assert!(!source_accounts.is_empty());
+    let accounts: Vec<_> = (0..num_accounts)
+        .map(|_| LocalAccount::generate(rng))
+        .collect();
+    let accounts_per_source = (accounts.len() + source_accounts.len() - 1) / source_accounts.len();
+    let source_accounts = &mut source_accounts[..accounts.chunks(accounts_per_source).len()];
+    let balances = txn_executor
+        .get_account_balances(
+            &source_accounts
+                .iter()
+                .map(LocalAccount::address)
+                .collect::<Vec<_>>(),
+        )
+        .await
+        .unwrap();
+    let mut fund_txns = Vec::with_capacity(accounts.len());
+    for ((source_account, balance), funded) in source_accounts
+        .iter_mut()
+        .zip(balances)
+        .zip(accounts.chunks(accounts_per_source))
+    {
+        let amount = balance / 2 / funded.len() as u64;
+        for account in funded {
+            fund_txns.push(create_and_fund_account_request(
+                source_account,
+                amount,
+                account.public_key(),
+                init_txn_factory,
+            ));
+        }
+    }
+    info!("Creating {} funded accounts", fund_txns.len());
+    // per account limit is 100
+    for chunk in fund_txns.chunks(100) {
+        txn_executor.execute_transactions(chunk).await.unwrap();
+    }
+    accounts
+}
@@ -0,0 +1,335 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::TransactionExecutor;
+use crate::{
+    nft_mint_and_transfer::create_and_fund_accounts,
+    token_v2_mint_transfer_burn::{
+        token_address, token_objects_address, token_type, FIRST_TOKEN_CREATION_NUM,
+    },
+    TransactionGenerator, TransactionGeneratorCreator,
+};
+use aptos_logger::info;
+use aptos_sdk::{
+    bcs,
+    move_types::{
+        account_address::AccountAddress, ident_str, identifier::Identifier,
+        language_storage::ModuleId,
+    },
+    transaction_builder::{aptos_stdlib::aptos_token_objects_stdlib, TransactionFactory},
+    types::{
+        transaction::{EntryFunction, SignedTransaction, TransactionPayload},
+        LocalAccount,
+    },
+};
+use rand::{prelude::SliceRandom, rngs::StdRng, Rng, SeedableRng};
+
+// tokens of each creator, mutated once all of them are minted
+const TOKENS_PER_CREATOR: usize = 100;
+const PROPERTY_TYPE: &[u8] = b"vector<u8>";
+
+fn property_key(index: usize) -> Vec<u8> {
+    format!("property {}", index).into_bytes()
+}
+
+fn token_function_payload(
+    function: &'static str,
+    token: AccountAddress,
+    mut args: Vec<Vec<u8>>,
+) -> TransactionPayload {
+    args.insert(0, bcs::to_bytes(&token).unwrap());
+    TransactionPayload::EntryFunction(EntryFunction::new(
+        ModuleId::new(
+            token_objects_address(),
+            ident_str!("aptos_token").to_owned(),
+        ),
+        Identifier::new(function).unwrap(),
+        vec![token_type()],
+        args,
+    ))
+}
+
+/// Tokens of a creator, minted into its account.
+pub struct ObjectCreator {
+    creator: LocalAccount,
+    collection_name: Vec<u8>,
+    next_token_creation_num: u64,
+    // minted in earlier batches, so can be mutated
+    minted: Vec<AccountAddress>,
+    // minted in the last batch
+    pending: Vec<AccountAddress>,
+}
+
+impl ObjectCreator {
+    pub fn new(creator: LocalAccount, collection_name: Vec<u8>) -> Self {
+        Self {
+            creator,
+            collection_name,
+            next_token_creation_num: FIRST_TOKEN_CREATION_NUM,
+            minted: vec![],
+            pending: vec![],
+        }
+    }
+}
+
+/// Objects with several resources in their resource group, Token V2 digital assets
+/// holding num_properties properties of property_size bytes in their PropertyMap,
+/// minted by the creators and then mutated, by updating a property or the description
+/// of one of them, each rewriting the whole resource group. Transactions are sent by
+/// the creators, as many as the accounts given would send, and are of the creators
+/// in turn.
+pub struct ObjectResourceGroup {
+    rng: StdRng,
+    txn_factory: TransactionFactory,
+    creators: Vec<ObjectCreator>,
+    num_properties: usize,
+    property_size: usize,
+    next_creator: usize,
+}
+
+impl ObjectResourceGroup {
+    pub fn new(
+        rng: StdRng,
+        txn_factory: TransactionFactory,
+        creators: Vec<ObjectCreator>,
+        num_properties: usize,
+        property_size: usize,
+    ) -> Self {
+        Self {
+            rng,
+            txn_factory,
+            creators,
+            num_properties,
+            property_size,
+            next_creator: 0,
+        }
+    }
+
+    fn property_value(&mut self) -> Vec<u8> {
+        let value: Vec<u8> = (0..self.property_size).map(|_| self.rng.gen()).collect();
+        bcs::to_bytes(&value).unwrap()
+    }
+
+    fn mint_payload(&mut self, collection_name: Vec<u8>) -> TransactionPayload {
+        let property_values = (0..self.num_properties)
+            .map(|_| self.property_value())
+            .collect();
+        aptos_token_objects_stdlib::aptos_token_mint(
+            collection_name,
+            b"description".to_vec(),
+            b"token".to_vec(),
+            b"uri".to_vec(),
+            (0..self.num_properties).map(property_key).collect(),
+            vec![PROPERTY_TYPE.to_vec(); self.num_properties],
+            property_values,
+        )
+    }
+
+    fn mutate_payload(&mut self, token: AccountAddress) -> TransactionPayload {
+        if self.num_properties > 0 && self.rng.gen_bool(0.5) {
+            let key = property_key(self.rng.gen_range(0, self.num_properties));
+            let value = self.property_value();
+            token_function_payload("update_property", token, vec![
+                bcs::to_bytes(&key).unwrap(),
+                bcs::to_bytes(&PROPERTY_TYPE.to_vec()).unwrap(),
+                bcs::to_bytes(&value).unwrap(),
+            ])
+        } else {
+            let description = format!("description {}", self.rng.gen::<u64>()).into_bytes();
+            token_function_payload("set_description", token, vec![
+                bcs::to_bytes(&description).unwrap()
+            ])
+        }
+    }
+}
+
+impl TransactionGenerator for ObjectResourceGroup {
+    fn generate_transactions(
+        &mut self,
+        accounts: Vec<&mut LocalAccount>,
+        transactions_per_account: usize,
+    ) -> Vec<SignedTransaction> {
+        for creator in &mut self.creators {
+            creator.minted.append(&mut creator.pending);
+        }
+        let num_transactions = accounts.len() * transactions_per_account;
+        let mut requests = Vec::with_capacity(num_transactions);
+        for _ in 0..num_transactions {
+            let index = self.next_creator;
+            self.next_creator = (index + 1) % self.creators.len();
+            let creator = &self.creators[index];
+            let payload = if creator.minted.len() + creator.pending.len() < TOKENS_PER_CREATOR {
+                let collection_name = creator.collection_name.clone();
+                let creator = &mut self.creators[index];
+                creator.pending.push(token_address(
+                    creator.creator.address(),
+                    creator.next_token_creation_num,
+                ));
+                creator.next_token_creation_num += 1;
+                self.mint_payload(collection_name)
+            } else {
+                match creator.minted.choose(&mut self.rng) {
+                    Some(token) => {
+                        let token = *token;
+                        self.mutate_payload(token)
+                    },
+                    None => continue,
+                }
+            };
+            requests.push(
+                self.creators[index]
+                    .creator
+                    .sign_with_transaction_builder(self.txn_factory.payload(payload)),
+            );
+        }
+        requests
+    }
+}
+
+pub struct ObjectResourceGroupCreator {
+    rng: StdRng,
+    txn_factory: TransactionFactory,
+    // creators of each worker
+    creators: Vec<ObjectCreator>,
+    num_properties: usize,
+    property_size: usize,
+}
+
+impl ObjectResourceGroupCreator {
+    /// Creates a creator for each of the workers, with a collection of tokens with
+    /// mutable descriptions and properties.
+    pub async fn new(
+        mut rng: StdRng,
+        txn_factory: TransactionFactory,
+        init_txn_factory: TransactionFactory,
+        source_accounts: &mut [LocalAccount],
+        txn_executor: &dyn TransactionExecutor,
+        num_workers: usize,
+        num_properties: usize,
+        property_size: usize,
+    ) -> Self {
+        let mut creators: Vec<_> = create_and_fund_accounts(
+            &mut rng,
+            &init_txn_factory,
+            source_accounts,
+            txn_executor,
+            num_workers,
+        )
+        .await
+        .into_iter()
+        .enumerate()
+        .map(|(index, creator)| {
+            ObjectCreator::new(creator, format!("objects {}", index).into_bytes())
+        })
+        .collect();
+        let collection_txns: Vec<_> = creators
+            .iter_mut()
+            .map(|creator| {
+                creator
+                    .creator
+                    .sign_with_transaction_builder(init_txn_factory.payload(
+                        aptos_token_objects_stdlib::aptos_token_create_collection(
+                            b"description".to_vec(),
+                            u64::MAX,
+                            creator.collection_name.clone(),
+                            b"uri".to_vec(),
+                            false,
+                            false,
+                            false,
+                            true,
+                            false,
+                            true,
+                            false,
+                            false,
+                            false,
+                            0,
+                            1,
+                        ),
+                    ))
+            })
+            .collect();
+        info!("Creating {} collections", collection_txns.len());
+        for chunk in collection_txns.chunks(100) {
+            txn_executor.execute_transactions(chunk).await.unwrap();
+        }
+        info!("Done creating {} collections", collection_txns.len());
+
+        Self {
+            rng,
+            txn_factory,
+            creators,
+            num_properties,
+            property_size,
+        }
+    }
+}
+
+impl TransactionGeneratorCreator for ObjectResourceGroupCreator {
+    fn create_transaction_generator(&mut self) -> Box<dyn TransactionGenerator> {
+        Box::new(ObjectResourceGroup::new(
+            StdRng::from_rng(&mut self.rng).unwrap(),
+            self.txn_factory.clone(),
+            vec![self.creators.pop().unwrap()],
+            self.num_properties,
+            self.property_size,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ObjectCreator, ObjectResourceGroup, TOKENS_PER_CREATOR};
+    use crate::{token_v2_mint_transfer_burn::token_address, TransactionGenerator};
+    use aptos_sdk::{
+        bcs,
+        transaction_builder::TransactionFactory,
+        types::{chain_id::ChainId, transaction::TransactionPayload, LocalAccount},
+    };
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    pub fn test_object_resource_group() {
+        let mut rng = StdRng::from_seed([0; 32]);
+        let creator = LocalAccount::generate(&mut rng);
+        let creator_address = creator.address();
+        let mut generator = ObjectResourceGroup::new(
+            StdRng::from_seed([0; 32]),
+            TransactionFactory::new(ChainId::test()),
+            vec![ObjectCreator::new(creator, b"collection".to_vec())],
+            3,
+            10,
+        );
+        let mut account = LocalAccount::generate(&mut rng);
+        let txns = generator.generate_transactions(vec![&mut account], TOKENS_PER_CREATOR + 5);
+        // tokens minted in this batch aren't mutated before the next one
+        assert_eq!(txns.len(), TOKENS_PER_CREATOR);
+        for txn in &txns {
+            assert_eq!(txn.sender(), creator_address);
+            match txn.payload() {
+                TransactionPayload::EntryFunction(entry_function) => {
+                    assert_eq!(entry_function.function().as_str(), "mint");
+                    let values: Vec<Vec<u8>> = bcs::from_bytes(&entry_function.args()[6]).unwrap();
+                    assert_eq!(values.len(), 3);
+                    assert!(values.iter().all(|value| value.len() == 11));
+                },
+                _ => panic!("Expected an entry function payload"),
+            }
+        }
+
+        let tokens: Vec<_> = (0..TOKENS_PER_CREATOR as u64)
+            .map(|index| token_address(creator_address, 4 + index).to_vec())
+            .collect();
+        let txns = generator.generate_transactions(vec![&mut account], 10);
+        assert_eq!(txns.len(), 10);
+        for txn in &txns {
+            match txn.payload() {
+                TransactionPayload::EntryFunction(entry_function) => {
+                    assert!(["update_property", "set_description"]
+                        .contains(&entry_function.function().as_str()));
+                    assert!(tokens.contains(&entry_function.args()[0]));
+                },
+                _ => panic!("Expected an entry function payload"),
+            }
+        }
+    }
+}
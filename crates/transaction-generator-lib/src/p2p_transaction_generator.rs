@@ -1,6 +1,6 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
-use crate::{TransactionGenerator, TransactionGeneratorCreator};
+use crate::{hot_spot::HotSpot, TransactionGenerator, TransactionGeneratorCreator};
 use aptos_infallible::RwLock;
 use aptos_sdk::{
     move_types::account_address::AccountAddress,
@@ -21,6 +21,7 @@ pub struct P2PTransactionGenerator {
     txn_factory: TransactionFactory,
     all_addresses: Arc<RwLock<Vec<AccountAddress>>>,
     invalid_transaction_ratio: usize,
+    hot_spot: HotSpot,
 }
 
 impl P2PTransactionGenerator {
@@ -30,6 +31,7 @@ impl P2PTransactionGenerator {
         txn_factory: TransactionFactory,
         all_addresses: Arc<RwLock<Vec<AccountAddress>>>,
         invalid_transaction_ratio: usize,
+        hot_spot: HotSpot,
     ) -> Self {
         Self {
             rng,
@@ -37,6 +39,7 @@ impl P2PTransactionGenerator {
             txn_factory,
             all_addresses,
             invalid_transaction_ratio,
+            hot_spot,
         }
     }
 
@@ -131,12 +134,20 @@ impl TransactionGenerator for P2PTransactionGenerator {
         };
         let mut num_valid_tx = transactions_per_account * (accounts.len() - invalid_size);
         for sender in accounts {
-            let receivers = self
-                .all_addresses
-                .read()
-                .choose_multiple(&mut self.rng, transactions_per_account)
-                .cloned()
-                .collect::<Vec<_>>();
+            let receivers = {
+                let all_addresses = self.all_addresses.read();
+                let receivers = all_addresses
+                    .choose_multiple(&mut self.rng, transactions_per_account)
+                    .cloned()
+                    .collect::<Vec<_>>();
+                receivers
+                    .into_iter()
+                    .map(|receiver| {
+                        self.hot_spot
+                            .receiver(&mut self.rng, &all_addresses, receiver)
+                    })
+                    .collect::<Vec<_>>()
+            };
             assert!(
                 receivers.len() >= transactions_per_account,
                 "failed: {} >= {}",
@@ -164,24 +175,30 @@ impl TransactionGenerator for P2PTransactionGenerator {
 }
 
 pub struct P2PTransactionGeneratorCreator {
+    rng: StdRng,
     txn_factory: TransactionFactory,
     amount: u64,
     all_addresses: Arc<RwLock<Vec<AccountAddress>>>,
     invalid_transaction_ratio: usize,
+    hot_spot: HotSpot,
 }
 
 impl P2PTransactionGeneratorCreator {
     pub fn new(
+        rng: StdRng,
         txn_factory: TransactionFactory,
         amount: u64,
         all_addresses: Arc<RwLock<Vec<AccountAddress>>>,
         invalid_transaction_ratio: usize,
+        hot_spot: HotSpot,
     ) -> Self {
         Self {
+            rng,
             txn_factory,
             amount,
             all_addresses,
             invalid_transaction_ratio,
+            hot_spot,
         }
     }
 }
@@ -189,11 +206,55 @@ impl P2PTransactionGeneratorCreator {
 impl TransactionGeneratorCreator for P2PTransactionGeneratorCreator {
     fn create_transaction_generator(&mut self) -> Box<dyn TransactionGenerator> {
         Box::new(P2PTransactionGenerator::new(
-            StdRng::from_entropy(),
+            StdRng::from_rng(&mut self.rng).unwrap(),
             self.amount,
             self.txn_factory.clone(),
             self.all_addresses.clone(),
             self.invalid_transaction_ratio,
+            self.hot_spot,
         ))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::P2PTransactionGeneratorCreator;
+    use crate::{hot_spot::HotSpot, TransactionGeneratorCreator};
+    use aptos_infallible::RwLock;
+    use aptos_sdk::{
+        move_types::account_address::AccountAddress,
+        transaction_builder::TransactionFactory,
+        types::{chain_id::ChainId, LocalAccount},
+    };
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::sync::Arc;
+
+    #[test]
+    pub fn test_seeded_transactions() {
+        let addresses = Arc::new(RwLock::new(
+            (0..100).map(|_| AccountAddress::random()).collect(),
+        ));
+        let generate = |seed| {
+            let mut creator = P2PTransactionGeneratorCreator::new(
+                StdRng::seed_from_u64(seed),
+                TransactionFactory::new(ChainId::test()),
+                1,
+                addresses.clone(),
+                0,
+                HotSpot::none(),
+            );
+            let mut rng = StdRng::seed_from_u64(0);
+            let mut accounts: Vec<_> = (0..4).map(|_| LocalAccount::generate(&mut rng)).collect();
+            (0..2)
+                .flat_map(|_| {
+                    creator
+                        .create_transaction_generator()
+                        .generate_transactions(accounts.iter_mut().collect(), 5)
+                })
+                .map(|txn| (txn.sender(), txn.sequence_number(), txn.payload().clone()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(generate(1), generate(1));
+        assert_ne!(generate(1), generate(2));
+    }
+}
@@ -0,0 +1,228 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{TransactionGenerator, TransactionGeneratorCreator};
+use aptos_infallible::Mutex;
+use aptos_sdk::types::{transaction::SignedTransaction, LocalAccount};
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+/// When a generator phase ends, and the next one starts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PhaseLimit {
+    Duration(Duration),
+    /// At least as many transactions, as the last batch isn't cut short
+    Transactions(usize),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GeneratorPhase {
+    /// Name of the phase in stats
+    pub label: String,
+    /// None runs the phase until the end, so only makes sense for the last one
+    pub limit: Option<PhaseLimit>,
+}
+
+impl GeneratorPhase {
+    pub fn new(label: impl Into<String>, limit: Option<PhaseLimit>) -> Self {
+        Self {
+            label: label.into(),
+            limit,
+        }
+    }
+}
+
+// phase all generators of a creator are in
+struct PhaseState {
+    cur_phase: usize,
+    phase_start: Instant,
+}
+
+struct SharedPhases {
+    phases: Vec<GeneratorPhase>,
+    state: Mutex<PhaseState>,
+    // generated by all generators, in each phase
+    phase_transactions: Vec<AtomicUsize>,
+}
+
+impl SharedPhases {
+    fn new(phases: Vec<GeneratorPhase>) -> Self {
+        assert!(!phases.is_empty(), "No generator phases");
+        Self {
+            phase_transactions: phases.iter().map(|_| AtomicUsize::new(0)).collect(),
+            phases,
+            state: Mutex::new(PhaseState {
+                cur_phase: 0,
+                phase_start: Instant::now(),
+            }),
+        }
+    }
+
+    fn phase_over(&self, state: &PhaseState) -> bool {
+        match self.phases[state.cur_phase].limit {
+            Some(PhaseLimit::Duration(duration)) => state.phase_start.elapsed() >= duration,
+            Some(PhaseLimit::Transactions(num_transactions)) => {
+                self.phase_transactions[state.cur_phase].load(Ordering::Relaxed) >= num_transactions
+            },
+            None => false,
+        }
+    }
+
+    // current phase, after moving on from the ones that are over
+    fn cur_phase(&self) -> usize {
+        let mut state = self.state.lock();
+        while state.cur_phase + 1 < self.phases.len() && self.phase_over(&state) {
+            state.cur_phase += 1;
+            state.phase_start = Instant::now();
+        }
+        state.cur_phase
+    }
+}
+
+/// Generators run one after another, each for its phase, e.g. minting a collection for a
+/// minute and then trading its tokens, instead of the weighted mix of a phase of the run.
+/// Phases are shared by all generators of the PhasedTransactionGeneratorCreator, so they
+/// are timed from its creation at the start of the job, and count the transactions of
+/// all workers. The last one keeps running once it's over.
+pub struct PhasedTransactionGenerator {
+    generators: Vec<Box<dyn TransactionGenerator>>,
+    phases: Arc<SharedPhases>,
+    last_generated_phase: Option<usize>,
+}
+
+impl TransactionGenerator for PhasedTransactionGenerator {
+    fn generate_transactions(
+        &mut self,
+        accounts: Vec<&mut LocalAccount>,
+        transactions_per_account: usize,
+    ) -> Vec<SignedTransaction> {
+        let phase = self.phases.cur_phase();
+        let requests =
+            self.generators[phase].generate_transactions(accounts, transactions_per_account);
+        self.phases.phase_transactions[phase].fetch_add(requests.len(), Ordering::Relaxed);
+        self.last_generated_phase = Some(phase);
+        requests
+    }
+
+    fn last_generated_label(&self) -> Option<&str> {
+        self.last_generated_phase
+            .map(|phase| self.phases.phases[phase].label.as_str())
+    }
+}
+
+pub struct PhasedTransactionGeneratorCreator {
+    creators: Vec<Box<dyn TransactionGeneratorCreator>>,
+    phases: Arc<SharedPhases>,
+}
+
+impl PhasedTransactionGeneratorCreator {
+    /// The first phase starts right away.
+    pub fn new(phases: Vec<(GeneratorPhase, Box<dyn TransactionGeneratorCreator>)>) -> Self {
+        let (phases, creators) = phases.into_iter().unzip();
+        Self {
+            creators,
+            phases: Arc::new(SharedPhases::new(phases)),
+        }
+    }
+}
+
+impl TransactionGeneratorCreator for PhasedTransactionGeneratorCreator {
+    fn create_transaction_generator(&mut self) -> Box<dyn TransactionGenerator> {
+        Box::new(PhasedTransactionGenerator {
+            generators: self
+                .creators
+                .iter_mut()
+                .map(|creator| creator.create_transaction_generator())
+                .collect(),
+            phases: self.phases.clone(),
+            last_generated_phase: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{GeneratorPhase, PhaseLimit, PhasedTransactionGeneratorCreator};
+    use crate::{TransactionGenerator, TransactionGeneratorCreator, SEND_AMOUNT};
+    use aptos_sdk::{
+        transaction_builder::{aptos_stdlib, TransactionFactory},
+        types::{chain_id::ChainId, transaction::SignedTransaction, LocalAccount},
+    };
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::time::Duration;
+
+    struct SelfTransfers;
+
+    impl TransactionGenerator for SelfTransfers {
+        fn generate_transactions(
+            &mut self,
+            accounts: Vec<&mut LocalAccount>,
+            transactions_per_account: usize,
+        ) -> Vec<SignedTransaction> {
+            let txn_factory = TransactionFactory::new(ChainId::test());
+            accounts
+                .into_iter()
+                .flat_map(|account| {
+                    (0..transactions_per_account)
+                        .map(|_| {
+                            let address = account.address();
+                            account.sign_with_transaction_builder(txn_factory.payload(
+                                aptos_stdlib::aptos_account_transfer(address, SEND_AMOUNT),
+                            ))
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .collect()
+        }
+    }
+
+    struct SelfTransfersCreator;
+
+    impl TransactionGeneratorCreator for SelfTransfersCreator {
+        fn create_transaction_generator(&mut self) -> Box<dyn TransactionGenerator> {
+            Box::new(SelfTransfers)
+        }
+    }
+
+    #[test]
+    pub fn test_phased_transaction_generator() {
+        let phase = |label: &str, limit| {
+            let creator: Box<dyn TransactionGeneratorCreator> = Box::new(SelfTransfersCreator);
+            (GeneratorPhase::new(label, limit), creator)
+        };
+        let mut creator = PhasedTransactionGeneratorCreator::new(vec![
+            phase("mint", Some(PhaseLimit::Transactions(4))),
+            phase("warm up", Some(PhaseLimit::Duration(Duration::ZERO))),
+            phase("trade", None),
+        ]);
+        let mut generators = vec![
+            creator.create_transaction_generator(),
+            creator.create_transaction_generator(),
+        ];
+        let mut account = LocalAccount::generate(&mut StdRng::from_seed([0; 32]));
+        assert_eq!(generators[0].last_generated_label(), None);
+
+        // phases are counted over both generators
+        let mut labels = Vec::new();
+        for index in 0..5 {
+            let generator = &mut generators[index % 2];
+            assert_eq!(
+                generator.generate_transactions(vec![&mut account], 2).len(),
+                2
+            );
+            labels.push(generator.last_generated_label().unwrap().to_string());
+        }
+        assert_eq!(labels, vec!["mint", "mint", "warm up", "trade", "trade"]);
+        assert_eq!(account.sequence_number(), 10);
+
+        // and generators created later start in the current phase
+        let mut generator = creator.create_transaction_generator();
+        generator.generate_transactions(vec![&mut account], 1);
+        assert_eq!(generator.last_generated_label(), Some("trade"));
+    }
+}
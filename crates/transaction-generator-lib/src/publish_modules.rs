@@ -8,8 +8,8 @@ use aptos_sdk::{
     transaction_builder::TransactionFactory,
     types::{transaction::SignedTransaction, LocalAccount},
 };
-use rand::{rngs::StdRng, SeedableRng};
-use std::sync::Arc;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{cmp::min, sync::Arc};
 
 pub struct PublishPackageGenerator {
     rng: StdRng,
@@ -66,13 +66,15 @@ impl TransactionGenerator for PublishPackageGenerator {
 }
 
 pub struct PublishPackageCreator {
+    rng: StdRng,
     txn_factory: TransactionFactory,
     package_handler: Arc<RwLock<PackageHandler>>,
 }
 
 impl PublishPackageCreator {
-    pub fn new(txn_factory: TransactionFactory) -> Self {
+    pub fn new(rng: StdRng, txn_factory: TransactionFactory) -> Self {
         Self {
+            rng,
             txn_factory,
             package_handler: Arc::new(RwLock::new(PackageHandler::new())),
         }
@@ -82,9 +84,169 @@ impl PublishPackageCreator {
 impl TransactionGeneratorCreator for PublishPackageCreator {
     fn create_transaction_generator(&mut self) -> Box<dyn TransactionGenerator> {
         Box::new(PublishPackageGenerator::new(
-            StdRng::from_entropy(),
+            StdRng::from_rng(&mut self.rng).unwrap(),
+            self.package_handler.clone(),
+            self.txn_factory.clone(),
+        ))
+    }
+}
+
+/// Packages published and then upgraded, again and again, by the same accounts, to
+/// exercise code publishing and module cache invalidation under load. Publishers are
+/// taken from the accounts pool (e.g. filled by account generation) while generating,
+/// and returned to it, so that their next publish is a compatible upgrade of their
+/// package. Falls back to the given accounts when the pool is empty.
+/// The package is used after each publish, so that the new code gets loaded.
+pub struct PublishAndUpgradeGenerator {
+    rng: StdRng,
+    package_handler: Arc<RwLock<PackageHandler>>,
+    txn_factory: TransactionFactory,
+    accounts_pool: Arc<RwLock<Vec<LocalAccount>>>,
+}
+
+impl PublishAndUpgradeGenerator {
+    pub fn new(
+        rng: StdRng,
+        package_handler: Arc<RwLock<PackageHandler>>,
+        txn_factory: TransactionFactory,
+        accounts_pool: Arc<RwLock<Vec<LocalAccount>>>,
+    ) -> Self {
+        Self {
+            rng,
+            package_handler,
+            txn_factory,
+            accounts_pool,
+        }
+    }
+
+    fn publish_and_use(
+        &mut self,
+        publisher: &mut LocalAccount,
+        transactions_per_account: usize,
+        requests: &mut Vec<SignedTransaction>,
+    ) {
+        let package = self
+            .package_handler
+            .write()
+            .pick_package(&mut self.rng, publisher);
+        requests.push(package.publish_transaction(publisher, &self.txn_factory));
+        for _ in 1..transactions_per_account {
+            requests.push(package.use_random_transaction(
+                &mut self.rng,
+                publisher,
+                &self.txn_factory,
+            ));
+        }
+    }
+}
+
+impl TransactionGenerator for PublishAndUpgradeGenerator {
+    fn generate_transactions(
+        &mut self,
+        accounts: Vec<&mut LocalAccount>,
+        transactions_per_account: usize,
+    ) -> Vec<SignedTransaction> {
+        let mut requests = Vec::with_capacity(accounts.len() * transactions_per_account);
+        // taken out of the pool, so that no other worker publishes from them meanwhile
+        let mut publishers: Vec<_> = {
+            let mut accounts_pool = self.accounts_pool.write();
+            let needed = min(accounts.len(), accounts_pool.len());
+            (0..needed)
+                .map(|_| {
+                    let index = self.rng.gen_range(0, accounts_pool.len());
+                    accounts_pool.swap_remove(index)
+                })
+                .collect()
+        };
+        if publishers.is_empty() {
+            for account in accounts {
+                self.publish_and_use(account, transactions_per_account, &mut requests);
+            }
+        } else {
+            for publisher in publishers.iter_mut() {
+                self.publish_and_use(publisher, transactions_per_account, &mut requests);
+            }
+            self.accounts_pool.write().extend(publishers);
+        }
+        requests
+    }
+}
+
+pub struct PublishAndUpgradeCreator {
+    rng: StdRng,
+    txn_factory: TransactionFactory,
+    package_handler: Arc<RwLock<PackageHandler>>,
+    accounts_pool: Arc<RwLock<Vec<LocalAccount>>>,
+}
+
+impl PublishAndUpgradeCreator {
+    pub fn new(
+        rng: StdRng,
+        txn_factory: TransactionFactory,
+        accounts_pool: Arc<RwLock<Vec<LocalAccount>>>,
+    ) -> Self {
+        Self {
+            rng,
+            txn_factory,
+            package_handler: Arc::new(RwLock::new(PackageHandler::new())),
+            accounts_pool,
+        }
+    }
+}
+
+impl TransactionGeneratorCreator for PublishAndUpgradeCreator {
+    fn create_transaction_generator(&mut self) -> Box<dyn TransactionGenerator> {
+        Box::new(PublishAndUpgradeGenerator::new(
+            StdRng::from_rng(&mut self.rng).unwrap(),
             self.package_handler.clone(),
             self.txn_factory.clone(),
+            self.accounts_pool.clone(),
         ))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::PublishAndUpgradeGenerator;
+    use crate::{publishing::publish_util::PackageHandler, TransactionGenerator};
+    use aptos_infallible::RwLock;
+    use aptos_sdk::{
+        transaction_builder::TransactionFactory,
+        types::{chain_id::ChainId, LocalAccount},
+    };
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::sync::Arc;
+
+    #[test]
+    pub fn test_publish_and_upgrade() {
+        let mut rng = StdRng::from_seed([0; 32]);
+        let accounts_pool = Arc::new(RwLock::new(vec![]));
+        let mut generator = PublishAndUpgradeGenerator::new(
+            StdRng::from_seed([0; 32]),
+            Arc::new(RwLock::new(PackageHandler::new())),
+            TransactionFactory::new(ChainId::test()),
+            accounts_pool.clone(),
+        );
+        let mut accounts: Vec<_> = (0..2).map(|_| LocalAccount::generate(&mut rng)).collect();
+        // with an empty pool, the given accounts publish
+        let txns = generator.generate_transactions(accounts.iter_mut().collect(), 2);
+        assert_eq!(txns.len(), 4);
+        assert!(accounts
+            .iter()
+            .all(|account| account.sequence_number() == 2));
+
+        let pool_account = LocalAccount::generate(&mut rng);
+        let pool_address = pool_account.address();
+        accounts_pool.write().push(pool_account);
+        for round in 0..3 {
+            let txns = generator.generate_transactions(accounts.iter_mut().collect(), 2);
+            // only one account in the pool, publishing its package and upgrading it after
+            assert_eq!(txns.len(), 2);
+            assert!(txns.iter().all(|txn| txn.sender() == pool_address));
+            assert_eq!(txns[0].sequence_number(), round * 2);
+        }
+        let accounts_pool = accounts_pool.read();
+        assert_eq!(accounts_pool.len(), 1);
+        assert_eq!(accounts_pool[0].sequence_number(), 6);
+    }
+}
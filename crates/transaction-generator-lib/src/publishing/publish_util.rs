@@ -6,7 +6,7 @@ use crate::publishing::module_simple;
 use aptos_framework::natives::code::PackageMetadata;
 use aptos_sdk::{
     bcs,
-    move_types::identifier::Identifier,
+    move_types::{identifier::Identifier, language_storage::ModuleId},
     transaction_builder::{aptos_stdlib, TransactionFactory},
     types::{account_address::AccountAddress, transaction::SignedTransaction, LocalAccount},
 };
@@ -152,6 +152,13 @@ impl Package {
         }
     }
 
+    // Return the id of the module called by the package's transactions
+    pub fn module_id(&self) -> ModuleId {
+        match self {
+            Self::Simple(modules, _) => modules[0].self_id(),
+        }
+    }
+
     // Return a transaction to use the current package
     pub fn use_random_transaction(
         &self,
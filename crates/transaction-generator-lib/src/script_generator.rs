@@ -0,0 +1,381 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{TransactionGenerator, TransactionGeneratorCreator};
+use aptos_infallible::RwLock;
+use aptos_sdk::{
+    move_types::{account_address::AccountAddress, identifier::Identifier},
+    transaction_builder::TransactionFactory,
+    types::{
+        transaction::{Script, SignedTransaction, TransactionArgument, TransactionPayload},
+        LocalAccount,
+    },
+};
+use move_binary_format::{
+    file_format::{
+        empty_script, Ability, AbilitySet, AddressIdentifierIndex, Bytecode, CompiledScript,
+        FunctionHandle, FunctionHandleIndex, FunctionInstantiation, FunctionInstantiationIndex,
+        IdentifierIndex, ModuleHandle, ModuleHandleIndex, Signature, SignatureIndex,
+        SignatureToken, StructHandle, StructHandleIndex, StructTypeParameter,
+    },
+    file_format_common::VERSION_5,
+};
+use rand::{prelude::SliceRandom, rngs::StdRng, Rng, SeedableRng};
+use std::sync::Arc;
+
+/// Script executed by ScriptGenerator transactions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScriptKind {
+    /// `fun main(_amount: u64, _to: address) {}`, so that mostly script deserialization,
+    /// verification and argument checks are measured
+    Noop,
+    /// `fun main(sender: signer, to: address, amount: u64)`, transferring coins with
+    /// 0x1::aptos_account::transfer
+    Transfer,
+    /// `fun main(sender: signer, num_events: u64)`, emitting num_events u64 events on
+    /// an event handle created by the script, so that event writes come with little
+    /// state writes. The framework has no module events, so only handle events are.
+    EmitEvents { num_events: u64 },
+}
+
+impl ScriptKind {
+    /// Compiled script, in bytecode version 5, so that it's accepted whether or not
+    /// version 6 is enabled on chain.
+    pub fn code(&self) -> Vec<u8> {
+        let mut script = empty_script();
+        script.version = VERSION_5;
+        match self {
+            ScriptKind::Noop => {
+                script.signatures.push(Signature(vec![
+                    SignatureToken::U64,
+                    SignatureToken::Address,
+                ]));
+                script.parameters = SignatureIndex(1);
+            },
+            ScriptKind::Transfer => set_transfer_code(&mut script),
+            ScriptKind::EmitEvents { .. } => set_emit_events_code(&mut script),
+        }
+        let mut code = vec![];
+        script
+            .serialize_for_version(Some(VERSION_5), &mut code)
+            .expect("Script must serialize");
+        code
+    }
+}
+
+fn set_transfer_code(script: &mut CompiledScript) {
+    script.address_identifiers.push(AccountAddress::ONE);
+    script.identifiers.extend([
+        Identifier::new("aptos_account").unwrap(),
+        Identifier::new("transfer").unwrap(),
+    ]);
+    script.module_handles.push(ModuleHandle {
+        address: AddressIdentifierIndex(0),
+        name: IdentifierIndex(0),
+    });
+    // script parameters, and the ones of transfer
+    script.signatures.extend([
+        Signature(vec![
+            SignatureToken::Signer,
+            SignatureToken::Address,
+            SignatureToken::U64,
+        ]),
+        Signature(vec![
+            SignatureToken::Reference(Box::new(SignatureToken::Signer)),
+            SignatureToken::Address,
+            SignatureToken::U64,
+        ]),
+    ]);
+    script.parameters = SignatureIndex(1);
+    script.function_handles.push(FunctionHandle {
+        module: ModuleHandleIndex(0),
+        name: IdentifierIndex(1),
+        parameters: SignatureIndex(2),
+        return_: SignatureIndex(0),
+        type_parameters: vec![],
+    });
+    script.code.code = vec![
+        Bytecode::ImmBorrowLoc(0),
+        Bytecode::MoveLoc(1),
+        Bytecode::MoveLoc(2),
+        Bytecode::Call(FunctionHandleIndex(0)),
+        Bytecode::Ret,
+    ];
+}
+
+fn set_emit_events_code(script: &mut CompiledScript) {
+    script.address_identifiers.push(AccountAddress::ONE);
+    script.identifiers.extend([
+        Identifier::new("account").unwrap(),
+        Identifier::new("event").unwrap(),
+        Identifier::new("EventHandle").unwrap(),
+        Identifier::new("new_event_handle").unwrap(),
+        Identifier::new("emit_event").unwrap(),
+        Identifier::new("destroy_handle").unwrap(),
+    ]);
+    script.module_handles.extend([
+        ModuleHandle {
+            address: AddressIdentifierIndex(0),
+            name: IdentifierIndex(0),
+        },
+        ModuleHandle {
+            address: AddressIdentifierIndex(0),
+            name: IdentifierIndex(1),
+        },
+    ]);
+    // struct EventHandle<phantom T: drop + store> has store
+    script.struct_handles.push(StructHandle {
+        module: ModuleHandleIndex(1),
+        name: IdentifierIndex(2),
+        abilities: AbilitySet::EMPTY | Ability::Store,
+        type_parameters: vec![StructTypeParameter {
+            constraints: AbilitySet::EMPTY | Ability::Drop | Ability::Store,
+            is_phantom: true,
+        }],
+    });
+    let event_handle =
+        |type_param| SignatureToken::StructInstantiation(StructHandleIndex(0), vec![type_param]);
+    // script parameters, locals (the handle and the loop index), parameters of
+    // new_event_handle, its return (and destroy_handle parameters), emit_event
+    // parameters, and the instantiation of the three of them
+    script.signatures.extend([
+        Signature(vec![SignatureToken::Signer, SignatureToken::U64]),
+        Signature(vec![event_handle(SignatureToken::U64), SignatureToken::U64]),
+        Signature(vec![SignatureToken::Reference(Box::new(
+            SignatureToken::Signer,
+        ))]),
+        Signature(vec![event_handle(SignatureToken::TypeParameter(0))]),
+        Signature(vec![
+            SignatureToken::MutableReference(Box::new(event_handle(
+                SignatureToken::TypeParameter(0),
+            ))),
+            SignatureToken::TypeParameter(0),
+        ]),
+        Signature(vec![SignatureToken::U64]),
+    ]);
+    script.parameters = SignatureIndex(1);
+    script.code.locals = SignatureIndex(2);
+    let type_param = AbilitySet::EMPTY | Ability::Drop | Ability::Store;
+    script.function_handles.extend([
+        FunctionHandle {
+            module: ModuleHandleIndex(0),
+            name: IdentifierIndex(3),
+            parameters: SignatureIndex(3),
+            return_: SignatureIndex(4),
+            type_parameters: vec![type_param],
+        },
+        FunctionHandle {
+            module: ModuleHandleIndex(1),
+            name: IdentifierIndex(4),
+            parameters: SignatureIndex(5),
+            return_: SignatureIndex(0),
+            type_parameters: vec![type_param],
+        },
+        FunctionHandle {
+            module: ModuleHandleIndex(1),
+            name: IdentifierIndex(5),
+            parameters: SignatureIndex(4),
+            return_: SignatureIndex(0),
+            type_parameters: vec![type_param],
+        },
+    ]);
+    script
+        .function_instantiations
+        .extend((0..3).map(|handle| FunctionInstantiation {
+            handle: FunctionHandleIndex(handle),
+            type_parameters: SignatureIndex(6),
+        }));
+    // locals are sender, num_events, handle and i
+    script.code.code = vec![
+        Bytecode::ImmBorrowLoc(0),
+        Bytecode::CallGeneric(FunctionInstantiationIndex(0)),
+        Bytecode::StLoc(2),
+        Bytecode::LdU64(0),
+        Bytecode::StLoc(3),
+        // while (i < num_events)
+        Bytecode::CopyLoc(3),
+        Bytecode::CopyLoc(1),
+        Bytecode::Lt,
+        Bytecode::BrFalse(17),
+        // emit_event(&mut handle, i)
+        Bytecode::MutBorrowLoc(2),
+        Bytecode::CopyLoc(3),
+        Bytecode::CallGeneric(FunctionInstantiationIndex(1)),
+        // i = i + 1
+        Bytecode::MoveLoc(3),
+        Bytecode::LdU64(1),
+        Bytecode::Add,
+        Bytecode::StLoc(3),
+        Bytecode::Branch(5),
+        Bytecode::MoveLoc(2),
+        Bytecode::CallGeneric(FunctionInstantiationIndex(2)),
+        Bytecode::Ret,
+    ];
+}
+
+/// Script payloads, instead of entry functions, so that the cost of script verification
+/// and execution is covered by load tests.
+pub struct ScriptGenerator {
+    rng: StdRng,
+    txn_factory: TransactionFactory,
+    kind: ScriptKind,
+    code: Arc<Vec<u8>>,
+    send_amount: u64,
+    all_addresses: Arc<RwLock<Vec<AccountAddress>>>,
+}
+
+impl ScriptGenerator {
+    pub fn new(
+        rng: StdRng,
+        txn_factory: TransactionFactory,
+        kind: ScriptKind,
+        code: Arc<Vec<u8>>,
+        send_amount: u64,
+        all_addresses: Arc<RwLock<Vec<AccountAddress>>>,
+    ) -> Self {
+        Self {
+            rng,
+            txn_factory,
+            kind,
+            code,
+            send_amount,
+            all_addresses,
+        }
+    }
+}
+
+impl TransactionGenerator for ScriptGenerator {
+    fn generate_transactions(
+        &mut self,
+        accounts: Vec<&mut LocalAccount>,
+        transactions_per_account: usize,
+    ) -> Vec<SignedTransaction> {
+        let all_addresses = self.all_addresses.read();
+        let mut requests = Vec::with_capacity(accounts.len() * transactions_per_account);
+        for account in accounts {
+            for _ in 0..transactions_per_account {
+                let to = all_addresses
+                    .choose(&mut self.rng)
+                    .copied()
+                    .unwrap_or_else(|| account.address());
+                let args = match self.kind {
+                    ScriptKind::Noop => vec![
+                        TransactionArgument::U64(self.rng.gen()),
+                        TransactionArgument::Address(to),
+                    ],
+                    ScriptKind::Transfer => vec![
+                        TransactionArgument::Address(to),
+                        TransactionArgument::U64(self.send_amount),
+                    ],
+                    ScriptKind::EmitEvents { num_events } => {
+                        vec![TransactionArgument::U64(num_events)]
+                    },
+                };
+                let payload =
+                    TransactionPayload::Script(Script::new((*self.code).clone(), vec![], args));
+                requests
+                    .push(account.sign_with_transaction_builder(self.txn_factory.payload(payload)));
+            }
+        }
+        requests
+    }
+}
+
+pub struct ScriptGeneratorCreator {
+    rng: StdRng,
+    txn_factory: TransactionFactory,
+    kind: ScriptKind,
+    code: Arc<Vec<u8>>,
+    send_amount: u64,
+    all_addresses: Arc<RwLock<Vec<AccountAddress>>>,
+}
+
+impl ScriptGeneratorCreator {
+    pub fn new(
+        rng: StdRng,
+        txn_factory: TransactionFactory,
+        kind: ScriptKind,
+        send_amount: u64,
+        all_addresses: Arc<RwLock<Vec<AccountAddress>>>,
+    ) -> Self {
+        Self {
+            rng,
+            txn_factory,
+            kind,
+            code: Arc::new(kind.code()),
+            send_amount,
+            all_addresses,
+        }
+    }
+}
+
+impl TransactionGeneratorCreator for ScriptGeneratorCreator {
+    fn create_transaction_generator(&mut self) -> Box<dyn TransactionGenerator> {
+        Box::new(ScriptGenerator::new(
+            StdRng::from_rng(&mut self.rng).unwrap(),
+            self.txn_factory.clone(),
+            self.kind,
+            self.code.clone(),
+            self.send_amount,
+            self.all_addresses.clone(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ScriptGeneratorCreator, ScriptKind};
+    use crate::TransactionGeneratorCreator;
+    use aptos_infallible::RwLock;
+    use aptos_sdk::{
+        move_types::account_address::AccountAddress,
+        transaction_builder::TransactionFactory,
+        types::{
+            chain_id::ChainId,
+            transaction::{TransactionArgument, TransactionPayload},
+            LocalAccount,
+        },
+    };
+    use move_binary_format::file_format::CompiledScript;
+    use move_bytecode_verifier::verify_script;
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::sync::Arc;
+
+    #[test]
+    pub fn test_script_generator() {
+        for kind in [
+            ScriptKind::Noop,
+            ScriptKind::Transfer,
+            ScriptKind::EmitEvents { num_events: 1 },
+        ] {
+            let script = CompiledScript::deserialize(&kind.code()).unwrap();
+            assert_eq!(script.version, 5);
+            verify_script(&script).unwrap();
+        }
+
+        let receiver = AccountAddress::random();
+        let mut generator = ScriptGeneratorCreator::new(
+            StdRng::from_seed([0; 32]),
+            TransactionFactory::new(ChainId::test()),
+            ScriptKind::Transfer,
+            1,
+            Arc::new(RwLock::new(vec![receiver])),
+        )
+        .create_transaction_generator();
+        let mut account = LocalAccount::generate(&mut StdRng::from_seed([0; 32]));
+        let txns = generator.generate_transactions(vec![&mut account], 2);
+        assert_eq!(txns.len(), 2);
+        for txn in txns {
+            match txn.payload() {
+                TransactionPayload::Script(script) => {
+                    assert_eq!(script.code(), ScriptKind::Transfer.code().as_slice());
+                    assert_eq!(script.args(), &[
+                        TransactionArgument::Address(receiver),
+                        TransactionArgument::U64(1)
+                    ]);
+                },
+                _ => panic!("Expected a script payload"),
+            }
+        }
+    }
+}
@@ -0,0 +1,297 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::TransactionExecutor;
+use crate::{
+    nft_mint_and_transfer::create_and_fund_account_request, TransactionGenerator,
+    TransactionGeneratorCreator,
+};
+use aptos_infallible::RwLock;
+use aptos_logger::info;
+use aptos_sdk::{
+    transaction_builder::{aptos_stdlib, TransactionFactory},
+    types::{
+        account_address::{create_resource_address, AccountAddress},
+        transaction::{SignedTransaction, TransactionPayload},
+        LocalAccount,
+    },
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{cmp::min, collections::HashMap, sync::Arc};
+
+// staked, unlocked and withdrawn again by each account in each cycle
+const STAKE_AMOUNT: u64 = 1_000;
+// 0x1::delegation_pool::MIN_COINS_ON_SHARES_POOL, the least a delegator can have staked
+const DELEGATION_AMOUNT: u64 = 1_000_000_000;
+// 0x1::delegation_pool::MODULE_SALT
+const DELEGATION_POOL_MODULE_SALT: &[u8] = b"aptos_framework::delegation_pool";
+const DELEGATION_POOL_SEED: &[u8] = b"transaction emitter";
+
+/// Stake pool operations are done on a stake pool of each account, and delegation pool
+/// ones on a single delegation pool, created when the workload is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StakingFlow {
+    StakePool,
+    DelegationPool(AccountAddress),
+}
+
+impl StakingFlow {
+    /// Payload of the step-th operation of owner. Stake pools are initialized first,
+    /// with owner as their operator and voter, and then stake is added, unlocked and
+    /// withdrawn in turn. Pools aren't in the validator set, so their stake can be
+    /// withdrawn right after it's unlocked.
+    pub fn payload(&self, owner: AccountAddress, step: u64) -> TransactionPayload {
+        match self {
+            StakingFlow::StakePool => {
+                if step == 0 {
+                    return aptos_stdlib::stake_initialize_stake_owner(STAKE_AMOUNT, owner, owner);
+                }
+                match (step - 1) % 3 {
+                    0 => aptos_stdlib::stake_add_stake(STAKE_AMOUNT),
+                    1 => aptos_stdlib::stake_unlock(STAKE_AMOUNT),
+                    _ => aptos_stdlib::stake_withdraw(STAKE_AMOUNT),
+                }
+            },
+            StakingFlow::DelegationPool(pool_address) => match step % 3 {
+                0 => aptos_stdlib::delegation_pool_add_stake(*pool_address, DELEGATION_AMOUNT),
+                1 => aptos_stdlib::delegation_pool_unlock(*pool_address, DELEGATION_AMOUNT),
+                _ => aptos_stdlib::delegation_pool_withdraw(*pool_address, DELEGATION_AMOUNT),
+            },
+        }
+    }
+}
+
+/// Staking operations, from accounts taken from the accounts pool (e.g. filled by
+/// account generation) while generating and returned to it after, or from the given
+/// accounts when the pool is empty. Steps done by each account are tracked, so that
+/// operations of an account follow each other whichever worker it's used by.
+/// Delegators need DELEGATION_AMOUNT more coins than for gas.
+pub struct StakingOperations {
+    rng: StdRng,
+    txn_factory: TransactionFactory,
+    flow: StakingFlow,
+    accounts_pool: Arc<RwLock<Vec<LocalAccount>>>,
+    steps: Arc<RwLock<HashMap<AccountAddress, u64>>>,
+}
+
+impl StakingOperations {
+    pub fn new(
+        rng: StdRng,
+        txn_factory: TransactionFactory,
+        flow: StakingFlow,
+        accounts_pool: Arc<RwLock<Vec<LocalAccount>>>,
+        steps: Arc<RwLock<HashMap<AccountAddress, u64>>>,
+    ) -> Self {
+        Self {
+            rng,
+            txn_factory,
+            flow,
+            accounts_pool,
+            steps,
+        }
+    }
+
+    fn generate_for_account(
+        &self,
+        account: &mut LocalAccount,
+        transactions_per_account: usize,
+        requests: &mut Vec<SignedTransaction>,
+    ) {
+        let mut steps = self.steps.write();
+        let step = steps.entry(account.address()).or_insert(0);
+        for _ in 0..transactions_per_account {
+            requests.push(
+                account.sign_with_transaction_builder(
+                    self.txn_factory
+                        .payload(self.flow.payload(account.address(), *step)),
+                ),
+            );
+            *step += 1;
+        }
+    }
+}
+
+impl TransactionGenerator for StakingOperations {
+    fn generate_transactions(
+        &mut self,
+        accounts: Vec<&mut LocalAccount>,
+        transactions_per_account: usize,
+    ) -> Vec<SignedTransaction> {
+        let mut requests = Vec::with_capacity(accounts.len() * transactions_per_account);
+        // taken out of the pool, so that no other worker uses them meanwhile
+        let mut pool_accounts: Vec<_> = {
+            let mut accounts_pool = self.accounts_pool.write();
+            let needed = min(accounts.len(), accounts_pool.len());
+            (0..needed)
+                .map(|_| {
+                    let index = self.rng.gen_range(0, accounts_pool.len());
+                    accounts_pool.swap_remove(index)
+                })
+                .collect()
+        };
+        if pool_accounts.is_empty() {
+            for account in accounts {
+                self.generate_for_account(account, transactions_per_account, &mut requests);
+            }
+        } else {
+            for account in pool_accounts.iter_mut() {
+                self.generate_for_account(account, transactions_per_account, &mut requests);
+            }
+            self.accounts_pool.write().extend(pool_accounts);
+        }
+        requests
+    }
+}
+
+pub struct StakingOperationsCreator {
+    rng: StdRng,
+    txn_factory: TransactionFactory,
+    flow: StakingFlow,
+    accounts_pool: Arc<RwLock<Vec<LocalAccount>>>,
+    steps: Arc<RwLock<HashMap<AccountAddress, u64>>>,
+}
+
+impl StakingOperationsCreator {
+    /// Creates the delegation pool, for delegation pool operations, which needs
+    /// delegation pools to be enabled on chain.
+    pub async fn new(
+        mut rng: StdRng,
+        txn_factory: TransactionFactory,
+        init_txn_factory: TransactionFactory,
+        source_accounts: &mut [LocalAccount],
+        txn_executor: &dyn TransactionExecutor,
+        accounts_pool: Arc<RwLock<Vec<LocalAccount>>>,
+        delegation_pool: bool,
+    ) -> Self {
+        let flow = if delegation_pool {
+            let source_account = source_accounts.get_mut(0).unwrap();
+            let mut owner = LocalAccount::generate(&mut rng);
+            let balance = txn_executor
+                .get_account_balance(source_account.address())
+                .await
+                .unwrap();
+            txn_executor
+                .execute_transactions(&[create_and_fund_account_request(
+                    source_account,
+                    balance / 10,
+                    owner.public_key(),
+                    &init_txn_factory,
+                )])
+                .await
+                .unwrap();
+            txn_executor
+                .execute_transactions(&[owner.sign_with_transaction_builder(
+                    init_txn_factory.payload(
+                        aptos_stdlib::delegation_pool_initialize_delegation_pool(
+                            0,
+                            DELEGATION_POOL_SEED.to_vec(),
+                        ),
+                    ),
+                )])
+                .await
+                .unwrap();
+            let pool_address = delegation_pool_address(owner.address(), DELEGATION_POOL_SEED);
+            info!("Created delegation pool {}", pool_address);
+            StakingFlow::DelegationPool(pool_address)
+        } else {
+            StakingFlow::StakePool
+        };
+        Self {
+            rng,
+            txn_factory,
+            flow,
+            accounts_pool,
+            steps: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+}
+
+/// Address of the delegation pool created by owner from seed, as by
+/// 0x1::delegation_pool::initialize_delegation_pool.
+pub fn delegation_pool_address(owner: AccountAddress, seed: &[u8]) -> AccountAddress {
+    create_resource_address(owner, &[DELEGATION_POOL_MODULE_SALT, seed].concat())
+}
+
+impl TransactionGeneratorCreator for StakingOperationsCreator {
+    fn create_transaction_generator(&mut self) -> Box<dyn TransactionGenerator> {
+        Box::new(StakingOperations::new(
+            StdRng::from_rng(&mut self.rng).unwrap(),
+            self.txn_factory.clone(),
+            self.flow,
+            self.accounts_pool.clone(),
+            self.steps.clone(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{StakingFlow, StakingOperations};
+    use crate::TransactionGenerator;
+    use aptos_infallible::RwLock;
+    use aptos_sdk::{
+        transaction_builder::TransactionFactory,
+        types::{
+            account_address::AccountAddress,
+            chain_id::ChainId,
+            transaction::{SignedTransaction, TransactionPayload},
+            LocalAccount,
+        },
+    };
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::{collections::HashMap, sync::Arc};
+
+    fn function_names(txns: &[SignedTransaction]) -> Vec<String> {
+        txns.iter()
+            .map(|txn| match txn.payload() {
+                TransactionPayload::EntryFunction(entry_function) => {
+                    entry_function.function().to_string()
+                },
+                _ => panic!("Expected an entry function payload"),
+            })
+            .collect()
+    }
+
+    #[test]
+    pub fn test_staking_operations() {
+        let mut rng = StdRng::from_seed([0; 32]);
+        let accounts_pool = Arc::new(RwLock::new(vec![]));
+        let steps = Arc::new(RwLock::new(HashMap::new()));
+        let new_generator = |flow| {
+            StakingOperations::new(
+                StdRng::from_seed([0; 32]),
+                TransactionFactory::new(ChainId::test()),
+                flow,
+                accounts_pool.clone(),
+                steps.clone(),
+            )
+        };
+        let mut account = LocalAccount::generate(&mut rng);
+        let mut generator = new_generator(StakingFlow::StakePool);
+        let txns = generator.generate_transactions(vec![&mut account], 3);
+        assert_eq!(function_names(&txns), vec![
+            "initialize_stake_owner",
+            "add_stake",
+            "unlock"
+        ]);
+        // the next generator continues where the previous one stopped
+        let txns =
+            new_generator(StakingFlow::StakePool).generate_transactions(vec![&mut account], 2);
+        assert_eq!(function_names(&txns), vec!["withdraw", "add_stake"]);
+        assert_eq!(account.sequence_number(), 5);
+
+        // accounts of the pool are used, when there are any
+        let pool_account = LocalAccount::generate(&mut rng);
+        let pool_address = pool_account.address();
+        accounts_pool.write().push(pool_account);
+        let mut generator = new_generator(StakingFlow::DelegationPool(AccountAddress::ONE));
+        let txns = generator.generate_transactions(vec![&mut account], 3);
+        assert!(txns.iter().all(|txn| txn.sender() == pool_address));
+        assert_eq!(function_names(&txns), vec![
+            "add_stake",
+            "unlock",
+            "withdraw"
+        ]);
+        assert_eq!(accounts_pool.read().len(), 1);
+    }
+}
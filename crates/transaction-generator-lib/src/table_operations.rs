@@ -0,0 +1,450 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    bytecode_builder::BytecodeBuilder, TransactionExecutor, TransactionGenerator,
+    TransactionGeneratorCreator,
+};
+use aptos_framework::natives::code::{
+    ModuleMetadata, MoveOption, PackageDep, PackageMetadata, UpgradePolicy,
+};
+use aptos_logger::info;
+use aptos_sdk::{
+    bcs,
+    move_types::{
+        account_address::AccountAddress, ident_str, identifier::Identifier,
+        language_storage::ModuleId,
+    },
+    transaction_builder::{aptos_stdlib, TransactionFactory},
+    types::{
+        transaction::{EntryFunction, SignedTransaction, TransactionPayload},
+        LocalAccount,
+    },
+};
+use move_binary_format::file_format::{
+    Ability, AbilitySet, Bytecode, CodeUnit, FieldDefinition, FieldHandle, FieldHandleIndex,
+    FunctionDefinition, SignatureToken, StructDefinition, StructDefinitionIndex,
+    StructFieldInformation, StructTypeParameter, TypeSignature, Visibility,
+};
+
+const MODULE_NAME: &str = "table_operations";
+
+// loop over the counter (local 6) from 0 to the count local, with the code of body, which
+// starts at offset
+fn counted_loop(offset: usize, count: u8, body: Vec<Bytecode>) -> Vec<Bytecode> {
+    let head = offset as u16 + 2;
+    let end = head + 4 + body.len() as u16 + 5;
+    let mut code = vec![
+        Bytecode::LdU64(0),
+        Bytecode::StLoc(6),
+        Bytecode::CopyLoc(6),
+        Bytecode::CopyLoc(count),
+        Bytecode::Lt,
+        Bytecode::BrFalse(end),
+    ];
+    code.extend(body);
+    code.extend([
+        Bytecode::CopyLoc(6),
+        Bytecode::LdU64(1),
+        Bytecode::Add,
+        Bytecode::StLoc(6),
+        Bytecode::Branch(head),
+    ]);
+    code
+}
+
+/// Module of the publisher, holding a table of each account in its resource:
+/// ```text
+/// module publisher::table_operations {
+///     struct Items has key { table: Table<u64, u64>, len: u64 }
+///
+///     public entry fun run(account: &signer, inserts: u64, updates: u64, reads: u64)
+/// }
+/// ```
+/// run adds inserts new items to the table of the account (creating it on the first
+/// call), under the keys after the ones added before, and then upserts updates and
+/// borrows reads of its items, cycling through all of them. There have to be items to
+/// update or read, so a first call with no inserts aborts.
+pub fn table_operations_module(publisher: AccountAddress) -> Vec<u8> {
+    use SignatureToken::*;
+
+    let mut builder = BytecodeBuilder::default();
+    let self_module = builder.module(publisher, MODULE_NAME);
+    let table = builder.module(AccountAddress::ONE, "table");
+    let signer = builder.module(AccountAddress::ONE, "signer");
+
+    let copy_drop = AbilitySet::EMPTY | Ability::Copy | Ability::Drop;
+    let items_type = builder.struct_handle(
+        self_module,
+        "Items",
+        AbilitySet::EMPTY | Ability::Key,
+        vec![],
+    );
+    let table_type =
+        builder.struct_handle(table, "Table", AbilitySet::EMPTY | Ability::Store, vec![
+            StructTypeParameter {
+                constraints: copy_drop,
+                is_phantom: true,
+            },
+            StructTypeParameter {
+                constraints: AbilitySet::EMPTY,
+                is_phantom: true,
+            },
+        ]);
+    let generic_table =
+        || StructInstantiation(table_type, vec![TypeParameter(0), TypeParameter(1)]);
+    let u64_table = || StructInstantiation(table_type, vec![U64, U64]);
+
+    let run = builder.function(
+        self_module,
+        "run",
+        vec![Reference(Box::new(Signer)), U64, U64, U64],
+        vec![],
+        vec![],
+    );
+    let address_of = builder.function(
+        signer,
+        "address_of",
+        vec![Reference(Box::new(Signer))],
+        vec![Address],
+        vec![],
+    );
+    let new = builder.function(table, "new", vec![], vec![generic_table()], vec![
+        copy_drop,
+        AbilitySet::EMPTY | Ability::Store,
+    ]);
+    let add = builder.function(
+        table,
+        "add",
+        vec![
+            MutableReference(Box::new(generic_table())),
+            TypeParameter(0),
+            TypeParameter(1),
+        ],
+        vec![],
+        vec![copy_drop, AbilitySet::EMPTY],
+    );
+    let upsert = builder.function(
+        table,
+        "upsert",
+        vec![
+            MutableReference(Box::new(generic_table())),
+            TypeParameter(0),
+            TypeParameter(1),
+        ],
+        vec![],
+        vec![copy_drop, AbilitySet::EMPTY | Ability::Drop],
+    );
+    let borrow = builder.function(
+        table,
+        "borrow",
+        vec![Reference(Box::new(generic_table())), TypeParameter(0)],
+        vec![Reference(Box::new(TypeParameter(1)))],
+        vec![copy_drop, AbilitySet::EMPTY],
+    );
+    let [new, add, upsert, borrow] =
+        [new, add, upsert, borrow].map(|handle| builder.instantiation(handle, vec![U64, U64]));
+
+    let items = StructDefinitionIndex(0);
+    let items_def = StructDefinition {
+        struct_handle: items_type,
+        field_information: StructFieldInformation::Declared(vec![
+            FieldDefinition {
+                name: builder.identifier("table"),
+                signature: TypeSignature(u64_table()),
+            },
+            FieldDefinition {
+                name: builder.identifier("len"),
+                signature: TypeSignature(U64),
+            },
+        ]),
+    };
+    let (table_field, len_field) = (FieldHandleIndex(0), FieldHandleIndex(1));
+    let field_handles = vec![
+        FieldHandle {
+            owner: items,
+            field: 0,
+        },
+        FieldHandle {
+            owner: items,
+            field: 1,
+        },
+    ];
+
+    // locals after the parameters: 4 address of the account, 5 its Items, 6 loop
+    // counter, 7 number of items in the table
+    let locals = builder.signature(vec![
+        Address,
+        MutableReference(Box::new(Struct(items_type))),
+        U64,
+        U64,
+    ]);
+    let mut code = vec![
+        Bytecode::CopyLoc(0),
+        Bytecode::Call(address_of),
+        Bytecode::StLoc(4),
+        Bytecode::CopyLoc(4),
+        Bytecode::Exists(items),
+        Bytecode::BrTrue(11),
+        Bytecode::CopyLoc(0),
+        Bytecode::CallGeneric(new),
+        Bytecode::LdU64(0),
+        Bytecode::Pack(items),
+        Bytecode::MoveTo(items),
+        Bytecode::MoveLoc(4),
+        Bytecode::MutBorrowGlobal(items),
+        Bytecode::StLoc(5),
+        Bytecode::CopyLoc(5),
+        Bytecode::ImmBorrowField(len_field),
+        Bytecode::ReadRef,
+        Bytecode::StLoc(7),
+    ];
+    // inserts under the next key, with the counter as value
+    code.extend(counted_loop(code.len(), 1, vec![
+        Bytecode::CopyLoc(5),
+        Bytecode::MutBorrowField(table_field),
+        Bytecode::CopyLoc(7),
+        Bytecode::CopyLoc(6),
+        Bytecode::CallGeneric(add),
+        Bytecode::CopyLoc(7),
+        Bytecode::LdU64(1),
+        Bytecode::Add,
+        Bytecode::StLoc(7),
+    ]));
+    code.extend([
+        Bytecode::CopyLoc(7),
+        Bytecode::CopyLoc(5),
+        Bytecode::MutBorrowField(len_field),
+        Bytecode::WriteRef,
+    ]);
+    // upserts of the counter-th item
+    code.extend(counted_loop(code.len(), 2, vec![
+        Bytecode::CopyLoc(5),
+        Bytecode::MutBorrowField(table_field),
+        Bytecode::CopyLoc(6),
+        Bytecode::CopyLoc(7),
+        Bytecode::Mod,
+        Bytecode::CopyLoc(6),
+        Bytecode::CallGeneric(upsert),
+    ]));
+    // reads of the counter-th item
+    code.extend(counted_loop(code.len(), 3, vec![
+        Bytecode::CopyLoc(5),
+        Bytecode::ImmBorrowField(table_field),
+        Bytecode::CopyLoc(6),
+        Bytecode::CopyLoc(7),
+        Bytecode::Mod,
+        Bytecode::CallGeneric(borrow),
+        Bytecode::ReadRef,
+        Bytecode::Pop,
+    ]));
+    code.push(Bytecode::Ret);
+
+    let run_def = FunctionDefinition {
+        function: run,
+        visibility: Visibility::Public,
+        is_entry: true,
+        acquires_global_resources: vec![items],
+        code: Some(CodeUnit { locals, code }),
+    };
+    builder.into_module(self_module, field_handles, vec![items_def], vec![run_def])
+}
+
+fn package_metadata() -> PackageMetadata {
+    let dep = |package_name: &str| PackageDep {
+        account: AccountAddress::ONE,
+        package_name: package_name.to_string(),
+    };
+    PackageMetadata {
+        name: "TableOperations".to_string(),
+        upgrade_policy: UpgradePolicy::compat(),
+        upgrade_number: 0,
+        source_digest: String::new(),
+        manifest: vec![],
+        modules: vec![ModuleMetadata {
+            name: MODULE_NAME.to_string(),
+            source: vec![],
+            source_map: vec![],
+            extension: MoveOption::none(),
+        }],
+        deps: vec![dep("MoveStdlib"), dep("AptosStdlib")],
+        extension: MoveOption::none(),
+    }
+}
+
+/// Calls of run of the table_operations module with the same counts, each adding to,
+/// updating and reading from a table of the sender.
+pub struct TableOperationsGenerator {
+    txn_factory: TransactionFactory,
+    module_id: ModuleId,
+    inserts: usize,
+    updates: usize,
+    reads: usize,
+}
+
+impl TableOperationsGenerator {
+    pub fn new(
+        txn_factory: TransactionFactory,
+        module_id: ModuleId,
+        inserts: usize,
+        updates: usize,
+        reads: usize,
+    ) -> Self {
+        Self {
+            txn_factory,
+            module_id,
+            inserts,
+            updates,
+            reads,
+        }
+    }
+}
+
+impl TransactionGenerator for TableOperationsGenerator {
+    fn generate_transactions(
+        &mut self,
+        accounts: Vec<&mut LocalAccount>,
+        transactions_per_account: usize,
+    ) -> Vec<SignedTransaction> {
+        let payload = TransactionPayload::EntryFunction(EntryFunction::new(
+            self.module_id.clone(),
+            ident_str!("run").to_owned(),
+            vec![],
+            vec![
+                bcs::to_bytes(&(self.inserts as u64)).unwrap(),
+                bcs::to_bytes(&(self.updates as u64)).unwrap(),
+                bcs::to_bytes(&(self.reads as u64)).unwrap(),
+            ],
+        ));
+        let mut requests = Vec::with_capacity(accounts.len() * transactions_per_account);
+        for account in accounts {
+            for _ in 0..transactions_per_account {
+                requests.push(
+                    account
+                        .sign_with_transaction_builder(self.txn_factory.payload(payload.clone())),
+                );
+            }
+        }
+        requests
+    }
+}
+
+pub struct TableOperationsCreator {
+    txn_factory: TransactionFactory,
+    module_id: ModuleId,
+    inserts: usize,
+    updates: usize,
+    reads: usize,
+}
+
+impl TableOperationsCreator {
+    /// Publishes the table_operations module from the first source account. Runs
+    /// reusing the accounts publish it again as an upgrade, and keep adding to the tables
+    /// of the ones before.
+    pub async fn new(
+        txn_factory: TransactionFactory,
+        init_txn_factory: TransactionFactory,
+        source_accounts: &mut [LocalAccount],
+        txn_executor: &dyn TransactionExecutor,
+        inserts: usize,
+        updates: usize,
+        reads: usize,
+    ) -> Self {
+        assert!(
+            inserts > 0,
+            "Table operations need inserts, for items to update and read"
+        );
+        let publisher = source_accounts
+            .first_mut()
+            .expect("Table operations need source accounts");
+        let metadata = bcs::to_bytes(&package_metadata()).expect("PackageMetadata must serialize");
+        let publish_txn = publisher.sign_with_transaction_builder(init_txn_factory.payload(
+            aptos_stdlib::code_publish_package_txn(metadata, vec![table_operations_module(
+                publisher.address(),
+            )]),
+        ));
+        let module_id = ModuleId::new(publisher.address(), Identifier::new(MODULE_NAME).unwrap());
+        info!("Publishing {} for table operations", module_id);
+        txn_executor
+            .execute_transactions(&[publish_txn])
+            .await
+            .unwrap();
+
+        Self {
+            txn_factory,
+            module_id,
+            inserts,
+            updates,
+            reads,
+        }
+    }
+}
+
+impl TransactionGeneratorCreator for TableOperationsCreator {
+    fn create_transaction_generator(&mut self) -> Box<dyn TransactionGenerator> {
+        Box::new(TableOperationsGenerator::new(
+            self.txn_factory.clone(),
+            self.module_id.clone(),
+            self.inserts,
+            self.updates,
+            self.reads,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{table_operations_module, TableOperationsGenerator, MODULE_NAME};
+    use crate::TransactionGenerator;
+    use aptos_sdk::{
+        bcs,
+        move_types::{
+            account_address::AccountAddress, identifier::Identifier, language_storage::ModuleId,
+        },
+        transaction_builder::TransactionFactory,
+        types::{chain_id::ChainId, transaction::TransactionPayload, LocalAccount},
+    };
+    use move_binary_format::{access::ModuleAccess, CompiledModule};
+    use move_bytecode_verifier::verify_module;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    pub fn test_table_operations() {
+        let publisher = AccountAddress::random();
+        let module_id = ModuleId::new(publisher, Identifier::new(MODULE_NAME).unwrap());
+        let module = CompiledModule::deserialize(&table_operations_module(publisher)).unwrap();
+        assert_eq!(module.version, 5);
+        assert_eq!(module.self_id(), module_id);
+        assert_eq!(module.function_defs.len(), 1);
+        assert!(module.function_defs[0].is_entry);
+        assert_eq!(module.function_instantiations.len(), 4);
+        verify_module(&module).unwrap();
+
+        let mut generator = TableOperationsGenerator::new(
+            TransactionFactory::new(ChainId::test()),
+            module_id.clone(),
+            10,
+            5,
+            20,
+        );
+        let mut account = LocalAccount::generate(&mut StdRng::from_seed([0; 32]));
+        let txns = generator.generate_transactions(vec![&mut account], 2);
+        assert_eq!(txns.len(), 2);
+        for txn in txns {
+            match txn.payload() {
+                TransactionPayload::EntryFunction(entry_function) => {
+                    assert_eq!(entry_function.module(), &module_id);
+                    assert_eq!(entry_function.function().as_str(), "run");
+                    let counts: Vec<u64> = entry_function
+                        .args()
+                        .iter()
+                        .map(|arg| bcs::from_bytes(arg).unwrap())
+                        .collect();
+                    assert_eq!(counts, vec![10, 5, 20]);
+                },
+                _ => panic!("Expected an entry function"),
+            }
+        }
+        assert_eq!(account.sequence_number(), 2);
+    }
+}
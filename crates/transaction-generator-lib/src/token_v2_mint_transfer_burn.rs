@@ -0,0 +1,359 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::TransactionExecutor;
+use crate::{
+    nft_mint_and_transfer::create_and_fund_accounts, TransactionGenerator,
+    TransactionGeneratorCreator,
+};
+use aptos_crypto::HashValue;
+use aptos_logger::info;
+use aptos_sdk::{
+    bcs,
+    move_types::{
+        account_address::AccountAddress,
+        ident_str,
+        language_storage::{ModuleId, StructTag, TypeTag},
+    },
+    transaction_builder::{aptos_stdlib::aptos_token_objects_stdlib, TransactionFactory},
+    types::{
+        transaction::{EntryFunction, SignedTransaction, TransactionPayload},
+        LocalAccount,
+    },
+};
+use rand::rngs::StdRng;
+use std::collections::HashMap;
+
+// guid creation number of a fresh account after its creation and registration for APT,
+// so the one the first token minted by a creator is created from
+pub(crate) const FIRST_TOKEN_CREATION_NUM: u64 = 4;
+// 0x1::object::OBJECT_FROM_GUID_ADDRESS_SCHEME
+const OBJECT_FROM_GUID_ADDRESS_SCHEME: u8 = 0xFD;
+
+pub(crate) fn token_objects_address() -> AccountAddress {
+    AccountAddress::from_hex_literal("0x4").unwrap()
+}
+
+/// Address of the token created from the creation_num-th guid of the creator, as by
+/// 0x1::object::create_guid_object_address.
+pub fn token_address(creator: AccountAddress, creation_num: u64) -> AccountAddress {
+    let mut bytes = bcs::to_bytes(&creation_num).unwrap();
+    bytes.extend(creator.to_vec());
+    bytes.push(OBJECT_FROM_GUID_ADDRESS_SCHEME);
+    AccountAddress::new(*HashValue::sha3_256_of(&bytes))
+}
+
+pub(crate) fn token_type() -> TypeTag {
+    TypeTag::Struct(Box::new(StructTag {
+        address: token_objects_address(),
+        module: ident_str!("token").to_owned(),
+        name: ident_str!("Token").to_owned(),
+        type_params: vec![],
+    }))
+}
+
+fn transfer_payload(token: AccountAddress, to: AccountAddress) -> TransactionPayload {
+    TransactionPayload::EntryFunction(EntryFunction::new(
+        ModuleId::new(AccountAddress::ONE, ident_str!("object").to_owned()),
+        ident_str!("transfer").to_owned(),
+        vec![token_type()],
+        vec![bcs::to_bytes(&token).unwrap(), bcs::to_bytes(&to).unwrap()],
+    ))
+}
+
+fn burn_payload(token: AccountAddress) -> TransactionPayload {
+    TransactionPayload::EntryFunction(EntryFunction::new(
+        ModuleId::new(
+            token_objects_address(),
+            ident_str!("aptos_token").to_owned(),
+        ),
+        ident_str!("burn").to_owned(),
+        vec![token_type()],
+        vec![bcs::to_bytes(&token).unwrap()],
+    ))
+}
+
+/// Collection of a creator, tracking the tokens it minted by their addresses.
+pub struct TokenV2Collection {
+    creator: LocalAccount,
+    name: Vec<u8>,
+    next_token_creation_num: u64,
+    num_live_tokens: usize,
+    // transferred back to the creator in the last batch
+    returned: Vec<AccountAddress>,
+    // transferred back to the creator in earlier batches, so ready to be burned
+    burnable: Vec<AccountAddress>,
+}
+
+impl TokenV2Collection {
+    pub fn new(creator: LocalAccount, name: Vec<u8>) -> Self {
+        Self {
+            creator,
+            name,
+            next_token_creation_num: FIRST_TOKEN_CREATION_NUM,
+            num_live_tokens: 0,
+            returned: vec![],
+            burnable: vec![],
+        }
+    }
+}
+
+/// Lifecycle of Token V2 (object based) digital assets: each token is minted by the
+/// creator of its collection and transferred to one of the accounts, which in a later
+/// batch transfers it back to the creator, who then burns it. Mints and burns are sent by the
+/// creators (one per collection), transfers back by the accounts.
+/// Token addresses are derived from the guids of the creators, so a creator transaction
+/// failing to commit throws off tracking of the tokens of its collection.
+pub struct TokenV2MintTransferBurn {
+    txn_factory: TransactionFactory,
+    collections: Vec<TokenV2Collection>,
+    tokens_per_collection: usize,
+    // tokens each account holds, with the index of their collection
+    held_tokens: HashMap<AccountAddress, Vec<(usize, AccountAddress)>>,
+    next_collection: usize,
+}
+
+impl TokenV2MintTransferBurn {
+    pub fn new(
+        txn_factory: TransactionFactory,
+        collections: Vec<TokenV2Collection>,
+        tokens_per_collection: usize,
+    ) -> Self {
+        Self {
+            txn_factory,
+            collections,
+            tokens_per_collection,
+            held_tokens: HashMap::new(),
+            next_collection: 0,
+        }
+    }
+}
+
+impl TransactionGenerator for TokenV2MintTransferBurn {
+    fn generate_transactions(
+        &mut self,
+        accounts: Vec<&mut LocalAccount>,
+        transactions_per_account: usize,
+    ) -> Vec<SignedTransaction> {
+        for collection in &mut self.collections {
+            collection.burnable.append(&mut collection.returned);
+        }
+        let mut requests = Vec::with_capacity(accounts.len() * transactions_per_account);
+        for account in accounts {
+            let held_tokens = self.held_tokens.entry(account.address()).or_default();
+            // only held once the transfers from the creators are done
+            let mut received_tokens = vec![];
+            for _ in 0..transactions_per_account {
+                if let Some((index, token)) = held_tokens.pop() {
+                    let collection = &mut self.collections[index];
+                    requests.push(
+                        account.sign_with_transaction_builder(
+                            self.txn_factory
+                                .payload(transfer_payload(token, collection.creator.address())),
+                        ),
+                    );
+                    collection.returned.push(token);
+                    continue;
+                }
+
+                let index = self.next_collection;
+                self.next_collection = (index + 1) % self.collections.len();
+                let collection = &mut self.collections[index];
+                if let Some(token) = collection.burnable.pop() {
+                    requests.push(collection.creator.sign_with_transaction_builder(
+                        self.txn_factory.payload(burn_payload(token)),
+                    ));
+                    collection.num_live_tokens -= 1;
+                } else if collection.num_live_tokens < self.tokens_per_collection {
+                    let token = token_address(
+                        collection.creator.address(),
+                        collection.next_token_creation_num,
+                    );
+                    requests.push(
+                        collection
+                            .creator
+                            .sign_with_transaction_builder(self.txn_factory.payload(
+                                aptos_token_objects_stdlib::aptos_token_mint(
+                                    collection.name.clone(),
+                                    b"description".to_vec(),
+                                    b"token".to_vec(),
+                                    b"uri".to_vec(),
+                                    vec![],
+                                    vec![],
+                                    vec![],
+                                ),
+                            )),
+                    );
+                    requests.push(
+                        collection.creator.sign_with_transaction_builder(
+                            self.txn_factory
+                                .payload(transfer_payload(token, account.address())),
+                        ),
+                    );
+                    collection.next_token_creation_num += 1;
+                    collection.num_live_tokens += 1;
+                    received_tokens.push((index, token));
+                }
+            }
+            held_tokens.append(&mut received_tokens);
+        }
+        requests
+    }
+}
+
+pub struct TokenV2MintTransferBurnCreator {
+    txn_factory: TransactionFactory,
+    // collections of each worker
+    collections_per_worker: Vec<Vec<TokenV2Collection>>,
+    tokens_per_collection: usize,
+}
+
+impl TokenV2MintTransferBurnCreator {
+    /// Creates num_collections collections for each of the workers, each with its own
+    /// creator, funded by the source accounts with half of their balance.
+    pub async fn new(
+        mut rng: StdRng,
+        txn_factory: TransactionFactory,
+        init_txn_factory: TransactionFactory,
+        source_accounts: &mut [LocalAccount],
+        txn_executor: &dyn TransactionExecutor,
+        num_workers: usize,
+        num_collections: usize,
+        tokens_per_collection: usize,
+    ) -> Self {
+        assert!(num_collections > 0);
+        let creators = create_and_fund_accounts(
+            &mut rng,
+            &init_txn_factory,
+            source_accounts,
+            txn_executor,
+            num_workers * num_collections,
+        )
+        .await;
+
+        let mut collections: Vec<_> = creators
+            .into_iter()
+            .enumerate()
+            .map(|(index, creator)| {
+                TokenV2Collection::new(creator, format!("collection {}", index).into_bytes())
+            })
+            .collect();
+        let collection_txns: Vec<_> = collections
+            .iter_mut()
+            .map(|collection| {
+                collection
+                    .creator
+                    .sign_with_transaction_builder(init_txn_factory.payload(
+                        aptos_token_objects_stdlib::aptos_token_create_collection(
+                            b"description".to_vec(),
+                            // supply is bounded by burning, and minting is never to fail, as
+                            // that would throw off the addresses of the tokens
+                            u64::MAX,
+                            collection.name.clone(),
+                            b"uri".to_vec(),
+                            false,
+                            false,
+                            false,
+                            false,
+                            false,
+                            false,
+                            false,
+                            true,
+                            false,
+                            0,
+                            1,
+                        ),
+                    ))
+            })
+            .collect();
+        info!("Creating {} collections", collection_txns.len());
+        for chunk in collection_txns.chunks(100) {
+            txn_executor.execute_transactions(chunk).await.unwrap();
+        }
+        info!("Done creating {} collections", collection_txns.len());
+
+        let mut collections_per_worker = Vec::with_capacity(num_workers);
+        while !collections.is_empty() {
+            collections_per_worker.push(collections.split_off(collections.len() - num_collections));
+        }
+        Self {
+            txn_factory,
+            collections_per_worker,
+            tokens_per_collection,
+        }
+    }
+}
+
+impl TransactionGeneratorCreator for TokenV2MintTransferBurnCreator {
+    fn create_transaction_generator(&mut self) -> Box<dyn TransactionGenerator> {
+        Box::new(TokenV2MintTransferBurn::new(
+            self.txn_factory.clone(),
+            self.collections_per_worker.pop().unwrap(),
+            self.tokens_per_collection,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{token_address, TokenV2Collection, TokenV2MintTransferBurn};
+    use crate::TransactionGenerator;
+    use aptos_sdk::{
+        transaction_builder::TransactionFactory,
+        types::{chain_id::ChainId, transaction::TransactionPayload, LocalAccount},
+    };
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    pub fn test_token_v2_mint_transfer_burn() {
+        let mut rng = StdRng::from_seed([0; 32]);
+        let creator = LocalAccount::generate(&mut rng);
+        let creator_address = creator.address();
+        let mut generator = TokenV2MintTransferBurn::new(
+            TransactionFactory::new(ChainId::test()),
+            vec![TokenV2Collection::new(creator, b"collection".to_vec())],
+            1,
+        );
+        let mut account = LocalAccount::generate(&mut rng);
+        let functions = |generator: &mut TokenV2MintTransferBurn, account: &mut LocalAccount| {
+            generator
+                .generate_transactions(vec![account], 1)
+                .into_iter()
+                .map(|txn| match txn.payload() {
+                    TransactionPayload::EntryFunction(entry_function) => (
+                        txn.sender(),
+                        entry_function.function().to_string(),
+                        entry_function.args().to_vec(),
+                    ),
+                    _ => panic!("Expected an entry function"),
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let token = token_address(creator_address, 4);
+        let minted = functions(&mut generator, &mut account);
+        assert_eq!(minted.len(), 2);
+        assert_eq!(
+            (minted[0].0, minted[0].1.as_str()),
+            (creator_address, "mint")
+        );
+        assert_eq!(minted[1].1, "transfer");
+        assert_eq!(minted[1].2[0], token.to_vec());
+        // the account transfers the token back, and it is burned in the next batch
+        let returned = functions(&mut generator, &mut account);
+        assert_eq!(returned.len(), 1);
+        assert_eq!(
+            (returned[0].0, returned[0].1.as_str()),
+            (account.address(), "transfer")
+        );
+        let burned = functions(&mut generator, &mut account);
+        assert_eq!(
+            (burned[0].0, burned[0].1.as_str()),
+            (creator_address, "burn")
+        );
+        assert_eq!(burned[0].2[0], token.to_vec());
+        // the next token comes from the next guid of the creator
+        let minted = functions(&mut generator, &mut account);
+        assert_eq!(minted[1].2[0], token_address(creator_address, 5).to_vec());
+    }
+}
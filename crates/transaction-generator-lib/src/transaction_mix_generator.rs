@@ -14,6 +14,7 @@ pub struct PhasedTxnMixGenerator {
     txn_mix_per_phase: Vec<Vec<(Box<dyn TransactionGenerator>, usize)>>,
     total_weight_per_phase: Vec<usize>,
     phase: Arc<AtomicUsize>,
+    last_generated_workload: Option<(usize, usize)>,
 }
 
 impl PhasedTxnMixGenerator {
@@ -31,6 +32,7 @@ impl PhasedTxnMixGenerator {
             txn_mix_per_phase,
             total_weight_per_phase,
             phase,
+            last_generated_workload: None,
         }
     }
 }
@@ -49,8 +51,9 @@ impl TransactionGenerator for PhasedTxnMixGenerator {
         };
 
         let mut picked = self.rng.gen_range(0, self.total_weight_per_phase[phase]);
-        for (gen, weight) in &mut self.txn_mix_per_phase[phase] {
+        for (index, (gen, weight)) in self.txn_mix_per_phase[phase].iter_mut().enumerate() {
             if picked < *weight {
+                self.last_generated_workload = Some((phase, index));
                 return gen.generate_transactions(accounts, transactions_per_account);
             }
             picked -= *weight;
@@ -60,19 +63,33 @@ impl TransactionGenerator for PhasedTxnMixGenerator {
             picked, self.total_weight_per_phase[phase], phase,
         );
     }
+
+    fn last_generated_workload(&self) -> Option<(usize, usize)> {
+        self.last_generated_workload
+    }
+
+    fn last_generated_label(&self) -> Option<&str> {
+        let (phase, index) = self.last_generated_workload?;
+        self.txn_mix_per_phase[phase][index]
+            .0
+            .last_generated_label()
+    }
 }
 
 pub struct PhasedTxnMixGeneratorCreator {
+    rng: StdRng,
     txn_mix_per_phase_creators: Vec<Vec<(Box<dyn TransactionGeneratorCreator>, usize)>>,
     phase: Arc<AtomicUsize>,
 }
 
 impl PhasedTxnMixGeneratorCreator {
     pub fn new(
+        rng: StdRng,
         txn_mix_per_phase_creators: Vec<Vec<(Box<dyn TransactionGeneratorCreator>, usize)>>,
         phase: Arc<AtomicUsize>,
     ) -> Self {
         Self {
+            rng,
             txn_mix_per_phase_creators,
             phase,
         }
@@ -91,7 +108,7 @@ impl TransactionGeneratorCreator for PhasedTxnMixGeneratorCreator {
         }
 
         Box::new(PhasedTxnMixGenerator::new(
-            StdRng::from_entropy(),
+            StdRng::from_rng(&mut self.rng).unwrap(),
             txn_mix_per_phase,
             self.phase.clone(),
         ))
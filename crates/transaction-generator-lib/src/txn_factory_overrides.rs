@@ -0,0 +1,105 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::{format_err, Result};
+use aptos_sdk::transaction_builder::TransactionFactory;
+
+/// Transaction settings of a workload, instead of the ones of the run, e.g. for module
+/// publishing, which needs a far larger max gas amount than transfers. Unset ones are
+/// kept from the run's TransactionFactory.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TxnFactoryOverrides {
+    pub expiration_secs: Option<u64>,
+    pub max_gas_amount: Option<u64>,
+    pub gas_unit_price: Option<u64>,
+    /// Of the run's gas unit price, if gas_unit_price isn't set
+    pub gas_unit_price_multiplier: Option<u64>,
+}
+
+impl TxnFactoryOverrides {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Gas unit price of the workload's transactions, for the run's gas_unit_price, and
+    /// an error if multiplying it overflows.
+    pub fn checked_gas_unit_price(&self, gas_unit_price: u64) -> Result<u64> {
+        match (self.gas_unit_price, self.gas_unit_price_multiplier) {
+            (Some(gas_unit_price), _) => Ok(gas_unit_price),
+            (None, Some(multiplier)) => gas_unit_price.checked_mul(multiplier).ok_or_else(|| {
+                format_err!(
+                    "Gas unit price {} times multiplier {} overflows",
+                    gas_unit_price,
+                    multiplier
+                )
+            }),
+            (None, None) => Ok(gas_unit_price),
+        }
+    }
+
+    /// txn_factory with the overridden settings. The overrides need to be checked
+    /// against its gas unit price first, see checked_gas_unit_price.
+    pub fn apply(&self, txn_factory: &TransactionFactory) -> TransactionFactory {
+        let mut txn_factory = txn_factory.clone();
+        if let Some(expiration_secs) = self.expiration_secs {
+            txn_factory = txn_factory.with_transaction_expiration_time(expiration_secs);
+        }
+        if let Some(max_gas_amount) = self.max_gas_amount {
+            txn_factory = txn_factory.with_max_gas_amount(max_gas_amount);
+        }
+        let gas_unit_price = self
+            .checked_gas_unit_price(txn_factory.get_gas_unit_price())
+            .expect("Workload gas unit price should have been checked");
+        txn_factory.with_gas_unit_price(gas_unit_price)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::TxnFactoryOverrides;
+    use aptos_sdk::{transaction_builder::TransactionFactory, types::chain_id::ChainId};
+
+    #[test]
+    pub fn test_txn_factory_overrides() {
+        let txn_factory = TransactionFactory::new(ChainId::test())
+            .with_gas_unit_price(100)
+            .with_max_gas_amount(1000)
+            .with_transaction_expiration_time(60);
+        assert!(TxnFactoryOverrides::default().is_empty());
+
+        let overridden = TxnFactoryOverrides {
+            max_gas_amount: Some(2_000_000),
+            gas_unit_price_multiplier: Some(3),
+            ..TxnFactoryOverrides::default()
+        }
+        .apply(&txn_factory);
+        assert_eq!(overridden.get_max_gas_amount(), 2_000_000);
+        assert_eq!(overridden.get_gas_unit_price(), 300);
+        assert_eq!(overridden.get_transaction_expiration_time(), 60);
+
+        // an explicit price wins over the multiplier
+        let overridden = TxnFactoryOverrides {
+            expiration_secs: Some(120),
+            gas_unit_price: Some(150),
+            gas_unit_price_multiplier: Some(3),
+            ..TxnFactoryOverrides::default()
+        }
+        .apply(&txn_factory);
+        assert_eq!(overridden.get_gas_unit_price(), 150);
+        assert_eq!(overridden.get_max_gas_amount(), 1000);
+        assert_eq!(overridden.get_transaction_expiration_time(), 120);
+
+        let overflowing = TxnFactoryOverrides {
+            gas_unit_price_multiplier: Some(u64::MAX),
+            ..TxnFactoryOverrides::default()
+        };
+        assert!(overflowing.checked_gas_unit_price(100).is_err());
+        assert_eq!(overflowing.checked_gas_unit_price(1).unwrap(), u64::MAX);
+        assert_eq!(
+            TxnFactoryOverrides::default()
+                .checked_gas_unit_price(100)
+                .unwrap(),
+            100
+        );
+    }
+}
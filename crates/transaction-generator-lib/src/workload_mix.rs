@@ -0,0 +1,412 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    args::TransactionTypeArg,
+    create_txn_generator_creator,
+    entry_function_generator::EntryFunctionTemplate,
+    phased_generator::{GeneratorPhase, PhaseLimit},
+    txn_factory_overrides::TxnFactoryOverrides,
+    TransactionExecutor, TransactionGeneratorCreator, TransactionType,
+};
+use anyhow::{ensure, Context, Result};
+use aptos_infallible::RwLock;
+use aptos_sdk::{
+    move_types::account_address::AccountAddress, transaction_builder::TransactionFactory,
+    types::LocalAccount,
+};
+use rand::rngs::StdRng;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::Path,
+    sync::{atomic::AtomicUsize, Arc},
+    time::Duration,
+};
+
+/// Mix of workloads, run phase after phase, described in a config file (YAML), so that
+/// the emitter and the executor benchmark run the same mixes, e.g.
+/// ```yaml
+/// workloads:
+///   - transaction_type: CoinTransferHotSpot
+///     weight: 3
+///     hot_spot_ratio: 90
+///   - transaction_type: ComputeLoop
+///     loop_count: 100000
+///     max_gas_amount: 2000000
+///     gas_unit_price_multiplier: 2
+///     phase: 1
+/// ```
+/// Workloads of a phase with run_for_secs or run_for_transactions run one after
+/// another instead of by weight, each for as long as it's given, and the last one until
+/// the end of the phase, e.g.
+/// ```yaml
+/// workloads:
+///   - transaction_type: TokenV2MintTransferBurn
+///     label: mint
+///     run_for_secs: 60
+///   - transaction_type: CoinTransfer
+///     label: trade
+/// ```
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct WorkloadMix {
+    pub workloads: Vec<WorkloadConfig>,
+}
+
+/// Workload of a mix, a transaction type with the knobs it takes.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct WorkloadConfig {
+    pub transaction_type: TransactionTypeArg,
+    /// Share of transactions of its phase, relative to the weights of the others
+    #[serde(default = "WorkloadConfig::default_weight")]
+    pub weight: usize,
+    /// Phases start from 0, and can't be skipped
+    #[serde(default)]
+    pub phase: usize,
+    /// Percent of transfers going to the same few receivers, for transfer types
+    pub hot_spot_ratio: Option<usize>,
+    /// Iterations of each call, and so gas of each transaction, for compute loop types
+    pub loop_count: Option<u64>,
+    /// Resources written by each transaction, for write-set types
+    pub num_resources: Option<usize>,
+    /// Bytes written by each transaction, across its resources, for write-set types
+    pub write_set_bytes: Option<usize>,
+    /// Expiration of its transactions, instead of the one of the run
+    pub expiration_secs: Option<u64>,
+    /// Max gas amount of its transactions, instead of the one of the run
+    pub max_gas_amount: Option<u64>,
+    /// Gas unit price of its transactions, instead of the one of the run
+    pub gas_unit_price: Option<u64>,
+    /// Of the run's gas unit price, if gas_unit_price isn't set
+    pub gas_unit_price_multiplier: Option<u64>,
+    /// Name of its stats, when run one after another, instead of its transaction type
+    pub label: Option<String>,
+    /// Time to run it for before the next workload of its phase
+    pub run_for_secs: Option<u64>,
+    /// Transactions to generate before the next workload of its phase
+    pub run_for_transactions: Option<usize>,
+}
+
+impl WorkloadConfig {
+    fn default_weight() -> usize {
+        1
+    }
+
+    pub fn new(transaction_type: TransactionTypeArg) -> Self {
+        Self {
+            transaction_type,
+            weight: Self::default_weight(),
+            phase: 0,
+            hot_spot_ratio: None,
+            loop_count: None,
+            num_resources: None,
+            write_set_bytes: None,
+            expiration_secs: None,
+            max_gas_amount: None,
+            gas_unit_price: None,
+            gas_unit_price_multiplier: None,
+            label: None,
+            run_for_secs: None,
+            run_for_transactions: None,
+        }
+    }
+
+    pub fn transaction_type(&self) -> TransactionType {
+        let mut transaction_type = self.transaction_type.materialize();
+        if let Some(ratio) = self.hot_spot_ratio {
+            transaction_type = transaction_type.with_hot_spot_ratio(ratio);
+        }
+        if let Some(loop_count) = self.loop_count {
+            transaction_type = transaction_type.with_loop_count(loop_count);
+        }
+        transaction_type.with_write_set(self.num_resources, self.write_set_bytes)
+    }
+
+    /// Transaction settings of its own, 0 expiration keeping the one of the run.
+    pub fn txn_factory_overrides(&self) -> TxnFactoryOverrides {
+        TxnFactoryOverrides {
+            expiration_secs: self.expiration_secs.filter(|secs| *secs > 0),
+            max_gas_amount: self.max_gas_amount,
+            gas_unit_price: self.gas_unit_price,
+            gas_unit_price_multiplier: self.gas_unit_price_multiplier,
+        }
+    }
+
+    fn run_for(&self) -> Option<PhaseLimit> {
+        self.run_for_secs
+            .map(|secs| PhaseLimit::Duration(Duration::from_secs(secs)))
+            .or_else(|| self.run_for_transactions.map(PhaseLimit::Transactions))
+    }
+
+    pub fn generator_phase(&self) -> GeneratorPhase {
+        GeneratorPhase::new(
+            self.label
+                .clone()
+                .unwrap_or_else(|| format!("{:?}", self.transaction_type)),
+            self.run_for(),
+        )
+    }
+}
+
+impl WorkloadMix {
+    /// Mix of the single given workload.
+    pub fn single(transaction_type: TransactionTypeArg) -> Self {
+        Self {
+            workloads: vec![WorkloadConfig::new(transaction_type)],
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read workload mix {}", path.display()))?;
+        Self::parse(&contents).with_context(|| format!("Invalid workload mix {}", path.display()))
+    }
+
+    pub fn parse(contents: &str) -> Result<Self> {
+        let mix: Self = serde_yaml::from_str(contents)?;
+        ensure!(!mix.workloads.is_empty(), "No workloads in the mix");
+        for workload in &mix.workloads {
+            ensure!(
+                workload.run_for_secs.is_none() || workload.run_for_transactions.is_none(),
+                "{:?} can only run for either a time or a number of transactions",
+                workload.transaction_type
+            );
+        }
+        for workloads in mix.per_phase(|workload| workload)? {
+            ensure!(
+                workloads.iter().all(|workload| workload.run_for().is_none())
+                    || workloads[..workloads.len() - 1]
+                        .iter()
+                        .all(|workload| workload.run_for().is_some()),
+                "Workloads run one after another, but the last, need a time or number of transactions to run for"
+            );
+        }
+        Ok(mix)
+    }
+
+    /// Entry-function workloads, each calling the entry function of the same index.
+    pub fn num_entry_functions(&self) -> usize {
+        self.workloads
+            .iter()
+            .filter(|workload| {
+                matches!(workload.transaction_type, TransactionTypeArg::EntryFunction)
+            })
+            .count()
+    }
+
+    /// Transaction types of each phase, with their weights.
+    pub fn transaction_mix_per_phase(&self) -> Result<Vec<Vec<(TransactionType, usize)>>> {
+        // the n-th entry-function workload calls the n-th entry function
+        let mut num_entry_functions = 0;
+        self.per_phase(|workload| {
+            let transaction_type = match workload.transaction_type() {
+                TransactionType::CustomEntryFunction { .. } => {
+                    num_entry_functions += 1;
+                    TransactionType::CustomEntryFunction {
+                        index: num_entry_functions - 1,
+                    }
+                },
+                transaction_type => transaction_type,
+            };
+            (transaction_type, workload.weight)
+        })
+    }
+
+    /// Transaction settings of each workload of each phase, applied over the ones of the
+    /// run.
+    pub fn txn_factory_overrides_per_phase(&self) -> Result<Vec<Vec<TxnFactoryOverrides>>> {
+        self.per_phase(WorkloadConfig::txn_factory_overrides)
+    }
+
+    /// Whether any workload has transaction settings of its own.
+    pub fn has_txn_factory_overrides(&self) -> bool {
+        self.workloads
+            .iter()
+            .any(|workload| !workload.txn_factory_overrides().is_empty())
+    }
+
+    /// Generator phases of each phase running its workloads one after another, None for
+    /// ones running them as a weighted mix.
+    pub fn generator_phases_per_phase(&self) -> Result<Vec<Option<Vec<GeneratorPhase>>>> {
+        Ok(self
+            .per_phase(|workload| workload)?
+            .into_iter()
+            .map(|workloads| {
+                workloads
+                    .iter()
+                    .any(|workload| workload.run_for().is_some())
+                    .then(|| {
+                        workloads
+                            .iter()
+                            .map(|workload| workload.generator_phase())
+                            .collect()
+                    })
+            })
+            .collect())
+    }
+
+    fn per_phase<'a, T>(
+        &'a self,
+        mut f: impl FnMut(&'a WorkloadConfig) -> T,
+    ) -> Result<Vec<Vec<T>>> {
+        let mut per_phase: Vec<Vec<T>> = Vec::new();
+        for workload in &self.workloads {
+            ensure!(
+                workload.phase <= per_phase.len(),
+                "cannot skip phases ({})",
+                per_phase.len()
+            );
+            if workload.phase == per_phase.len() {
+                per_phase.push(Vec::new());
+            }
+            per_phase[workload.phase].push(f(workload));
+        }
+        Ok(per_phase)
+    }
+
+    /// Creates the composite generator of the mix, see create_txn_generator_creator.
+    pub async fn create_txn_generator_creator(
+        &self,
+        num_workers: usize,
+        source_accounts: &mut [LocalAccount],
+        initial_burner_accounts: Vec<LocalAccount>,
+        txn_executor: &dyn TransactionExecutor,
+        txn_factory: &TransactionFactory,
+        init_txn_factory: &TransactionFactory,
+        entry_functions: &[EntryFunctionTemplate],
+        rng: StdRng,
+        cur_phase: Arc<AtomicUsize>,
+    ) -> Result<(
+        Box<dyn TransactionGeneratorCreator>,
+        Arc<RwLock<Vec<AccountAddress>>>,
+        Arc<RwLock<Vec<LocalAccount>>>,
+    )> {
+        ensure!(
+            self.num_entry_functions() == entry_functions.len(),
+            "Each entry-function workload needs an entry function, got {} workloads and {} functions",
+            self.num_entry_functions(),
+            entry_functions.len()
+        );
+        for workload in &self.workloads {
+            workload
+                .txn_factory_overrides()
+                .checked_gas_unit_price(txn_factory.get_gas_unit_price())
+                .with_context(|| format!("Invalid gas of {:?}", workload.transaction_type))?;
+        }
+        Ok(create_txn_generator_creator(
+            &self.transaction_mix_per_phase()?,
+            num_workers,
+            source_accounts,
+            initial_burner_accounts,
+            txn_executor,
+            txn_factory,
+            init_txn_factory,
+            &self.txn_factory_overrides_per_phase()?,
+            &self.generator_phases_per_phase()?,
+            entry_functions,
+            rng,
+            cur_phase,
+        )
+        .await)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::WorkloadMix;
+    use crate::{
+        hot_spot::HotSpot,
+        phased_generator::{GeneratorPhase, PhaseLimit},
+        txn_factory_overrides::TxnFactoryOverrides,
+        EntryPoints, TransactionType,
+    };
+    use std::time::Duration;
+
+    #[test]
+    pub fn test_workload_mix() {
+        let yaml = r#"
+workloads:
+  - transaction_type: CoinTransfer
+    weight: 3
+    hot_spot_ratio: 90
+  - transaction_type: ComputeLoop
+    loop_count: 100
+    expiration_secs: 120
+    max_gas_amount: 2000000
+  - transaction_type: EntryFunction
+    phase: 1
+    gas_unit_price_multiplier: 2
+  - transaction_type: EntryFunction
+    phase: 1
+"#;
+        let mix = WorkloadMix::parse(yaml).unwrap();
+        assert_eq!(mix.num_entry_functions(), 2);
+        let mix_per_phase = mix.transaction_mix_per_phase().unwrap();
+        assert_eq!(mix_per_phase.len(), 2);
+        match mix_per_phase[0][0] {
+            (TransactionType::CoinTransfer { hot_spot, .. }, 3) => {
+                assert_eq!(hot_spot, HotSpot::new(90))
+            },
+            _ => panic!("Unexpected workload {:?}", mix_per_phase[0][0]),
+        }
+        match mix_per_phase[0][1] {
+            (
+                TransactionType::CallCustomModules {
+                    entry_point: EntryPoints::Loopy { loop_count },
+                    ..
+                },
+                1,
+            ) => assert_eq!(loop_count, Some(100)),
+            _ => panic!("Unexpected workload {:?}", mix_per_phase[0][1]),
+        }
+        assert!(matches!(
+            mix_per_phase[1][1].0,
+            TransactionType::CustomEntryFunction { index: 1 }
+        ));
+        let overrides = mix.txn_factory_overrides_per_phase().unwrap();
+        assert_eq!(overrides[0][0], TxnFactoryOverrides::default());
+        assert_eq!(overrides[0][1], TxnFactoryOverrides {
+            expiration_secs: Some(120),
+            max_gas_amount: Some(2000000),
+            ..TxnFactoryOverrides::default()
+        });
+        assert_eq!(overrides[1][0].gas_unit_price_multiplier, Some(2));
+        assert!(overrides[1][1].is_empty());
+        assert!(mix.has_txn_factory_overrides());
+        assert_eq!(mix.generator_phases_per_phase().unwrap(), vec![None, None]);
+
+        let sequential = WorkloadMix::parse(
+            r#"
+workloads:
+  - transaction_type: NftMintAndTransfer
+    label: mint
+    run_for_secs: 60
+  - transaction_type: CoinTransfer
+    run_for_transactions: 1000
+  - transaction_type: NoOp
+"#,
+        )
+        .unwrap();
+        assert_eq!(sequential.generator_phases_per_phase().unwrap(), vec![
+            Some(vec![
+                GeneratorPhase::new("mint", Some(PhaseLimit::Duration(Duration::from_secs(60)))),
+                GeneratorPhase::new("CoinTransfer", Some(PhaseLimit::Transactions(1000))),
+                GeneratorPhase::new("NoOp", None),
+            ])
+        ]);
+        // only the last one can run until the end
+        assert!(WorkloadMix::parse(
+            "workloads:\n  - transaction_type: NoOp\n  - transaction_type: NoOp\n    run_for_secs: 1\n"
+        )
+        .is_err());
+
+        assert!(
+            WorkloadMix::parse("workloads:\n  - transaction_type: NoOp\n    phase: 1\n").is_err()
+        );
+        assert!(
+            WorkloadMix::parse("workloads:\n  - transaction_type: NoOp\n    gas: 1\n").is_err()
+        );
+    }
+}
@@ -126,15 +126,20 @@ impl Checker for TpsChecker {
         let cluster_config = ClusterArgs {
             targets: Some(vec![target_url; self.config.repeat_target_count]),
             targets_file: None,
+            discover_targets_from: None,
+            discovered_rest_port: None,
+            local_testnet: false,
+            local_testnet_dir: None,
             reuse_accounts: false,
             coin_source_args: self.config.coin_source_args.clone(),
             chain_id,
+            http_client_args: Default::default(),
         };
         let cluster = Cluster::try_from_cluster_args(&cluster_config)
             .await
             .map_err(TpsCheckerError::BuildClusterError)?;
 
-        let stats = emit_transactions_with_cluster(&cluster, &self.config.emit_config, false)
+        let stats = emit_transactions_with_cluster(&cluster, &self.config.emit_config, false, None)
             .await
             .map_err(TpsCheckerError::TransactionEmitterError)?;
 
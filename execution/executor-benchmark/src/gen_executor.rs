@@ -6,22 +6,28 @@ use crate::{
     benchmark_transaction::BenchmarkTransaction,
     db_access::{CoinStore, DbAccessUtil},
 };
-use anyhow::Result;
+use anyhow::{format_err, Result};
+use aptos_api_types::AsConverter;
+use aptos_config::config::DEFAULT_MAX_VIEW_GAS;
 use aptos_crypto::HashValue;
-use aptos_state_view::account_with_state_view::AsAccountWithStateView;
+use aptos_state_view::{account_with_state_view::AsAccountWithStateView, TStateView};
 use aptos_storage_interface::{state_view::LatestDbStateCheckpointView, DbReaderWriter};
 use aptos_transaction_generator_lib::{
-    CounterState, TransactionExecutor as GenInitTransactionExecutor,
+    view_request, CounterState, SimulationResult, TransactionExecutor as GenInitTransactionExecutor,
 };
 use aptos_types::{
+    access_path::AccessPath,
     account_address::AccountAddress,
     account_view::AccountView,
+    state_store::state_key::StateKey,
     transaction::{SignedTransaction, Transaction},
 };
+use aptos_vm::{data_cache::AsMoveResolver, AptosVM};
 use async_trait::async_trait;
+use move_core_types::language_storage::StructTag;
 use std::{
-    collections::HashMap,
     iter::once,
+    str::FromStr,
     sync::{atomic::AtomicUsize, mpsc},
     time::Duration,
 };
@@ -43,6 +49,21 @@ impl GenInitTransactionExecutor for DbGenInitTransactionExecutor {
         Ok(sender_coin_store.coin)
     }
 
+    async fn get_account_balances(&self, account_addresses: &[AccountAddress]) -> Result<Vec<u64>> {
+        // all read from the same checkpoint
+        let db_state_view = self.db.reader.latest_state_checkpoint_view().unwrap();
+        account_addresses
+            .iter()
+            .map(|account_address| {
+                let coin_store_key = DbAccessUtil::new_state_key_aptos_coin(*account_address);
+                let coin_store =
+                    DbAccessUtil::get_db_value::<CoinStore>(&coin_store_key, &db_state_view)?
+                        .ok_or_else(|| format_err!("No coin store for {}", account_address))?;
+                Ok(coin_store.coin)
+            })
+            .collect()
+    }
+
     async fn query_sequence_number(&self, address: AccountAddress) -> Result<u64> {
         let db_state_view = self.db.reader.latest_state_checkpoint_view().unwrap();
         let address_account_view = db_state_view.as_account_with_state_view(&address);
@@ -53,6 +74,84 @@ impl GenInitTransactionExecutor for DbGenInitTransactionExecutor {
             .sequence_number())
     }
 
+    async fn get_account_resource(
+        &self,
+        account_address: AccountAddress,
+        resource_type: &str,
+    ) -> Result<Option<serde_json::Value>> {
+        let resource_type = StructTag::from_str(resource_type)?;
+        let bytes = match self
+            .get_resource_bytes(account_address, &resource_type)
+            .await?
+        {
+            Some(bytes) => bytes,
+            None => return Ok(None),
+        };
+        // annotated with the published modules, like in the resources API
+        let db_state_view = self.db.reader.latest_state_checkpoint_view()?;
+        let resolver = db_state_view.as_move_resolver();
+        let resource = resolver
+            .as_converter(self.db.reader.clone())
+            .try_into_resource(&resource_type, &bytes)?;
+        Ok(Some(serde_json::to_value(resource.data)?))
+    }
+
+    async fn get_resource_bytes(
+        &self,
+        account_address: AccountAddress,
+        resource_type: &StructTag,
+    ) -> Result<Option<Vec<u8>>> {
+        let db_state_view = self.db.reader.latest_state_checkpoint_view()?;
+        let state_key = StateKey::access_path(AccessPath::resource_access_path(
+            account_address,
+            resource_type.clone(),
+        )?);
+        db_state_view.get_state_value_bytes(&state_key)
+    }
+
+    async fn view(
+        &self,
+        function: &str,
+        type_args: &[String],
+        args: Vec<serde_json::Value>,
+    ) -> Result<Vec<serde_json::Value>> {
+        let db_state_view = self.db.reader.latest_state_checkpoint_view()?;
+        let resolver = db_state_view.as_move_resolver();
+        let converter = resolver.as_converter(self.db.reader.clone());
+        let entry_function =
+            converter.convert_view_function(view_request(function, type_args, args)?)?;
+        let return_values = AptosVM::execute_view_function(
+            &db_state_view,
+            entry_function.module().clone(),
+            entry_function.function().to_owned(),
+            entry_function.ty_args().to_owned(),
+            entry_function.args().to_owned(),
+            DEFAULT_MAX_VIEW_GAS,
+        )?;
+        converter
+            .function_return_types(&entry_function)?
+            .into_iter()
+            .zip(return_values)
+            .map(|(return_type, return_value)| {
+                let move_value =
+                    converter.try_into_move_value(&return_type.try_into()?, &return_value)?;
+                Ok(serde_json::to_value(move_value)?)
+            })
+            .collect()
+    }
+
+    async fn simulate(&self, txn: &SignedTransaction) -> Result<SimulationResult> {
+        let db_state_view = self.db.reader.latest_state_checkpoint_view()?;
+        let (_, output) = AptosVM::simulate_signed_transaction(txn, &db_state_view);
+        let output = output.txn_output();
+        Ok(SimulationResult::new(
+            output.gas_used(),
+            output.status(),
+            output.write_set(),
+            output.events().len(),
+        ))
+    }
+
     async fn execute_transactions_with_counter(
         &self,
         txns: &[SignedTransaction],
@@ -82,8 +181,8 @@ impl GenInitTransactionExecutor for DbGenInitTransactionExecutor {
         CounterState {
             submit_failures: vec![AtomicUsize::new(0)],
             wait_failures: vec![AtomicUsize::new(0)],
-            successes: AtomicUsize::new(0),
-            by_client: HashMap::new(),
+            successes_by_round: vec![AtomicUsize::new(0)],
+            ..Default::default()
         }
     }
 }
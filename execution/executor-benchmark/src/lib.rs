@@ -27,11 +27,10 @@ use aptos_jellyfish_merkle::metrics::{
 };
 use aptos_logger::info;
 use aptos_storage_interface::DbReaderWriter;
-use aptos_transaction_generator_lib::{
-    create_txn_generator_creator, TransactionGeneratorCreator, TransactionType,
-};
+use aptos_transaction_generator_lib::{workload_mix::WorkloadMix, TransactionGeneratorCreator};
 use gen_executor::DbGenInitTransactionExecutor;
 use pipeline::PipelineConfig;
+use rand::{rngs::StdRng, SeedableRng};
 use std::{
     fs,
     path::Path,
@@ -83,7 +82,7 @@ fn create_checkpoint(
 pub fn run_benchmark<V>(
     block_size: usize,
     num_transfer_blocks: usize,
-    transaction_type: Option<TransactionType>,
+    workload_mix: Option<WorkloadMix>,
     transactions_per_sender: usize,
     num_main_signer_accounts: usize,
     source_dir: impl AsRef<Path>,
@@ -110,9 +109,9 @@ pub fn run_benchmark<V>(
 
     let (db, executor) = init_db_and_executor::<V>(&config);
 
-    let transaction_generator_creator = transaction_type.map(|transaction_type| {
+    let transaction_generator_creator = workload_mix.as_ref().map(|workload_mix| {
         init_workload::<V, _>(
-            transaction_type,
+            workload_mix,
             num_main_signer_accounts,
             db.clone(),
             &source_dir,
@@ -149,8 +148,8 @@ pub fn run_benchmark<V>(
     let delta_v = db.reader.get_latest_version().unwrap() - version;
     info!(
         "Overall TPS: {}: {} txn/s",
-        if let Some(ttype) = transaction_type {
-            format!("{:?} via txn generator", ttype)
+        if let Some(workload_mix) = &workload_mix {
+            format!("{:?} via txn generator", workload_mix.workloads)
         } else {
             "raw transfer".to_string()
         },
@@ -162,8 +161,9 @@ pub fn run_benchmark<V>(
     }
 }
 
+/// Only the first phase of the workload mix is run, as phases aren't advanced.
 fn init_workload<V, P: AsRef<Path>>(
-    transaction_type: TransactionType,
+    workload_mix: &WorkloadMix,
     num_main_signer_accounts: usize,
     db: DbReaderWriter,
     db_dir: &P,
@@ -195,17 +195,20 @@ where
             block_sender,
         };
 
-        create_txn_generator_creator(
-            &[vec![(transaction_type, 1)]],
-            1,
-            &mut main_signer_accounts,
-            burner_accounts,
-            &db_gen_init_transaction_executor,
-            &transaction_factory,
-            &transaction_factory,
-            phase,
-        )
-        .await
+        workload_mix
+            .create_txn_generator_creator(
+                1,
+                &mut main_signer_accounts,
+                burner_accounts,
+                &db_gen_init_transaction_executor,
+                &transaction_factory,
+                &transaction_factory,
+                &[],
+                StdRng::from_entropy(),
+                phase,
+            )
+            .await
+            .expect("Workload mix should be valid")
     });
 
     pipeline.join();
@@ -334,7 +337,7 @@ mod tests {
     use aptos_config::config::NO_OP_STORAGE_PRUNER_CONFIG;
     use aptos_executor::block_executor::TransactionBlockExecutor;
     use aptos_temppath::TempPath;
-    use aptos_transaction_generator_lib::args::TransactionTypeArg;
+    use aptos_transaction_generator_lib::{args::TransactionTypeArg, workload_mix::WorkloadMix};
     use aptos_vm::AptosVM;
 
     fn test_generic_benchmark<E>(
@@ -372,7 +375,7 @@ mod tests {
         super::run_benchmark::<E>(
             6, /* block_size */
             5, /* num_transfer_blocks */
-            transaction_type.map(|t| t.materialize()),
+            transaction_type.map(WorkloadMix::single),
             2,  /* transactions per sender */
             25, /* num_main_signer_accounts */
             storage_dir.as_ref(),
@@ -12,7 +12,7 @@ use aptos_executor_benchmark::{
 };
 use aptos_metrics_core::{register_int_gauge, IntGauge};
 use aptos_push_metrics::MetricsPusher;
-use aptos_transaction_generator_lib::args::TransactionTypeArg;
+use aptos_transaction_generator_lib::{args::TransactionTypeArg, workload_mix::WorkloadMix};
 use aptos_vm::AptosVM;
 use clap::{Parser, Subcommand};
 use once_cell::sync::Lazy;
@@ -178,6 +178,11 @@ enum Command {
         #[clap(long, arg_enum, ignore_case = true)]
         transaction_type: Option<TransactionTypeArg>,
 
+        /// Workload mix (see WorkloadMix) from a YAML file, instead of transaction_type.
+        /// Only its first phase is run
+        #[clap(long, parse(from_os_str), conflicts_with = "transaction-type")]
+        workload_mix_file: Option<PathBuf>,
+
         #[clap(long, parse(from_os_str))]
         data_dir: PathBuf,
 
@@ -224,13 +229,20 @@ where
         Command::RunExecutor {
             blocks,
             transaction_type,
+            workload_mix_file,
             data_dir,
             checkpoint_dir,
         } => {
+            let workload_mix = match workload_mix_file {
+                Some(workload_mix_file) => {
+                    Some(WorkloadMix::load(&workload_mix_file).expect("Workload mix should load"))
+                },
+                None => transaction_type.map(WorkloadMix::single),
+            };
             aptos_executor_benchmark::run_benchmark::<E>(
                 opt.block_size,
                 blocks,
-                transaction_type.map(|t| t.materialize()),
+                workload_mix,
                 opt.transactions_per_sender,
                 opt.main_signer_accounts,
                 data_dir,
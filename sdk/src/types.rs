@@ -5,7 +5,8 @@
 use crate::{
     crypto::{
         ed25519::{Ed25519PrivateKey, Ed25519PublicKey},
-        traits::Uniform,
+        multi_ed25519::{MultiEd25519PrivateKey, MultiEd25519PublicKey},
+        traits::{SigningKey, Uniform},
     },
     transaction_builder::TransactionBuilder,
     types::{
@@ -32,6 +33,9 @@ pub struct LocalAccount {
     key: AccountKey,
     /// Latest known sequence number of the account, it can be different from validator.
     sequence_number: u64,
+    /// Multi-ed25519 key of the account, if it has one, with `key` being the first of
+    /// its keys. Transactions are signed with it instead.
+    multi_ed25519_key: Option<MultiEd25519AccountKey>,
 }
 
 impl LocalAccount {
@@ -43,6 +47,7 @@ impl LocalAccount {
             address,
             key: key.into(),
             sequence_number,
+            multi_ed25519_key: None,
         }
     }
 
@@ -66,6 +71,7 @@ impl LocalAccount {
             address,
             key,
             sequence_number,
+            multi_ed25519_key: None,
         })
     }
 
@@ -82,7 +88,40 @@ impl LocalAccount {
         Self::new(address, key, 0)
     }
 
+    /// Generate a new account locally, with a K-of-N multi-ed25519 key. Transactions
+    /// are signed with the first `threshold` of its keys.
+    pub fn generate_multi_ed25519<R>(rng: &mut R, num_keys: u8, threshold: u8) -> Result<Self>
+    where
+        R: ::rand_core::RngCore + ::rand_core::CryptoRng,
+    {
+        let key = AccountKey::generate(rng);
+        let mut private_keys = vec![Ed25519PrivateKey::try_from(
+            key.private_key().to_bytes().as_ref(),
+        )?];
+        private_keys.extend((1..num_keys).map(|_| Ed25519PrivateKey::generate(rng)));
+        let multi_ed25519_key = MultiEd25519AccountKey::from_private_key(
+            MultiEd25519PrivateKey::new(private_keys, threshold)?,
+        );
+        Ok(Self {
+            address: multi_ed25519_key.authentication_key().derived_address(),
+            key,
+            sequence_number: 0,
+            multi_ed25519_key: Some(multi_ed25519_key),
+        })
+    }
+
     pub fn sign_transaction(&self, txn: RawTransaction) -> SignedTransaction {
+        if let Some(multi_ed25519_key) = &self.multi_ed25519_key {
+            let signature = multi_ed25519_key
+                .private_key()
+                .sign(&txn)
+                .expect("Signing a txn can't fail");
+            return SignedTransaction::new_multisig(
+                txn,
+                multi_ed25519_key.public_key().clone(),
+                signature,
+            );
+        }
         txn.sign(self.private_key(), self.public_key().clone())
             .expect("Signing a txn can't fail")
             .into_inner()
@@ -104,6 +143,19 @@ impl LocalAccount {
         &mut self,
         secondary_signers: Vec<&Self>,
         builder: TransactionBuilder,
+    ) -> SignedTransaction {
+        let raw_txn = builder
+            .sender(self.address())
+            .sequence_number(self.sequence_number())
+            .build();
+        *self.sequence_number_mut() += 1;
+        self.sign_multi_agent_transaction(secondary_signers, raw_txn)
+    }
+
+    pub fn sign_multi_agent_transaction(
+        &self,
+        secondary_signers: Vec<&Self>,
+        txn: RawTransaction,
     ) -> SignedTransaction {
         let secondary_signer_addresses = secondary_signers
             .iter()
@@ -113,19 +165,13 @@ impl LocalAccount {
             .iter()
             .map(|signer| signer.private_key())
             .collect();
-        let raw_txn = builder
-            .sender(self.address())
-            .sequence_number(self.sequence_number())
-            .build();
-        *self.sequence_number_mut() += 1;
-        raw_txn
-            .sign_multi_agent(
-                self.private_key(),
-                secondary_signer_addresses,
-                secondary_signer_privkeys,
-            )
-            .expect("Signing multi agent txn failed")
-            .into_inner()
+        txn.sign_multi_agent(
+            self.private_key(),
+            secondary_signer_addresses,
+            secondary_signer_privkeys,
+        )
+        .expect("Signing multi agent txn failed")
+        .into_inner()
     }
 
     pub fn address(&self) -> AccountAddress {
@@ -141,7 +187,14 @@ impl LocalAccount {
     }
 
     pub fn authentication_key(&self) -> AuthenticationKey {
-        self.key.authentication_key()
+        match &self.multi_ed25519_key {
+            Some(multi_ed25519_key) => multi_ed25519_key.authentication_key(),
+            None => self.key.authentication_key(),
+        }
+    }
+
+    pub fn multi_ed25519_key(&self) -> Option<&MultiEd25519AccountKey> {
+        self.multi_ed25519_key.as_ref()
     }
 
     pub fn sequence_number(&self) -> u64 {
@@ -153,6 +206,7 @@ impl LocalAccount {
     }
 
     pub fn rotate_key<T: Into<AccountKey>>(&mut self, new_key: T) -> AccountKey {
+        self.multi_ed25519_key = None;
         std::mem::replace(&mut self.key, new_key.into())
     }
 
@@ -211,6 +265,38 @@ impl From<Ed25519PrivateKey> for AccountKey {
     }
 }
 
+#[derive(Debug)]
+pub struct MultiEd25519AccountKey {
+    private_key: MultiEd25519PrivateKey,
+    public_key: MultiEd25519PublicKey,
+    authentication_key: AuthenticationKey,
+}
+
+impl MultiEd25519AccountKey {
+    pub fn from_private_key(private_key: MultiEd25519PrivateKey) -> Self {
+        let public_key = MultiEd25519PublicKey::from(&private_key);
+        let authentication_key = AuthenticationKey::multi_ed25519(&public_key);
+
+        Self {
+            private_key,
+            public_key,
+            authentication_key,
+        }
+    }
+
+    pub fn private_key(&self) -> &MultiEd25519PrivateKey {
+        &self.private_key
+    }
+
+    pub fn public_key(&self) -> &MultiEd25519PublicKey {
+        &self.public_key
+    }
+
+    pub fn authentication_key(&self) -> AuthenticationKey {
+        self.authentication_key
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -234,4 +320,37 @@ mod tests {
         // Return an error for empty mnemonic phrase.
         assert!(LocalAccount::from_derive_path(derive_path, "", 0).is_err());
     }
+
+    #[test]
+    fn test_multi_ed25519_account() {
+        use crate::types::{
+            chain_id::ChainId,
+            transaction::{authenticator::TransactionAuthenticator, Script, TransactionPayload},
+        };
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let mut rng = StdRng::from_seed([0; 32]);
+        let account = LocalAccount::generate_multi_ed25519(&mut rng, 3, 2).unwrap();
+        assert_eq!(
+            account.authentication_key().derived_address(),
+            account.address()
+        );
+        let txn = account.sign_transaction(RawTransaction::new(
+            account.address(),
+            0,
+            TransactionPayload::Script(Script::new(vec![], vec![], vec![])),
+            0,
+            0,
+            0,
+            ChainId::test(),
+        ));
+        assert!(matches!(
+            txn.authenticator(),
+            TransactionAuthenticator::MultiEd25519 { .. }
+        ));
+        assert!(txn.check_signature().is_ok());
+
+        // threshold can't be above the number of keys
+        assert!(LocalAccount::generate_multi_ed25519(&mut rng, 2, 3).is_err());
+    }
 }
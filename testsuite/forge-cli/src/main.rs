@@ -5,6 +5,7 @@
 use anyhow::{format_err, Context, Result};
 use aptos_config::config::ConsensusConfig;
 use aptos_forge::{
+    hot_spot::HotSpot,
     success_criteria::{LatencyType, StateProgressThreshold, SuccessCriteria},
     system_metrics::{MetricsThreshold, SystemMetricsThreshold},
     ForgeConfig, Options, *,
@@ -806,6 +807,7 @@ fn graceful_overload(config: ForgeConfig) -> ForgeConfig {
             inner_transaction_type: TransactionType::CoinTransfer {
                 invalid_transaction_ratio: 0,
                 sender_use_account_pool: false,
+                hot_spot: HotSpot::none(),
             },
             // Additionally - we are not really gracefully handling overlaods,
             // setting limits based on current reality, to make sure they
@@ -860,6 +862,7 @@ fn three_region_sim_graceful_overload(config: ForgeConfig) -> ForgeConfig {
                 inner_transaction_type: TransactionType::CoinTransfer {
                     invalid_transaction_ratio: 0,
                     sender_use_account_pool: false,
+                    hot_spot: HotSpot::none(),
                 },
                 // Additionally - we are not really gracefully handling overlaods,
                 // setting limits based on current reality, to make sure they
@@ -8,7 +8,8 @@ use crate::{
 use aptos::test::CliTestFramework;
 use aptos_consensus::QUORUM_STORE_DB_NAME;
 use aptos_forge::{
-    reconfig, wait_for_all_nodes_to_catchup, NodeExt, Swarm, SwarmExt, TransactionType,
+    hot_spot::HotSpot, reconfig, wait_for_all_nodes_to_catchup, NodeExt, Swarm, SwarmExt,
+    TransactionType,
 };
 use aptos_logger::info;
 use aptos_rest_client::Client;
@@ -40,6 +41,7 @@ async fn generate_traffic_and_assert_committed(
             TransactionType::CoinTransfer {
                 invalid_transaction_ratio: 0,
                 sender_use_account_pool: false,
+                hot_spot: HotSpot::none(),
             },
             70,
         ),
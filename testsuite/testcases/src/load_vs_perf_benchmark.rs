@@ -3,8 +3,8 @@
 
 use crate::NetworkLoadTest;
 use aptos_forge::{
-    EmitJobMode, EmitJobRequest, EntryPoints, NetworkContext, NetworkTest, Result, Test,
-    TransactionType, TxnStats,
+    hot_spot::HotSpot, EmitJobMode, EmitJobRequest, EntryPoints, NetworkContext, NetworkTest,
+    Result, Test, TransactionType, TxnStats,
 };
 use aptos_logger::info;
 use rand::SeedableRng;
@@ -127,6 +127,7 @@ impl TransactinWorkload {
                     TransactionType::CoinTransfer {
                         invalid_transaction_ratio: 0,
                         sender_use_account_pool: true,
+                        hot_spot: HotSpot::none(),
                     }
                 } else {
                     TransactionType::CallCustomModules {